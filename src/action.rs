@@ -0,0 +1,369 @@
+//! User interactions as data: every key press or click is translated into an
+//! `Action`, which the event loop's dispatcher applies to its state.
+//!
+//! Keeping the mapping separate from the handling makes bindings remappable
+//! and lets input handling be exercised without a terminal.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Something the user can ask the application to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    /// Clear all change highlights.
+    ResetHighlights,
+    MoveUp,
+    MoveDown,
+    /// Move the selection a few entries up (mouse wheel).
+    ScrollUp,
+    /// Move the selection a few entries down (mouse wheel).
+    ScrollDown,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    /// Select the entry at the given index.
+    Select(usize),
+    /// Move the selection by the given number of entries (up if negative).
+    Scroll(isize),
+    /// Show this many levels below the root (`None`: all of them).
+    SetDepth(Option<usize>),
+    ToggleSummary,
+    TogglePreview,
+    ToggleHidden,
+    /// Mark files that have not changed for a while.
+    ToggleStale,
+    /// Show or hide the heaviest-paths panel.
+    ToggleHeavyReport,
+    /// Show or hide the debug overlay (frame and walk times, event rate).
+    ToggleStats,
+    /// Tint entries by how often they changed lately.
+    ToggleHeatmap,
+    /// Show or hide the panel of directories with the most recent changes.
+    ToggleChangers,
+    /// Mark or unmark the selected entry.
+    ToggleMark,
+    /// Select the next marked entry below the selection (wrapping around).
+    NextMark,
+    /// Select the previous marked entry above the selection.
+    PreviousMark,
+    /// Open the command palette with a `find` command.
+    Search,
+    /// Select the next search match below the selection (wrapping around).
+    NextMatch,
+    /// Select the previous search match above the selection.
+    PreviousMatch,
+    /// Show only the search's matches, best first, instead of the tree.
+    ToggleRanked,
+    /// Open the command palette.
+    Goto,
+    /// Open the command palette with `export ` typed in.
+    Export,
+    /// Show or hide entry numbers in a gutter.
+    ToggleLineNumbers,
+    /// Number gutter rows by their distance from the selection.
+    ToggleRelativeLineNumbers,
+    /// Pin or unpin the directories enclosing the top row.
+    ToggleStickyHeaders,
+    /// List entries as paths relative to the root instead of a tree.
+    ToggleFlat,
+    /// Pack a short tree into columns on a wide pane.
+    ToggleColumns,
+    /// Move the keyboard focus to the other pane of split view.
+    SwitchPane,
+    /// Mark the entries that differ between the panes of split view.
+    ToggleCompare,
+    /// Show the selected directory as the root of the tree.
+    ZoomIn,
+    /// Go back to the root shown before the last `ZoomIn`.
+    ZoomOut,
+    /// Move the root up to its parent directory.
+    RootUp,
+    /// Select the most recently changed entry.
+    JumpToLastChange,
+    /// Keep selecting each changed entry as changes arrive.
+    ToggleFollow,
+    /// Collapse or expand the selected directory, or open or close the
+    /// selected archive.
+    ToggleCollapse,
+    /// Open the selected entry in `$VISUAL` / `$EDITOR`.
+    OpenInEditor,
+    /// Copy the selected entry's path to the clipboard.
+    CopyPath,
+    /// Show the selected entry in the OS file manager.
+    Reveal,
+    /// Open the selected entry with its default application.
+    Open,
+    /// Run `$SHELL` in the selected directory until it exits.
+    Shell,
+    /// Stop livetree and return to the shell until it is resumed with `fg`.
+    Suspend,
+    /// Open the command palette with `touch ` typed in.
+    NewFile,
+    /// Open the command palette with `mkdir ` typed in.
+    NewDir,
+    /// Open the command palette with `rename <selected name>` typed in.
+    Rename,
+    /// Delete the selected entry, after confirmation.
+    Delete,
+    IncreaseHighlightDuration,
+    DecreaseHighlightDuration,
+    /// Double the entry limit (`--max-entries`).
+    IncreaseMaxEntries,
+    /// Halve the entry limit.
+    DecreaseMaxEntries,
+    /// Redraw the screen (e.g. after a resize).
+    Redraw,
+}
+
+impl Action {
+    /// The actions a motion typed after a count (`25j`) stands for: the
+    /// movement repeated `count` times, or the entry numbered `count` for
+    /// `First` and `Last`, as in vim's `25G`. Other actions ignore counts.
+    pub fn counted(self, count: usize) -> Vec<Action> {
+        let count = count.min(MAX_COUNT);
+        let signed = count as isize;
+        match self {
+            Action::MoveUp => vec![Action::Scroll(-signed)],
+            Action::MoveDown => vec![Action::Scroll(signed)],
+            Action::PageUp
+            | Action::PageDown
+            | Action::NextMark
+            | Action::PreviousMark
+            | Action::NextMatch
+            | Action::PreviousMatch => vec![self; count],
+            Action::First | Action::Last => vec![Action::Select(count.saturating_sub(1))],
+            action => vec![action],
+        }
+    }
+}
+
+/// Largest count a motion takes; more digits are ignored.
+const MAX_COUNT: usize = 99_999;
+
+/// The count typed so far before a motion, vim style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PendingCount(Option<usize>);
+
+impl PendingCount {
+    /// Take a digit key into the count. A count can't start with `0`.
+    /// Returns whether the key was taken.
+    pub fn push(&mut self, key: &KeyEvent) -> bool {
+        let KeyCode::Char(c) = key.code else {
+            return false;
+        };
+        let digit = match c.to_digit(10) {
+            Some(digit) if key.modifiers - KeyModifiers::SHIFT == KeyModifiers::NONE => digit,
+            _ => return false,
+        };
+        if digit == 0 && self.0.is_none() {
+            return false;
+        }
+        let count = self.0.unwrap_or(0) * 10 + digit as usize;
+        self.0 = Some(count.min(MAX_COUNT));
+        true
+    }
+
+    /// The count typed, clearing it.
+    pub fn take(&mut self) -> Option<usize> {
+        self.0.take()
+    }
+}
+
+/// Key bindings from key presses to actions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: HashMap::new(),
+        };
+        let none = KeyModifiers::NONE;
+        for (code, action) in [
+            (KeyCode::Char('q'), Action::Quit),
+            (KeyCode::Char('r'), Action::ResetHighlights),
+            (KeyCode::Char('s'), Action::ToggleSummary),
+            (KeyCode::Char('p'), Action::TogglePreview),
+            (KeyCode::Char('.'), Action::ToggleHidden),
+            (KeyCode::Char('H'), Action::ToggleHeavyReport),
+            (KeyCode::Char('S'), Action::ToggleStale),
+            (KeyCode::Char('T'), Action::ToggleHeatmap),
+            (KeyCode::Char('C'), Action::ToggleChangers),
+            (KeyCode::Char('f'), Action::Reveal),
+            (KeyCode::Char('O'), Action::Open),
+            (KeyCode::Char('!'), Action::Shell),
+            (KeyCode::Char('m'), Action::ToggleMark),
+            (KeyCode::Char('\''), Action::NextMark),
+            (KeyCode::Char('"'), Action::PreviousMark),
+            (KeyCode::Char('/'), Action::Search),
+            (KeyCode::Char('n'), Action::NextMatch),
+            (KeyCode::Char('N'), Action::PreviousMatch),
+            (KeyCode::Char('o'), Action::ToggleRanked),
+            (KeyCode::Char(':'), Action::Goto),
+            (KeyCode::Char('g'), Action::Goto),
+            (KeyCode::Char('w'), Action::Export),
+            (KeyCode::Char('a'), Action::NewFile),
+            (KeyCode::Char('A'), Action::NewDir),
+            (KeyCode::Char('R'), Action::Rename),
+            (KeyCode::Char('D'), Action::Delete),
+            (KeyCode::Char('#'), Action::ToggleLineNumbers),
+            (KeyCode::Char('%'), Action::ToggleRelativeLineNumbers),
+            (KeyCode::Char('P'), Action::ToggleStickyHeaders),
+            (KeyCode::Char('v'), Action::ToggleFlat),
+            (KeyCode::Char('|'), Action::ToggleColumns),
+            (KeyCode::Char('L'), Action::JumpToLastChange),
+            (KeyCode::Char('F'), Action::ToggleFollow),
+            (KeyCode::Enter, Action::ToggleCollapse),
+            (KeyCode::Tab, Action::SwitchPane),
+            (KeyCode::Char('='), Action::ToggleCompare),
+            (KeyCode::Right, Action::ZoomIn),
+            (KeyCode::Char('l'), Action::ZoomIn),
+            (KeyCode::Left, Action::ZoomOut),
+            (KeyCode::Char('h'), Action::ZoomOut),
+            (KeyCode::Backspace, Action::RootUp),
+            (KeyCode::Char('u'), Action::RootUp),
+            (KeyCode::Up, Action::MoveUp),
+            (KeyCode::Char('k'), Action::MoveUp),
+            (KeyCode::Down, Action::MoveDown),
+            (KeyCode::Char('j'), Action::MoveDown),
+            (KeyCode::PageUp, Action::PageUp),
+            (KeyCode::PageDown, Action::PageDown),
+            (KeyCode::Home, Action::First),
+            (KeyCode::End, Action::Last),
+            (KeyCode::F(12), Action::ToggleStats),
+            (KeyCode::Char('+'), Action::IncreaseHighlightDuration),
+            (KeyCode::Char('-'), Action::DecreaseHighlightDuration),
+            (KeyCode::Char(']'), Action::IncreaseMaxEntries),
+            (KeyCode::Char('['), Action::DecreaseMaxEntries),
+        ] {
+            keymap.bind(code, none, action);
+        }
+        keymap.bind(KeyCode::Char('c'), KeyModifiers::CONTROL, Action::Quit);
+        keymap.bind(KeyCode::Char('z'), KeyModifiers::CONTROL, Action::Suspend);
+        keymap
+    }
+}
+
+impl Keymap {
+    /// An empty keymap with no bindings.
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Bind a key (with modifiers) to `action`, replacing any previous binding.
+    pub fn bind(&mut self, code: KeyCode, modifiers: KeyModifiers, action: Action) {
+        self.bindings.insert(normalize(code, modifiers), action);
+    }
+
+    /// Remove the binding for a key, if any.
+    pub fn unbind(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        self.bindings.remove(&normalize(code, modifiers));
+    }
+
+    /// The action bound to a key press.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .get(&normalize(key.code, key.modifiers))
+            .copied()
+    }
+}
+
+/// Shift is implied by the character itself (`+`, `G`), so ignore it for
+/// character keys; otherwise terminals that report it would miss bindings.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(_) => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn default_bindings() {
+        let keymap = Keymap::default();
+        let none = KeyModifiers::NONE;
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('q'), none)),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(&key(KeyCode::Char('c'), none)), None);
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+            Some(Action::Suspend)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('j'), none)),
+            Some(Action::MoveDown)
+        );
+    }
+
+    #[test]
+    fn shift_is_ignored_for_characters() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('+'), KeyModifiers::SHIFT)),
+            Some(Action::IncreaseHighlightDuration)
+        );
+    }
+
+    #[test]
+    fn rebinding_replaces_action() {
+        let mut keymap = Keymap::default();
+        keymap.bind(KeyCode::Char('j'), KeyModifiers::NONE, Action::PageDown);
+        keymap.unbind(KeyCode::Char('q'), KeyModifiers::NONE);
+        let none = KeyModifiers::NONE;
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('j'), none)),
+            Some(Action::PageDown)
+        );
+        assert_eq!(keymap.action(&key(KeyCode::Char('q'), none)), None);
+    }
+
+    #[test]
+    fn actions_serialize_as_kebab_case() {
+        let parsed: HashMap<String, Action> =
+            toml::from_str("a = \"toggle-hidden\"\nb = { select = 3 }").unwrap();
+        assert_eq!(parsed["a"], Action::ToggleHidden);
+        assert_eq!(parsed["b"], Action::Select(3));
+    }
+
+    #[test]
+    fn counts_are_typed_digit_by_digit() {
+        let mut count = PendingCount::default();
+        let none = KeyModifiers::NONE;
+        assert!(!count.push(&key(KeyCode::Char('0'), none)));
+        assert!(count.push(&key(KeyCode::Char('2'), none)));
+        assert!(count.push(&key(KeyCode::Char('0'), none)));
+        assert!(!count.push(&key(KeyCode::Char('j'), none)));
+        assert!(!count.push(&key(KeyCode::Char('3'), KeyModifiers::CONTROL)));
+        assert_eq!(count.take(), Some(20));
+        assert_eq!(count.take(), None);
+    }
+
+    #[test]
+    fn counts_repeat_motions() {
+        assert_eq!(Action::MoveUp.counted(10), [Action::Scroll(-10)]);
+        assert_eq!(Action::PageDown.counted(2), [Action::PageDown; 2]);
+        assert_eq!(Action::Last.counted(12), [Action::Select(11)]);
+        assert_eq!(Action::ToggleHidden.counted(3), [Action::ToggleHidden]);
+    }
+}
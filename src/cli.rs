@@ -1,8 +1,17 @@
 //! Command-line argument parsing via `clap` derive.
 
-use clap::Parser;
+use crate::event_loop::AnnouncePosition;
+use crate::export::Format;
+use crate::remote::RemoteSpec;
+use crate::render::AmbiguousWidth;
+use crate::script::StreamFormat;
+use crate::serve::Endpoint;
+use crate::status::StatusTemplate;
+use crate::tree::CASE_INSENSITIVE;
+use clap::{Parser, Subcommand};
 use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Parsed command-line arguments for livetree.
 #[derive(Parser, Debug, Clone)]
@@ -10,10 +19,15 @@ use std::path::PathBuf;
     name = "livetree",
     version = concat!(env!("CARGO_PKG_VERSION"), " (build ", env!("BUILD_NUMBER"), ")"),
     about = "Real-time directory tree watcher",
-    after_help = "Examples:\n  livetree .\n  livetree -L 3 -I target -I \"*.log\" ./my-project\n  NO_COLOR=1 livetree --dirs-only ."
+    after_help = "Examples:\n  livetree .\n  livetree -L 3 -I target -I \"*.log\" ./my-project\n  NO_COLOR=1 livetree --dirs-only .\n  livetree check-config",
+    args_conflicts_with_subcommands = true
 )]
 pub struct Args {
-    /// Directory to watch (default: current directory)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Directory to watch (default: current directory); a file watches its
+    /// directory with the file selected and followed
     #[arg(default_value = ".")]
     pub path: PathBuf,
 
@@ -21,10 +35,25 @@ pub struct Args {
     #[arg(short = 'L', long = "level")]
     pub max_depth: Option<usize>,
 
-    /// Glob patterns to exclude (repeatable)
+    /// Glob patterns to exclude (repeatable); a trailing `/` matches
+    /// directories only
     #[arg(short = 'I', long = "ignore", action = clap::ArgAction::Append)]
     pub ignore: Vec<String>,
 
+    /// Like -I, but matching case-insensitively (repeatable)
+    #[arg(long = "iglob", value_name = "PATTERN", action = clap::ArgAction::Append)]
+    pub iglob: Vec<String>,
+
+    /// Read more patterns to exclude from FILE (one glob per line, `#`
+    /// comments); the file is re-read when it changes
+    #[arg(long = "ignore-file", value_name = "FILE")]
+    pub ignore_file: Option<PathBuf>,
+
+    /// Do not ignore .git, node_modules, __pycache__, and .DS_Store by
+    /// default (dotfiles still need -a)
+    #[arg(long = "no-default-ignores")]
+    pub no_default_ignores: bool,
+
     /// Show hidden files (dotfiles)
     #[arg(short = 'a', long = "all")]
     pub show_hidden: bool,
@@ -41,6 +70,11 @@ pub struct Args {
     #[arg(long = "debounce", default_value = "200")]
     pub debounce_ms: u64,
 
+    /// Redraw at most this many times per second while changes stream in
+    /// (0 = no limit); key presses are always drawn at once
+    #[arg(long = "max-fps", value_name = "N", default_value_t = crate::frame::DEFAULT_MAX_FPS)]
+    pub max_fps: u32,
+
     /// Disable colored output
     #[arg(long = "no-color")]
     pub no_color: bool,
@@ -53,13 +87,235 @@ pub struct Args {
     #[arg(long = "quiet")]
     pub quiet: bool,
 
-    /// Maximum number of entries to include in the tree (default: 1000)
+    /// Maximum number of entries to include in the tree (default: 1000); the
+    /// deepest levels are dropped first, and `]`/`[` change it at runtime
     #[arg(long = "max-entries", default_value = "1000")]
     pub max_entries: usize,
 
+    /// Walk only DEPTH levels up front; deeper directories are shown
+    /// collapsed and walked when expanded (for very large trees)
+    #[arg(long = "lazy", value_name = "DEPTH")]
+    pub lazy: Option<usize>,
+
+    /// Wait for the first change under the root (hidden and ignored paths
+    /// do not count), print the changed paths one per line, and exit
+    #[arg(long = "exit-on-change")]
+    pub exit_on_change: bool,
+
+    /// Print a line per change (time, kind, path) to stdout instead of
+    /// drawing the tree, until interrupted; --stream=ndjson prints JSON lines
+    #[arg(long = "stream", value_name = "FORMAT", num_args = 0..=1, require_equals = true,
+          default_missing_value = "text", value_parser = parse_stream_format)]
+    pub stream: Option<StreamFormat>,
+
     /// Do not set the terminal title (pane/window title remains unchanged)
     #[arg(long = "no-title")]
     pub no_title: bool,
+
+    /// Show per-directory file counts by extension (toggle with `s`)
+    #[arg(long = "summary")]
+    pub summary: bool,
+
+    /// Tint entries by how often they changed in the last minute (toggle
+    /// with `T`)
+    #[arg(long = "heatmap")]
+    pub heatmap: bool,
+
+    /// Mark files that are hard links to a file shown earlier in the tree
+    /// (Unix only; reads every file's inode)
+    #[arg(long = "hard-links")]
+    pub hard_links: bool,
+
+    /// Show each entry's permissions (rwxr-xr-x) in a column before the
+    /// tree and mark executables with `*` (Unix only)
+    #[arg(long = "permissions")]
+    pub permissions: bool,
+
+    /// Show each entry's owning user and group in a column before the tree
+    /// (Unix only; names are looked up once per id)
+    #[arg(long = "owner")]
+    pub owner: bool,
+
+    /// Print a report and exit instead of watching: `heavy` lists the
+    /// largest files and directories (see --top); `stale` lists files not
+    /// modified within --older-than
+    #[arg(long = "report", value_name = "KIND")]
+    pub report: Option<String>,
+
+    /// How many entries `--report heavy` lists (default 20)
+    #[arg(long = "top", value_name = "N", requires = "report")]
+    pub top: Option<usize>,
+
+    /// Age past which `--report stale` lists a file (e.g. 30d, 12h)
+    #[arg(long = "older-than", value_name = "DURATION", requires = "report", value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Write the tree to FILE (`-` for stdout) and exit instead of watching;
+    /// the format follows the extension: .md (nested lists), .json, .html, or
+    /// plain text
+    #[arg(long = "export", value_name = "FILE")]
+    pub export: Option<PathBuf>,
+
+    /// Format for --export, overriding the extension: text, markdown, json,
+    /// or html (a page with collapsible directories)
+    #[arg(long = "format", value_name = "FORMAT", requires = "export", value_parser = parse_export_format)]
+    pub format: Option<Format>,
+
+    /// Age after which `S` marks files as stale (e.g. 90d, 12h, 30m)
+    #[arg(long = "stale-after", value_name = "DURATION", default_value = "30d", value_parser = parse_duration)]
+    pub stale_after: Duration,
+
+    /// How far back the top-changers panel (`C`) counts changes per
+    /// directory (e.g. 5m, 1h)
+    #[arg(long = "changers-window", value_name = "DURATION", default_value = "5m", value_parser = parse_duration)]
+    pub changers_window: Duration,
+
+    /// Clip long names at the window edge instead of shortening their middle
+    #[arg(long = "no-truncate")]
+    pub no_truncate: bool,
+
+    /// Columns the terminal gives ambiguous-width characters (`…`, `±`, box
+    /// drawing): 1, or 2 for CJK terminals that draw them wide
+    #[arg(long = "ambiguous-width", value_name = "1|2", default_value = "1", value_parser = parse_ambiguous_width)]
+    pub ambiguous_width: AmbiguousWidth,
+
+    /// Number entries in a gutter (toggle with `#`); `:N` jumps to entry N
+    #[arg(long = "line-numbers")]
+    pub line_numbers: bool,
+
+    /// Number rows in the gutter by their distance from the selection, as
+    /// vim's `relativenumber` does (toggle with `%`); with `--line-numbers`
+    /// the selection keeps its entry number
+    #[arg(long = "relative-line-numbers")]
+    pub relative_line_numbers: bool,
+
+    /// List entries as paths relative to the root, one per line like `fd`
+    /// prints them, instead of a tree (toggle with `v`)
+    #[arg(long = "flat")]
+    pub flat: bool,
+
+    /// Pack the tree into columns, like `ls -C`, when it is too long for
+    /// the terminal but fits across it (toggle with `|`)
+    #[arg(long = "columns")]
+    pub columns: bool,
+
+    /// Don't pin the directories enclosing the top row while scrolling
+    /// (toggle with `P`)
+    #[arg(long = "no-sticky-headers")]
+    pub no_sticky_headers: bool,
+
+    /// Delete entries (`D`) permanently instead of moving them to the trash
+    #[arg(long = "no-trash")]
+    pub no_trash: bool,
+
+    /// Status bar layout, e.g. "{path} | {entries} | {last_change} | {watcher}".
+    /// Fields: path, entries, last_change, last_file, follow, watcher, fps
+    #[arg(long = "status-format", value_name = "TEMPLATE", value_parser = parse_status_template)]
+    pub status_format: Option<StatusTemplate>,
+
+    /// Show a plain-text line describing the latest change (e.g. "created
+    /// src/new.rs at 12:31:05") at the top or bottom, for screen readers
+    #[arg(long = "announce", value_name = "top|bottom", value_parser = parse_announce_position)]
+    pub announce: Option<AnnouncePosition>,
+
+    /// Watch a directory on another machine, listed over ssh (key-based
+    /// auth only) and polled for created and removed entries; file
+    /// operations and views that read files are unavailable
+    #[arg(long = "remote", value_name = "[USER@]HOST:PATH", conflicts_with = "path", value_parser = parse_remote)]
+    pub remote: Option<RemoteSpec>,
+
+    /// Watch a directory inside a running Docker container, listed with
+    /// `docker exec` and polled like --remote
+    #[arg(long = "docker", value_name = "CONTAINER:PATH", conflicts_with_all = ["path", "remote"], value_parser = RemoteSpec::docker)]
+    pub docker: Option<RemoteSpec>,
+
+    /// Run without a terminal UI and send the tree, then every change to it,
+    /// to clients connecting to SOCKET (host:port, or a path for a Unix
+    /// socket) as newline-delimited JSON
+    #[arg(long = "serve", value_name = "SOCKET", value_parser = parse_endpoint)]
+    pub serve: Option<Endpoint>,
+
+    /// Show the tree a `livetree --serve` sends on SOCKET instead of
+    /// watching a directory; file operations and views that read files
+    /// are unavailable
+    #[arg(
+        long = "connect",
+        value_name = "SOCKET",
+        value_parser = parse_endpoint,
+        conflicts_with_all = ["path", "remote", "docker", "serve", "report", "export", "exit_on_change", "stream", "lazy"]
+    )]
+    pub connect: Option<Endpoint>,
+
+    /// Accept commands on the Unix socket SOCKET (a path; only you may
+    /// connect) that drive this livetree: action names such as
+    /// `toggle-hidden`, `scroll N`, `set-depth N`, and palette commands such
+    /// as `export FILE` and `quit`, one per line
+    #[arg(long = "control", value_name = "SOCKET", value_parser = parse_control_socket)]
+    pub control: Option<Endpoint>,
+
+    /// Watch DIR too and show it beside the tree in its own pane, with its
+    /// own selection and scrolling; `Tab` moves the focus between the panes
+    #[arg(
+        long = "split",
+        value_name = "DIR",
+        conflicts_with_all = ["remote", "docker", "connect", "serve", "stream", "export", "exit_on_change"]
+    )]
+    pub split: Option<PathBuf>,
+
+    /// Start --split in compare mode (toggle with `=`): mark entries found
+    /// in only one pane, of another kind, or with another size or
+    /// modification time, and directories holding such differences
+    #[arg(long = "compare", requires = "split")]
+    pub compare: bool,
+
+    /// Restore the workspace saved in FILE (the watched directory, --split
+    /// pane, compare mode, and tree options), replacing those given on the
+    /// command line, and save it there again on exit
+    #[arg(
+        long = "session",
+        value_name = "FILE",
+        conflicts_with_all = ["remote", "docker", "connect", "serve", "stream", "export", "exit_on_change"]
+    )]
+    pub session: Option<PathBuf>,
+
+    /// How often --remote and --docker list the tree again (e.g. 5s, 1m)
+    #[arg(long = "poll-interval", value_name = "DURATION", default_value = "2s", value_parser = parse_duration)]
+    pub poll_interval: Duration,
+
+    /// Configuration file (default: $XDG_CONFIG_HOME/livetree/config.toml)
+    #[arg(long = "config", value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Append diagnostics to FILE instead of dropping them: watcher errors,
+    /// and with -v/-vv walk, render, and watcher timings (filter with
+    /// LIVETREE_LOG, e.g. "livetree=debug")
+    #[arg(long = "log-file", value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// Run with built-in defaults only, for diagnosing crashes or odd
+    /// rendering: no config file, mouse capture, window title, palette
+    /// history, saved UI state, external programs, or extra columns and
+    /// lines
+    #[arg(long = "safe-mode")]
+    pub safe_mode: bool,
+}
+
+/// Subcommands that run instead of the watcher.
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Check a config file and report every problem with its line number
+    CheckConfig {
+        /// Config file to check (default: the file livetree would load)
+        file: Option<PathBuf>,
+    },
+    /// Write a commented config file with every setting and its default
+    InitConfig {
+        /// Where to write it (default: $XDG_CONFIG_HOME/livetree/config.toml)
+        file: Option<PathBuf>,
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 impl Args {
@@ -68,6 +324,14 @@ impl Args {
         if self.debounce_ms < 50 {
             self.debounce_ms = 50;
         }
+        // --iglob patterns are -I patterns with the case-insensitive prefix.
+        let iglob = std::mem::take(&mut self.iglob);
+        self.ignore
+            .extend(iglob.into_iter().map(|p| format!("{CASE_INSENSITIVE}{p}")));
+        // A container is a remote tree reached another way.
+        if let Some(docker) = self.docker.take() {
+            self.remote = Some(docker);
+        }
         // Respect NO_COLOR env var
         if std::env::var("NO_COLOR").is_ok() {
             self.no_color = true;
@@ -80,6 +344,90 @@ impl Args {
         if self.quiet {
             self.verbose = 0;
         }
+        // Safe mode drops everything beyond the plain tree; the config file
+        // is skipped when it is loaded.
+        if self.safe_mode {
+            self.no_title = true;
+            self.summary = false;
+            self.heatmap = false;
+            self.permissions = false;
+            self.owner = false;
+            self.line_numbers = false;
+            self.relative_line_numbers = false;
+            self.flat = false;
+            self.columns = false;
+            self.no_sticky_headers = true;
+            self.status_format = None;
+            self.announce = None;
+            self.control = None;
+        }
         self
     }
 }
+
+fn parse_status_template(s: &str) -> Result<StatusTemplate, String> {
+    s.parse()
+}
+
+fn parse_announce_position(s: &str) -> Result<AnnouncePosition, String> {
+    s.parse()
+}
+
+fn parse_ambiguous_width(s: &str) -> Result<AmbiguousWidth, String> {
+    s.parse()
+}
+
+fn parse_export_format(s: &str) -> Result<Format, String> {
+    s.parse()
+}
+
+fn parse_stream_format(s: &str) -> Result<StreamFormat, String> {
+    s.parse()
+}
+
+fn parse_remote(s: &str) -> Result<RemoteSpec, String> {
+    s.parse()
+}
+
+fn parse_endpoint(s: &str) -> Result<Endpoint, String> {
+    s.parse()
+}
+
+/// Parse a `--control` socket, which must be a Unix socket path: its
+/// commands can write files, so it is not offered over TCP.
+fn parse_control_socket(s: &str) -> Result<Endpoint, String> {
+    match s.parse()? {
+        Endpoint::Tcp(_) => Err(format!(
+            "'{s}' is a TCP address; the control socket must be a Unix socket path"
+        )),
+        unix => Ok(unix),
+    }
+}
+
+/// Parse a duration such as `500ms`, `90s`, `45m`, `12h`, `30d`, or `2w`.
+/// A bare number is taken as seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid duration '{s}' (expected e.g. 30d, 12h, 45m)"))?;
+    let scale_ms = match unit {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 7 * 86_400_000,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in '{s}' (use ms, s, m, h, d, or w)"
+            ))
+        }
+    };
+    value
+        .checked_mul(scale_ms)
+        .map(Duration::from_millis)
+        .ok_or_else(|| format!("duration '{s}' is too large"))
+}
@@ -0,0 +1,213 @@
+//! Short-window correlation of watcher changes, so an editor's
+//! "write temp file, rename over target" save shows up as a single
+//! modification of the target instead of a temp file flashing by.
+
+use crate::watcher::{ChangeKind, PathChange};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long a freshly created path is remembered as a possible temp file.
+const CORRELATION_WINDOW: Duration = Duration::from_secs(2);
+
+/// Result of coalescing one batch of changes.
+#[derive(Debug, Default, PartialEq)]
+pub struct Coalesced {
+    /// Changes that should be highlighted.
+    pub changes: Vec<PathChange>,
+    /// Short-lived paths that vanished again; any highlight they got from an
+    /// earlier batch should be cleared.
+    pub vanished: Vec<PathBuf>,
+}
+
+/// Remembers recently created paths across debounced batches.
+#[derive(Debug, Default)]
+pub struct RenameCoalescer {
+    recent_creates: HashMap<PathBuf, Instant>,
+}
+
+impl RenameCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coalesce a batch of changes observed at `now`.
+    ///
+    /// A path created within the correlation window that no longer exists is
+    /// treated as a temp file and dropped. The rename destination it was
+    /// moved to, if the batch has one, is reported as `Modified` rather than
+    /// `Renamed`, since it was saved over rather than moved; other renames
+    /// in the batch are left alone.
+    pub fn process(&mut self, changes: Vec<PathChange>, now: Instant) -> Coalesced {
+        self.recent_creates
+            .retain(|_, created| now.duration_since(*created) < CORRELATION_WINDOW);
+        for change in &changes {
+            if change.kind == ChangeKind::Created {
+                self.recent_creates.insert(change.path.clone(), now);
+            }
+        }
+
+        let mut out = Coalesced::default();
+        for change in changes {
+            let transient = self.recent_creates.contains_key(&change.path) && !change.path.exists();
+            if transient {
+                self.recent_creates.remove(&change.path);
+                out.vanished.push(change.path);
+            } else {
+                out.changes.push(change);
+            }
+        }
+
+        // Renamed paths that still exist are destinations; those that are
+        // gone (and were not temp files) are the sources of other renames.
+        let (mut destinations, sources): (Vec<usize>, Vec<usize>) = (0..out.changes.len())
+            .filter(|&i| out.changes[i].kind == ChangeKind::Renamed)
+            .partition(|&i| out.changes[i].path.exists());
+        for temp in &out.vanished {
+            let beside = |i: &usize| out.changes[*i].path.parent() == temp.parent();
+            let candidates: Vec<usize> = destinations.iter().copied().filter(beside).collect();
+            let target = candidates
+                .iter()
+                .copied()
+                .find(|&i| saved_over(temp, &out.changes[i].path))
+                .or_else(|| {
+                    let alone = candidates.len() == 1 && !sources.iter().any(beside);
+                    alone.then(|| candidates[0])
+                });
+            if let Some(target) = target {
+                destinations.retain(|&i| i != target);
+                out.changes[target].kind = ChangeKind::Modified;
+            }
+        }
+        out
+    }
+}
+
+/// Whether `temp` is named after `target`, as editors name the file they
+/// save to before renaming it into place (`.main.rs.swp`, `main.rs~`,
+/// `main.tmp` for `main.rs`).
+fn saved_over(temp: &Path, target: &Path) -> bool {
+    let (Some(temp), Some(target)) = (temp.file_name(), target.file_name()) else {
+        return false;
+    };
+    let (temp, target) = (temp.to_string_lossy(), target.to_string_lossy());
+    let stem = |name: &str| {
+        name.split('.')
+            .find(|part| !part.is_empty())
+            .map(str::to_owned)
+    };
+    temp.contains(target.as_ref()) || stem(&temp) == stem(&target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn change(path: PathBuf, kind: ChangeKind) -> PathChange {
+        PathChange { path, kind }
+    }
+
+    #[test]
+    fn temp_write_then_rename_collapses_to_modified_target() {
+        let tmp = TempDir::new().unwrap();
+        let temp = tmp.path().join(".main.rs.swp");
+        let target = tmp.path().join("main.rs");
+        fs::write(&target, "new").unwrap();
+
+        let mut coalescer = RenameCoalescer::new();
+        let now = Instant::now();
+        // Within one batch the watcher reports the temp file as created.
+        let out = coalescer.process(
+            vec![
+                change(temp.clone(), ChangeKind::Created),
+                change(target.clone(), ChangeKind::Renamed),
+            ],
+            now,
+        );
+
+        assert_eq!(out.changes, vec![change(target, ChangeKind::Modified)]);
+        assert_eq!(out.vanished, vec![temp]);
+    }
+
+    #[test]
+    fn temp_created_in_earlier_batch_is_cleared() {
+        let tmp = TempDir::new().unwrap();
+        let temp = tmp.path().join("file.tmp");
+        let target = tmp.path().join("file.txt");
+
+        let mut coalescer = RenameCoalescer::new();
+        let t0 = Instant::now();
+        fs::write(&temp, "data").unwrap();
+        let first = coalescer.process(vec![change(temp.clone(), ChangeKind::Created)], t0);
+        assert_eq!(first.changes.len(), 1, "temp file is still present");
+
+        fs::rename(&temp, &target).unwrap();
+        let second = coalescer.process(
+            vec![
+                change(temp.clone(), ChangeKind::Renamed),
+                change(target.clone(), ChangeKind::Renamed),
+            ],
+            t0 + Duration::from_millis(500),
+        );
+        assert_eq!(second.vanished, vec![temp]);
+        assert_eq!(second.changes, vec![change(target, ChangeKind::Modified)]);
+    }
+
+    #[test]
+    fn only_the_rename_over_the_target_becomes_modified() {
+        let tmp = TempDir::new().unwrap();
+        let temp = tmp.path().join(".main.rs.swp");
+        let target = tmp.path().join("main.rs");
+        let old = tmp.path().join("old.txt");
+        let new = tmp.path().join("new.txt");
+        fs::write(&target, "new").unwrap();
+        fs::write(&new, "").unwrap();
+
+        let mut coalescer = RenameCoalescer::new();
+        let out = coalescer.process(
+            vec![
+                change(old.clone(), ChangeKind::Renamed),
+                change(temp.clone(), ChangeKind::Created),
+                change(new.clone(), ChangeKind::Renamed),
+                change(target.clone(), ChangeKind::Renamed),
+            ],
+            Instant::now(),
+        );
+        assert_eq!(out.vanished, vec![temp]);
+        assert_eq!(
+            out.changes,
+            vec![
+                change(old, ChangeKind::Renamed),
+                change(new, ChangeKind::Renamed),
+                change(target, ChangeKind::Modified)
+            ]
+        );
+    }
+
+    #[test]
+    fn ordinary_rename_is_left_alone() {
+        let tmp = TempDir::new().unwrap();
+        let old = tmp.path().join("old.txt");
+        let new = tmp.path().join("new.txt");
+        fs::write(&new, "").unwrap();
+
+        let mut coalescer = RenameCoalescer::new();
+        let out = coalescer.process(
+            vec![
+                change(old.clone(), ChangeKind::Renamed),
+                change(new.clone(), ChangeKind::Renamed),
+            ],
+            Instant::now(),
+        );
+        assert!(out.vanished.is_empty());
+        assert_eq!(
+            out.changes,
+            vec![
+                change(old, ChangeKind::Renamed),
+                change(new, ChangeKind::Renamed)
+            ]
+        );
+    }
+}
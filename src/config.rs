@@ -0,0 +1,292 @@
+//! User configuration loaded from `config.toml` in the XDG config directory
+//! (`$XDG_CONFIG_HOME/livetree/config.toml`, falling back to
+//! `~/.config/livetree/config.toml`).
+//!
+//! A missing file yields the defaults; a malformed one is an error.
+
+use crate::cli::parse_duration;
+use crate::paths;
+use crate::status::StatusConfig;
+use crate::watcher::ChangeKind;
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// What a mouse click on a tree entry does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClickAction {
+    /// Do nothing.
+    None,
+    /// Move the selection to the clicked entry.
+    Select,
+    /// Collapse or expand a directory, or open or close an archive (no-op
+    /// for other files).
+    Expand,
+    /// Open the entry in `$VISUAL` / `$EDITOR`.
+    Open,
+    /// Copy the entry's absolute path to the clipboard (OSC 52).
+    CopyPath,
+    /// Show the entry in the OS file manager.
+    Reveal,
+}
+
+/// Actions for single, double and middle clicks on one kind of entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickBindings {
+    pub single: ClickAction,
+    pub double: ClickAction,
+    pub middle: ClickAction,
+}
+
+/// The `[mouse]` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "RawMouseConfig")]
+pub struct MouseConfig {
+    /// Capture mouse events. When off, the terminal's own text selection works as usual.
+    pub enabled: bool,
+    /// Click bindings for files (and symlinks to files).
+    pub file: ClickBindings,
+    /// Click bindings for directories.
+    pub dir: ClickBindings,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file: ClickBindings {
+                single: ClickAction::Select,
+                double: ClickAction::Open,
+                middle: ClickAction::CopyPath,
+            },
+            dir: ClickBindings {
+                single: ClickAction::Select,
+                double: ClickAction::Expand,
+                middle: ClickAction::CopyPath,
+            },
+        }
+    }
+}
+
+/// `[mouse.file]` / `[mouse.dir]` as written; missing keys keep the
+/// per-kind defaults from `MouseConfig::default`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawClickBindings {
+    single: Option<ClickAction>,
+    double: Option<ClickAction>,
+    middle: Option<ClickAction>,
+}
+
+impl RawClickBindings {
+    fn over(self, defaults: ClickBindings) -> ClickBindings {
+        ClickBindings {
+            single: self.single.unwrap_or(defaults.single),
+            double: self.double.unwrap_or(defaults.double),
+            middle: self.middle.unwrap_or(defaults.middle),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawMouseConfig {
+    enabled: Option<bool>,
+    file: RawClickBindings,
+    dir: RawClickBindings,
+}
+
+impl From<RawMouseConfig> for MouseConfig {
+    fn from(raw: RawMouseConfig) -> Self {
+        let defaults = MouseConfig::default();
+        Self {
+            enabled: raw.enabled.unwrap_or(defaults.enabled),
+            file: raw.file.over(defaults.file),
+            dir: raw.dir.over(defaults.dir),
+        }
+    }
+}
+
+impl MouseConfig {
+    /// Bindings that apply to an entry.
+    pub fn bindings(&self, is_dir: bool) -> &ClickBindings {
+        if is_dir {
+            &self.dir
+        } else {
+            &self.file
+        }
+    }
+}
+
+/// Color and lifetime overrides for one kind of change highlight. Unset
+/// fields keep the built-in style and the global highlight duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawKindStyle")]
+pub struct KindStyle {
+    pub color: Option<Color>,
+    pub duration: Option<Duration>,
+}
+
+/// `[highlight.<kind>]` as written: a color name (`green`, `lightred`,
+/// `#40e0d0`, or a 0–255 index) and a duration (`5s`, `1500ms`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawKindStyle {
+    color: Option<String>,
+    duration: Option<String>,
+}
+
+impl TryFrom<RawKindStyle> for KindStyle {
+    type Error = String;
+
+    fn try_from(raw: RawKindStyle) -> Result<Self, String> {
+        let color = raw
+            .color
+            .map(|c| Color::from_str(&c).map_err(|_| format!("invalid color '{c}'")))
+            .transpose()?;
+        let duration = raw.duration.as_deref().map(parse_duration).transpose()?;
+        Ok(Self { color, duration })
+    }
+}
+
+/// The `[highlight]` section: per-kind overrides for change highlights.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightConfig {
+    pub created: KindStyle,
+    pub modified: KindStyle,
+    pub deleted: KindStyle,
+}
+
+impl HighlightConfig {
+    /// The overrides for a watcher change; metadata changes and renames
+    /// count as modifications.
+    pub fn for_change(&self, kind: ChangeKind) -> &KindStyle {
+        match kind {
+            ChangeKind::Created => &self.created,
+            ChangeKind::Removed => &self.deleted,
+            ChangeKind::Modified | ChangeKind::Metadata | ChangeKind::Renamed => &self.modified,
+        }
+    }
+}
+
+/// Top-level configuration file contents.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub mouse: MouseConfig,
+    pub highlight: HighlightConfig,
+    pub status: StatusConfig,
+}
+
+impl Config {
+    /// Parse configuration from TOML text.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Load the configuration from `path`, returning defaults if it does not exist.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// A commented config file listing every setting with its default, written
+/// by `livetree init-config`.
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// Write `DEFAULT_CONFIG` to `path`, creating parent directories. An
+/// existing file is only replaced if `force` is set.
+pub fn write_default(path: &Path, force: bool) -> Result<(), String> {
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists (pass --force to overwrite it)",
+            path.display()
+        ));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, DEFAULT_CONFIG).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Default location of the configuration file, if a home directory is known.
+pub fn default_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| paths::home_dir().map(|h| h.join(".config")))?;
+    Some(base.join("livetree").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_gives_defaults() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn partial_mouse_section_overrides_only_given_keys() {
+        let config =
+            Config::parse("[mouse.dir]\ndouble = \"reveal\"\n\n[mouse.file]\nmiddle = \"open\"\n")
+                .unwrap();
+        assert_eq!(config.mouse.dir.double, ClickAction::Reveal);
+        assert_eq!(config.mouse.dir.single, ClickAction::Select);
+        assert_eq!(config.mouse.dir.middle, ClickAction::CopyPath);
+        assert_eq!(config.mouse.file.middle, ClickAction::Open);
+        assert!(config.mouse.enabled);
+    }
+
+    #[test]
+    fn unknown_action_is_an_error() {
+        let err = Config::parse("[mouse.file]\nsingle = \"explode\"\n").unwrap_err();
+        assert!(err.contains("explode"), "{err}");
+    }
+
+    #[test]
+    fn highlight_overrides_per_kind() {
+        let config = Config::parse(
+            "[highlight.created]\ncolor = \"green\"\nduration = \"5s\"\n\n\
+             [highlight.deleted]\ncolor = \"#ff0000\"\n",
+        )
+        .unwrap();
+        let created = config.highlight.for_change(ChangeKind::Created);
+        assert_eq!(created.color, Some(Color::Green));
+        assert_eq!(created.duration, Some(Duration::from_secs(5)));
+        let deleted = config.highlight.for_change(ChangeKind::Removed);
+        assert_eq!(deleted.color, Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(deleted.duration, None);
+        assert_eq!(
+            *config.highlight.for_change(ChangeKind::Metadata),
+            KindStyle::default()
+        );
+    }
+
+    #[test]
+    fn invalid_highlight_color_is_an_error() {
+        let err = Config::parse("[highlight.modified]\ncolor = \"blurple\"\n").unwrap_err();
+        assert!(err.contains("blurple"), "{err}");
+    }
+
+    #[test]
+    fn generated_config_is_valid_and_matches_defaults() {
+        assert_eq!(crate::schema::check(DEFAULT_CONFIG), Vec::new());
+        assert_eq!(Config::parse(DEFAULT_CONFIG).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let config = Config::load(Path::new("/nonexistent_path_xyz/config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+}
@@ -0,0 +1,208 @@
+//! The control socket (`--control`): a Unix socket on which scripts and
+//! editor plugins drive a running livetree, one command per line. Only its
+//! owner may connect, since commands can write files; TCP is refused.
+//!
+//! A command is a palette command (`export tree.json`, `set hidden on`,
+//! `goto src`, `quit`), one of the actions that take a number (`select 12`,
+//! `scroll -3`, `set-depth 2` or `set-depth none`), or any other action by
+//! its name in key bindings (`toggle-hidden`, `page-down`).
+//! Every command is answered with a line reading `ok` or `error: <why>`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::thread;
+
+use crossbeam_channel::{Receiver, Sender};
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+
+use crate::action::Action;
+use crate::palette;
+use crate::serve::{Endpoint, Listener};
+
+/// A parsed control command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// An action, applied as if its key had been pressed.
+    Action(Action),
+    /// Palette input, run as if it had been typed after `:`.
+    Palette(String),
+}
+
+impl ControlCommand {
+    /// Parse one line of input.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (word, arg) = match line.split_once(char::is_whitespace) {
+            Some((word, arg)) => (word, Some(arg.trim())),
+            None => (line, None),
+        };
+        let action = match word {
+            "" => return Err("empty command".to_string()),
+            "select" => Action::Select(number(word, arg)?),
+            "scroll" => Action::Scroll(number(word, arg)?),
+            "set-depth" => match arg {
+                Some("none") => Action::SetDepth(None),
+                arg => Action::SetDepth(Some(number(word, arg)?)),
+            },
+            _ if palette::COMMANDS.iter().any(|(name, _)| *name == word) => {
+                palette::parse(line)?;
+                return Ok(ControlCommand::Palette(line.to_string()));
+            }
+            _ => match (named_action(word), arg) {
+                (Some(action), None) => action,
+                (Some(_), Some(_)) => return Err(format!("{word} takes no argument")),
+                (None, _) => return Err(format!("unknown command '{word}'")),
+            },
+        };
+        Ok(ControlCommand::Action(action))
+    }
+}
+
+/// The number `word` takes.
+fn number<T: std::str::FromStr>(word: &str, arg: Option<&str>) -> Result<T, String> {
+    let arg = arg.ok_or_else(|| format!("{word} needs a number"))?;
+    arg.parse()
+        .map_err(|_| format!("{word}: '{arg}' is not a number"))
+}
+
+/// The action named `name` in kebab case, if it takes no argument.
+fn named_action(name: &str) -> Option<Action> {
+    let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> =
+        name.into_deserializer();
+    Action::deserialize(deserializer).ok()
+}
+
+/// A command from a client, with where to send the answer.
+#[derive(Debug)]
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: Sender<Result<(), String>>,
+}
+
+/// A bound control socket. Its socket file is removed when it is dropped.
+pub struct ControlServer {
+    _listener: Listener,
+}
+
+/// Listen for control clients on the Unix socket `endpoint`, which only
+/// its owner may connect to. Each command received is passed on as a
+/// `ControlRequest`; the client gets the answer sent back on its `reply`
+/// channel.
+pub fn listen(endpoint: &Endpoint) -> Result<(ControlServer, Receiver<ControlRequest>), String> {
+    if let Endpoint::Tcp(_) = endpoint {
+        return Err(format!(
+            "cannot listen on {endpoint}: the control socket must be a Unix socket path"
+        ));
+    }
+    let listener = Listener::bind(endpoint)?;
+    listener
+        .restrict_to_owner()
+        .map_err(|e| format!("cannot restrict access to {endpoint}: {e}"))?;
+    let accepting = listener
+        .try_clone()
+        .map_err(|e| format!("cannot listen on {endpoint}: {e}"))?;
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || loop {
+        match accepting.accept() {
+            Ok(client) => {
+                let tx = tx.clone();
+                thread::spawn(move || serve_client(client, &tx));
+            }
+            Err(e) => tracing::warn!(error = %e, "cannot accept control client"),
+        }
+    });
+    Ok((
+        ControlServer {
+            _listener: listener,
+        },
+        rx,
+    ))
+}
+
+/// Answer `client`'s commands until it disconnects or livetree exits.
+fn serve_client(client: Box<dyn crate::serve::Stream>, requests: &Sender<ControlRequest>) {
+    let mut client = BufReader::new(client);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match client.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) if line.trim().is_empty() => continue,
+            Ok(_) => {}
+        }
+        let answer = match ControlCommand::parse(&line) {
+            Ok(command) => {
+                let (reply, answer) = crossbeam_channel::bounded(1);
+                if requests.send(ControlRequest { command, reply }).is_err() {
+                    return;
+                }
+                answer
+                    .recv()
+                    .unwrap_or_else(|_| Err("livetree is exiting".to_string()))
+            }
+            Err(e) => Err(e),
+        };
+        let answer = match answer {
+            Ok(()) => "ok\n".to_string(),
+            Err(e) => format!("error: {e}\n"),
+        };
+        if client.get_mut().write_all(answer.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_map_onto_actions_or_the_palette() {
+        let parse = |line| ControlCommand::parse(line);
+        assert_eq!(
+            parse("toggle-hidden"),
+            Ok(ControlCommand::Action(Action::ToggleHidden))
+        );
+        assert_eq!(
+            parse(" scroll -3\n"),
+            Ok(ControlCommand::Action(Action::Scroll(-3)))
+        );
+        assert_eq!(
+            parse("set-depth 2"),
+            Ok(ControlCommand::Action(Action::SetDepth(Some(2))))
+        );
+        assert_eq!(
+            parse("set-depth none"),
+            Ok(ControlCommand::Action(Action::SetDepth(None)))
+        );
+        assert_eq!(
+            parse("page-down"),
+            Ok(ControlCommand::Action(Action::PageDown))
+        );
+        assert_eq!(
+            parse("export out.json"),
+            Ok(ControlCommand::Palette("export out.json".to_string()))
+        );
+        assert_eq!(
+            parse("scroll down"),
+            Err("scroll: 'down' is not a number".to_string())
+        );
+        assert_eq!(
+            parse("toggle-hidden now"),
+            Err("toggle-hidden takes no argument".to_string())
+        );
+        assert_eq!(
+            parse("src/main.rs"),
+            Err("unknown command 'src/main.rs'".to_string())
+        );
+        assert!(parse("set bogus on").is_err());
+        assert!(parse("export").is_err());
+    }
+
+    #[test]
+    fn tcp_addresses_are_refused() {
+        let endpoint = Endpoint::Tcp("127.0.0.1:0".to_string());
+        let error = listen(&endpoint).err().unwrap();
+        assert!(error.contains("must be a Unix socket path"), "{error}");
+    }
+}
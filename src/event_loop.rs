@@ -1,21 +1,489 @@
 //! Main event loop: multiplexes filesystem events and keyboard input,
 //! rendering via ratatui's immediate-mode draw loop.
 
-use crate::highlight::HighlightTracker;
-use crate::render::{help_bar_line, status_bar_line, tree_to_lines, truncation_line, RenderConfig};
-use crate::terminal::Term;
-use crate::tree::{TreeBuilder, TreeConfig, TreeSnapshot, WalkdirTreeBuilder};
-use crate::watcher::WatchEvent;
-use crossbeam_channel::{select, Receiver};
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
-use ratatui::layout::{Constraint, Layout};
-use ratatui::text::Line;
-use ratatui::widgets::Paragraph;
-use std::path::Path;
+use crate::action::{Action, Keymap, PendingCount};
+use crate::coalesce::{Coalesced, RenameCoalescer};
+use crate::config::{ClickAction, HighlightConfig, MouseConfig};
+use crate::control::{ControlCommand, ControlRequest};
+use crate::export;
+use crate::external;
+use crate::frame::{Fingerprint, FrameScheduler, Trigger, DEFAULT_MAX_FPS};
+use crate::goto::{self, Target};
+use crate::highlight::{
+    inherit_to_ancestors, ActiveHighlight, Channel, HighlightKind, HighlightLayers, Highlights,
+};
+use crate::ops;
+use crate::owners::OwnerNames;
+use crate::palette::{self, Command as PaletteCommand, History, Palette, Setting};
+use crate::paths;
+use crate::render::{
+    announcement, announcement_line, case_collision_span, collapsed_span, compare_legend_line,
+    compare_span, delete_prompt_line, dir_preview_lines, file_preview_lines, gutter_span,
+    hard_link_span, heavy_report_lines, help_bar_line, mark_channels, mark_heat, mark_margin_span,
+    mark_stale, owner_span, palette_line, permissions_span, root_deleted_line, scanning_line,
+    stats_lines, status_bar, summary_span, toast_line, top_changers_lines, tree_to_lines,
+    truncation_line, Listing, RenderConfig, PERMISSIONS_WIDTH,
+};
+use crate::report::DEFAULT_HEAVY_TOP;
+use crate::search::Search;
+use crate::split::SplitPane;
+use crate::stats::{Activity, Stats, DEFAULT_CHANGERS_WINDOW};
+use crate::status::{StatusTemplate, StatusValues};
+use crate::terminal::{self, Shutdown, StopSignal, Term};
+use crate::toast::Toasts;
+use crate::tree::{
+    archive_subtree, build_ignore_set, build_ignore_set_no_defaults, case_collisions, compare,
+    hard_links, is_archive, is_stale, list_archive, read_ignore_file, spawn_dir_details,
+    spawn_heaviest, spawn_summarize, CompareSide, Comparison, DirDetails, HeavyReport, PathIndex,
+    SummaryMap, TreeBuilder, TreeConfig, TreeEntry, TreeSnapshot, WalkProgress, WalkdirTreeBuilder,
+};
+use crate::ui_state::UiState;
+use crate::watcher::{self, ChangeKind, SupervisedWatcher, WatchEvent, WatcherOptions};
+use crate::workspace::Workspace;
+use crossbeam_channel::{select, Receiver, Sender};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+use ratatui::backend::Backend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Paragraph};
+use ratatui::Terminal;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Style applied to the last snapshot after the root is deleted.
+const DELETED_STYLE: Style = Style::new().fg(Color::DarkGray).add_modifier(Modifier::DIM);
+
+/// Style applied on top of the selected tree line.
+const SELECTED_STYLE: Style = Style::new().add_modifier(Modifier::REVERSED);
+
+/// Style applied on top of pinned directory headers.
+const STICKY_STYLE: Style = Style::new().add_modifier(Modifier::BOLD);
+
+/// Blank columns between the columns of a packed tree.
+const COLUMN_GAP: usize = 2;
+
+/// Two left clicks on the same entry within this window form a double click.
+const DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(400);
+
+/// Longest the loop waits for events before checking for interrupts.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Entries moved per mouse wheel notch.
+const WHEEL_STEP: isize = 3;
+
+/// Lowest entry limit `[` goes down to.
+const MIN_MAX_ENTRIES: usize = 10;
+
+/// Directories listed in the top-changers panel.
+const TOP_CHANGERS: usize = 20;
+
+/// How long the first scan runs before the top of the tree walked so far
+/// and a progress line replace the blank screen, and how often they are
+/// redrawn.
+const SCAN_INDICATOR_DELAY: Duration = Duration::from_millis(100);
+const SCAN_INDICATOR_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Behavioural options for the event loop.
+#[derive(Debug, Clone)]
+pub struct LoopConfig {
+    /// Start with the per-directory composition summary column visible.
+    pub show_summary: bool,
+    /// Start with entries tinted by recent change frequency.
+    pub show_heatmap: bool,
+    /// How far back the top-changers panel counts changes.
+    pub changers_window: Duration,
+    /// Mark files that are hard links to an earlier entry (Unix only).
+    pub hard_links: bool,
+    /// Show a permissions column; the tree must be built with
+    /// `TreeConfig::stat` for it to be filled in.
+    pub permissions: bool,
+    /// Show an owner (user and group) column; also needs `TreeConfig::stat`.
+    pub owner: bool,
+    /// Start with entry numbers shown in a gutter.
+    pub line_numbers: bool,
+    /// Start with the gutter counting rows away from the selection, as
+    /// vim's `relativenumber` does; the selection keeps its entry number
+    /// if `line_numbers` is set too.
+    pub relative_line_numbers: bool,
+    /// Pin the directories enclosing the top row while scrolling.
+    pub sticky_headers: bool,
+    /// Pack a tree that fits across the pane into columns, like `ls -C`.
+    pub columns: bool,
+    /// Mouse capture and per-entry click actions.
+    pub mouse: MouseConfig,
+    /// Key bindings.
+    pub keymap: Keymap,
+    /// Age after which files are marked stale when the stale filter is on.
+    pub stale_after: Duration,
+    /// Per-kind colors and durations for change highlights.
+    pub highlight: HighlightConfig,
+    /// Layout of the status bar.
+    pub status_template: StatusTemplate,
+    /// The `-I` patterns the tree config's ignore set was built from; the
+    /// palette's `ignore`/`unignore` commands rebuild the set from these.
+    pub ignore_patterns: Vec<String>,
+    /// File with more ignore patterns (already part of the tree config's
+    /// set), re-read when it changes.
+    pub ignore_file: Option<PathBuf>,
+    /// Whether the tree config's ignore set includes the default ignores
+    /// (`.git`, `node_modules`, ...); kept when the set is rebuilt.
+    pub default_ignores: bool,
+    /// Where palette history is saved (`None` keeps it in memory).
+    pub history_file: Option<PathBuf>,
+    /// Show a line describing the latest change in words, above the tree
+    /// or below the help bar.
+    pub announce: Option<AnnouncePosition>,
+    /// Allow launching the editor, the file manager, default applications,
+    /// and clipboard copies.
+    pub external_programs: bool,
+    /// Walk only this many levels up front; deeper directories are walked
+    /// when they are expanded.
+    pub lazy_depth: Option<usize>,
+    /// Where UI state (toggles, collapsed directories, selection) is
+    /// restored from at startup and saved to on exit (`None` starts fresh
+    /// and saves nothing).
+    pub state_file: Option<PathBuf>,
+    /// Session file (`--session`) the workspace is saved to on exit.
+    pub session_file: Option<PathBuf>,
+    /// Move deleted entries to the trash instead of deleting them for good.
+    pub trash: bool,
+    /// The tree's paths are not on this machine (a remote tree): refuse
+    /// file operations and the views that read files (preview, summary,
+    /// stale files, heavy report, archive contents).
+    pub read_only: bool,
+    /// File to select at startup, mark, and select again whenever it
+    /// changes (a file passed instead of a directory).
+    pub focus: Option<PathBuf>,
+    /// Commands from the control socket (`--control`).
+    pub control: Option<Receiver<ControlRequest>>,
+    /// The directory shown beside the tree in split view (`--split`), and
+    /// the events from its watcher.
+    pub split: Option<(PathBuf, Receiver<WatchEvent>)>,
+    /// Start split view in compare mode, marking entries that differ
+    /// between the panes.
+    pub compare: bool,
+    /// Most frames drawn per second for filesystem changes and background
+    /// results; changes arriving faster are coalesced into the next frame.
+    /// Input is always drawn at once. 0 = uncapped.
+    pub max_fps: u32,
+    /// How to watch a directory above the watched root when the tree's
+    /// root is moved up to it (`None` keeps the events from `fs_rx`).
+    /// `run_headless` never starts watchers.
+    pub watcher_options: Option<WatcherOptions>,
+}
+
+/// Where the announcement line goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnouncePosition {
+    Top,
+    Bottom,
+}
+
+impl std::str::FromStr for AnnouncePosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "top" => Ok(AnnouncePosition::Top),
+            "bottom" => Ok(AnnouncePosition::Bottom),
+            other => Err(format!(
+                "invalid announcement position '{other}' (expected top or bottom)"
+            )),
+        }
+    }
+}
+
+impl Default for LoopConfig {
+    fn default() -> Self {
+        Self {
+            show_summary: false,
+            show_heatmap: false,
+            changers_window: DEFAULT_CHANGERS_WINDOW,
+            hard_links: false,
+            permissions: false,
+            owner: false,
+            line_numbers: false,
+            relative_line_numbers: false,
+            sticky_headers: true,
+            columns: false,
+            mouse: MouseConfig::default(),
+            keymap: Keymap::default(),
+            stale_after: Duration::from_secs(30 * 86_400),
+            highlight: HighlightConfig::default(),
+            status_template: StatusTemplate::default(),
+            ignore_patterns: Vec::new(),
+            ignore_file: None,
+            default_ignores: true,
+            history_file: None,
+            announce: None,
+            external_programs: true,
+            lazy_depth: None,
+            state_file: None,
+            session_file: None,
+            trash: true,
+            read_only: false,
+            focus: None,
+            control: None,
+            split: None,
+            compare: false,
+            max_fps: DEFAULT_MAX_FPS,
+            watcher_options: None,
+        }
+    }
+}
+
+/// Whether the loop keeps running after an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Exit(ExitReason),
+}
+
+/// Why the event loop stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The user quit (`q`, `Ctrl+C` key press).
+    Quit,
+    /// The user quit after the watched root was deleted.
+    RootDeleted,
+    /// The process received SIGINT.
+    Interrupted,
+    /// The process received SIGTERM.
+    Terminated,
+    /// The terminal hung up (SIGHUP), e.g. its window was closed.
+    HungUp,
+    /// The watcher channel closed unexpectedly.
+    WatcherStopped,
+}
+
+impl From<StopSignal> for ExitReason {
+    fn from(signal: StopSignal) -> Self {
+        match signal {
+            StopSignal::Interrupt => ExitReason::Interrupted,
+            StopSignal::Terminate => ExitReason::Terminated,
+            StopSignal::Hangup => ExitReason::HungUp,
+        }
+    }
+}
+
+/// Number of changes of each kind seen during a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangeCounts {
+    pub created: usize,
+    pub modified: usize,
+    pub metadata: usize,
+    pub removed: usize,
+    pub renamed: usize,
+}
+
+impl ChangeCounts {
+    /// Count one change.
+    pub fn record(&mut self, kind: ChangeKind) {
+        match kind {
+            ChangeKind::Created => self.created += 1,
+            ChangeKind::Modified => self.modified += 1,
+            ChangeKind::Metadata => self.metadata += 1,
+            ChangeKind::Removed => self.removed += 1,
+            ChangeKind::Renamed => self.renamed += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.created + self.modified + self.metadata + self.removed + self.renamed
+    }
+}
+
+/// What happened during a run, summarized on exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    /// Why the loop stopped.
+    pub reason: ExitReason,
+    /// How long the loop ran.
+    pub duration: Duration,
+    /// Changes seen (after coalescing editor saves).
+    pub changes: ChangeCounts,
+}
+
+impl fmt::Display for Session {
+    /// e.g. `5m 12s, 3 changes (2 created, 1 modified)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.duration.as_secs();
+        match secs {
+            0..60 => write!(f, "{secs}s")?,
+            60..3600 => write!(f, "{}m {}s", secs / 60, secs % 60)?,
+            _ => write!(f, "{}h {}m", secs / 3600, secs % 3600 / 60)?,
+        }
+        let c = &self.changes;
+        let total = c.total();
+        write!(f, ", {total} change{}", if total == 1 { "" } else { "s" })?;
+        let parts: Vec<String> = [
+            (c.created, "created"),
+            (c.modified, "modified"),
+            (c.metadata, "metadata"),
+            (c.removed, "removed"),
+            (c.renamed, "renamed"),
+        ]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, label)| format!("{n} {label}"))
+        .collect();
+        if !parts.is_empty() {
+            write!(f, " ({})", parts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The watched root was deleted: its last snapshot stays on screen, grayed
+/// out, until the user quits.
+struct DeletedRoot {
+    snapshot: TreeSnapshot,
+    /// The user pressed `w` to keep looking instead of exiting.
+    waiting: bool,
+}
+
+/// An `--ignore-file` and what was last read from it.
+struct IgnoreFile {
+    path: PathBuf,
+    /// Modification time and size when it was read, to notice edits.
+    stamp: Option<(SystemTime, u64)>,
+    patterns: Vec<String>,
+}
+
+impl IgnoreFile {
+    fn read(path: PathBuf) -> Self {
+        let stamp = file_stamp(&path);
+        let patterns = read_ignore_file(&path).unwrap_or_default();
+        Self {
+            path,
+            stamp,
+            patterns,
+        }
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Lets the main thread stop the input reader while another program (such
+/// as the user's editor) owns the terminal.
+#[derive(Default)]
+struct InputGate {
+    paused: AtomicBool,
+    /// Held by the reader while it polls, so `pause` can wait out a poll in flight.
+    reading: Mutex<()>,
+}
+
+impl InputGate {
+    /// Stop reading input; reading resumes when the guard is dropped and
+    /// `resume` is called.
+    fn pause(&self) -> MutexGuard<'_, ()> {
+        self.paused.store(true, Ordering::SeqCst);
+        self.reading.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+}
+
+/// One kind of background walk (summaries, the heavy report), kept to a
+/// single thread: asking again while one runs only marks it to be rerun
+/// once it finishes, so a stream of changes cannot pile up walks.
+#[derive(Debug, Default)]
+struct JobSlot {
+    /// Generation of the most recent request; older results are discarded.
+    generation: u64,
+    running: bool,
+    /// Requested again while running.
+    rerun: bool,
+}
+
+impl JobSlot {
+    /// Ask for a fresh result. Returns the generation to start a job with,
+    /// or `None` if one is already running.
+    fn request(&mut self) -> Option<u64> {
+        self.generation += 1;
+        if self.running {
+            self.rerun = true;
+            return None;
+        }
+        self.running = true;
+        Some(self.generation)
+    }
+
+    /// The running job finished with `generation`; returns whether its
+    /// result is still current.
+    fn finish(&mut self, generation: u64) -> bool {
+        self.running = false;
+        generation == self.generation
+    }
+
+    /// Whether a request came in while the finished job ran.
+    fn take_rerun(&mut self) -> bool {
+        std::mem::take(&mut self.rerun)
+    }
+}
+
+/// Senders used by background jobs to report results to the event loop.
+struct JobSenders {
+    summary: Sender<(u64, SummaryMap)>,
+    details: Sender<(u64, PathBuf, DirDetails)>,
+    heavy: Sender<(u64, HeavyReport)>,
+}
+
+/// Receiving ends of `JobSenders`, polled by the main `select!`.
+struct JobReceivers {
+    summary: Receiver<(u64, SummaryMap)>,
+    details: Receiver<(u64, PathBuf, DirDetails)>,
+    heavy: Receiver<(u64, HeavyReport)>,
+}
+
+/// Create the channels connecting background jobs to the event loop.
+fn job_channels() -> (JobSenders, JobReceivers) {
+    let (summary_tx, summary_rx) = crossbeam_channel::unbounded();
+    let (details_tx, details_rx) = crossbeam_channel::unbounded();
+    let (heavy_tx, heavy_rx) = crossbeam_channel::unbounded();
+    (
+        JobSenders {
+            summary: summary_tx,
+            details: details_tx,
+            heavy: heavy_tx,
+        },
+        JobReceivers {
+            summary: summary_rx,
+            details: details_rx,
+            heavy: heavy_rx,
+        },
+    )
+}
+
+/// What tree lines are decorated with, gathered once per frame.
+struct LineDecorations<'f> {
+    config: &'f RenderConfig,
+    overlays: &'f HashMap<PathBuf, Vec<Channel>>,
+    marks: &'f HashSet<PathBuf>,
+    /// Differences from the split pane in compare mode.
+    differences: Option<&'f Comparison>,
+    /// User and group names by entry, with `--owner`.
+    owners: Option<&'f [Option<(String, String)>]>,
+    /// Widest user and group names.
+    owner_widths: (usize, usize),
+    now: Instant,
+}
 
 /// Tracks scrolling state (offset + total lines) for the tree view.
 struct ScrollState {
@@ -43,20 +511,16 @@ impl ScrollState {
         }
     }
 
-    fn scroll_up(&mut self, n: usize) {
-        self.offset = self.offset.saturating_sub(n);
-    }
-
-    fn scroll_down(&mut self, n: usize) {
-        self.offset = self.offset.saturating_add(n);
-    }
-
-    fn scroll_home(&mut self) {
-        self.offset = 0;
-    }
-
-    fn scroll_end(&mut self) {
-        self.offset = usize::MAX;
+    /// Adjust the offset so that line `index` is within the view.
+    fn ensure_visible(&mut self, index: usize, view_height: usize) {
+        if view_height == 0 {
+            return;
+        }
+        if index < self.offset {
+            self.offset = index;
+        } else if index >= self.offset + view_height {
+            self.offset = index + 1 - view_height;
+        }
     }
 
     fn offset(&self) -> usize {
@@ -65,55 +529,1918 @@ impl ScrollState {
 }
 
 /// Holds mutable state for the application's render loop.
-struct AppState<'a> {
-    terminal: Term,
+struct AppState<'a, B: Backend> {
+    terminal: Terminal<B>,
     last_change: Option<String>,
-    use_color: bool,
-    path: &'a Path,
-    tree_config: &'a TreeConfig,
+    /// Most recently changed path, named in the status bar.
+    last_changed_path: Option<PathBuf>,
+    /// Where the announcement line is shown, if at all.
+    announce: Option<AnnouncePosition>,
+    /// The latest change in words, for the announcement line.
+    announcement: Option<String>,
+    /// Transient notifications in the tree pane's bottom-right corner.
+    toasts: Toasts,
+    /// Select each changed entry as changes arrive.
+    follow: bool,
+    /// Screen area of the last-changed file name in the status bar.
+    status_file_area: Option<Rect>,
+    /// Layout of the status bar.
+    status_template: StatusTemplate,
+    /// Options for tree lines; the width is filled in per frame.
+    render_config: RenderConfig,
+    /// Root of the tree shown, which zooming changes.
+    path: PathBuf,
+    /// The root given at startup, which the UI state is kept for.
+    start_root: PathBuf,
+    /// Roots zoomed in from, outermost first; zooming out returns to the
+    /// last one.
+    zoomed_from: Vec<PathBuf>,
+    /// Root of the watcher behind the events: the root given at startup,
+    /// or a directory above it that the tree's root was moved up to.
+    watch_root: PathBuf,
+    /// Options for watching a new `watch_root`.
+    watcher_options: Option<WatcherOptions>,
+    /// The watcher on `watch_root` once the root was moved above the one
+    /// given at startup.
+    watcher: Option<SupervisedWatcher>,
+    /// Events of a watcher just started, for the loop to take in place of
+    /// the previous ones.
+    rewatched: Option<Receiver<WatchEvent>>,
+    /// Tree options; owned so they can change at runtime (e.g. hidden files).
+    tree_config: TreeConfig,
     /// Scroll state for the tree view.
     scroll: ScrollState,
-    /// Tracks recently changed paths with per-entry expiration.
-    highlights: HighlightTracker,
+    /// Index of the selected entry in the snapshot (clamped on render).
+    selected: usize,
+    /// Whether the preview pane is shown next to the tree.
+    show_preview: bool,
+    /// Directory details for the preview pane, computed lazily per directory.
+    details_cache: HashMap<PathBuf, DirDetails>,
+    /// Directories whose details are currently being computed.
+    details_pending: HashSet<PathBuf>,
+    /// Incremented whenever pending details go stale; results tagged with
+    /// an older generation are dropped.
+    details_generation: u64,
+    /// Highlight layers: recent changes (expiring), marks, search matches.
+    highlights: HighlightLayers,
+    /// Per-kind colors and durations for change highlights.
+    highlight_styles: HighlightConfig,
+    /// Correlates temp-file + rename saves across watcher batches.
+    coalescer: RenameCoalescer,
     /// Current highlight duration in whole seconds (0 disables highlighting).
     highlight_duration_secs: u64,
+    /// Decides when to redraw; every state change requests a frame here.
+    scheduler: FrameScheduler,
     /// Cached tree snapshot; invalidated on WatchEvent::Changed to avoid rebuild on every key.
     tree_cache: Option<TreeSnapshot>,
+    /// Path → row lookup for `tree_cache`, rebuilt with it.
+    path_index: PathIndex,
     /// Strategy for building the tree (allows swapping/mocking).
     tree_builder: &'a dyn TreeBuilder,
+    /// Whether the composition summary column is shown for directories.
+    show_summary: bool,
+    /// Whether entry numbers are shown in a gutter.
+    line_numbers: bool,
+    /// Whether the gutter shows distances from the selection.
+    relative_line_numbers: bool,
+    /// Whether the directories enclosing the top row are pinned above it.
+    sticky_headers: bool,
+    /// Rows of the pinned directories drawn last frame, outermost first.
+    sticky: Vec<usize>,
+    /// Whether a short tree is packed into columns on a wide pane.
+    columns: bool,
+    /// Entries per column and column widths of the last frame, if it was
+    /// packed into columns.
+    column_layout: Option<(usize, Vec<u16>)>,
+    /// Latest background-computed summaries (`None` until the first result arrives).
+    summaries: Option<SummaryMap>,
+    /// The summary walk in flight, if any.
+    summary_job: JobSlot,
+    /// Whether files older than `stale_after` are marked in the tree.
+    show_stale: bool,
+    stale_after: Duration,
+    /// Stale entries of the current snapshot (`None` until computed).
+    stale: Option<HashSet<PathBuf>>,
+    /// Entries of the current snapshot whose names differ from a sibling
+    /// only in case.
+    case_collisions: HashSet<PathBuf>,
+    /// Whether hard links are looked for (`--hard-links`).
+    show_hard_links: bool,
+    /// Entries of the current snapshot that are hard links to an earlier
+    /// entry, with that entry's path.
+    hard_links: HashMap<PathBuf, PathBuf>,
+    /// Whether the permissions column is shown (`--permissions`).
+    permissions: bool,
+    /// User and group names for the owner column; `Some` with `--owner`.
+    /// Kept across rebuilds so each id is looked up once.
+    owner_names: Option<OwnerNames>,
+    /// Whether the heaviest-paths panel is shown (takes the preview's place).
+    show_heavy: bool,
+    /// Latest heaviest-paths report (`None` while computing).
+    heavy: Option<HeavyReport>,
+    /// The heaviest-paths walk in flight, if any.
+    heavy_job: JobSlot,
+    /// Whether the debug overlay is shown.
+    show_stats: bool,
+    /// Frame, walk, and watcher counters for the debug overlay.
+    stats: Stats,
+    /// Whether entries are tinted by recent change frequency.
+    show_heatmap: bool,
+    /// Recent changes per path, counted even while the heatmap and the
+    /// top-changers panel are hidden.
+    activity: Activity,
+    /// Whether the top-changers panel is shown (takes the preview's place).
+    show_changers: bool,
+    /// Channels on which background jobs report back.
+    jobs: JobSenders,
+    /// Directories whose contents are hidden.
+    collapsed: HashSet<PathBuf>,
+    /// Levels walked below the root and below each directory loaded on
+    /// demand (`None` walks everything up front).
+    lazy_depth: Option<usize>,
+    /// Directories expanded past `lazy_depth`; parents sort first.
+    loaded: BTreeSet<PathBuf>,
+    /// Directories of the current snapshot whose contents were not walked.
+    unloaded: HashSet<PathBuf>,
+    /// Archives opened to show their members below them.
+    open_archives: HashSet<PathBuf>,
+    /// Member lists of open archives and the modification time they were
+    /// read at, so rebuilds only reread archives that changed.
+    archive_listings: HashMap<PathBuf, (Option<SystemTime>, TreeSnapshot)>,
+    /// Set once the watched root has been deleted.
+    deleted: Option<DeletedRoot>,
+    /// When the session started.
+    started: Instant,
+    /// Changes seen so far, for the exit summary.
+    changes: ChangeCounts,
+    /// The last search (`/`), stepped through with `n` and `N`.
+    search: Option<Search>,
+    /// Whether a search replaces the tree with its matches, best first.
+    ranked: bool,
+    /// The command palette, while it is open.
+    palette: Option<Palette>,
+    /// Commands run from the palette.
+    history: History,
+    /// Entry waiting for `y` before it is deleted.
+    pending_delete: Option<PathBuf>,
+    /// Deleting moves entries to the trash (off with `--no-trash`).
+    trash: bool,
+    /// Set once the first tree build has finished.
+    scanned: bool,
+    /// User ignore patterns (`-I` plus palette `ignore` commands).
+    ignore_patterns: Vec<String>,
+    /// Patterns from `--ignore-file`, kept apart from `ignore_patterns` so
+    /// that re-reading the file replaces only its own.
+    ignore_file: Option<IgnoreFile>,
+    /// Rebuilt ignore sets include the default ignores.
+    default_ignores: bool,
+    /// Mouse capture and click bindings.
+    mouse: MouseConfig,
+    /// Key bindings.
+    keymap: Keymap,
+    /// Count typed for the next motion (`25j`).
+    count: PendingCount,
+    /// Screen area of the tree in the last frame, for mapping clicks to entries.
+    tree_area: Rect,
+    /// The second tree of split view.
+    split: Option<SplitPane>,
+    /// Rows of the split pane's tree in the last frame.
+    split_area: Rect,
+    /// Whether entries that differ between the panes are marked.
+    compare: bool,
+    /// Differences between the panes (`None` until computed).
+    comparison: Option<Comparison>,
+    /// Entry index and time of the last left click, for double-click detection.
+    last_click: Option<(usize, Instant)>,
+    /// Shared with the input thread so it can be paused for external programs.
+    input_gate: Arc<InputGate>,
+    /// Set by the stop-signal handlers (SIGINT, SIGTERM, SIGHUP).
+    signals: Shutdown,
+    /// Running without a real terminal: never launch external programs.
+    headless: bool,
+    /// External programs are allowed (off in safe mode).
+    external_programs: bool,
+    /// Paths are not local; see `LoopConfig::read_only`.
+    read_only: bool,
+    /// Where the UI state is saved on exit.
+    state_file: Option<PathBuf>,
+    /// Where the workspace is saved on exit.
+    session_file: Option<PathBuf>,
+    /// Restored selection, applied once the first tree is built.
+    restored_selection: Option<PathBuf>,
+    /// File selected again whenever it changes.
+    focus: Option<PathBuf>,
+    /// Every drawn frame, collected when running headless.
+    frames: Option<Vec<Buffer>>,
+    /// Changes arrived since the summary and heavy-report jobs were last
+    /// started; they are restarted once per frame, not once per batch.
+    jobs_stale: bool,
+    /// What the last drawn frame showed; a frame that would show the same
+    /// is not drawn.
+    last_frame: Fingerprint,
 }
 
-impl<'a> AppState<'a> {
-    fn new(
-        terminal: Term,
-        path: &'a Path,
-        tree_config: &'a TreeConfig,
-        use_color: bool,
-        tree_builder: &'a dyn TreeBuilder,
-    ) -> Self {
-        Self {
-            terminal,
-            last_change: None,
-            use_color,
-            path,
-            tree_config,
-            scroll: ScrollState::new(),
-            highlights: HighlightTracker::new(std::time::Duration::from_secs(3)),
-            highlight_duration_secs: 3,
-            tree_cache: None,
-            tree_builder,
+impl<'a, B: Backend> AppState<'a, B> {
+    fn new(
+        terminal: Terminal<B>,
+        path: &'a Path,
+        tree_config: &'a TreeConfig,
+        render_config: &RenderConfig,
+        tree_builder: &'a dyn TreeBuilder,
+        loop_config: &LoopConfig,
+        jobs: JobSenders,
+    ) -> Self {
+        let mut state = Self {
+            terminal,
+            last_change: None,
+            last_changed_path: None,
+            announce: loop_config.announce,
+            announcement: None,
+            toasts: Toasts::default(),
+            follow: false,
+            status_file_area: None,
+            status_template: loop_config.status_template.clone(),
+            render_config: render_config.clone(),
+            path: path.to_path_buf(),
+            start_root: path.to_path_buf(),
+            zoomed_from: Vec::new(),
+            watch_root: path.to_path_buf(),
+            watcher_options: loop_config.watcher_options,
+            watcher: None,
+            rewatched: None,
+            tree_config: tree_config.clone(),
+            scroll: ScrollState::new(),
+            selected: 0,
+            show_preview: false,
+            details_cache: HashMap::new(),
+            details_pending: HashSet::new(),
+            details_generation: 0,
+            highlights: HighlightLayers::default(),
+            highlight_styles: loop_config.highlight,
+            coalescer: RenameCoalescer::new(),
+            highlight_duration_secs: 3,
+            scheduler: FrameScheduler::new(loop_config.max_fps),
+            tree_cache: None,
+            path_index: PathIndex::default(),
+            tree_builder,
+            show_summary: loop_config.show_summary,
+            line_numbers: loop_config.line_numbers,
+            relative_line_numbers: loop_config.relative_line_numbers,
+            sticky_headers: loop_config.sticky_headers,
+            sticky: Vec::new(),
+            columns: loop_config.columns,
+            column_layout: None,
+            summaries: None,
+            summary_job: JobSlot::default(),
+            show_stale: false,
+            stale_after: loop_config.stale_after,
+            stale: None,
+            case_collisions: HashSet::new(),
+            show_hard_links: loop_config.hard_links,
+            hard_links: HashMap::new(),
+            permissions: loop_config.permissions,
+            owner_names: loop_config.owner.then(OwnerNames::new),
+            show_heavy: false,
+            heavy: None,
+            heavy_job: JobSlot::default(),
+            show_stats: false,
+            stats: Stats::default(),
+            show_heatmap: loop_config.show_heatmap,
+            activity: Activity::new(loop_config.changers_window),
+            show_changers: false,
+            jobs,
+            collapsed: HashSet::new(),
+            lazy_depth: loop_config.lazy_depth,
+            loaded: BTreeSet::new(),
+            unloaded: HashSet::new(),
+            open_archives: HashSet::new(),
+            archive_listings: HashMap::new(),
+            deleted: None,
+            started: Instant::now(),
+            changes: ChangeCounts::default(),
+            search: None,
+            ranked: false,
+            palette: None,
+            history: History::load(loop_config.history_file.clone()),
+            pending_delete: None,
+            trash: loop_config.trash,
+            scanned: false,
+            ignore_patterns: loop_config.ignore_patterns.clone(),
+            ignore_file: loop_config.ignore_file.clone().map(IgnoreFile::read),
+            default_ignores: loop_config.default_ignores,
+            mouse: loop_config.mouse,
+            keymap: loop_config.keymap.clone(),
+            count: PendingCount::default(),
+            tree_area: Rect::default(),
+            split: loop_config
+                .split
+                .clone()
+                .map(|(root, events)| SplitPane::new(root, events)),
+            split_area: Rect::default(),
+            compare: loop_config.compare,
+            comparison: None,
+            last_click: None,
+            input_gate: Arc::new(InputGate::default()),
+            signals: Shutdown::default(),
+            headless: false,
+            external_programs: loop_config.external_programs,
+            read_only: loop_config.read_only,
+            state_file: loop_config.state_file.clone(),
+            session_file: loop_config.session_file.clone(),
+            restored_selection: None,
+            focus: loop_config.focus.clone(),
+            frames: None,
+            last_frame: Fingerprint::default(),
+            jobs_stale: false,
+        };
+        if let Some(saved) = loop_config.state_file.as_deref().and_then(UiState::load) {
+            state.restore(saved);
+        }
+        if let Some(focus) = &loop_config.focus {
+            state.restored_selection = Some(focus.clone());
+            state
+                .highlights
+                .get_mut(Channel::Marked)
+                .insert(focus.clone(), Instant::now());
+        }
+        state
+    }
+
+    /// Whether the line-number gutter is shown, with entry numbers or
+    /// distances from the selection.
+    fn gutter(&self) -> bool {
+        self.line_numbers || self.relative_line_numbers
+    }
+
+    /// Whether entries are listed as relative paths instead of a tree.
+    fn flat(&self) -> bool {
+        self.render_config.listing == Listing::Flat
+    }
+
+    /// Whether the tree is replaced by the search's matches, best first.
+    fn ranked_view(&self) -> bool {
+        self.ranked && self.search.is_some()
+    }
+
+    /// Apply UI state saved by an earlier session. Toggles that command
+    /// line flags turned on stay on.
+    fn restore(&mut self, saved: UiState) {
+        self.tree_config.show_hidden |= saved.show_hidden;
+        self.show_summary |= saved.show_summary;
+        self.line_numbers |= saved.line_numbers;
+        self.relative_line_numbers |= saved.relative_line_numbers;
+        if saved.flat {
+            self.render_config.listing = Listing::Flat;
+        }
+        self.columns |= saved.columns;
+        self.show_heatmap |= saved.show_heatmap;
+        self.show_changers = saved.show_changers;
+        self.show_preview = saved.show_preview;
+        self.show_heavy = saved.show_heavy;
+        self.show_stale = saved.show_stale;
+        self.follow = saved.follow;
+        self.highlight_duration_secs = saved.highlight_duration_secs.min(3600);
+        self.highlights
+            .get_mut(Channel::Changes)
+            .set_duration(Duration::from_secs(self.highlight_duration_secs));
+        self.collapsed = saved.collapsed.iter().map(|p| self.path.join(p)).collect();
+        self.loaded = saved.loaded.iter().map(|p| self.path.join(p)).collect();
+        self.restored_selection = saved.selected.map(|p| self.path.join(p));
+        self.scroll.offset = saved.scroll_offset;
+        self.request_heavy();
+    }
+
+    /// Select the entry selected when the state was saved, once the first
+    /// tree is built.
+    fn restore_selection(&mut self) {
+        if let Some(path) = self.restored_selection.take() {
+            let _ = self.select_path(&path);
+        }
+    }
+
+    /// The UI state to save on exit.
+    fn ui_state(&self) -> UiState {
+        let relative = |p: &PathBuf| p.strip_prefix(&self.start_root).unwrap_or(p).to_path_buf();
+        let mut collapsed: Vec<PathBuf> = self.collapsed.iter().map(relative).collect();
+        collapsed.sort();
+        UiState {
+            show_hidden: self.tree_config.show_hidden,
+            show_summary: self.show_summary,
+            show_preview: self.show_preview,
+            show_heavy: self.show_heavy,
+            show_stale: self.show_stale,
+            line_numbers: self.line_numbers,
+            relative_line_numbers: self.relative_line_numbers,
+            flat: self.flat(),
+            columns: self.columns,
+            follow: self.follow,
+            show_heatmap: self.show_heatmap,
+            show_changers: self.show_changers,
+            highlight_duration_secs: self.highlight_duration_secs,
+            collapsed,
+            loaded: self.loaded.iter().map(relative).collect(),
+            selected: self.selected_path().as_ref().map(relative),
+            scroll_offset: self.scroll.offset(),
+        }
+    }
+
+    /// Save the UI state for the next session, if a state file is set.
+    fn save_ui_state(&self) {
+        let Some(file) = &self.state_file else {
+            return;
+        };
+        if let Err(e) = self.ui_state().save(file) {
+            tracing::warn!(error = %e, "cannot save UI state");
+        }
+    }
+
+    /// Save the root, split pane, and options to the session file, if any.
+    fn save_workspace(&self) {
+        let Some(file) = &self.session_file else {
+            return;
+        };
+        let workspace = Workspace {
+            root: self.path.to_path_buf(),
+            split: self.split.as_ref().map(|pane| pane.root().to_path_buf()),
+            compare: self.compare,
+            show_hidden: self.tree_config.show_hidden,
+            dirs_only: self.tree_config.dirs_only,
+            max_depth: self.tree_config.max_depth,
+            ignore: self.ignore_patterns.clone(),
+            line_numbers: self.line_numbers,
+            relative_line_numbers: self.relative_line_numbers,
+            flat: self.flat(),
+            columns: self.columns,
+            sticky_headers: self.sticky_headers,
+        };
+        if let Err(e) = workspace.save(file) {
+            tracing::warn!(error = %e, "cannot save session");
+        }
+    }
+
+    /// Start a background summary computation if the summary column is enabled.
+    fn request_summary(&mut self) {
+        if !self.show_summary {
+            return;
+        }
+        let Some(generation) = self.summary_job.request() else {
+            return;
+        };
+        spawn_summarize(
+            &self.path,
+            self.tree_config.show_hidden,
+            self.tree_config.follow_symlinks,
+            self.tree_config.ignore_patterns.clone(),
+            generation,
+            self.jobs.summary.clone(),
+        );
+    }
+
+    /// Recompute the heaviest-paths report in the background if it is shown.
+    fn request_heavy(&mut self) {
+        if !self.show_heavy {
+            return;
+        }
+        let Some(generation) = self.heavy_job.request() else {
+            return;
+        };
+        spawn_heaviest(
+            &self.path,
+            self.tree_config.show_hidden,
+            self.tree_config.follow_symlinks,
+            self.tree_config.ignore_patterns.clone(),
+            DEFAULT_HEAVY_TOP,
+            generation,
+            self.jobs.heavy.clone(),
+        );
+    }
+
+    /// Rebuild everything that depends on which entries are shown.
+    fn filters_changed(&mut self) {
+        self.tree_cache = None;
+        if let Some(split) = self.split.as_mut() {
+            split.invalidate();
+        }
+        self.details_cache.clear();
+        self.details_pending.clear();
+        self.details_generation += 1;
+        self.request_summary();
+        self.request_heavy();
+    }
+
+    /// Rebuild the tree with a new entry limit.
+    fn set_max_entries(&mut self, max: usize) {
+        if self.tree_config.max_entries == Some(max) {
+            return;
+        }
+        self.tree_config.max_entries = Some(max);
+        self.tree_cache = None;
+        self.toasts
+            .info(format!("showing up to {max} entries"), Instant::now());
+    }
+
+    /// Toggle the summary column, kicking off a computation when enabled.
+    fn toggle_summary(&mut self) {
+        self.show_summary = !self.show_summary;
+        if self.show_summary {
+            self.request_summary();
+        } else {
+            self.summaries = None;
+        }
+    }
+
+    /// The currently selected entry, if the tree is not empty.
+    fn selected_entry(&self) -> Option<&TreeEntry> {
+        self.tree_cache.as_ref()?.entries.get(self.selected)
+    }
+
+    /// Move the selection by `delta` entries (negative moves up).
+    fn move_selection(&mut self, delta: isize) {
+        self.selected = self.selected.saturating_add_signed(delta);
+        self.clamp_selection();
+    }
+
+    /// Keep the selection within the current snapshot.
+    fn clamp_selection(&mut self) {
+        let len = self.tree_cache.as_ref().map_or(0, |s| s.entries.len());
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+
+    /// Index of the entry drawn at screen position (`column`, `row`), if any.
+    fn entry_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.tree_area;
+        if column < area.x
+            || column >= area.x + area.width
+            || row < area.y
+            || row >= area.y + area.height
+        {
+            return None;
+        }
+        if let Some(&header) = self.sticky.get((row - area.y) as usize) {
+            return Some(header);
+        }
+        let mut index = self.scroll.offset() + (row - area.y) as usize;
+        if let Some((per_column, widths)) = &self.column_layout {
+            // Each column takes the gap after it, up to the next one.
+            let mut right = area.x;
+            let column = widths.iter().position(|&width| {
+                right = right.saturating_add(width + COLUMN_GAP as u16);
+                column < right
+            })?;
+            let row = (row - area.y) as usize;
+            if row >= *per_column {
+                return None;
+            }
+            index = column * per_column + row;
+        }
+        let len = self.tree_cache.as_ref().map_or(0, |s| s.entries.len());
+        (index < len).then_some(index)
+    }
+
+    /// Translate a mouse event into actions, using the configured click
+    /// bindings for the entry under the pointer.
+    fn mouse_actions(&mut self, mouse: MouseEvent) -> Vec<Action> {
+        let button = match mouse.kind {
+            MouseEventKind::ScrollUp => return vec![Action::ScrollUp],
+            MouseEventKind::ScrollDown => return vec![Action::ScrollDown],
+            MouseEventKind::Down(button) => button,
+            _ => return Vec::new(),
+        };
+        if let Some(split) = self.split.as_mut() {
+            let in_split = self.split_area.contains((mouse.column, mouse.row).into());
+            split.focused = in_split;
+            if in_split {
+                if button == MouseButton::Left {
+                    split.select_row((mouse.row - self.split_area.y) as usize);
+                }
+                return vec![Action::Redraw];
+            }
+        }
+        if button == MouseButton::Left
+            && self
+                .status_file_area
+                .is_some_and(|area| area.contains((mouse.column, mouse.row).into()))
+        {
+            return vec![Action::JumpToLastChange];
+        }
+        let Some(index) = self.entry_at(mouse.column, mouse.row) else {
+            return Vec::new();
+        };
+        let Some(is_dir) = self.tree_cache.as_ref().map(|s| s.entries[index].is_dir) else {
+            return Vec::new();
+        };
+        let bindings = *self.mouse.bindings(is_dir);
+        let now = Instant::now();
+        let click = match button {
+            MouseButton::Left => {
+                let double = self.last_click.is_some_and(|(last, at)| {
+                    last == index && now.duration_since(at) <= DOUBLE_CLICK_WINDOW
+                });
+                if double {
+                    self.last_click = None;
+                    bindings.double
+                } else {
+                    self.last_click = Some((index, now));
+                    bindings.single
+                }
+            }
+            MouseButton::Middle => bindings.middle,
+            MouseButton::Right => return Vec::new(),
+        };
+        let follow_up = match click {
+            ClickAction::None => return Vec::new(),
+            ClickAction::Select => None,
+            ClickAction::Expand => Some(Action::ToggleCollapse),
+            ClickAction::Open => Some(Action::OpenInEditor),
+            ClickAction::CopyPath => Some(Action::CopyPath),
+            ClickAction::Reveal => Some(Action::Reveal),
+        };
+        std::iter::once(Action::Select(index))
+            .chain(follow_up)
+            .collect()
+    }
+
+    /// Apply `action` to the state and redraw. Returns `Flow::Exit` when the
+    /// application should exit.
+    fn dispatch(&mut self, action: Action) -> Flow {
+        if self.read_only && reads_local_files(action) {
+            self.toasts
+                .error("not available for a remote tree", Instant::now());
+            self.scheduler.request(Trigger::Input);
+            return Flow::Continue;
+        }
+        // The focused split pane takes the movement keys.
+        let page = self.visible_height().saturating_sub(2);
+        if let Some(split) = self.split.as_mut().filter(|s| s.focused) {
+            let action = match action {
+                Action::ScrollUp => Action::Scroll(-WHEEL_STEP),
+                Action::ScrollDown => Action::Scroll(WHEEL_STEP),
+                action => action,
+            };
+            if split.navigate(action, page) {
+                if action == Action::ToggleCollapse {
+                    self.comparison = None;
+                }
+                self.scheduler.request(Trigger::Input);
+                return Flow::Continue;
+            }
+        }
+        match action {
+            Action::Quit if self.deleted.is_some() => return Flow::Exit(ExitReason::RootDeleted),
+            Action::Quit => return Flow::Exit(ExitReason::Quit),
+            Action::ResetHighlights => self.highlights.get_mut(Channel::Changes).clear(),
+            Action::MoveUp => self.move_selection(-1),
+            Action::MoveDown => self.move_selection(1),
+            Action::ScrollUp => self.move_selection(-WHEEL_STEP),
+            Action::ScrollDown => self.move_selection(WHEEL_STEP),
+            Action::PageUp => {
+                let h = self.visible_height() as isize;
+                self.move_selection(-h);
+            }
+            Action::PageDown => {
+                let h = self.visible_height() as isize;
+                self.move_selection(h);
+            }
+            Action::First => self.selected = 0,
+            Action::Last => self.selected = usize::MAX,
+            Action::Select(index) => self.selected = index,
+            Action::Scroll(delta) => self.move_selection(delta),
+            Action::SetDepth(depth) => {
+                if self.tree_config.max_depth != depth {
+                    self.tree_config.max_depth = depth;
+                    self.filters_changed();
+                }
+            }
+            Action::ToggleSummary => self.toggle_summary(),
+            Action::TogglePreview => self.show_preview = !self.show_preview,
+            Action::ToggleHidden => {
+                self.tree_config.show_hidden = !self.tree_config.show_hidden;
+                self.filters_changed();
+            }
+            Action::ToggleStale => {
+                self.show_stale = !self.show_stale;
+                self.stale = None;
+            }
+            Action::ToggleHeavyReport => {
+                self.show_heavy = !self.show_heavy;
+                self.heavy = None;
+                self.request_heavy();
+            }
+            Action::ToggleStats => self.show_stats = !self.show_stats,
+            Action::ToggleHeatmap => self.show_heatmap = !self.show_heatmap,
+            Action::ToggleChangers => self.show_changers = !self.show_changers,
+            Action::Goto => self.palette = Some(Palette::default()),
+            Action::Export => self.palette = Some(Palette::with_input("export ")),
+            Action::NewFile => self.palette = Some(Palette::with_input("touch ")),
+            Action::NewDir => self.palette = Some(Palette::with_input("mkdir ")),
+            Action::Rename => {
+                if let Some(name) = self.selected_path().as_deref().and_then(Path::file_name) {
+                    let input = format!("rename {}", name.to_string_lossy());
+                    self.palette = Some(Palette::with_input(&input));
+                }
+            }
+            Action::Delete => self.pending_delete = self.selected_path(),
+            Action::ToggleLineNumbers => self.line_numbers = !self.line_numbers,
+            Action::ToggleRelativeLineNumbers => {
+                self.relative_line_numbers = !self.relative_line_numbers;
+            }
+            Action::ToggleStickyHeaders => self.sticky_headers = !self.sticky_headers,
+            Action::ToggleFlat => {
+                self.render_config.listing = if self.flat() {
+                    Listing::Tree
+                } else {
+                    Listing::Flat
+                };
+            }
+            Action::ToggleColumns => self.columns = !self.columns,
+            Action::SwitchPane => {
+                if let Some(split) = self.split.as_mut() {
+                    split.focused = !split.focused;
+                }
+            }
+            Action::ToggleCompare if self.split.is_none() => {
+                self.toasts
+                    .error("compare mode needs a split view (--split)", Instant::now());
+            }
+            Action::ToggleCompare => {
+                self.compare = !self.compare;
+                self.comparison = None;
+            }
+            Action::ZoomIn => match self.selected_entry().filter(|e| e.is_dir) {
+                Some(_) if self.read_only => {
+                    self.toasts
+                        .error("cannot zoom into a remote tree", Instant::now());
+                }
+                Some(entry) => {
+                    let dir = entry.path.clone();
+                    self.zoomed_from.push(self.path.clone());
+                    self.set_root(dir.clone(), &dir);
+                }
+                None => {}
+            },
+            Action::ZoomOut => self.zoom_out(),
+            Action::RootUp if self.read_only => {
+                self.toasts
+                    .error("cannot move the root of a remote tree", Instant::now());
+            }
+            Action::RootUp => self.root_up(),
+            Action::JumpToLastChange => self.select_last_change(),
+            Action::ToggleFollow => {
+                self.follow = !self.follow;
+                if self.follow {
+                    self.select_last_change();
+                }
+            }
+            Action::ToggleCollapse => {
+                if let Some(entry) = self.selected_entry() {
+                    let path = entry.path.clone();
+                    if entry.is_dir {
+                        self.toggle_collapsed(path);
+                    } else if is_archive(&path) && !self.read_only {
+                        self.toggle_archive(path);
+                    }
+                }
+            }
+            Action::OpenInEditor => {
+                if let Some(path) = self.selected_path() {
+                    let _ = self.run_external(external::editor_command(&path));
+                }
+            }
+            Action::CopyPath => {
+                if let Some(path) = self.selected_path().filter(|_| self.may_run_external()) {
+                    let now = Instant::now();
+                    match external::copy_to_clipboard(&path.to_string_lossy()) {
+                        Ok(()) => self
+                            .toasts
+                            .info(format!("copied {}", self.relative(&path)), now),
+                        Err(e) => self.toasts.error(format!("cannot copy path: {e}"), now),
+                    }
+                }
+            }
+            Action::Reveal => {
+                if let Some(path) = self.selected_path() {
+                    self.reveal(&path);
+                }
+            }
+            Action::Suspend => self.suspend(),
+            Action::Shell => {
+                if self.may_run_external() {
+                    let dir = self.selected_dir();
+                    if let Err(e) = self.run_external(external::shell_command(&dir)) {
+                        self.toasts
+                            .error(format!("cannot start shell: {e}"), Instant::now());
+                    }
+                }
+            }
+            Action::Open => {
+                if let Some(path) = self.selected_path().filter(|_| self.may_run_external()) {
+                    let now = Instant::now();
+                    match external::open(&path) {
+                        Ok(()) => self
+                            .toasts
+                            .info(format!("opening {}", self.relative(&path)), now),
+                        Err(e) => self.toasts.error(format!("cannot open: {e}"), now),
+                    }
+                }
+            }
+            Action::IncreaseHighlightDuration => {
+                // Saturate at a reasonable upper bound.
+                if self.highlight_duration_secs < 3600 {
+                    self.highlight_duration_secs += 1;
+                }
+                self.highlights
+                    .get_mut(Channel::Changes)
+                    .set_duration(Duration::from_secs(self.highlight_duration_secs));
+            }
+            Action::DecreaseHighlightDuration => {
+                // Clamped at 0, which disables highlighting.
+                self.highlight_duration_secs = self.highlight_duration_secs.saturating_sub(1);
+                self.highlights
+                    .get_mut(Channel::Changes)
+                    .set_duration(Duration::from_secs(self.highlight_duration_secs));
+            }
+            Action::IncreaseMaxEntries => {
+                if let Some(max) = self.tree_config.max_entries {
+                    self.set_max_entries(max.saturating_mul(2));
+                }
+            }
+            Action::DecreaseMaxEntries => {
+                let current = self
+                    .tree_config
+                    .max_entries
+                    .unwrap_or_else(|| self.tree_cache.as_ref().map_or(0, |s| s.total_entries));
+                self.set_max_entries((current / 2).max(MIN_MAX_ENTRIES));
+            }
+            Action::ToggleMark => {
+                if let Some(path) = self.selected_path() {
+                    self.highlights
+                        .get_mut(Channel::Marked)
+                        .toggle(path, Instant::now());
+                }
+            }
+            Action::NextMark => self.select_mark(true),
+            Action::PreviousMark => self.select_mark(false),
+            Action::Search => self.palette = Some(Palette::with_input("find ")),
+            Action::ToggleRanked => {
+                let selected = self.selected_path();
+                self.ranked = !self.ranked;
+                if self.search.is_some() {
+                    self.tree_cache = None;
+                    self.reselect(selected);
+                }
+            }
+            Action::NextMatch => self.select_match(true),
+            Action::PreviousMatch => self.select_match(false),
+            Action::Redraw => {}
+        }
+        self.scheduler.request(Trigger::Input);
+        Flow::Continue
+    }
+
+    /// Files in `snapshot` not modified within `stale_after`.
+    fn stale_entries(&self, snapshot: &TreeSnapshot) -> HashSet<PathBuf> {
+        let now = SystemTime::now();
+        snapshot
+            .entries
+            .iter()
+            .filter(|e| !e.is_dir)
+            .filter(|e| {
+                std::fs::symlink_metadata(&e.path)
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|modified| is_stale(modified, self.stale_after, now))
+            })
+            .map(|e| e.path.clone())
+            .collect()
+    }
+
+    /// Path of the selected entry.
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.selected_entry().map(|e| e.path.clone())
+    }
+
+    /// `path` relative to the root, for messages.
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Show `path` in the OS file manager, handing over the terminal if the
+    /// opener might need it.
+    fn reveal(&mut self, path: &Path) {
+        if !self.may_run_external() {
+            return;
+        }
+        if external::reveal_needs_terminal() {
+            let _ = self.run_external(external::reveal_command(path));
+        } else {
+            let _ = external::reveal(path);
+        }
+    }
+
+    /// Apply a key press to the open command palette.
+    fn palette_key(&mut self, key: KeyEvent) -> Flow {
+        let Some(prompt) = self.palette.as_mut() else {
+            return Flow::Continue;
+        };
+        let mut flow = Flow::Continue;
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Enter => {
+                let input = prompt.input.clone();
+                match self.run_command(&input) {
+                    Ok(next) => {
+                        let _ = self.history.push(&input);
+                        self.palette = None;
+                        flow = next;
+                    }
+                    Err(e) => {
+                        if let Some(prompt) = self.palette.as_mut() {
+                            prompt.error = Some(e);
+                        }
+                    }
+                }
+            }
+            KeyCode::Tab => {
+                // A remote tree's paths are completed from its listing.
+                let listing = self
+                    .read_only
+                    .then(|| self.tree_builder.build_tree(&self.path, &self.tree_config));
+                let ctx = palette::Context {
+                    root: &self.path,
+                    show_hidden: self.tree_config.show_hidden,
+                    ignored: &self.ignore_patterns,
+                    listing: listing.as_ref().map(|s| s.entries.as_slice()),
+                };
+                prompt.complete(&ctx);
+            }
+            KeyCode::Up => prompt.history_prev(&self.history),
+            KeyCode::Down => prompt.history_next(&self.history),
+            KeyCode::Backspace => prompt.pop(),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.palette = None
+            }
+            KeyCode::Char(c) => prompt.push(c),
+            _ => return Flow::Continue,
+        }
+        self.scheduler.request(Trigger::Input);
+        flow
+    }
+
+    /// Run palette input.
+    fn run_command(&mut self, input: &str) -> Result<Flow, String> {
+        let command = palette::parse(input)?;
+        if self.read_only
+            && matches!(
+                command,
+                PaletteCommand::Touch(_)
+                    | PaletteCommand::Mkdir(_)
+                    | PaletteCommand::Rename(_)
+                    | PaletteCommand::Delete
+            )
+        {
+            return Err("not available for a remote tree".to_string());
+        }
+        match command {
+            PaletteCommand::Goto(target) => self.jump_to(&target)?,
+            PaletteCommand::Set(setting, value) => {
+                let (on, action) = match setting {
+                    Setting::Hidden => (self.tree_config.show_hidden, Action::ToggleHidden),
+                    Setting::Summary => (self.show_summary, Action::ToggleSummary),
+                    Setting::Preview => (self.show_preview, Action::TogglePreview),
+                    Setting::Numbers => (self.line_numbers, Action::ToggleLineNumbers),
+                    Setting::Relative => (
+                        self.relative_line_numbers,
+                        Action::ToggleRelativeLineNumbers,
+                    ),
+                    Setting::Follow => (self.follow, Action::ToggleFollow),
+                    Setting::Stale => (self.show_stale, Action::ToggleStale),
+                    Setting::Heavy => (self.show_heavy, Action::ToggleHeavyReport),
+                    Setting::Heatmap => (self.show_heatmap, Action::ToggleHeatmap),
+                    Setting::Changers => (self.show_changers, Action::ToggleChangers),
+                    Setting::Sticky => (self.sticky_headers, Action::ToggleStickyHeaders),
+                    Setting::Compare => (self.compare, Action::ToggleCompare),
+                    Setting::Flat => (self.flat(), Action::ToggleFlat),
+                    Setting::Columns => (self.columns, Action::ToggleColumns),
+                    Setting::Ranked => (self.ranked, Action::ToggleRanked),
+                };
+                if value != Some(on) {
+                    self.dispatch(action);
+                }
+            }
+            PaletteCommand::Duration(duration) => {
+                self.highlight_duration_secs = duration.as_secs().min(3600);
+                self.highlights
+                    .get_mut(Channel::Changes)
+                    .set_duration(Duration::from_secs(self.highlight_duration_secs));
+            }
+            PaletteCommand::Ignore(pattern) => {
+                if self.ignore_patterns.contains(&pattern) {
+                    return Err(format!("'{pattern}' is already ignored"));
+                }
+                self.ignore_patterns.push(pattern);
+                self.ignores_changed();
+            }
+            PaletteCommand::Unignore(pattern) => {
+                if let Some(file) = self
+                    .ignore_file
+                    .as_ref()
+                    .filter(|f| f.patterns.contains(&pattern))
+                {
+                    return Err(format!(
+                        "'{pattern}' comes from {}; edit the file instead",
+                        file.path.display()
+                    ));
+                }
+                let Some(index) = self.ignore_patterns.iter().position(|p| *p == pattern) else {
+                    return Err(if self.ignore_patterns.is_empty() {
+                        format!("'{pattern}' is not ignored (no patterns are)")
+                    } else {
+                        format!(
+                            "'{pattern}' is not ignored (ignored: {})",
+                            self.ignore_patterns.join(", ")
+                        )
+                    });
+                };
+                self.ignore_patterns.remove(index);
+                self.ignores_changed();
+            }
+            PaletteCommand::Export(file) => {
+                self.ensure_tree();
+                let snapshot = self.tree_cache.as_ref().ok_or("no tree to export")?;
+                export::write(snapshot, &self.path, &export::resolve(&file), None)?;
+                self.toasts
+                    .info(format!("wrote {}", file.trim()), Instant::now());
+            }
+            PaletteCommand::Touch(name) => {
+                let path = ops::create_file(&self.selected_dir(), &name)?;
+                self.entry_changed(format!("created {}", self.relative(&path)), Some(&path));
+            }
+            PaletteCommand::Mkdir(name) => {
+                let path = ops::create_dir(&self.selected_dir(), &name)?;
+                self.entry_changed(format!("created {}/", self.relative(&path)), Some(&path));
+            }
+            PaletteCommand::Rename(name) => {
+                let from = self.selected_path().ok_or("nothing selected")?;
+                let to = ops::rename(&from, &name)?;
+                let message = format!("renamed {} to {name}", self.relative(&from));
+                self.entry_changed(message, Some(&to));
+            }
+            PaletteCommand::Delete => {
+                self.pending_delete = Some(self.selected_path().ok_or("nothing selected")?);
+            }
+            PaletteCommand::Search(query) => self.start_search(&query)?,
+            PaletteCommand::Clear => return Ok(self.dispatch(Action::ResetHighlights)),
+            PaletteCommand::Quit => return Ok(self.dispatch(Action::Quit)),
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Apply a command from the control socket and answer it.
+    fn handle_control(&mut self, request: ControlRequest) -> Flow {
+        let (flow, answer) = match request.command {
+            ControlCommand::Action(action) if self.read_only && reads_local_files(action) => (
+                Flow::Continue,
+                Err("not available for a remote tree".to_string()),
+            ),
+            ControlCommand::Action(action) => (self.dispatch(action), Ok(())),
+            ControlCommand::Palette(input) => match self.run_command(&input) {
+                Ok(flow) => (flow, Ok(())),
+                Err(e) => (Flow::Continue, Err(e)),
+            },
+        };
+        let _ = request.reply.send(answer);
+        self.scheduler.request(Trigger::Input);
+        flow
+    }
+
+    /// The selected directory, the directory of the selected file, or the
+    /// root: where `touch` and `mkdir` create entries and `!` starts a shell.
+    fn selected_dir(&self) -> PathBuf {
+        match self.selected_entry() {
+            Some(entry) if entry.is_dir => entry.path.clone(),
+            Some(entry) => entry.path.parent().unwrap_or(&self.path).to_path_buf(),
+            None => self.path.to_path_buf(),
+        }
+    }
+
+    /// Show the outcome of a file operation and rebuild the tree without
+    /// waiting for the watcher, selecting `select` if given.
+    fn entry_changed(&mut self, message: String, select: Option<&Path>) {
+        self.toasts.info(message, Instant::now());
+        self.tree_cache = None;
+        match select {
+            Some(path) => {
+                let _ = self.select_path(path);
+            }
+            None => self.clamp_selection(),
+        }
+    }
+
+    /// Apply the answer to a pending deletion: `y` deletes, any other key
+    /// cancels.
+    fn confirm_key(&mut self, key: KeyEvent) -> Flow {
+        let Some(path) = self.pending_delete.take() else {
+            return Flow::Continue;
+        };
+        if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+            let name = self.relative(&path);
+            let done = if self.trash {
+                ops::trash(&path).map(|()| format!("moved {name} to the trash"))
+            } else {
+                ops::delete(&path).map(|()| format!("deleted {name}"))
+            };
+            match done {
+                Ok(message) => self.entry_changed(message, None),
+                Err(e) => self.toasts.error(e, Instant::now()),
+            }
+        }
+        self.scheduler.request(Trigger::Input);
+        Flow::Continue
+    }
+
+    /// Rebuild the ignore set after `ignore`/`unignore` or an edit of the
+    /// ignore file.
+    fn ignores_changed(&mut self) {
+        let mut patterns = self.ignore_patterns.clone();
+        if let Some(file) = &self.ignore_file {
+            patterns.extend(file.patterns.iter().cloned());
+        }
+        self.tree_config.ignore_patterns = if self.default_ignores {
+            build_ignore_set(&patterns)
+        } else {
+            build_ignore_set_no_defaults(&patterns)
+        };
+        self.stale = None;
+        self.filters_changed();
+    }
+
+    /// Re-read the ignore file if it was edited, created, or deleted since
+    /// it was last read.
+    fn reload_ignore_file(&mut self) {
+        let Some(file) = self.ignore_file.as_mut() else {
+            return;
+        };
+        let stamp = file_stamp(&file.path);
+        if stamp == file.stamp {
+            return;
+        }
+        file.stamp = stamp;
+        let patterns = if stamp.is_none() {
+            Vec::new()
+        } else {
+            match read_ignore_file(&file.path) {
+                Ok(patterns) => patterns,
+                Err(e) => {
+                    self.toasts.error(e, Instant::now());
+                    return;
+                }
+            }
+        };
+        if patterns == file.patterns {
+            return;
+        }
+        file.patterns = patterns;
+        let message = format!("reloaded {}", file.path.display());
+        self.toasts.info(message, Instant::now());
+        self.ignores_changed();
+        self.scheduler.request(Trigger::Fs);
+    }
+
+    /// Select the entry at `input` (relative to the root), expanding any
+    /// collapsed ancestors so it is visible.
+    fn jump_to(&mut self, input: &str) -> Result<(), String> {
+        let input = match goto::parse_target(input) {
+            Target::Line(line) => return self.select_line(line),
+            Target::Path(path) => path,
+        };
+        let target = goto::resolve(&self.path, input)
+            .ok_or_else(|| "path must stay inside the watched directory".to_string())?;
+        self.select_path(&target)
+    }
+
+    /// Select entry number `line` (1-based, as in the gutter).
+    fn select_line(&mut self, line: usize) -> Result<(), String> {
+        self.ensure_tree();
+        let count = self.tree_cache.as_ref().map_or(0, |s| s.entries.len());
+        if line == 0 || line > count {
+            return Err(format!("no entry {line} (1–{count})"));
+        }
+        self.selected = line - 1;
+        Ok(())
+    }
+
+    /// Search entry paths for `query`, selecting the first match below the
+    /// selection (the best one in the ranked view). An empty query ends
+    /// the search.
+    fn start_search(&mut self, query: &str) -> Result<(), String> {
+        // The ranked view shows only the last search's matches; the new
+        // one looks through the whole tree.
+        let ranked = self.ranked_view();
+        let selected = self.selected_path();
+        let previous = self.search.take();
+        if ranked {
+            self.tree_cache = None;
+        }
+        self.ensure_tree();
+        let entries = self.tree_cache.as_ref().map_or(&[][..], |s| &s.entries[..]);
+        let search = Search::new(query, entries);
+        let next = search.next(self.selected, true);
+        if next.is_none() && !query.is_empty() {
+            self.search = previous;
+            if ranked {
+                self.tree_cache = None;
+            }
+            return Err(format!("no entries match '{query}'"));
+        }
+        self.search = (!query.is_empty()).then_some(search);
+        self.render_config.search = self.search.as_ref().map(|s| s.query().to_string());
+        if self.ranked_view() {
+            self.tree_cache = None;
+            self.ensure_tree();
+            self.selected = 0;
+        } else if ranked {
+            self.reselect(selected);
+        } else {
+            self.selected = next.unwrap_or(self.selected);
+            self.refresh_search();
+        }
+        Ok(())
+    }
+
+    /// Select `path` again after the rows moved, or the root if it is no
+    /// longer shown.
+    fn reselect(&mut self, path: Option<PathBuf>) {
+        if path.is_none_or(|p| self.select_path(&p).is_err()) {
+            self.selected = 0;
+        }
+    }
+
+    /// Match the search against the tree again and mark its matches.
+    fn refresh_search(&mut self) {
+        let layer = self.highlights.get_mut(Channel::Search);
+        layer.clear();
+        let (Some(search), Some(snapshot)) = (self.search.as_mut(), self.tree_cache.as_ref())
+        else {
+            return;
+        };
+        search.refresh(&snapshot.entries);
+        let now = Instant::now();
+        for &row in search.rows() {
+            layer.insert(snapshot.entries[row].path.clone(), now);
+        }
+    }
+
+    /// Select the next (or previous) search match, wrapping around the ends
+    /// of the tree.
+    fn select_match(&mut self, forward: bool) {
+        self.ensure_tree();
+        let Some(search) = &self.search else {
+            self.toasts
+                .info("no search (type / to search)", Instant::now());
+            return;
+        };
+        match search.next(self.selected, forward) {
+            Some(row) => self.selected = row,
+            None => {
+                let message = format!("no entries match '{}'", search.query());
+                self.toasts.info(message, Instant::now());
+            }
+        }
+    }
+
+    /// Select the next (or previous) marked entry shown, wrapping around
+    /// the ends of the tree.
+    fn select_mark(&mut self, forward: bool) {
+        self.ensure_tree();
+        let marks = self
+            .highlights
+            .get_mut(Channel::Marked)
+            .active_set(Instant::now());
+        let rows: Vec<usize> = self
+            .tree_cache
+            .iter()
+            .flat_map(|s| s.entries.iter().enumerate())
+            .filter(|(_, e)| marks.contains(&e.path))
+            .map(|(i, _)| i)
+            .collect();
+        let target = if forward {
+            rows.iter().find(|&&i| i > self.selected).or(rows.first())
+        } else {
+            rows.iter()
+                .rev()
+                .find(|&&i| i < self.selected)
+                .or(rows.last())
+        };
+        match target {
+            Some(&row) => self.selected = row,
+            None => self.toasts.info("no marked entries shown", Instant::now()),
+        }
+    }
+
+    /// Select the most recently changed entry, if it is still shown.
+    fn select_last_change(&mut self) {
+        if let Some(path) = self.last_changed_path.clone() {
+            let _ = self.select_path(&path);
+        }
+    }
+
+    /// Select the entry for the absolute path `target`, expanding any
+    /// collapsed ancestors (and loading unloaded ones) so it is visible.
+    fn select_path(&mut self, target: &Path) -> Result<(), String> {
+        let expanded = self.collapsed.len();
+        self.collapsed
+            .retain(|dir| !(target.starts_with(dir) && target != dir));
+        if self.collapsed.len() != expanded {
+            self.tree_cache = None;
+        }
+        if self.lazy_depth.is_some() {
+            for dir in target.ancestors().skip(1) {
+                if dir == self.path || !dir.starts_with(&self.path) {
+                    break;
+                }
+                if self.loaded.insert(dir.to_path_buf()) && self.unloaded.contains(dir) {
+                    self.tree_cache = None;
+                }
+            }
+        }
+        self.ensure_tree();
+        if target == self.path {
+            self.selected = 0;
+            return Ok(());
+        }
+        match self.path_index.get(target) {
+            Some(index) => {
+                self.selected = index;
+                Ok(())
+            }
+            None if target.symlink_metadata().is_err() => Err("no such entry".to_string()),
+            None => Err("not shown (hidden, ignored, or beyond the depth/entry limit)".to_string()),
+        }
+    }
+
+    /// Collapse an expanded directory or expand a collapsed one.
+    /// A directory left unloaded by lazy loading is walked instead.
+    fn toggle_collapsed(&mut self, dir: PathBuf) {
+        if self.unloaded.contains(&dir) {
+            self.collapsed.remove(&dir);
+            self.loaded.insert(dir);
+        } else if !self.collapsed.remove(&dir) {
+            self.collapsed.insert(dir);
+        }
+        self.tree_cache = None;
+    }
+
+    /// Show the tree below `root` instead of the current one, with
+    /// `select` selected (the top of the tree if it is not shown).
+    fn set_root(&mut self, root: PathBuf, select: &Path) {
+        self.path = root;
+        self.tree_cache = None;
+        self.scroll = ScrollState::new();
+        self.selected = 0;
+        self.summaries = None;
+        self.heavy = None;
+        self.jobs_stale = true;
+        let _ = self.select_path(select);
+    }
+
+    /// Go back to the root zoomed in from, selecting the directory the
+    /// view was zoomed into.
+    fn zoom_out(&mut self) {
+        if let Some(root) = self.zoomed_from.pop() {
+            let dir = self.path.clone();
+            self.set_root(root, &dir);
+        }
+    }
+
+    /// Move the root up to its parent directory, watching the parent
+    /// instead if it is above the watched root.
+    fn root_up(&mut self) {
+        let Some(parent) = self.path.parent().map(Path::to_path_buf) else {
+            self.toasts.info("already at the top", Instant::now());
+            return;
+        };
+        if !parent.starts_with(&self.watch_root) {
+            if let Some(options) = &self.watcher_options {
+                match watcher::start_supervised_with(&parent, options, watcher::REVALIDATE_INTERVAL)
+                {
+                    Ok((watcher, events)) => {
+                        self.watcher = Some(watcher);
+                        self.rewatched = Some(events);
+                    }
+                    Err(e) => {
+                        self.toasts.error(format!("watcher: {e}"), Instant::now());
+                        return;
+                    }
+                }
+            }
+            self.watch_root = parent.clone();
+        }
+        // Roots zoomed in from below the new one are left behind.
+        self.zoomed_from
+            .retain(|root| parent.starts_with(root) && *root != parent);
+        let dir = self.path.clone();
+        self.set_root(parent, &dir);
+    }
+
+    /// Open an archive to show its members, or close an open one.
+    fn toggle_archive(&mut self, archive: PathBuf) {
+        if !self.open_archives.remove(&archive) {
+            self.open_archives.insert(archive);
+        }
+        self.tree_cache = None;
+    }
+
+    /// Whether the editor, file manager, or clipboard may be used.
+    fn may_run_external(&self) -> bool {
+        self.external_programs && !self.headless
+    }
+
+    /// Return to the shell until livetree is continued, as Ctrl+Z does
+    /// for other programs: the terminal is restored before the process
+    /// stops and taken over again, with a full redraw, when it continues.
+    fn suspend(&mut self) {
+        if self.headless {
+            return;
+        }
+        if !terminal::CAN_SUSPEND {
+            self.toasts
+                .error("suspending is not supported here", Instant::now());
+            return;
+        }
+        let gate = Arc::clone(&self.input_gate);
+        let reading = gate.pause();
+        terminal::suspend();
+        let stopped = terminal::suspend_process();
+        let resumed = terminal::resume(&mut self.terminal, self.mouse.enabled);
+        self.last_frame.reset();
+        drop(reading);
+        gate.resume();
+        if let Err(e) = stopped.and(resumed) {
+            self.toasts
+                .error(format!("cannot suspend: {e}"), Instant::now());
+        }
+        self.scheduler.request(Trigger::Exec);
+    }
+
+    /// Run `cmd` in the foreground with the terminal handed over to it,
+    /// then restore the TUI.
+    fn run_external(&mut self, mut cmd: Command) -> std::io::Result<std::process::ExitStatus> {
+        if !self.may_run_external() {
+            return Err(std::io::ErrorKind::Unsupported.into());
+        }
+        let gate = Arc::clone(&self.input_gate);
+        let reading = gate.pause();
+        terminal::suspend();
+        let status = cmd.status();
+        // Ctrl+C pressed while the program ran was meant for it.
+        self.signals.forgive_interrupt();
+        let resumed = terminal::resume(&mut self.terminal, self.mouse.enabled);
+        self.last_frame.reset();
+        drop(reading);
+        gate.resume();
+        resumed?;
+        self.scheduler.request(Trigger::Exec);
+        status
+    }
+
+    /// Drop cached directory details affected by the given changed paths.
+    /// Details still being computed for an affected directory are dropped
+    /// when they arrive, and every pending directory is asked for again.
+    fn invalidate_details(&mut self, changed: &[PathBuf]) {
+        let affected = |dir: &PathBuf| changed.iter().any(|p| p.starts_with(dir));
+        self.details_cache.retain(|dir, _| !affected(dir));
+        if self.details_pending.iter().any(affected) {
+            self.details_pending.clear();
+            self.details_generation += 1;
+        }
+    }
+
+    /// Build the preview pane for `entry`, requesting directory details in
+    /// the background on first use.
+    fn preview_lines(&mut self, entry: Option<&TreeEntry>) -> Vec<Line<'static>> {
+        let Some(entry) = entry else {
+            return Vec::new();
+        };
+        let now = SystemTime::now();
+        if entry.is_dir && entry.error.is_none() {
+            let details = self.details_cache.get(&entry.path);
+            if details.is_none() && self.details_pending.insert(entry.path.clone()) {
+                spawn_dir_details(
+                    &entry.path,
+                    &self.path,
+                    self.tree_config.show_hidden,
+                    self.tree_config.follow_symlinks,
+                    self.tree_config.ignore_patterns.clone(),
+                    self.details_generation,
+                    self.jobs.details.clone(),
+                );
+            }
+            dir_preview_lines(&entry.path, self.details_cache.get(&entry.path), now)
+        } else {
+            let meta = std::fs::symlink_metadata(&entry.path).ok();
+            file_preview_lines(
+                entry,
+                meta.as_ref().map(|m| m.len()),
+                meta.and_then(|m| m.modified().ok()),
+                now,
+            )
+        }
+    }
+
+    /// Rebuild the tree and its path index if the cache was invalidated.
+    /// After the root is deleted, the last snapshot is kept instead.
+    fn ensure_tree(&mut self) {
+        if let Some(deleted) = &self.deleted {
+            if self.tree_cache.is_none() {
+                self.path_index = deleted.snapshot.path_index();
+                self.case_collisions = case_collisions(&deleted.snapshot.entries);
+                self.hard_links.clear();
+                self.tree_cache = Some(deleted.snapshot.clone());
+                self.refresh_search();
+            }
+            return;
+        }
+        if self.tree_cache.is_none() {
+            // Only the first scan shows progress; later rebuilds keep the
+            // previous frame on screen until they finish.
+            let show_progress = !self.scanned && !self.headless;
+            let (walk_config, cut) = self.walk_config(0);
+            let root = &self.path;
+            let terminal = &mut self.terminal;
+            let collapsed = &self.collapsed;
+            let render_config = &self.render_config;
+            let started = Instant::now();
+            let mut last_drawn: Option<Instant> = None;
+            let mut progress = |walked: &WalkProgress| {
+                let now = Instant::now();
+                if !show_progress
+                    || now.duration_since(started) < SCAN_INDICATOR_DELAY
+                    || last_drawn.is_some_and(|at| now.duration_since(at) < SCAN_INDICATOR_INTERVAL)
+                {
+                    return;
+                }
+                last_drawn = Some(now);
+                let current = walked.current.strip_prefix(root).unwrap_or(walked.current);
+                let line = scanning_line(&format_watched_path(root), walked.count, current);
+                let _ = terminal.draw(|frame| {
+                    let area = frame.area();
+                    let rows = area.height.saturating_sub(2);
+                    // The top of the tree as walked so far; entries below
+                    // collapsed directories are dropped, so walk a little
+                    // further than the screen holds.
+                    let mut preview = walked.preview(usize::from(rows) * 4);
+                    preview.collapse(collapsed);
+                    let config = RenderConfig {
+                        terminal_width: area.width,
+                        ..render_config.clone()
+                    };
+                    let lines = tree_to_lines(
+                        &preview.entries[..preview.len().min(usize::from(rows))],
+                        &config,
+                        &HashSet::<PathBuf>::new(),
+                    );
+                    frame.render_widget(
+                        Paragraph::new(lines),
+                        Rect {
+                            height: rows,
+                            ..area
+                        },
+                    );
+                    let row = Rect {
+                        y: rows,
+                        height: area.height.min(1),
+                        ..area
+                    };
+                    frame.render_widget(Paragraph::new(line), row);
+                });
+            };
+            let mut snapshot =
+                self.tree_builder
+                    .build_tree_with_preview(&self.path, &walk_config, &mut progress);
+            self.unloaded.clear();
+            self.mark_unloaded(&snapshot, cut);
+            self.load_subtrees(&mut snapshot);
+            self.graft_archives(&mut snapshot);
+            self.stats.record_build(started.elapsed());
+            self.scanned = true;
+            snapshot.collapse(&self.collapsed);
+            if let Some(search) = self.search.as_mut().filter(|_| self.ranked) {
+                rank_matches(&mut snapshot, search);
+            }
+            self.path_index = snapshot.path_index();
+            self.case_collisions = case_collisions(&snapshot.entries);
+            if self.show_hard_links {
+                self.hard_links = hard_links(&snapshot.entries);
+            }
+            self.tree_cache = Some(snapshot);
+            self.stale = None;
+            self.comparison = None;
+            self.refresh_search();
+        }
+    }
+
+    /// Tree options for walking below a directory at `depth` (0 for the
+    /// root), and the depth relative to it at which lazy loading cuts the
+    /// walk short, if it does.
+    fn walk_config(&self, depth: usize) -> (TreeConfig, Option<usize>) {
+        let mut config = self.tree_config.clone();
+        config.max_depth = config.max_depth.map(|max| max.saturating_sub(depth));
+        let cut = self
+            .lazy_depth
+            .filter(|&lazy| config.max_depth.is_none_or(|max| max > lazy));
+        if cut.is_some() {
+            config.max_depth = cut;
+        }
+        (config, cut)
+    }
+
+    /// Remember the directories at depth `cut` of a walk as unloaded.
+    fn mark_unloaded(&mut self, walked: &TreeSnapshot, cut: Option<usize>) {
+        let Some(cut) = cut else {
+            return;
+        };
+        self.unloaded.extend(
+            walked
+                .entries
+                .iter()
+                .filter(|e| e.is_dir && e.depth == cut && e.error.is_none())
+                .map(|e| e.path.clone()),
+        );
+    }
+
+    /// Walk the directories expanded past the lazy depth and graft them
+    /// onto `snapshot`, parents before their children.
+    fn load_subtrees(&mut self, snapshot: &mut TreeSnapshot) {
+        let loaded: Vec<PathBuf> = self.loaded.iter().cloned().collect();
+        for dir in loaded {
+            // Gone, or within a level that was walked anyway.
+            if !self.unloaded.remove(&dir) {
+                continue;
+            }
+            let depth = dir
+                .strip_prefix(&self.path)
+                .map_or(0, |p| p.components().count());
+            let (config, cut) = self.walk_config(depth);
+            let subtree = self.tree_builder.build_subtree(&self.path, &dir, &config);
+            self.mark_unloaded(&subtree, cut);
+            snapshot.graft(&dir, subtree);
+        }
+    }
+
+    /// Graft the members of each open archive below it. An archive is
+    /// listed again only when its modification time changed; one that is
+    /// gone or cannot be read is closed.
+    fn graft_archives(&mut self, snapshot: &mut TreeSnapshot) {
+        let open: Vec<PathBuf> = self.open_archives.iter().cloned().collect();
+        self.archive_listings
+            .retain(|archive, _| self.open_archives.contains(archive));
+        for archive in open {
+            let modified = match archive.metadata() {
+                Ok(meta) => meta.modified().ok(),
+                Err(_) => {
+                    self.open_archives.remove(&archive);
+                    self.archive_listings.remove(&archive);
+                    continue;
+                }
+            };
+            let listed = self
+                .archive_listings
+                .get(&archive)
+                .is_some_and(|(at, _)| at.is_some() && *at == modified);
+            if !listed {
+                match list_archive(&archive) {
+                    Ok(members) => {
+                        let subtree = archive_subtree(&archive, &members);
+                        self.archive_listings
+                            .insert(archive.clone(), (modified, subtree));
+                    }
+                    Err(e) => {
+                        self.toasts.error(e, Instant::now());
+                        self.open_archives.remove(&archive);
+                        self.archive_listings.remove(&archive);
+                        continue;
+                    }
+                }
+            }
+            if let Some((_, subtree)) = self.archive_listings.get(&archive) {
+                snapshot.graft(&archive, subtree.clone());
+            }
+        }
+    }
+
+    /// Lines for the entries of `snapshot` at `rows` with everything the
+    /// view adds to them: marks, columns, and the selection. The first
+    /// rows are the pinned headers, the rest consecutive entries; `tail`
+    /// (the truncation notice) follows them.
+    fn tree_lines(
+        &self,
+        snapshot: &TreeSnapshot,
+        rows: &[usize],
+        highlights: &HashMap<PathBuf, ActiveHighlight>,
+        tail: Option<Line<'static>>,
+        decorations: &LineDecorations<'_>,
+    ) -> Vec<Line<'static>> {
+        let r_cfg = decorations.config;
+        let below = &rows[self.sticky.len()..];
+        let entries = below.first().map_or(&[][..], |&first| {
+            &snapshot.entries[first..first + below.len()]
+        });
+        let mut tree_lines: Vec<Line<'static>> = self
+            .sticky
+            .iter()
+            .flat_map(|&i| {
+                let header = std::slice::from_ref(&snapshot.entries[i]);
+                tree_to_lines(header, r_cfg, highlights)
+            })
+            .collect();
+        tree_lines.extend(tree_to_lines(entries, r_cfg, highlights));
+        for (line, &row) in tree_lines.iter_mut().zip(rows) {
+            let entry = &snapshot.entries[row];
+            if entry.is_dir
+                && (self.collapsed.contains(&entry.path) || self.unloaded.contains(&entry.path))
+            {
+                line.spans.push(collapsed_span(r_cfg));
+            }
+            if self.case_collisions.contains(&entry.path) {
+                line.spans.push(case_collision_span(r_cfg));
+            }
+            if let Some(original) = self.hard_links.get(&entry.path) {
+                let original = original.strip_prefix(&self.path).unwrap_or(original);
+                line.spans.push(hard_link_span(original, r_cfg));
+            }
+            let stale = self.stale.as_ref().filter(|_| self.show_stale);
+            let heat = self
+                .show_heatmap
+                .then(|| self.activity.level(&entry.path, decorations.now))
+                .flatten();
+            // A change highlight wins over heat, and heat over stale marks.
+            if highlights.kind_of(&entry.path).is_none() {
+                if let Some(level) = heat {
+                    *line = mark_heat(std::mem::take(line), level, r_cfg);
+                } else if stale.is_some_and(|s| s.contains(&entry.path)) {
+                    *line = mark_stale(std::mem::take(line), r_cfg);
+                }
+            }
+            if let Some(channels) = decorations.overlays.get(&entry.path) {
+                // Layers below `Changes` give way while a change is highlighted.
+                let changed = r_cfg.use_color && highlights.kind_of(&entry.path).is_some();
+                let shown: Vec<Channel> = channels
+                    .iter()
+                    .copied()
+                    .filter(|&c| !changed || c > Channel::Changes)
+                    .collect();
+                *line = mark_channels(std::mem::take(line), &shown, r_cfg);
+            }
+        }
+        if let Some(summaries) = self.summaries.as_ref().filter(|_| self.show_summary) {
+            for (line, &row) in tree_lines.iter_mut().zip(rows) {
+                let entry = &snapshot.entries[row];
+                if !entry.is_dir {
+                    continue;
+                }
+                if let Some(summary) = summaries.get(&entry.path).filter(|s| !s.is_empty()) {
+                    line.spans.push(summary_span(summary, r_cfg));
+                }
+            }
+        }
+        if self.deleted.is_some() {
+            for line in tree_lines.iter_mut() {
+                *line = std::mem::take(line).patch_style(DELETED_STYLE);
+            }
+        }
+        for line in tree_lines.iter_mut().take(self.sticky.len()) {
+            *line = std::mem::take(line).patch_style(STICKY_STYLE);
+        }
+        let split_focused = self.split.as_ref().is_some_and(|s| s.focused);
+        if let Some(line) = rows
+            .iter()
+            .position(|&row| row == self.selected)
+            .and_then(|i| tree_lines.get_mut(i))
+            .filter(|_| !split_focused)
+        {
+            *line = std::mem::take(line).patch_style(SELECTED_STYLE);
+        }
+        tree_lines.extend(tail);
+        if let Some(differences) = decorations.differences {
+            for (line, &row) in tree_lines.iter_mut().zip(rows) {
+                let difference = differences.left.get(&snapshot.entries[row].path).copied();
+                line.spans.insert(0, compare_span(difference, r_cfg));
+            }
         }
+        if let Some(owners) = decorations.owners {
+            let widths = decorations.owner_widths;
+            for (i, line) in tree_lines.iter_mut().enumerate() {
+                let owner = rows
+                    .get(i)
+                    .and_then(|&row| owners.get(row))
+                    .and_then(|o| o.as_ref())
+                    .map(|(user, group)| (user.as_str(), group.as_str()));
+                line.spans.insert(0, owner_span(owner, widths, r_cfg));
+            }
+        }
+        if self.permissions {
+            for (i, line) in tree_lines.iter_mut().enumerate() {
+                let mode = rows.get(i).and_then(|&row| snapshot.entries[row].mode);
+                line.spans.insert(0, permissions_span(mode, r_cfg));
+            }
+        }
+        if !decorations.marks.is_empty() {
+            for (i, line) in tree_lines.iter_mut().enumerate() {
+                let marked = rows
+                    .get(i)
+                    .is_some_and(|&row| decorations.marks.contains(&snapshot.entries[row].path));
+                line.spans.insert(0, mark_margin_span(marked, r_cfg));
+            }
+        }
+        if self.gutter() {
+            let width = gutter_width(snapshot.entries.len()) - 1;
+            for (i, line) in tree_lines.iter_mut().enumerate() {
+                let number = rows.get(i).map(|&row| {
+                    if !self.relative_line_numbers || (row == self.selected && self.line_numbers) {
+                        row + 1
+                    } else {
+                        row.abs_diff(self.selected)
+                    }
+                });
+                line.spans.insert(0, gutter_span(number, width, r_cfg));
+            }
+        }
+        tree_lines
     }
 
     /// Rebuild the tree (if cache invalidated) and render a complete frame via ratatui.
     fn render(&mut self) {
+        let _span = tracing::trace_span!("render").entered();
         // Prune expired highlights and get the active set
         let now = Instant::now();
-        let active_highlights = self.highlights.active_set(now);
+        let fps = self.scheduler.fps(now);
+        let changes = self.highlights.get_mut(Channel::Changes);
+        let mut active_highlights = changes.active(now);
+        let next_change = changes.next_change(now);
+        self.scheduler.drawn(now);
+        self.scheduler.cancel_wake();
+        if let Some(at) = next_change.filter(|_| !self.headless) {
+            self.scheduler.wake_at(at);
+        }
+        let split_change = self.split.as_ref().and_then(|s| s.next_change(now));
+        if let Some(at) = split_change.filter(|_| !self.headless) {
+            self.scheduler.wake_at(at);
+        }
+        self.toasts.prune(now);
+        if let Some(at) = self.toasts.next_expiry().filter(|_| !self.headless) {
+            self.scheduler.wake_at(at);
+        }
+        let overlays = self.highlights.overlays(now);
+        let marks = self.highlights.get_mut(Channel::Marked).active_set(now);
 
-        if self.tree_cache.is_none() {
-            self.tree_cache = Some(self.tree_builder.build_tree(self.path, self.tree_config));
+        self.ensure_tree();
+        if std::mem::take(&mut self.jobs_stale) {
+            self.request_summary();
+            self.request_heavy();
+        }
+        if self.show_stale && self.stale.is_none() {
+            self.stale = self.tree_cache.as_ref().map(|s| self.stale_entries(s));
+        }
+        self.clamp_selection();
+        let side_pane = if self.show_heavy {
+            Some((
+                " Heaviest paths ",
+                heavy_report_lines(self.heavy.as_ref(), &self.path),
+            ))
+        } else if self.show_changers {
+            self.activity.prune(now);
+            if let Some(at) = self.activity.next_expiry().filter(|_| !self.headless) {
+                self.scheduler.wake_at(at);
+            }
+            let top = self.activity.top_dirs(TOP_CHANGERS, now);
+            Some((
+                " Top changers ",
+                top_changers_lines(&top, &self.path, self.activity.window()),
+            ))
+        } else if self.show_preview {
+            let entry = self.selected_entry().cloned();
+            Some((" Preview ", self.preview_lines(entry.as_ref())))
+        } else {
+            None
+        };
+        if self.compare && self.comparison.is_none() {
+            if let (Some(snapshot), Some(split)) = (self.tree_cache.as_ref(), self.split.as_mut()) {
+                let unlisted: HashSet<PathBuf> =
+                    self.collapsed.union(&self.unloaded).cloned().collect();
+                let main = CompareSide {
+                    root: &self.path,
+                    snapshot,
+                    unlisted: &unlisted,
+                };
+                self.comparison = Some(compare(&main, &split.compare_side(&self.tree_config)));
+            }
         }
+        let differences = self
+            .comparison
+            .as_ref()
+            .filter(|_| self.compare && self.split.is_some());
         let Some(snapshot) = self.tree_cache.as_ref() else {
             return;
         };
@@ -126,22 +2453,185 @@ impl<'a> AppState<'a> {
             .map(|s| (s.width, s.height))
             .unwrap_or((80, 24));
 
+        let owners: Option<Vec<Option<(String, String)>>> =
+            self.owner_names.as_mut().map(|names| {
+                snapshot
+                    .entries
+                    .iter()
+                    .map(|e| {
+                        e.owner.map(|(uid, gid)| {
+                            (names.user(uid).to_string(), names.group(gid).to_string())
+                        })
+                    })
+                    .collect()
+            });
+        let owner_widths = owners.as_ref().map(|owners| {
+            let widths = self.render_config.ambiguous_width;
+            owners
+                .iter()
+                .flatten()
+                .fold((0, 0), |(u, g), (user, group)| {
+                    (
+                        u.max(widths.str_width(user)),
+                        g.max(widths.str_width(group)),
+                    )
+                })
+        });
+
+        // Columns left for tree lines beside the split and side panes,
+        // gutter, and permissions and owner columns.
+        let main_width = if self.split.is_some() {
+            term_width - term_width / 2
+        } else {
+            term_width
+        };
+        let pane_width = if side_pane.is_some() {
+            main_width * 3 / 5
+        } else {
+            main_width
+        };
+        let mut tree_width = pane_width;
+        if self.gutter() {
+            tree_width = tree_width.saturating_sub(gutter_width(entry_count_shown) as u16);
+        }
+        if !marks.is_empty() {
+            tree_width = tree_width.saturating_sub(1);
+        }
+        if differences.is_some() {
+            tree_width = tree_width.saturating_sub(1);
+        }
+        if self.permissions {
+            tree_width = tree_width.saturating_sub(PERMISSIONS_WIDTH as u16);
+        }
+        if let Some((user, group)) = owner_widths {
+            tree_width = tree_width.saturating_sub((user + group + 2) as u16);
+        }
+        // Ranked matches are no tree; they are listed as paths.
         let r_cfg = RenderConfig {
-            use_color: self.use_color,
-            terminal_width: term_width,
+            terminal_width: tree_width,
+            listing: if self.ranked_view() {
+                Listing::Flat
+            } else {
+                self.render_config.listing
+            },
+            ..self.render_config.clone()
+        };
+
+        let decorations = LineDecorations {
+            config: &r_cfg,
+            overlays: &overlays,
+            marks: &marks,
+            differences,
+            owners: owners.as_deref(),
+            owner_widths: owner_widths.unwrap_or_default(),
+            now,
         };
 
-        let mut tree_lines = tree_to_lines(&snapshot.entries, &r_cfg, &active_highlights);
+        // A short tree on a wide pane is packed into columns, so that it
+        // shows whole without scrolling.
         let truncated = entry_count_total > entry_count_shown;
-        if truncated {
-            tree_lines.push(truncation_line(entry_count_shown, entry_count_total));
+        let tree_area_height = area_height.saturating_sub(self.chrome_rows()) as usize;
+        self.sticky.clear();
+        self.column_layout = None;
+        let mut packed = None;
+        if self.columns
+            && !truncated
+            && entry_count_shown > tree_area_height
+            && entry_count_shown.div_ceil(tree_area_height.max(1)) * (1 + COLUMN_GAP)
+                <= usize::from(pane_width) + COLUMN_GAP
+        {
+            let rows: Vec<usize> = (0..entry_count_shown).collect();
+            let path_index = &self.path_index;
+            inherit_to_ancestors(&mut active_highlights, &self.path, |p| {
+                path_index.get(p).is_some()
+            });
+            let lines = self.tree_lines(snapshot, &rows, &active_highlights, None, &decorations);
+            let widths: Vec<usize> = lines.iter().map(Line::width).collect();
+            packed = pack_columns(&widths, usize::from(pane_width), tree_area_height)
+                .map(|layout| (lines, layout));
         }
-        let tree_area_height = area_height.saturating_sub(2) as usize;
-        self.scroll
-            .update_total_and_clamp(tree_lines.len(), tree_area_height);
+        let tree_lines = if let Some((lines, (per_column, widths))) = packed {
+            self.scroll
+                .update_total_and_clamp(per_column, tree_area_height);
+            self.column_layout = Some((per_column, widths));
+            lines
+        } else {
+            // Scroll first: changes outside the rows on screen light up their
+            // visible ancestors.
+            self.scroll.update_total_and_clamp(
+                entry_count_shown + usize::from(truncated),
+                tree_area_height,
+            );
+            self.scroll.ensure_visible(self.selected, tree_area_height);
+            // Pin the directories enclosing the top rows above them. A
+            // selection they would cover scrolls to just below them. Rows of a
+            // flat list name their directories already.
+            if self.sticky_headers && !self.flat() && !self.ranked_view() {
+                let mut sticky = sticky_headers(snapshot, self.scroll.offset, tree_area_height);
+                let covered = |sticky: &[usize], offset: usize| {
+                    (offset..offset + sticky.len()).contains(&self.selected)
+                };
+                if covered(&sticky, self.scroll.offset) {
+                    self.scroll.offset = self.selected.saturating_sub(sticky.len());
+                    sticky = sticky_headers(snapshot, self.scroll.offset, tree_area_height);
+                }
+                if !covered(&sticky, self.scroll.offset) {
+                    self.sticky = sticky;
+                }
+            }
+            let scroll_offset = self.scroll.offset();
+            // Only the rows on screen are turned into lines: the pinned
+            // directories, then the rows below them.
+            let window = snapshot.window(
+                scroll_offset + self.sticky.len(),
+                tree_area_height - self.sticky.len(),
+            );
+            let rows: Vec<usize> = self
+                .sticky
+                .iter()
+                .copied()
+                .chain(window.offset..window.offset + window.entries.len())
+                .collect();
+            let shown: HashSet<&Path> = rows
+                .iter()
+                .map(|&i| snapshot.entries[i].path.as_path())
+                .collect();
+            inherit_to_ancestors(&mut active_highlights, &self.path, |p| shown.contains(p));
 
+            let truncation = (truncated
+                && (scroll_offset..scroll_offset + tree_area_height).contains(&entry_count_shown))
+            .then(|| {
+                let depth = snapshot.entries.iter().map(|e| e.depth).max().unwrap_or(0);
+                truncation_line(entry_count_shown, entry_count_total, depth)
+            });
+            self.tree_lines(
+                snapshot,
+                &rows,
+                &active_highlights,
+                truncation,
+                &decorations,
+            )
+        };
         let scroll_offset = self.scroll.offset();
 
+        // The split pane's tree, inside its border, with the legend for
+        // compare mode's marks along the bottom.
+        let split_pane = self.split.as_mut().map(|split| {
+            split.set_highlight_duration(Duration::from_secs(self.highlight_duration_secs));
+            let margin = u16::from(differences.is_some());
+            let config = RenderConfig {
+                terminal_width: (term_width / 2).saturating_sub(2 + margin),
+                search: None,
+                ..self.render_config.clone()
+            };
+            let title = format!(" {} ", format_watched_path(split.root()));
+            let height = tree_area_height.saturating_sub(2);
+            let right = differences.map(|d| &d.right);
+            let lines = split.lines(&self.tree_config, &config, height, right);
+            let legend = differences.map(|d| compare_legend_line(d.is_empty(), &config));
+            (title, lines, legend)
+        });
+
         // Build status bar
         let display_count = if truncated {
             format!(
@@ -159,14 +2649,105 @@ impl<'a> AppState<'a> {
         } else {
             format!("{} entries", entry_count_total)
         };
-        let path_str = format_watched_path(self.path);
-        let status = status_bar_line(&path_str, &display_count, self.last_change.as_deref());
+        let path_str = format_watched_path(&self.path);
+        let last_file = self
+            .last_changed_path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|name| name.to_string_lossy());
+        let status_bar = status_bar(
+            &self.status_template,
+            &StatusValues {
+                path: &path_str,
+                entries: &display_count,
+                last_change: self.last_change.as_deref(),
+                last_file: last_file.as_deref(),
+                follow: self.follow,
+                watcher: crate::watcher::backend_name(),
+                fps,
+                search: self.search.as_ref().map(|s| s.position(self.selected)),
+            },
+        );
+        let status = status_bar.line;
+        // Remember the columns of the file name for clicks.
+        let file_span = status_bar.last_file_span.map(|i| {
+            let offset: usize = status.spans[..i].iter().map(Span::width).sum();
+            (offset as u16, status.spans[i].width() as u16)
+        });
 
         // Build help bar
-        let help = help_bar_line();
+        let help = match (&self.palette, &self.pending_delete) {
+            (Some(prompt), _) => palette_line(&prompt.input, &prompt.feedback()),
+            (None, Some(path)) => {
+                let is_dir = path.symlink_metadata().is_ok_and(|m| m.is_dir());
+                delete_prompt_line(&self.relative(path), is_dir, self.trash)
+            }
+            (None, None) => help_bar_line(),
+        };
+
+        let banner = self
+            .deleted
+            .as_ref()
+            .map(|d| root_deleted_line(&path_str, d.waiting, &r_cfg));
+        let toasts: Vec<Line> = self.toasts.iter().map(|t| toast_line(t, &r_cfg)).collect();
+        let stats = self.show_stats.then(|| {
+            stats_lines(
+                &self.stats,
+                fps,
+                entry_count_shown,
+                snapshot.estimated_size(),
+                now,
+            )
+        });
+        let announce = self
+            .announce
+            .map(|at| (at, announcement_line(self.announcement.as_deref())));
+
+        // Nothing on screen would change: skip the draw (headless runs
+        // record every frame).
+        let shown = (
+            (term_width, area_height, scroll_offset),
+            (&tree_lines, &self.column_layout, &split_pane),
+            (&side_pane, &banner, &announce, &toasts, &stats),
+            (&status, &help),
+        );
+        if !self.last_frame.changed(&shown) && !self.headless {
+            self.stats.record_skipped_frame();
+            return;
+        }
+
+        let mut drawn_tree_area = Rect::default();
+        let mut drawn_split_area = Rect::default();
+        let mut drawn_file_area = None;
+        let column_layout = &self.column_layout;
+        let drawn = self.terminal.draw(|frame| {
+            let mut area = frame.area();
+
+            // Root-deleted banner (1 row) above everything else
+            if let Some(banner) = banner {
+                let rows =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(area);
+                frame.render_widget(Paragraph::new(banner), rows[0]);
+                area = rows[1];
+            }
 
-        let _ = self.terminal.draw(|frame| {
-            let area = frame.area();
+            // Announcement line (1 row) at the very top or bottom
+            if let Some((at, line)) = announce {
+                let (row, rest) = match at {
+                    AnnouncePosition::Top => {
+                        let rows = Layout::vertical([Constraint::Length(1), Constraint::Min(1)])
+                            .split(area);
+                        (rows[0], rows[1])
+                    }
+                    AnnouncePosition::Bottom => {
+                        let rows = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+                            .split(area);
+                        (rows[1], rows[0])
+                    }
+                };
+                frame.render_widget(Paragraph::new(line), row);
+                area = rest;
+            }
 
             // Split: tree area, status bar (1 row), help bar (1 row)
             let chunks = Layout::vertical([
@@ -176,11 +2757,86 @@ impl<'a> AppState<'a> {
             ])
             .split(area);
 
-            // Tree paragraph with scroll
-            let tree_widget = Paragraph::new(tree_lines).scroll((scroll_offset as u16, 0));
-            frame.render_widget(tree_widget, chunks[0]);
+            // The split pane takes the right half
+            let mut main_area = chunks[0];
+            if let Some((title, lines, legend)) = split_pane {
+                let panes =
+                    Layout::horizontal([Constraint::Length(main_width), Constraint::Min(1)])
+                        .split(chunks[0]);
+                let mut block = Block::bordered().title(title);
+                if let Some(legend) = legend {
+                    block = block.title_bottom(legend);
+                }
+                drawn_split_area = block.inner(panes[1]);
+                frame.render_widget(Paragraph::new(lines).block(block), panes[1]);
+                main_area = panes[0];
+            }
+
+            // Tree area, optionally split to make room for the side pane
+            let tree_area = if let Some((title, lines)) = side_pane {
+                let panes =
+                    Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .split(main_area);
+                let pane_widget = Paragraph::new(lines).block(Block::bordered().title(title));
+                frame.render_widget(pane_widget, panes[1]);
+                panes[0]
+            } else {
+                main_area
+            };
+
+            // Tree paragraph, already cut to the rows on screen, or one
+            // paragraph per column of a packed tree
+            if let Some((per_column, widths)) = column_layout {
+                let mut x = tree_area.x;
+                for (lines, &width) in tree_lines.chunks(*per_column).zip(widths) {
+                    let column = Rect {
+                        x,
+                        width: width.min(tree_area.right().saturating_sub(x)),
+                        ..tree_area
+                    };
+                    frame.render_widget(Paragraph::new(lines.to_vec()), column);
+                    x = column.right().saturating_add(COLUMN_GAP as u16);
+                }
+            } else {
+                frame.render_widget(Paragraph::new(tree_lines), tree_area);
+            }
+            drawn_tree_area = tree_area;
+
+            // Toasts stacked in the tree pane's bottom-right corner
+            if !toasts.is_empty() {
+                let width = toasts.iter().map(Line::width).max().unwrap_or(0) as u16;
+                let height = (toasts.len() as u16).min(tree_area.height);
+                let corner = Rect {
+                    x: tree_area.right().saturating_sub(width).max(tree_area.x),
+                    y: tree_area.bottom() - height,
+                    width: width.min(tree_area.width),
+                    height,
+                };
+                frame.render_widget(Clear, corner);
+                frame.render_widget(Paragraph::new(toasts).right_aligned(), corner);
+            }
+
+            // Debug overlay in the tree pane's top-right corner
+            if let Some(lines) = stats {
+                let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+                let height = lines.len() as u16 + 2;
+                let corner = Rect {
+                    x: tree_area.right().saturating_sub(width).max(tree_area.x),
+                    y: tree_area.y,
+                    width: width.min(tree_area.width),
+                    height: height.min(tree_area.height),
+                };
+                frame.render_widget(Clear, corner);
+                frame.render_widget(
+                    Paragraph::new(lines).block(Block::bordered().title(" Stats ")),
+                    corner,
+                );
+            }
 
             // Status bar
+            drawn_file_area = file_span.map(|(offset, width)| {
+                Rect::new(chunks[1].x + offset, chunks[1].y, width, 1).intersection(chunks[1])
+            });
             let status_widget = Paragraph::new(status);
             frame.render_widget(status_widget, chunks[1]);
 
@@ -188,69 +2844,304 @@ impl<'a> AppState<'a> {
             let help_widget = Paragraph::new(help);
             frame.render_widget(help_widget, chunks[2]);
         });
+        if let (Ok(frame), Some(frames)) = (drawn.as_ref(), self.frames.as_mut()) {
+            frames.push(frame.buffer.clone());
+        }
+        if drawn.is_ok() {
+            self.stats.record_frame(now.elapsed());
+        }
+        self.tree_area = drawn_tree_area;
+        self.split_area = drawn_split_area;
+        self.status_file_area = drawn_file_area;
     }
 
-    /// Render a message (e.g., "Directory deleted") and wait briefly.
-    fn render_message(&mut self, lines: Vec<Line<'static>>) {
-        let _ = self.terminal.draw(|frame| {
-            let area = frame.area();
-            let widget = Paragraph::new(lines);
-            frame.render_widget(widget, area);
-        });
+    /// Apply a filesystem event. Once the root is deleted, further events
+    /// are ignored and the last snapshot stays on screen.
+    fn handle_watch_event(&mut self, event: WatchEvent) -> Flow {
+        if self.deleted.is_some() && !matches!(event, WatchEvent::Error(_)) {
+            return Flow::Continue;
+        }
+        match event {
+            WatchEvent::Changed(mut changes) => {
+                // Only changes below a zoomed-in root are shown.
+                if self.path != self.watch_root {
+                    changes.retain(|c| c.path.starts_with(&self.path));
+                    if changes.is_empty() {
+                        self.reload_ignore_file();
+                        return Flow::Continue;
+                    }
+                }
+                self.stats.record_events(changes.len(), Instant::now());
+                self.reload_ignore_file();
+                self.last_change = Some(chrono_lite_now());
+                // Invalidate so the next frame rebuilds the tree; until then
+                // the old one says which remote paths are directories.
+                let listed = self.tree_cache.take();
+                self.jobs_stale = true;
+                // Highlight both files and directories; a directory that is only
+                // reported because its children changed gets the dimmer style.
+                let now = Instant::now();
+                let changed: Vec<PathBuf> = changes.iter().map(|c| c.path.clone()).collect();
+                self.invalidate_details(&changed);
+                // Telling temp files apart takes the local disk, which a
+                // remote tree's paths are not on.
+                let coalesced = if self.read_only {
+                    Coalesced {
+                        changes,
+                        vanished: Vec::new(),
+                    }
+                } else {
+                    self.coalescer.process(changes, now)
+                };
+                for path in &coalesced.vanished {
+                    self.highlights.get_mut(Channel::Changes).remove(path);
+                }
+                if let Some(last) = coalesced.changes.last() {
+                    self.last_changed_path = Some(last.path.clone());
+                    let path = last.path.strip_prefix(&self.path).unwrap_or(&last.path);
+                    self.announcement = Some(announcement(
+                        last.kind,
+                        &path.to_string_lossy(),
+                        self.last_change.as_deref().unwrap_or_default(),
+                        coalesced.changes.len() - 1,
+                    ));
+                }
+                self.activity.prune(now);
+                let is_dir = |path: &Path| match &listed {
+                    _ if !self.read_only => path.is_dir(),
+                    Some(tree) => self
+                        .path_index
+                        .get(path)
+                        .and_then(|row| tree.entries.get(row))
+                        .is_some_and(|e| e.path == path && e.is_dir),
+                    None => false,
+                };
+                for change in coalesced.changes.into_iter() {
+                    self.changes.record(change.kind);
+                    let kind = HighlightKind::classify(change.kind, is_dir(&change.path));
+                    // Directories reported for their children already get
+                    // the children's changes counted.
+                    if kind != HighlightKind::Descendant {
+                        self.activity.record(&self.path, &change.path, now);
+                    }
+                    let style = self.highlight_styles.for_change(change.kind);
+                    self.highlights.get_mut(Channel::Changes).insert_styled(
+                        change.path,
+                        now,
+                        kind,
+                        style,
+                    );
+                }
+                if self.follow {
+                    self.select_last_change();
+                } else if let Some(focus) = self.focus.clone().filter(|f| changed.contains(f)) {
+                    let _ = self.select_path(&focus);
+                }
+                // A zoomed-in root that is gone is zoomed out of.
+                while !self.zoomed_from.is_empty() && self.path.symlink_metadata().is_err() {
+                    self.zoom_out();
+                }
+                // Keep scroll position; render() will clamp if tree shrunk
+                self.scheduler.request(Trigger::Fs);
+            }
+            WatchEvent::RootDeleted => {
+                self.deleted = Some(DeletedRoot {
+                    snapshot: self.tree_cache.take().unwrap_or_default(),
+                    waiting: false,
+                });
+                self.palette = None;
+                self.pending_delete = None;
+                self.show_heavy = false;
+                self.scheduler.request(Trigger::Fs);
+            }
+            WatchEvent::Reattached => {
+                // The new mount may hold anything; rebuild from scratch.
+                self.tree_cache = None;
+                self.toasts.info("re-attached watcher", Instant::now());
+                self.jobs_stale = true;
+                self.scheduler.request(Trigger::Fs);
+            }
+            // Also logged by the watcher; printing would tear the screen.
+            WatchEvent::Error(e) => {
+                self.toasts.error(format!("watcher: {e}"), Instant::now());
+                self.scheduler.request(Trigger::Fs);
+            }
+        }
+        Flow::Continue
+    }
+
+    /// Apply a filesystem event from the split pane's watcher.
+    fn handle_split_event(&mut self, event: WatchEvent) -> Flow {
+        if let Some(split) = self.split.as_mut() {
+            split.handle_watch_event(event, &self.highlight_styles);
+            self.comparison = None;
+            self.scheduler.request(Trigger::Fs);
+        }
+        Flow::Continue
+    }
+
+    /// Apply a terminal input event through the keymap / click bindings.
+    fn handle_input(&mut self, event: Event) -> Flow {
+        let actions = match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.palette.is_some() => {
+                return self.palette_key(key);
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.pending_delete.is_some() => {
+                return self.confirm_key(key);
+            }
+            // Pasted text only ever goes into an open prompt; it must not be
+            // read as key bindings.
+            Event::Paste(text) => match self.palette.as_mut() {
+                Some(prompt) => {
+                    prompt.paste(&text);
+                    vec![Action::Redraw]
+                }
+                None => Vec::new(),
+            },
+            Event::Key(key)
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Char('w')
+                    && self.deleted.as_ref().is_some_and(|d| !d.waiting) =>
+            {
+                if let Some(deleted) = self.deleted.as_mut() {
+                    deleted.waiting = true;
+                }
+                vec![Action::Redraw]
+            }
+            // Digits bound to nothing make up a count for the next key.
+            Event::Key(key) if key.kind == KeyEventKind::Press => match self.keymap.action(&key) {
+                None if self.count.push(&key) => Vec::new(),
+                action => match (action, self.count.take()) {
+                    (Some(action), Some(count)) => action.counted(count),
+                    (action, _) => action.into_iter().collect(),
+                },
+            },
+            Event::Mouse(mouse) if self.mouse.enabled => self.mouse_actions(mouse),
+            Event::Resize(_, _) => vec![Action::Redraw],
+            _ => Vec::new(),
+        };
+        for action in actions {
+            if let Flow::Exit(reason) = self.dispatch(action) {
+                return Flow::Exit(reason);
+            }
+        }
+        Flow::Continue
     }
 
-    /// Scroll up by `n` lines.
-    fn scroll_up(&mut self, n: usize) {
-        self.scroll.scroll_up(n);
+    /// Store a finished summary computation unless a newer one was
+    /// requested, and start that one.
+    fn on_summary(&mut self, (generation, summaries): (u64, SummaryMap)) {
+        if self.summary_job.finish(generation) {
+            self.summaries = Some(summaries);
+            self.scheduler.request(Trigger::Job);
+        }
+        if self.summary_job.take_rerun() {
+            self.request_summary();
+        }
+    }
+
+    /// Store a finished heaviest-paths report unless it is stale, and
+    /// start the one asked for meanwhile.
+    fn on_heavy(&mut self, (generation, report): (u64, HeavyReport)) {
+        if self.heavy_job.finish(generation) && self.show_heavy {
+            self.heavy = Some(report);
+            self.scheduler.request(Trigger::Job);
+        }
+        if self.heavy_job.take_rerun() {
+            self.request_heavy();
+        }
+    }
+
+    /// Cache finished directory details for the preview pane, unless they
+    /// were invalidated while being computed.
+    fn on_details(&mut self, (generation, dir, details): (u64, PathBuf, DirDetails)) {
+        if generation != self.details_generation {
+            return;
+        }
+        self.details_pending.remove(&dir);
+        self.details_cache.insert(dir, details);
+        if self.show_preview {
+            self.scheduler.request(Trigger::Job);
+        }
     }
 
-    /// Scroll down by `n` lines.
-    fn scroll_down(&mut self, n: usize) {
-        self.scroll.scroll_down(n);
+    /// Draw a frame if one was requested and the scheduler lets it through.
+    fn draw_if_due(&mut self) {
+        if self.scheduler.due(Instant::now()) {
+            self.render();
+        }
     }
 
-    /// Scroll to top.
-    fn scroll_home(&mut self) {
-        self.scroll.scroll_home();
+    /// How long the loop may wait for events before it has to draw or poll
+    /// for an interrupt.
+    fn idle_timeout(&self) -> Duration {
+        self.scheduler
+            .timeout(Instant::now())
+            .map_or(TICK, |timeout| timeout.min(TICK))
     }
 
-    /// Scroll to bottom.
-    fn scroll_end(&mut self) {
-        self.scroll.scroll_end();
+    /// Rows taken by the status and help bars (and the deleted-root banner).
+    fn chrome_rows(&self) -> u16 {
+        2 + u16::from(self.deleted.is_some()) + u16::from(self.announce.is_some())
     }
 
     /// Get the visible tree area height (minus status bar + help bar).
     fn visible_height(&self) -> usize {
         let h = self.terminal.size().map(|s| s.height).unwrap_or(24);
-        h.saturating_sub(2) as usize
+        h.saturating_sub(self.chrome_rows()) as usize
+    }
+
+    /// Summary of the session so far.
+    fn session(&self, reason: ExitReason) -> Session {
+        Session {
+            reason,
+            duration: self.started.elapsed(),
+            changes: self.changes,
+        }
     }
 }
 
-/// Internal implementation of the main loop, parameterized over a `TreeBuilder`.
-fn run_with_tree_builder(
+/// Run the main application loop with snapshots from `tree_builder`, e.g.
+/// a `SourceTreeBuilder` over another backend. The watcher behind `fs_rx`
+/// decides when to rebuild. Blocks until the loop stops and returns a
+/// summary of the session.
+pub fn run_with_tree_builder(
     terminal: Term,
     path: &Path,
     tree_config: &TreeConfig,
     render_config: &RenderConfig,
     fs_rx: Receiver<WatchEvent>,
     tree_builder: &dyn TreeBuilder,
-    quiet: bool,
-) {
+    loop_config: &LoopConfig,
+) -> Session {
+    let mut fs_rx = fs_rx;
     let shutdown = Arc::new(AtomicBool::new(false));
-    let interrupted = Arc::new(AtomicBool::new(false));
-
-    {
-        let interrupted = interrupted.clone();
-        let _ = ctrlc::set_handler(move || {
-            interrupted.store(true, Ordering::SeqCst);
-        });
-    }
+    let (jobs, job_rx) = job_channels();
+    let mut state = AppState::new(
+        terminal,
+        path,
+        tree_config,
+        render_config,
+        tree_builder,
+        loop_config,
+        jobs,
+    );
+    state.signals = terminal::shutdown_on_signals();
 
-    // Spawn keyboard input reader
+    // Spawn keyboard/mouse input reader
     let (key_tx, key_rx) = crossbeam_channel::unbounded();
     let shutdown_clone = shutdown.clone();
+    let gate = Arc::clone(&state.input_gate);
     let input_handle = thread::spawn(move || {
         while !shutdown_clone.load(Ordering::Relaxed) {
+            if gate.paused.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(20));
+                continue;
+            }
+            let _reading = gate.reading.lock().unwrap_or_else(|e| e.into_inner());
+            if gate.paused.load(Ordering::SeqCst) {
+                continue;
+            }
             if event::poll(std::time::Duration::from_millis(100)).unwrap_or(false) {
                 if let Ok(evt) = event::read() {
                     let _ = key_tx.send(evt);
@@ -259,172 +3150,194 @@ fn run_with_tree_builder(
         }
     });
 
-    let mut state = AppState::new(
-        terminal,
-        path,
-        tree_config,
-        render_config.use_color,
-        tree_builder,
-    );
-
     // Initial render
-    state.render();
+    state.restore_selection();
+    state.request_summary();
+    state.scheduler.request(Trigger::Input);
+    state.draw_if_due();
+
+    let mut control_rx = loop_config
+        .control
+        .clone()
+        .unwrap_or_else(crossbeam_channel::never);
+    let mut split_rx = state
+        .split
+        .as_ref()
+        .map_or_else(crossbeam_channel::never, SplitPane::events);
 
     // Main event loop
-    loop {
-        select! {
-            recv(fs_rx) -> msg => {
-                match msg {
-                    Ok(WatchEvent::Changed(paths)) => {
-                        state.last_change = Some(chrono_lite_now());
-                        state.tree_cache = None; // invalidate so render() rebuilds tree
-                        // Highlight both files and directories; parent directories may also change.
-                        let now = Instant::now();
-                        for p in paths.into_iter() {
-                            state.highlights.insert(p, now);
-                        }
-                        // Keep scroll position; render() will clamp if tree shrunk
-                        state.render();
-                    }
-                    Ok(WatchEvent::RootDeleted) => {
-                        state.render_message(vec![
-                            Line::raw(format!("Directory deleted: {}", path.display())),
-                            Line::raw("Exiting...".to_string()),
-                        ]);
-                        break;
-                    }
-                    Ok(WatchEvent::Error(e)) => {
-                        if !quiet {
-                            eprintln!("Watcher error: {}", e);
-                        }
-                    }
-                    Err(_) => {
-                        // Channel closed, watcher thread died
-                        break;
-                    }
+    let reason = loop {
+        let idle = state.idle_timeout();
+        let flow = select! {
+            recv(fs_rx) -> msg => match msg {
+                Ok(event) => state.handle_watch_event(event),
+                // The watcher may stop once the root is gone; keep showing it
+                Err(_) if state.deleted.is_some() => {
+                    fs_rx = crossbeam_channel::never();
+                    Flow::Continue
+                }
+                // Channel closed, watcher thread died
+                Err(_) => Flow::Exit(ExitReason::WatcherStopped),
+            },
+            recv(split_rx) -> msg => match msg {
+                Ok(event) => state.handle_split_event(event),
+                // The pane keeps its last tree.
+                Err(_) => {
+                    split_rx = crossbeam_channel::never();
+                    Flow::Continue
+                }
+            },
+            recv(job_rx.summary) -> msg => {
+                if let Ok(result) = msg {
+                    state.on_summary(result);
                 }
+                Flow::Continue
             }
-            recv(key_rx) -> msg => {
-                match msg {
-                    Ok(Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, .. })) => {
-                        match code {
-                            KeyCode::Char('q') => break,
-                            KeyCode::Char('r') => {
-                                state.highlights.clear();
-                                state.render();
-                            }
-                            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                state.scroll_up(1);
-                                state.render();
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                state.scroll_down(1);
-                                state.render();
-                            }
-                            KeyCode::PageUp => {
-                                let h = state.visible_height();
-                                state.scroll_up(h);
-                                state.render();
-                            }
-                            KeyCode::PageDown => {
-                                let h = state.visible_height();
-                                state.scroll_down(h);
-                                state.render();
-                            }
-                            KeyCode::Home => {
-                                state.scroll_home();
-                                state.render();
-                            }
-                            KeyCode::End => {
-                                state.scroll_end();
-                                state.render();
-                            }
-                            KeyCode::Char('+') => {
-                                // Increase highlight duration by 1s, saturating at a reasonable upper bound.
-                                if state.highlight_duration_secs < 3600 {
-                                    state.highlight_duration_secs += 1;
-                                    state
-                                        .highlights
-                                        .set_duration(std::time::Duration::from_secs(
-                                            state.highlight_duration_secs,
-                                        ));
-                                }
-                                state.render();
-                            }
-                            KeyCode::Char('-') => {
-                                // Decrease highlight duration by 1s, clamped at 0 (disable).
-                                if state.highlight_duration_secs > 0 {
-                                    state.highlight_duration_secs -= 1;
-                                    state
-                                        .highlights
-                                        .set_duration(std::time::Duration::from_secs(
-                                            state.highlight_duration_secs,
-                                        ));
-                                } else {
-                                    state.highlights.set_duration(std::time::Duration::from_secs(0));
-                                }
-                                state.render();
-                            }
-                            _ => {}
-                        }
-                    }
-                    Ok(Event::Resize(_, _)) => {
-                        state.render();
-                    }
-                    _ => {}
+            recv(job_rx.heavy) -> msg => {
+                if let Ok(result) = msg {
+                    state.on_heavy(result);
                 }
+                Flow::Continue
             }
-            default(std::time::Duration::from_millis(100)) => {
-                if interrupted.load(Ordering::SeqCst) {
-                    break;
+            recv(job_rx.details) -> msg => {
+                if let Ok(result) = msg {
+                    state.on_details(result);
                 }
+                Flow::Continue
             }
+            recv(key_rx) -> msg => match msg {
+                Ok(event) => state.handle_input(event),
+                Err(_) => Flow::Continue,
+            },
+            recv(control_rx) -> msg => match msg {
+                Ok(request) => state.handle_control(request),
+                Err(_) => {
+                    control_rx = crossbeam_channel::never();
+                    Flow::Continue
+                }
+            },
+            default(idle) => {
+                if let Some(signal) = state.signals.requested() {
+                    Flow::Exit(signal.into())
+                } else if state.signals.take_suspend() {
+                    state.suspend();
+                    Flow::Continue
+                } else {
+                    // The file may be outside the watched root.
+                    state.reload_ignore_file();
+                    Flow::Continue
+                }
+            }
+        };
+        if let Flow::Exit(reason) = flow {
+            break reason;
         }
-    }
+        // The root was moved above the watched one.
+        if let Some(events) = state.rewatched.take() {
+            fs_rx = events;
+        }
+        state.draw_if_due();
+    };
 
     // Signal shutdown to input thread and wait
     shutdown.store(true, Ordering::Relaxed);
     if let Err(e) = input_handle.join() {
         std::panic::resume_unwind(e);
     }
+    state.save_ui_state();
+    state.save_workspace();
+    state.session(reason)
 }
 
-/// Format the watched path for status bar display, collapsing the user's home
-/// directory to `~` when applicable.
-fn format_watched_path(path: &Path) -> String {
-    let raw = path.to_string_lossy().to_string();
-    let home = std::env::var("HOME").ok();
-    let Some(home_str) = home else {
-        return raw;
-    };
+/// Columns taken by the line-number gutter (digits plus a space).
+fn gutter_width(entries: usize) -> usize {
+    entries.max(1).to_string().len() + 1
+}
 
-    let home_path = std::path::Path::new(&home_str);
-    if path == home_path {
-        return "~".to_string();
+/// Rows of the directories to pin over a view of `height` rows scrolled
+/// to `offset`: those enclosing the first row left showing below them,
+/// outermost first, taking at most half the view.
+fn sticky_headers(snapshot: &TreeSnapshot, offset: usize, height: usize) -> Vec<usize> {
+    if offset == 0 {
+        return Vec::new();
     }
-    if let Ok(stripped) = path.strip_prefix(home_path) {
-        let rest = stripped.to_string_lossy();
-        if rest.is_empty() {
-            "~".to_string()
-        } else {
-            format!("~/{}", rest)
+    // Each pinned row covers one more row, whose ancestors may differ;
+    // a few rounds settle it in practice.
+    let mut headers = Vec::new();
+    for _ in 0..4 {
+        let mut next = snapshot.ancestors(offset + headers.len());
+        next.truncate(height / 2);
+        if next.len() == headers.len() {
+            return next;
         }
-    } else {
-        raw
+        headers = next;
+    }
+    headers
+}
+
+/// Keep only the entries of `snapshot` matching `search`, best match
+/// first.
+fn rank_matches(snapshot: &mut TreeSnapshot, search: &mut Search) {
+    search.refresh(&snapshot.entries);
+    snapshot.entries = search
+        .ranked()
+        .into_iter()
+        .map(|row| snapshot.entries[row].clone())
+        .collect();
+    snapshot.total_entries = snapshot.entries.len();
+}
+
+/// Entries per column and column widths packing lines of `widths` into
+/// columns at most `height` lines tall, filled top to bottom; `None` if
+/// they don't fit across `width`.
+fn pack_columns(widths: &[usize], width: usize, height: usize) -> Option<(usize, Vec<u16>)> {
+    if widths.is_empty() || height == 0 {
+        return None;
     }
+    let per_column = widths.len().div_ceil(widths.len().div_ceil(height));
+    let columns: Vec<usize> = widths
+        .chunks(per_column)
+        .map(|chunk| chunk.iter().copied().max().unwrap_or(0))
+        .collect();
+    let total = columns.iter().sum::<usize>() + COLUMN_GAP * (columns.len() - 1);
+    (total <= width).then(|| {
+        let columns = columns.iter().map(|&w| w.min(usize::from(u16::MAX)) as u16);
+        (per_column, columns.collect())
+    })
+}
+
+/// Format the watched path for status bar display, collapsing the user's home
+/// directory to `~` when applicable.
+fn format_watched_path(path: &Path) -> String {
+    paths::display_path(path, paths::home_dir().as_deref())
+}
+
+/// Actions that read or change the files behind the tree, refused when it
+/// is read-only.
+fn reads_local_files(action: Action) -> bool {
+    matches!(
+        action,
+        Action::TogglePreview
+            | Action::ToggleSummary
+            | Action::ToggleStale
+            | Action::ToggleHeavyReport
+            | Action::NewFile
+            | Action::NewDir
+            | Action::Rename
+            | Action::Delete
+    )
 }
 
 /// Run the main application loop with the default `WalkdirTreeBuilder`.
-/// Blocks until the user quits.
+/// Blocks until the loop stops and returns a summary of the session.
 pub fn run(
     terminal: Term,
     path: &Path,
     tree_config: &TreeConfig,
     render_config: &RenderConfig,
     fs_rx: Receiver<WatchEvent>,
-    quiet: bool,
-) {
+    loop_config: &LoopConfig,
+) -> Session {
     let default_builder = WalkdirTreeBuilder;
     run_with_tree_builder(
         terminal,
@@ -433,8 +3346,135 @@ pub fn run(
         render_config,
         fs_rx,
         &default_builder,
-        quiet,
+        loop_config,
+    )
+}
+
+/// A synthetic event fed to `run_headless`.
+#[derive(Debug)]
+pub enum HeadlessEvent {
+    /// A key press, mouse event, or resize.
+    Input(Event),
+    /// A filesystem event, as the watcher would deliver it.
+    Fs(WatchEvent),
+}
+
+/// Drive the application against `backend` (typically ratatui's
+/// `TestBackend`) with events read from `events` instead of a terminal and
+/// watcher, and return every frame drawn.
+///
+/// Runs until a `Quit` action or until `events` is closed and drained.
+/// Background results (summaries, preview details) are applied whenever
+/// they arrive. External programs are never launched.
+pub fn run_headless<B: Backend>(
+    backend: B,
+    path: &Path,
+    tree_config: &TreeConfig,
+    render_config: &RenderConfig,
+    events: Receiver<HeadlessEvent>,
+    loop_config: &LoopConfig,
+) -> Vec<Buffer> {
+    run_headless_with_tree_builder(
+        backend,
+        path,
+        tree_config,
+        render_config,
+        events,
+        &WalkdirTreeBuilder,
+        loop_config,
+    )
+}
+
+/// `run_headless` with snapshots from `tree_builder` instead of the
+/// filesystem walk.
+pub fn run_headless_with_tree_builder<B: Backend>(
+    backend: B,
+    path: &Path,
+    tree_config: &TreeConfig,
+    render_config: &RenderConfig,
+    events: Receiver<HeadlessEvent>,
+    tree_builder: &dyn TreeBuilder,
+    loop_config: &LoopConfig,
+) -> Vec<Buffer> {
+    let Ok(terminal) = Terminal::new(backend) else {
+        return Vec::new();
+    };
+    let (jobs, job_rx) = job_channels();
+    let mut state = AppState::new(
+        terminal,
+        path,
+        tree_config,
+        render_config,
+        tree_builder,
+        loop_config,
+        jobs,
     );
+    state.headless = true;
+    state.watcher_options = None;
+    state.frames = Some(Vec::new());
+    state.scheduler = FrameScheduler::uncapped();
+
+    state.restore_selection();
+    state.request_summary();
+    state.scheduler.request(Trigger::Input);
+    state.draw_if_due();
+
+    let mut control_rx = loop_config
+        .control
+        .clone()
+        .unwrap_or_else(crossbeam_channel::never);
+    let mut split_rx = state
+        .split
+        .as_ref()
+        .map_or_else(crossbeam_channel::never, SplitPane::events);
+    loop {
+        let flow = select! {
+            recv(events) -> msg => match msg {
+                Ok(HeadlessEvent::Input(event)) => state.handle_input(event),
+                Ok(HeadlessEvent::Fs(event)) => state.handle_watch_event(event),
+                Err(_) => Flow::Exit(ExitReason::Quit),
+            },
+            recv(split_rx) -> msg => match msg {
+                Ok(event) => state.handle_split_event(event),
+                Err(_) => {
+                    split_rx = crossbeam_channel::never();
+                    Flow::Continue
+                }
+            },
+            recv(control_rx) -> msg => match msg {
+                Ok(request) => state.handle_control(request),
+                Err(_) => {
+                    control_rx = crossbeam_channel::never();
+                    Flow::Continue
+                }
+            },
+            recv(job_rx.summary) -> msg => {
+                if let Ok(result) = msg {
+                    state.on_summary(result);
+                }
+                Flow::Continue
+            }
+            recv(job_rx.heavy) -> msg => {
+                if let Ok(result) = msg {
+                    state.on_heavy(result);
+                }
+                Flow::Continue
+            }
+            recv(job_rx.details) -> msg => {
+                if let Ok(result) = msg {
+                    state.on_details(result);
+                }
+                Flow::Continue
+            }
+        };
+        if let Flow::Exit(_) = flow {
+            break;
+        }
+        state.draw_if_due();
+    }
+    state.save_ui_state();
+    state.save_workspace();
+    state.frames.take().unwrap_or_default()
 }
 
 /// Simple timestamp without pulling in chrono.
@@ -449,3 +3489,111 @@ fn chrono_lite_now() -> String {
     let s = secs % 60;
     format!("{:02}:{:02}:{:02}", h, m, s)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_slot_runs_one_walk_and_reruns_once() {
+        let mut slot = JobSlot::default();
+        let first = slot.request().unwrap();
+        // Changes while it runs start nothing new.
+        assert_eq!(slot.request(), None);
+        assert_eq!(slot.request(), None);
+        assert!(!slot.finish(first), "superseded while running");
+        assert!(slot.take_rerun());
+        assert!(!slot.take_rerun());
+        let second = slot.request().unwrap();
+        assert!(slot.finish(second));
+        assert!(!slot.take_rerun());
+    }
+
+    #[test]
+    fn session_summary_lists_nonzero_counts() {
+        let mut changes = ChangeCounts::default();
+        changes.record(ChangeKind::Created);
+        changes.record(ChangeKind::Created);
+        changes.record(ChangeKind::Modified);
+        let session = Session {
+            reason: ExitReason::Quit,
+            duration: Duration::from_secs(312),
+            changes,
+        };
+        assert_eq!(
+            session.to_string(),
+            "5m 12s, 3 changes (2 created, 1 modified)"
+        );
+        let idle = Session {
+            duration: Duration::from_secs(1),
+            changes: ChangeCounts::default(),
+            ..session
+        };
+        assert_eq!(idle.to_string(), "1s, 0 changes");
+    }
+
+    #[test]
+    fn details_invalidated_while_computed_are_dropped() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let tree_config = TreeConfig {
+            max_depth: None,
+            show_hidden: false,
+            dirs_only: false,
+            follow_symlinks: false,
+            ignore_patterns: crate::tree::build_ignore_set(&[]),
+            max_entries: None,
+            stat: false,
+        };
+        let (jobs, _job_rx) = job_channels();
+        let mut state = AppState::new(
+            Terminal::new(ratatui::backend::TestBackend::new(60, 10)).unwrap(),
+            tmp.path(),
+            &tree_config,
+            &RenderConfig::default(),
+            &WalkdirTreeBuilder,
+            &LoopConfig::default(),
+            jobs,
+        );
+        let dir = tmp.path().join("src");
+        let stale = DirDetails {
+            total_files: 1,
+            ..DirDetails::default()
+        };
+
+        // A job starts, then a change below its directory lands first.
+        state.details_pending.insert(dir.clone());
+        let started = state.details_generation;
+        state.invalidate_details(&[dir.join("new.rs")]);
+        state.on_details((started, dir.clone(), stale.clone()));
+        assert!(!state.details_cache.contains_key(&dir));
+        assert!(state.details_pending.is_empty());
+
+        // Asked for again, the fresh result is kept.
+        state.on_details((state.details_generation, dir.clone(), stale.clone()));
+        assert_eq!(state.details_cache.get(&dir), Some(&stale));
+
+        // Unrelated changes leave jobs in flight alone.
+        state.details_pending.insert(tmp.path().join("docs"));
+        let started = state.details_generation;
+        state.invalidate_details(&[tmp.path().join("README.md")]);
+        assert_eq!(state.details_generation, started);
+
+        // Changing the filters drops them.
+        state.filters_changed();
+        state.on_details((started, tmp.path().join("docs"), stale));
+        assert!(state.details_cache.is_empty());
+    }
+
+    #[test]
+    fn columns_are_as_wide_as_their_longest_line() {
+        let widths = [4, 9, 3, 5, 6];
+        // Five lines three high make two columns, the second one shorter.
+        assert_eq!(pack_columns(&widths, 20, 3), Some((3, vec![9, 6])));
+        assert_eq!(pack_columns(&widths, 16, 3), None);
+        // Columns are balanced: seven lines four high make 4 + 3, and
+        // nine lines four high make 3 + 3 + 3.
+        assert_eq!(pack_columns(&[1; 7], 80, 4).map(|l| l.0), Some(4));
+        assert_eq!(pack_columns(&[1; 9], 80, 4).map(|l| l.0), Some(3));
+        assert_eq!(pack_columns(&[], 80, 4), None);
+    }
+}
@@ -0,0 +1,48 @@
+//! Process exit codes, so wrapper scripts can tell failure modes apart.
+
+use crate::event_loop::ExitReason;
+
+/// Exit status of the `livetree` binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExitCode {
+    /// Normal exit: the user quit or a report was printed.
+    Success = 0,
+    /// Any failure without a more specific code.
+    Failure = 1,
+    /// Invalid arguments or configuration file (also used by argument parsing).
+    BadArgs = 2,
+    /// The root is missing, not a directory, or unreadable.
+    PathNotFound = 3,
+    /// The filesystem watcher could not be started or stopped unexpectedly.
+    WatcherFailed = 4,
+    /// The terminal could not be initialized.
+    TerminalFailed = 5,
+    /// The watched root was deleted while running.
+    RootDeleted = 6,
+    /// Stopped by SIGHUP, e.g. the terminal window was closed (128 + 1).
+    HungUp = 129,
+    /// Interrupted by SIGINT (128 + 2, as shells report it).
+    Interrupted = 130,
+    /// Stopped by SIGTERM (128 + 15).
+    Terminated = 143,
+}
+
+impl From<ExitReason> for ExitCode {
+    fn from(reason: ExitReason) -> Self {
+        match reason {
+            ExitReason::Quit => ExitCode::Success,
+            ExitReason::RootDeleted => ExitCode::RootDeleted,
+            ExitReason::Interrupted => ExitCode::Interrupted,
+            ExitReason::Terminated => ExitCode::Terminated,
+            ExitReason::HungUp => ExitCode::HungUp,
+            ExitReason::WatcherStopped => ExitCode::WatcherFailed,
+        }
+    }
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        std::process::ExitCode::from(code as u8)
+    }
+}
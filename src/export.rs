@@ -0,0 +1,399 @@
+//! Writing a snapshot to a file as plain text, Markdown, JSON, or HTML, from
+//! the palette's `export` command (`w`) or once with `--export <FILE>`.
+
+use crate::patch;
+use crate::paths;
+use crate::render::{
+    line_to_plain_text, tree_to_lines, RenderConfig, DIR_STYLE, ERROR_STYLE, PREFIX_STYLE,
+    SYMLINK_STYLE,
+};
+use crate::tree::{case_collisions, TreeSnapshot};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Output format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The tree as drawn, with box-drawing prefixes.
+    Text,
+    /// Nested Markdown lists.
+    Markdown,
+    /// The snapshot document used by `patch::snapshot_to_json`.
+    Json,
+    /// A self-contained page with collapsible directories.
+    Html,
+}
+
+impl Format {
+    /// The format for a file name: `.md`/`.markdown` is Markdown, `.json` is
+    /// JSON, `.html`/`.htm` is HTML, anything else plain text.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("md" | "markdown") => Format::Markdown,
+            Some("json") => Format::Json,
+            Some("html" | "htm") => Format::Html,
+            _ => Format::Text,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" | "txt" => Ok(Format::Text),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            "html" => Ok(Format::Html),
+            other => Err(format!(
+                "unknown export format '{other}' (available: text, markdown, json, html)"
+            )),
+        }
+    }
+}
+
+/// Format `snapshot` of the tree under `root`.
+pub fn render(snapshot: &TreeSnapshot, root: &Path, format: Format) -> String {
+    match format {
+        Format::Json => return patch::snapshot_to_json(snapshot) + "\n",
+        Format::Html => return html(snapshot, root),
+        Format::Text | Format::Markdown => {}
+    }
+    let name = paths::display_name(root);
+    let mut out = match format {
+        Format::Markdown => format!("- {}\n", code(&format!("{name}/"))),
+        _ => format!("{name}\n"),
+    };
+    match format {
+        Format::Markdown => {
+            for entry in &snapshot.entries {
+                let name = if entry.is_dir {
+                    format!("{}/", entry.name)
+                } else {
+                    entry.name.clone()
+                };
+                out.push_str(&"  ".repeat(entry.depth));
+                out.push_str("- ");
+                out.push_str(&code(&name));
+                if let Some(target) = entry.symlink_target.as_deref().filter(|_| entry.is_symlink) {
+                    out.push_str(" → ");
+                    out.push_str(&code(target));
+                }
+                out.push('\n');
+            }
+        }
+        _ => {
+            let config = RenderConfig::builder()
+                .use_color(false)
+                .terminal_width(0)
+                .truncate_names(false)
+                .build();
+            for line in tree_to_lines(&snapshot.entries, &config, &HashSet::new()) {
+                out.push_str(&line_to_plain_text(&line));
+                out.push('\n');
+            }
+        }
+    }
+    let hidden = snapshot
+        .total_entries
+        .saturating_sub(snapshot.entries.len());
+    if hidden > 0 {
+        let noun = if hidden == 1 { "entry" } else { "entries" };
+        out.push_str(&format!("\n({hidden} more {noun} not shown)\n"));
+    }
+    out
+}
+
+/// Write `snapshot` to `file` in `format`, or the format its extension
+/// implies. A `file` of `-` writes to stdout. Returns the format used.
+pub fn write(
+    snapshot: &TreeSnapshot,
+    root: &Path,
+    file: &Path,
+    format: Option<Format>,
+) -> Result<Format, String> {
+    let format = format.unwrap_or_else(|| Format::from_path(file));
+    let text = render(snapshot, root, format);
+    if file == Path::new("-") {
+        print!("{text}");
+        return Ok(format);
+    }
+    std::fs::write(file, text).map_err(|e| format!("{}: {}", file.display(), e))?;
+    Ok(format)
+}
+
+/// A self-contained HTML page: directories are `<details>` elements (open
+/// by default) and entries are colored as in the terminal. Every entry has
+/// an `id` made from its path, unique even when compared ignoring case, and
+/// names that differ from a sibling only in case are underlined.
+fn html(snapshot: &TreeSnapshot, root: &Path) -> String {
+    let name = escape_html(&paths::display_name(root));
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{name}</title>\n<style>\n\
+         body {{ background: #1e1e1e; {} font-family: ui-monospace, monospace; }}\n\
+         ul {{ list-style: none; margin: 0; padding-left: 1.5em; border-left: 1px solid #555; }}\n\
+         summary {{ cursor: pointer; }}\n\
+         .dir {{ {} }}\n.link {{ {} }}\n.err {{ {} }}\n.more {{ color: #888; }}\n\
+         .case {{ text-decoration: underline wavy #e5e510; }}\n\
+         </style>\n</head>\n<body>\n<details open><summary class=\"dir\">{name}/</summary>\n<ul>\n",
+        css(PREFIX_STYLE),
+        css(DIR_STYLE),
+        css(SYMLINK_STYLE),
+        css(ERROR_STYLE),
+    );
+    let collisions = case_collisions(&snapshot.entries);
+    let mut ids = Anchors::default();
+    // Depths of the directories whose lists are still open.
+    let mut open: Vec<usize> = Vec::new();
+    for entry in &snapshot.entries {
+        while open.last().is_some_and(|&depth| depth >= entry.depth) {
+            open.pop();
+            out.push_str("</ul></details></li>\n");
+        }
+        let id = ids.unique(entry.path.strip_prefix(root).unwrap_or(&entry.path));
+        let mut name = escape_html(&entry.name);
+        if collisions.contains(&entry.path) {
+            name = format!(
+                "<span class=\"case\" title=\"differs from a sibling only in case\">{name}</span>"
+            );
+        }
+        if let Some(err) = &entry.error {
+            let _ = writeln!(
+                out,
+                "<li id=\"{id}\" class=\"err\">{name} [{}]</li>",
+                escape_html(err)
+            );
+        } else if entry.is_symlink {
+            let target = entry.symlink_target.as_deref().unwrap_or("?");
+            let _ = writeln!(
+                out,
+                "<li id=\"{id}\"><span class=\"link\">{name}</span> -&gt; {}</li>",
+                escape_html(target)
+            );
+        } else if entry.is_dir {
+            let _ = writeln!(
+                out,
+                "<li id=\"{id}\"><details open><summary class=\"dir\">{name}/</summary><ul>"
+            );
+            open.push(entry.depth);
+        } else {
+            let _ = writeln!(out, "<li id=\"{id}\">{name}</li>");
+        }
+    }
+    for _ in open {
+        out.push_str("</ul></details></li>\n");
+    }
+    out.push_str("</ul>\n</details>\n");
+    let hidden = snapshot
+        .total_entries
+        .saturating_sub(snapshot.entries.len());
+    if hidden > 0 {
+        let noun = if hidden == 1 { "entry" } else { "entries" };
+        let _ = writeln!(
+            out,
+            "<p class=\"more\">({hidden} more {noun} not shown)</p>"
+        );
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Element ids for HTML entries. Fragments are often matched ignoring case
+/// (and some tools lowercase them), so ids are lowercase slugs of the path,
+/// with `-2`, `-3`, … added to ones that would otherwise repeat.
+#[derive(Default)]
+struct Anchors {
+    used: HashMap<String, usize>,
+}
+
+impl Anchors {
+    fn unique(&mut self, path: &Path) -> String {
+        let mut slug = String::from("entry-");
+        for c in path.to_string_lossy().chars().flat_map(char::to_lowercase) {
+            if c.is_alphanumeric() {
+                slug.push(c);
+            } else if !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        loop {
+            let count = self.used.entry(slug.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                return slug;
+            }
+            // Reserve the numbered form too, in case a later path slugs to it.
+            let numbered = format!("{slug}-{count}");
+            if !self.used.contains_key(&numbered) {
+                self.used.insert(numbered.clone(), 1);
+                return numbered;
+            }
+        }
+    }
+}
+
+/// CSS declarations for a terminal style.
+fn css(style: Style) -> String {
+    let mut out = String::new();
+    if let Some(color) = style.fg {
+        let _ = write!(out, "color: {};", css_color(color));
+    }
+    if style.add_modifier.contains(Modifier::BOLD) {
+        out.push_str(" font-weight: bold;");
+    }
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        out.push_str(" font-style: italic;");
+    }
+    out
+}
+
+/// A CSS color for a terminal color, using a common dark-theme palette for
+/// the named ANSI colors.
+fn css_color(color: Color) -> String {
+    let hex = match color {
+        Color::Rgb(r, g, b) => return format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::Black => "#000000",
+        Color::Red => "#cd3131",
+        Color::Green => "#0dbc79",
+        Color::Yellow => "#e5e510",
+        Color::Blue => "#2472c8",
+        Color::Magenta => "#bc3fbc",
+        Color::Cyan => "#11a8cd",
+        Color::Gray => "#e5e5e5",
+        Color::DarkGray => "#666666",
+        Color::LightRed => "#f14c4c",
+        Color::LightGreen => "#23d18b",
+        Color::LightYellow => "#f5f543",
+        Color::LightBlue => "#3b8eea",
+        Color::LightMagenta => "#d670d6",
+        Color::LightCyan => "#29b8db",
+        Color::White | Color::Reset | Color::Indexed(_) => "#e5e5e5",
+    };
+    hex.to_string()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Where a relative export path typed in the palette goes: the directory
+/// livetree was started in.
+pub fn resolve(file: &str) -> PathBuf {
+    let file = Path::new(file.trim());
+    match std::env::current_dir() {
+        Ok(cwd) if file.is_relative() => cwd.join(file),
+        _ => file.to_path_buf(),
+    }
+}
+
+/// `text` as a Markdown code span, fenced with enough backticks.
+fn code(text: &str) -> String {
+    let fence = if text.contains('`') { "``" } else { "`" };
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{pad}{text}{pad}{fence}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::TreeEntry;
+
+    fn entry(name: &str, depth: usize, is_dir: bool, is_last: bool, prefix: &str) -> TreeEntry {
+        let mut entry = TreeEntry::new(name, PathBuf::from("/p").join(name), depth, is_dir);
+        entry.is_last = is_last;
+        assert_eq!(entry.prefix(), prefix);
+        entry
+    }
+
+    fn snapshot() -> TreeSnapshot {
+        TreeSnapshot {
+            entries: vec![
+                entry("src", 1, true, false, "├── "),
+                entry("main.rs", 2, false, true, "│   └── "),
+                entry("a`b", 1, false, true, "└── "),
+            ],
+            total_entries: 4,
+        }
+    }
+
+    #[test]
+    fn format_follows_extension() {
+        assert_eq!(Format::from_path(Path::new("out.MD")), Format::Markdown);
+        assert_eq!(Format::from_path(Path::new("t.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("t.htm")), Format::Html);
+        assert_eq!(Format::from_path(Path::new("tree")), Format::Text);
+        assert_eq!("html".parse(), Ok(Format::Html));
+        assert!("pdf".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn text_and_markdown() {
+        let root = Path::new("/p/proj");
+        assert_eq!(
+            render(&snapshot(), root, Format::Text),
+            "proj\n├── src\n│   └── main.rs\n└── a`b\n\n(1 more entry not shown)\n"
+        );
+        assert_eq!(
+            render(&snapshot(), root, Format::Markdown),
+            "- `proj/`\n  - `src/`\n    - `main.rs`\n  - ``a`b``\n\n(1 more entry not shown)\n"
+        );
+    }
+
+    #[test]
+    fn html_nests_directories_and_escapes_names() {
+        let page = render(&snapshot(), Path::new("/p/proj"), Format::Html);
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains(
+            "<li id=\"entry-p-src\"><details open><summary class=\"dir\">src/</summary><ul>\n\
+             <li id=\"entry-p-main-rs\">main.rs</li>\n</ul></details></li>\n\
+             <li id=\"entry-p-a-b\">a`b</li>\n"
+        ));
+        assert!(page.contains(".dir { color: #2472c8; font-weight: bold; }"));
+        assert_eq!(escape_html("<a & 'b'>"), "&lt;a &amp; &#39;b&#39;&gt;");
+    }
+
+    #[test]
+    fn html_ids_stay_unique_ignoring_case() {
+        let snapshot = TreeSnapshot::from_outline([(1, "README.md"), (1, "readme.md"), (1, "x")]);
+        let page = render(&snapshot, Path::new(""), Format::Html);
+        assert!(page.contains("<li id=\"entry-readme-md\"><span class=\"case\""));
+        assert!(page.contains("<li id=\"entry-readme-md-2\"><span class=\"case\""));
+        assert!(page.contains("<li id=\"entry-x\">x</li>"));
+
+        let mut ids = Anchors::default();
+        let got: Vec<String> = ["a b", "a-b-2", "A_B", "a.b"]
+            .into_iter()
+            .map(|p| ids.unique(Path::new(p)))
+            .collect();
+        assert_eq!(
+            got,
+            ["entry-a-b", "entry-a-b-2", "entry-a-b-3", "entry-a-b-4"]
+        );
+    }
+}
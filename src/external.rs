@@ -0,0 +1,193 @@
+//! Bridges to programs outside the terminal: the user's editor and shell,
+//! the OS file manager and default applications, and the clipboard (via the
+//! OSC 52 escape sequence).
+
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// The editor command from `$VISUAL` or `$EDITOR`, split on whitespace so
+/// values like `code -w` work; falls back to `vi`.
+pub fn editor_command(path: &Path) -> Command {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let mut cmd = Command::new(parts.next().unwrap_or("vi"));
+    cmd.args(parts).arg(path);
+    cmd
+}
+
+/// The user's shell (`$SHELL`, or `%COMSPEC%` on Windows; `sh`/`cmd` if
+/// unset), started in `dir`.
+pub fn shell_command(dir: &Path) -> Command {
+    let (var, fallback) = if cfg!(windows) {
+        ("COMSPEC", "cmd")
+    } else {
+        ("SHELL", "sh")
+    };
+    let shell = std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| fallback.into());
+    let mut cmd = Command::new(shell);
+    cmd.current_dir(dir);
+    cmd
+}
+
+/// Program and arguments that show `path` in the platform file manager:
+/// `open -R` on macOS, `explorer /select,` on Windows, and `xdg-open` on the
+/// parent directory elsewhere (there is no portable "select" for it).
+pub fn reveal_args(path: &Path) -> (&'static str, Vec<OsString>) {
+    if cfg!(target_os = "macos") {
+        ("open", vec!["-R".into(), path.into()])
+    } else if cfg!(windows) {
+        let mut select = OsString::from("/select,");
+        select.push(path);
+        ("explorer", vec![select])
+    } else {
+        let dir = path.parent().unwrap_or(path);
+        ("xdg-open", vec![dir.into()])
+    }
+}
+
+/// The command that shows `path` in the OS file manager.
+pub fn reveal_command(path: &Path) -> Command {
+    let (program, args) = reveal_args(path);
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    cmd
+}
+
+/// Whether the opener may take over the terminal: on Unix desktops other
+/// than macOS, `xdg-open` without a graphical session falls back to console
+/// programs, so it has to run in the foreground.
+pub fn reveal_needs_terminal() -> bool {
+    if cfg!(any(windows, target_os = "macos")) {
+        return false;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .all(|var| std::env::var_os(var).is_none_or(|v| v.is_empty()))
+}
+
+/// Show `path` in the OS file manager without waiting for it to exit.
+pub fn reveal(path: &Path) -> io::Result<()> {
+    spawn_detached(reveal_command(path))
+}
+
+/// Program and arguments that open `path` with its default application:
+/// `open` on macOS, `start` on Windows, and `xdg-open` elsewhere.
+pub fn open_args(path: &Path) -> (&'static str, Vec<OsString>) {
+    if cfg!(target_os = "macos") {
+        ("open", vec![path.into()])
+    } else if cfg!(windows) {
+        // `start` is a cmd built-in; its first quoted argument is a title.
+        (
+            "cmd",
+            vec!["/C".into(), "start".into(), "".into(), path.into()],
+        )
+    } else {
+        ("xdg-open", vec![path.into()])
+    }
+}
+
+/// Open `path` with its default application without waiting for it or
+/// letting it write to the terminal.
+pub fn open(path: &Path) -> io::Result<()> {
+    let (program, args) = open_args(path);
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    spawn_detached(cmd)
+}
+
+/// Run `cmd` with no access to the terminal.
+fn spawn_detached(mut cmd: Command) -> io::Result<()> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the opener in the background so it does not linger as a zombie.
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// The OSC 52 sequence asking the terminal to put `text` on the clipboard.
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Copy `text` to the clipboard through the terminal (works over SSH).
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc4648_vectors() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn osc52_wraps_encoded_text() {
+        assert_eq!(osc52_sequence("/tmp/a"), "\x1b]52;c;L3RtcC9h\x07");
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn reveal_opens_parent_with_xdg_open() {
+        let (program, args) = reveal_args(Path::new("/tmp/dir/file.txt"));
+        assert_eq!(program, "xdg-open");
+        assert_eq!(args, vec![OsString::from("/tmp/dir")]);
+    }
+
+    #[test]
+    fn shell_starts_in_the_given_directory() {
+        let cmd = shell_command(Path::new("/tmp/dir"));
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/tmp/dir")));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn open_passes_the_entry_itself_to_xdg_open() {
+        let (program, args) = open_args(Path::new("/tmp/dir/file.txt"));
+        assert_eq!(program, "xdg-open");
+        assert_eq!(args, vec![OsString::from("/tmp/dir/file.txt")]);
+    }
+}
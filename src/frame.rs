@@ -0,0 +1,225 @@
+//! Frame scheduling: decides when the event loop redraws.
+//!
+//! Every state change requests a frame with the `Trigger` that caused it;
+//! the loop asks the scheduler whether a frame is due after handling each
+//! event. Requests that arrive between two frames are coalesced into one,
+//! and frames are spaced at least one frame interval apart (the FPS cap),
+//! except for user input, which is drawn immediately. Timers (e.g. the next
+//! highlight fade step) wake the loop at a given instant. A frame that
+//! would show exactly what the last one showed is not drawn at all.
+
+use std::collections::VecDeque;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Default frame rate cap (`--max-fps`): enough to watch changes arrive,
+/// and a busy tree is rebuilt at most this often.
+pub const DEFAULT_MAX_FPS: u32 = 10;
+
+/// What caused a frame request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Key press, mouse event, or resize; drawn without waiting for the cap.
+    Input,
+    /// A batch of filesystem events.
+    Fs,
+    /// A background job (summary, report, preview details) finished.
+    Job,
+    /// An external program returned control of the terminal.
+    Exec,
+    /// A timer set with `FrameScheduler::wake_at` fired (highlight fades,
+    /// digests).
+    Timer,
+}
+
+/// Merges frame requests and enforces the frame rate cap.
+#[derive(Debug, Clone)]
+pub struct FrameScheduler {
+    /// Minimum time between two frames (zero = uncapped).
+    interval: Duration,
+    /// When the last frame was drawn.
+    last_frame: Option<Instant>,
+    /// The strongest pending request, if any.
+    pending: Option<Trigger>,
+    /// Earliest timer deadline.
+    wake: Option<Instant>,
+    /// When frames were drawn during the last second.
+    recent: VecDeque<Instant>,
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FPS)
+    }
+}
+
+impl FrameScheduler {
+    /// A scheduler drawing at most `max_fps` frames per second (0 = uncapped).
+    pub fn new(max_fps: u32) -> Self {
+        let interval = if max_fps == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(1) / max_fps
+        };
+        Self {
+            interval,
+            last_frame: None,
+            pending: None,
+            wake: None,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// A scheduler that draws every request as soon as it is checked.
+    pub fn uncapped() -> Self {
+        Self::new(0)
+    }
+
+    /// Ask for a frame. Requests are merged until the next frame is drawn.
+    pub fn request(&mut self, trigger: Trigger) {
+        self.pending = match self.pending {
+            Some(Trigger::Input) => Some(Trigger::Input),
+            _ => Some(trigger),
+        };
+    }
+
+    /// Ask for a frame at `at`, unless an earlier timer is already set.
+    pub fn wake_at(&mut self, at: Instant) {
+        self.wake = Some(self.wake.map_or(at, |wake| wake.min(at)));
+    }
+
+    /// Drop any pending timer (e.g. once nothing is fading any more).
+    pub fn cancel_wake(&mut self) {
+        self.wake = None;
+    }
+
+    /// Whether a frame should be drawn at `now`. Fires due timers.
+    pub fn due(&mut self, now: Instant) -> bool {
+        if self.wake.is_some_and(|at| now >= at) {
+            self.wake = None;
+            self.request(Trigger::Timer);
+        }
+        match self.pending {
+            None => false,
+            Some(Trigger::Input) => true,
+            Some(_) => self
+                .last_frame
+                .is_none_or(|last| now.saturating_duration_since(last) >= self.interval),
+        }
+    }
+
+    /// Record that a frame was drawn at `now`, clearing pending requests.
+    pub fn drawn(&mut self, now: Instant) {
+        self.pending = None;
+        self.last_frame = Some(now);
+        self.recent.push_back(now);
+        while self
+            .recent
+            .front()
+            .is_some_and(|&at| now.saturating_duration_since(at) >= Duration::from_secs(1))
+        {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Frames drawn during the second before `now`.
+    pub fn fps(&self, now: Instant) -> usize {
+        self.recent
+            .iter()
+            .filter(|&&at| now.saturating_duration_since(at) < Duration::from_secs(1))
+            .count()
+    }
+
+    /// How long the loop may block waiting for events before the next
+    /// frame or timer is due; `None` if nothing is scheduled.
+    pub fn timeout(&self, now: Instant) -> Option<Duration> {
+        let capped = self.pending.map(|_| {
+            self.last_frame
+                .map_or(now, |last| last + self.interval)
+                .saturating_duration_since(now)
+        });
+        let timer = self.wake.map(|at| at.saturating_duration_since(now));
+        match (capped, timer) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Remembers a hash of what the last frame showed, so that a frame that
+/// would look the same can skip the terminal entirely.
+#[derive(Debug, Clone, Default)]
+pub struct Fingerprint(Option<u64>);
+
+impl Fingerprint {
+    /// Whether `shown` differs from what the last call saw; remembers it
+    /// either way.
+    pub fn changed(&mut self, shown: &impl Hash) -> bool {
+        let mut hasher = DefaultHasher::new();
+        shown.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.0.replace(hash) != Some(hash)
+    }
+
+    /// Forget the last frame, e.g. after the screen was cleared, so the next
+    /// one is drawn.
+    pub fn reset(&mut self) {
+        self.0 = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_frames_are_not_changes() {
+        let mut last = Fingerprint::default();
+        assert!(last.changed(&("a.txt", 0)));
+        assert!(!last.changed(&("a.txt", 0)));
+        assert!(last.changed(&("a.txt", 1)));
+        last.reset();
+        assert!(last.changed(&("a.txt", 1)));
+    }
+
+    #[test]
+    fn requests_between_frames_are_coalesced_and_capped() {
+        let mut scheduler = FrameScheduler::new(10);
+        let t0 = Instant::now();
+        scheduler.request(Trigger::Fs);
+        assert!(scheduler.due(t0));
+        scheduler.drawn(t0);
+
+        scheduler.request(Trigger::Fs);
+        scheduler.request(Trigger::Job);
+        let soon = t0 + Duration::from_millis(40);
+        assert!(!scheduler.due(soon));
+        assert_eq!(scheduler.timeout(soon), Some(Duration::from_millis(60)));
+        assert!(scheduler.due(t0 + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn input_is_drawn_immediately() {
+        let mut scheduler = FrameScheduler::new(10);
+        let t0 = Instant::now();
+        scheduler.request(Trigger::Fs);
+        scheduler.drawn(t0);
+        scheduler.request(Trigger::Fs);
+        scheduler.request(Trigger::Input);
+        assert!(scheduler.due(t0 + Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn timers_fire_once() {
+        let mut scheduler = FrameScheduler::uncapped();
+        let t0 = Instant::now();
+        scheduler.wake_at(t0 + Duration::from_secs(2));
+        scheduler.wake_at(t0 + Duration::from_secs(1));
+        assert!(!scheduler.due(t0));
+        assert_eq!(scheduler.timeout(t0), Some(Duration::from_secs(1)));
+        assert!(scheduler.due(t0 + Duration::from_secs(1)));
+        scheduler.drawn(t0 + Duration::from_secs(1));
+        assert!(!scheduler.due(t0 + Duration::from_secs(3)));
+        assert_eq!(scheduler.timeout(t0), None);
+    }
+}
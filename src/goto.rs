@@ -0,0 +1,233 @@
+//! Jump targets for the command palette: Tab completion against the
+//! filesystem (or a tree's entries, for a remote tree) and resolving a
+//! typed path relative to the watched root.
+//!
+//! A target is a path or an entry number (`42`), as shown in the
+//! line-number gutter, with an optional `goto` command word (`goto 42`,
+//! `goto src/main.rs`).
+
+use crate::tree::TreeEntry;
+use std::path::{Component, Path, PathBuf};
+
+/// What the prompt input asks to jump to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// An entry number, 1-based as in the line-number gutter.
+    Line(usize),
+    /// A path relative to the root.
+    Path(&'a str),
+}
+
+/// Interpret prompt input: digits are an entry number, anything else a path.
+/// Prefix a numeric file name with `./` to jump to the file.
+pub fn parse_target(input: &str) -> Target<'_> {
+    let (_, rest) = split_command(input.trim());
+    match rest.parse() {
+        Ok(line) => Target::Line(line),
+        Err(_) => Target::Path(rest),
+    }
+}
+
+/// Split off a leading `goto ` command word, returning (command, rest).
+fn split_command(input: &str) -> (&str, &str) {
+    match input.strip_prefix("goto ") {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            input.split_at(input.len() - rest.len())
+        }
+        None => ("", input),
+    }
+}
+
+/// Names in the directory named by `input` that start with its last
+/// component, sorted. Hidden names are only offered if `show_hidden` is set
+/// or the typed prefix itself starts with a dot.
+pub fn candidates(root: &Path, input: &str, show_hidden: bool) -> Vec<(String, bool)> {
+    let (dir, prefix) = split_input(input);
+    let Some(dir) = resolve(root, dir) else {
+        return Vec::new();
+    };
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let names = read_dir.flatten().map(|e| {
+        (
+            e.file_name().to_string_lossy().into_owned(),
+            e.path().is_dir(),
+        )
+    });
+    matching(names, prefix, show_hidden)
+}
+
+/// `candidates` taken from `entries` instead of the disk.
+pub fn listed_candidates(
+    root: &Path,
+    entries: &[TreeEntry],
+    input: &str,
+    show_hidden: bool,
+) -> Vec<(String, bool)> {
+    let (dir, prefix) = split_input(input);
+    let Some(dir) = resolve(root, dir) else {
+        return Vec::new();
+    };
+    let names = entries
+        .iter()
+        .filter(|e| e.path.parent() == Some(dir.as_path()))
+        .map(|e| (e.name.clone(), e.is_dir));
+    matching(names, prefix, show_hidden)
+}
+
+fn matching(
+    names: impl Iterator<Item = (String, bool)>,
+    prefix: &str,
+    show_hidden: bool,
+) -> Vec<(String, bool)> {
+    let mut names: Vec<(String, bool)> = names
+        .filter(|(name, _)| {
+            let hidden = name.starts_with('.') && !prefix.starts_with('.');
+            name.starts_with(prefix) && (show_hidden || !hidden)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// `input` extended by the longest prefix shared by all candidates, or
+/// `None` if there is nothing to add.
+pub fn complete(root: &Path, input: &str, show_hidden: bool) -> Option<String> {
+    extend(input, &candidates(root, input, show_hidden))
+}
+
+/// `complete` against `entries` instead of the disk.
+pub fn complete_listed(
+    root: &Path,
+    entries: &[TreeEntry],
+    input: &str,
+    show_hidden: bool,
+) -> Option<String> {
+    extend(input, &listed_candidates(root, entries, input, show_hidden))
+}
+
+fn extend(input: &str, names: &[(String, bool)]) -> Option<String> {
+    let (dir, prefix) = split_input(input);
+    let completion = match names {
+        [] => return None,
+        [(name, true)] => format!("{name}/"),
+        [(name, false)] => name.clone(),
+        [(first, _), rest @ ..] => rest.iter().fold(first.clone(), |common, (name, _)| {
+            let len = common
+                .char_indices()
+                .zip(name.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((i, c), _)| i + c.len_utf8());
+            common[..len].to_string()
+        }),
+    };
+    (completion.len() > prefix.len()).then(|| format!("{dir}{completion}"))
+}
+
+/// Absolute path for `input` relative to `root`. Leading `/` and `./` are
+/// accepted; `..` is rejected so jumps stay inside the watched tree.
+pub fn resolve(root: &Path, input: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for component in Path::new(input.trim()).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Split `input` after its last separator (`/`, or also `\` on Windows)
+/// into (directory part, name prefix).
+fn split_input(input: &str) -> (&str, &str) {
+    match input.rfind(std::path::is_separator) {
+        Some(i) => input.split_at(i + 1),
+        None => ("", input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn fixture() -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src/tree")).unwrap();
+        fs::write(tmp.path().join("src/main.rs"), "").unwrap();
+        fs::write(tmp.path().join("src/mod_a.rs"), "").unwrap();
+        fs::write(tmp.path().join("src/mod_b.rs"), "").unwrap();
+        fs::write(tmp.path().join(".hidden"), "").unwrap();
+        tmp
+    }
+
+    #[test]
+    fn completes_unique_directory_with_slash() {
+        let tmp = fixture();
+        assert_eq!(complete(tmp.path(), "s", false).as_deref(), Some("src/"));
+        assert_eq!(
+            complete(tmp.path(), "src/t", false).as_deref(),
+            Some("src/tree/")
+        );
+    }
+
+    #[test]
+    fn completes_common_prefix_of_several_matches() {
+        let tmp = fixture();
+        assert_eq!(
+            complete(tmp.path(), "src/mo", false).as_deref(),
+            Some("src/mod_")
+        );
+        assert_eq!(complete(tmp.path(), "src/mod_", false), None);
+    }
+
+    #[test]
+    fn hidden_names_need_a_dot_or_show_hidden() {
+        let tmp = fixture();
+        assert!(candidates(tmp.path(), "", false)
+            .iter()
+            .all(|(n, _)| n != ".hidden"));
+        assert_eq!(complete(tmp.path(), ".", false).as_deref(), Some(".hidden"));
+    }
+
+    #[test]
+    fn listed_entries_complete_without_the_disk() {
+        let root = Path::new("/srv/remote");
+        let entries = [
+            TreeEntry::new("src", root.join("src"), 1, true),
+            TreeEntry::new("main.rs", root.join("src/main.rs"), 2, false),
+            TreeEntry::new("mod_a.rs", root.join("src/mod_a.rs"), 2, false),
+            TreeEntry::new("mod_b.rs", root.join("src/mod_b.rs"), 2, false),
+        ];
+        assert_eq!(
+            complete_listed(root, &entries, "s", false).as_deref(),
+            Some("src/")
+        );
+        assert_eq!(
+            complete_listed(root, &entries, "src/mo", false).as_deref(),
+            Some("src/mod_")
+        );
+        assert_eq!(complete_listed(root, &entries, "main", false), None);
+    }
+
+    #[test]
+    fn numbers_and_command_word() {
+        assert_eq!(parse_target("42"), Target::Line(42));
+        assert_eq!(parse_target("goto  7 "), Target::Line(7));
+        assert_eq!(parse_target("goto src/a"), Target::Path("src/a"));
+        assert_eq!(parse_target("./42"), Target::Path("./42"));
+    }
+
+    #[test]
+    fn resolve_stays_inside_root() {
+        let root = Path::new("/r");
+        assert_eq!(resolve(root, "./a/b"), Some(PathBuf::from("/r/a/b")));
+        assert_eq!(resolve(root, "/a"), Some(PathBuf::from("/r/a")));
+        assert_eq!(resolve(root, "../etc"), None);
+    }
+}
@@ -1,13 +1,124 @@
 //! Per-file highlight expiration tracking.
 
+use crate::config::KindStyle;
+use crate::ttl::TtlSet;
+use crate::watcher::ChangeKind;
+use ratatui::style::Color;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+/// How a highlighted path changed, which selects its render style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    /// Contents or structure changed (create/modify/delete/rename).
+    Content,
+    /// Only metadata changed (e.g. `touch`, `chmod`); rendered more subtly.
+    Metadata,
+    /// A directory that was only reported because its children changed;
+    /// rendered as a dimmer "contains changes" marker.
+    Descendant,
+    /// A directory standing in for a highlighted entry below it that is
+    /// collapsed or scrolled out of view; added by `inherit_to_ancestors`,
+    /// never stored in a tracker.
+    Inherited,
+}
+
+impl HighlightKind {
+    /// Classify a watcher change. Directories are only highlighted as direct
+    /// changes when they were created, removed, or renamed themselves.
+    pub fn classify(change: ChangeKind, is_dir: bool) -> Self {
+        match change {
+            ChangeKind::Metadata => HighlightKind::Metadata,
+            ChangeKind::Modified if is_dir => HighlightKind::Descendant,
+            _ => HighlightKind::Content,
+        }
+    }
+
+    /// Precedence when several kinds hit the same path within one highlight window.
+    fn rank(self) -> u8 {
+        match self {
+            HighlightKind::Inherited => 0,
+            HighlightKind::Metadata => 1,
+            HighlightKind::Descendant => 2,
+            HighlightKind::Content => 3,
+        }
+    }
+}
+
+/// Number of brightness steps a highlight fades through over its lifetime.
+pub const FADE_STEPS: u8 = 4;
+
+/// An active highlight and how far it has faded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveHighlight {
+    pub kind: HighlightKind,
+    /// Time since the path was (last) highlighted.
+    pub age: Duration,
+    /// Fade step, from 0 (fresh) to `FADE_STEPS - 1` (about to expire).
+    pub fade: u8,
+    /// Configured color for this kind of change, replacing the default.
+    pub color: Option<Color>,
+}
+
+/// Read-only lookup of active highlights used by the renderer.
+///
+/// Implemented for plain path sets (every path counts as a content change)
+/// and for maps that carry a `HighlightKind` per path.
+pub trait Highlights {
+    /// The highlight kind for `path`, or `None` if it is not highlighted.
+    fn kind_of(&self, path: &Path) -> Option<HighlightKind>;
+
+    /// How far the highlight for `path` has faded (0 = full brightness).
+    fn fade_of(&self, _path: &Path) -> u8 {
+        0
+    }
+
+    /// A configured color replacing the default highlight color for `path`.
+    fn color_of(&self, _path: &Path) -> Option<Color> {
+        None
+    }
+}
+
+impl Highlights for HashSet<PathBuf> {
+    fn kind_of(&self, path: &Path) -> Option<HighlightKind> {
+        self.contains(path).then_some(HighlightKind::Content)
+    }
+}
+
+impl Highlights for HashMap<PathBuf, HighlightKind> {
+    fn kind_of(&self, path: &Path) -> Option<HighlightKind> {
+        self.get(path).copied()
+    }
+}
+
+impl Highlights for HashMap<PathBuf, ActiveHighlight> {
+    fn kind_of(&self, path: &Path) -> Option<HighlightKind> {
+        self.get(path).map(|h| h.kind)
+    }
+
+    fn fade_of(&self, path: &Path) -> u8 {
+        self.get(path).map_or(0, |h| h.fade)
+    }
+
+    fn color_of(&self, path: &Path) -> Option<Color> {
+        self.get(path).and_then(|h| h.color)
+    }
+}
+
+/// What a tracked highlight carries besides its timing.
+#[derive(Debug, Clone, Copy)]
+struct Mark {
+    kind: HighlightKind,
+    /// Color override for this entry (per change kind, from the config).
+    color: Option<Color>,
+}
+
 /// Tracks recently changed paths with per-entry expiration.
 pub struct HighlightTracker {
-    entries: HashMap<PathBuf, Instant>,
-    duration: Duration,
+    /// Without a default lifetime the tracker is sticky: entries stay until
+    /// removed (marks, search matches).
+    entries: TtlSet<PathBuf, Mark>,
 }
 
 impl Default for HighlightTracker {
@@ -19,32 +130,155 @@ impl Default for HighlightTracker {
 impl HighlightTracker {
     pub fn new(duration: Duration) -> Self {
         Self {
-            entries: HashMap::new(),
-            duration,
+            entries: TtlSet::new(duration),
+        }
+    }
+
+    /// A tracker whose entries stay until removed or cleared.
+    pub fn sticky() -> Self {
+        Self {
+            entries: TtlSet::unbounded(),
         }
     }
 
+    fn is_sticky(&self) -> bool {
+        self.entries.default_ttl().is_none()
+    }
+
     /// Update the global highlight duration for future pruning.
     /// Existing entries will respect the new duration on the next `active_set` call.
+    /// Sticky trackers are unaffected.
     pub fn set_duration(&mut self, duration: Duration) {
-        self.duration = duration;
+        if !self.is_sticky() {
+            self.entries.set_default_ttl(Some(duration));
+        }
     }
 
     /// Record a path as highlighted at the given instant.
     pub fn insert(&mut self, path: PathBuf, now: Instant) {
-        self.entries.insert(path, now);
+        self.insert_kind(path, now, HighlightKind::Content);
+    }
+
+    /// Record a path with an explicit highlight kind. A weaker kind (e.g. a
+    /// metadata-only touch) refreshes the timer but does not downgrade a
+    /// stronger highlight that is still active.
+    pub fn insert_kind(&mut self, path: PathBuf, now: Instant, kind: HighlightKind) {
+        self.insert_styled(path, now, kind, &KindStyle::default());
+    }
+
+    /// Like `insert_kind`, with the color and lifetime configured for the
+    /// kind of change (created, modified, deleted).
+    pub fn insert_styled(
+        &mut self,
+        path: PathBuf,
+        now: Instant,
+        kind: HighlightKind,
+        style: &KindStyle,
+    ) {
+        let mark = match self.entries.get_live(&path, now) {
+            Some(existing) if existing.value.kind.rank() > kind.rank() => existing.value,
+            _ => Mark {
+                kind,
+                color: style.color,
+            },
+        };
+        let ttl = if self.is_sticky() {
+            None
+        } else {
+            style.duration
+        };
+        self.entries.insert_with_ttl(path, mark, now, ttl);
+    }
+
+    /// Forget the highlight for a single path.
+    pub fn remove(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    /// Highlight `path` if it is not, otherwise remove it. Returns whether
+    /// it is highlighted afterwards.
+    pub fn toggle(&mut self, path: PathBuf, now: Instant) -> bool {
+        if self.entries.remove(&path).is_some() {
+            false
+        } else {
+            self.insert(path, now);
+            true
+        }
+    }
+
+    /// Whether `path` is currently highlighted.
+    pub fn contains(&self, path: &Path, now: Instant) -> bool {
+        self.entries.contains(path, now)
     }
 
     /// Return the set of paths whose highlights have not yet expired.
     pub fn active_set(&mut self, now: Instant) -> HashSet<PathBuf> {
-        if self.duration.is_zero() {
+        self.prune(now);
+        self.entries
+            .iter_live(now)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Return the active highlights together with their kinds.
+    pub fn active_map(&mut self, now: Instant) -> HashMap<PathBuf, HighlightKind> {
+        self.prune(now);
+        self.entries
+            .iter_live(now)
+            .map(|(path, entry)| (path.clone(), entry.value.kind))
+            .collect()
+    }
+
+    /// Return the active highlights with their age and fade step.
+    pub fn active(&mut self, now: Instant) -> HashMap<PathBuf, ActiveHighlight> {
+        self.prune(now);
+        self.entries
+            .iter_live(now)
+            .map(|(path, entry)| {
+                let age = now.saturating_duration_since(entry.inserted);
+                let fade = self.entries.ttl_of(entry).map_or(0, |lifetime| {
+                    (age.as_nanos() * u128::from(FADE_STEPS) / lifetime.as_nanos().max(1))
+                        .min(u128::from(FADE_STEPS - 1)) as u8
+                });
+                (
+                    path.clone(),
+                    ActiveHighlight {
+                        kind: entry.value.kind,
+                        age,
+                        fade,
+                        color: entry.value.color,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The next instant at which some highlight moves to another fade step
+    /// or expires, i.e. when the view needs redrawing; `None` if idle.
+    pub fn next_change(&self, now: Instant) -> Option<Instant> {
+        if self.entries.default_ttl().is_none_or(|d| d.is_zero()) {
+            return None;
+        }
+        self.entries
+            .iter_live(now)
+            .filter_map(|(_, entry)| {
+                let lifetime = self.entries.ttl_of(entry)?;
+                let step = lifetime / u32::from(FADE_STEPS);
+                let age = now.saturating_duration_since(entry.inserted);
+                let steps_done = (age.as_nanos() / step.as_nanos().max(1)) as u32;
+                Some((entry.inserted + step * (steps_done + 1)).min(entry.inserted + lifetime))
+            })
+            .min()
+    }
+
+    /// Drop expired entries (all of them when the duration is zero).
+    fn prune(&mut self, now: Instant) {
+        if self.entries.default_ttl().is_some_and(|d| d.is_zero()) {
             // When duration is zero, highlighting is effectively disabled.
             self.entries.clear();
-            return HashSet::new();
+            return;
         }
-        self.entries
-            .retain(|_, inserted| now.duration_since(*inserted) < self.duration);
-        self.entries.keys().cloned().collect()
+        self.entries.prune(now);
     }
 
     /// Remove all highlights (used by the reset key).
@@ -53,6 +287,119 @@ impl HighlightTracker {
     }
 }
 
+/// Highlight the ancestors (below `root`) of every highlighted path that is
+/// not `shown`, so a change inside a collapsed or scrolled-off directory
+/// still flashes the directories that are on screen. Ancestors with a
+/// highlight of their own keep it; the others get an `Inherited` one that
+/// fades with the freshest change below them.
+pub fn inherit_to_ancestors(
+    active: &mut HashMap<PathBuf, ActiveHighlight>,
+    root: &Path,
+    shown: impl Fn(&Path) -> bool,
+) {
+    let hidden: Vec<(PathBuf, ActiveHighlight)> = active
+        .iter()
+        .filter(|(path, h)| h.kind != HighlightKind::Inherited && !shown(path))
+        .map(|(path, h)| (path.clone(), *h))
+        .collect();
+    for (path, highlight) in hidden {
+        let ancestors = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != root && dir.starts_with(root));
+        for dir in ancestors {
+            let inherited = ActiveHighlight {
+                kind: HighlightKind::Inherited,
+                color: None,
+                ..highlight
+            };
+            match active.get_mut(dir) {
+                Some(h) if h.kind == HighlightKind::Inherited && h.age > highlight.age => {
+                    *h = inherited;
+                }
+                Some(_) => {}
+                None => {
+                    active.insert(dir.to_path_buf(), inherited);
+                }
+            }
+        }
+    }
+}
+
+/// An independent highlight layer. Each layer has its own style and
+/// lifetime, so features do not compete for the change highlight. Ordered by
+/// render precedence, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Channel {
+    /// Entries that differ from a baseline snapshot.
+    Baseline,
+    /// Entries the user marked.
+    Marked,
+    /// Recent filesystem changes; expire after the highlight duration.
+    Changes,
+    /// Entries matching the current search.
+    Search,
+}
+
+impl Channel {
+    /// Every channel, lowest render precedence first.
+    pub const ALL: [Channel; 4] = [
+        Channel::Baseline,
+        Channel::Marked,
+        Channel::Changes,
+        Channel::Search,
+    ];
+}
+
+/// One `HighlightTracker` per `Channel`. Changes expire; the other layers
+/// are sticky until cleared.
+pub struct HighlightLayers {
+    layers: HashMap<Channel, HighlightTracker>,
+}
+
+impl Default for HighlightLayers {
+    fn default() -> Self {
+        let layers = Channel::ALL
+            .into_iter()
+            .map(|channel| {
+                let tracker = match channel {
+                    Channel::Changes => HighlightTracker::default(),
+                    _ => HighlightTracker::sticky(),
+                };
+                (channel, tracker)
+            })
+            .collect();
+        Self { layers }
+    }
+}
+
+impl HighlightLayers {
+    /// The tracker for `channel`.
+    pub fn get(&self, channel: Channel) -> &HighlightTracker {
+        &self.layers[&channel]
+    }
+
+    /// The tracker for `channel`, mutably.
+    pub fn get_mut(&mut self, channel: Channel) -> &mut HighlightTracker {
+        self.layers.entry(channel).or_default()
+    }
+
+    /// The channels (other than `Changes`, which carries its own kinds and
+    /// fade) highlighting each path, in render precedence order.
+    pub fn overlays(&mut self, now: Instant) -> HashMap<PathBuf, Vec<Channel>> {
+        let mut overlays: HashMap<PathBuf, Vec<Channel>> = HashMap::new();
+        for channel in Channel::ALL {
+            if channel == Channel::Changes {
+                continue;
+            }
+            for path in self.get_mut(channel).active_set(now) {
+                overlays.entry(path).or_default().push(channel);
+            }
+        }
+        overlays
+    }
+}
+
 #[cfg(test)]
 impl HighlightTracker {
     /// Whether there are any tracked entries (before expiration pruning).
@@ -62,7 +409,7 @@ impl HighlightTracker {
 
     /// Current duration used for pruning.
     fn duration(&self) -> Duration {
-        self.duration
+        self.entries.default_ttl().unwrap_or_default()
     }
 }
 
@@ -98,6 +445,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fade_steps_over_lifetime() {
+        let mut tracker = HighlightTracker::new(Duration::from_secs(4));
+        let t0 = Instant::now();
+        let path = PathBuf::from("/tmp/a.txt");
+        tracker.insert(path.clone(), t0);
+
+        let fade_at = |tracker: &mut HighlightTracker, ms| {
+            tracker.active(t0 + Duration::from_millis(ms))[&path].fade
+        };
+        assert_eq!(fade_at(&mut tracker, 0), 0);
+        assert_eq!(fade_at(&mut tracker, 1500), 1);
+        assert_eq!(fade_at(&mut tracker, 3999), FADE_STEPS - 1);
+        assert_eq!(
+            tracker.next_change(t0 + Duration::from_millis(1500)),
+            Some(t0 + Duration::from_secs(2))
+        );
+        assert!(tracker.active(t0 + Duration::from_secs(4)).is_empty());
+        assert_eq!(tracker.next_change(t0), None);
+    }
+
+    #[test]
+    fn test_per_kind_lifetime_and_color() {
+        let mut tracker = HighlightTracker::new(Duration::from_secs(3));
+        let t0 = Instant::now();
+        let created = KindStyle {
+            color: Some(Color::Green),
+            duration: Some(Duration::from_secs(10)),
+        };
+        tracker.insert_styled(
+            PathBuf::from("/tmp/new.txt"),
+            t0,
+            HighlightKind::Content,
+            &created,
+        );
+        tracker.insert(PathBuf::from("/tmp/old.txt"), t0);
+
+        let active = tracker.active(t0 + Duration::from_secs(5));
+        assert_eq!(active.len(), 1);
+        let new = &active[&PathBuf::from("/tmp/new.txt")];
+        assert_eq!(new.color, Some(Color::Green));
+        assert_eq!(new.fade, 2);
+    }
+
+    #[test]
+    fn test_sticky_layers_do_not_expire() {
+        let mut layers = HighlightLayers::default();
+        let t0 = Instant::now();
+        let path = PathBuf::from("/tmp/a.txt");
+        layers.get_mut(Channel::Changes).insert(path.clone(), t0);
+        assert!(layers.get_mut(Channel::Marked).toggle(path.clone(), t0));
+
+        let later = t0 + Duration::from_secs(3600);
+        assert!(layers
+            .get_mut(Channel::Changes)
+            .active_set(later)
+            .is_empty());
+        assert_eq!(layers.overlays(later)[&path], vec![Channel::Marked]);
+        assert!(!layers.get_mut(Channel::Marked).toggle(path.clone(), later));
+        assert!(layers.overlays(later).is_empty());
+    }
+
     #[test]
     fn test_clear() {
         let mut tracker = HighlightTracker::new(Duration::from_secs(3));
@@ -144,6 +553,84 @@ mod tests {
         assert!(!active.contains(&PathBuf::from("/tmp/old.txt")));
     }
 
+    #[test]
+    fn test_metadata_does_not_downgrade_content() {
+        let mut tracker = HighlightTracker::new(Duration::from_secs(3));
+        let t0 = Instant::now();
+        let path = PathBuf::from("/tmp/a.txt");
+        tracker.insert(path.clone(), t0);
+        tracker.insert_kind(
+            path.clone(),
+            t0 + Duration::from_secs(1),
+            HighlightKind::Metadata,
+        );
+
+        let active = tracker.active_map(t0 + Duration::from_secs(1));
+        assert_eq!(active.get(&path), Some(&HighlightKind::Content));
+
+        // Once the content highlight has expired, a touch is metadata-only again.
+        let later = t0 + Duration::from_secs(10);
+        tracker.insert_kind(path.clone(), later, HighlightKind::Metadata);
+        let active = tracker.active_map(later);
+        assert_eq!(active.get(&path), Some(&HighlightKind::Metadata));
+    }
+
+    #[test]
+    fn test_hidden_changes_are_inherited_by_ancestors() {
+        let root = Path::new("/r");
+        let highlight = |kind, age| ActiveHighlight {
+            kind,
+            age: Duration::from_millis(age),
+            fade: 0,
+            color: Some(Color::Red),
+        };
+        let mut active = HashMap::from([
+            (
+                root.join("a/b/deep.txt"),
+                highlight(HighlightKind::Content, 500),
+            ),
+            (
+                root.join("a/b/new.txt"),
+                highlight(HighlightKind::Content, 100),
+            ),
+            (root.join("a"), highlight(HighlightKind::Metadata, 900)),
+            (root.join("shown.txt"), highlight(HighlightKind::Content, 0)),
+        ]);
+        let shown = |p: &Path| p == root.join("shown.txt") || p == root.join("a");
+        inherit_to_ancestors(&mut active, root, shown);
+
+        let b = active[&root.join("a/b")];
+        assert_eq!(b.kind, HighlightKind::Inherited);
+        assert_eq!(b.age, Duration::from_millis(100), "freshest change wins");
+        assert_eq!(b.color, None);
+        assert_eq!(active[&root.join("a")].kind, HighlightKind::Metadata);
+        assert!(
+            !active.contains_key(root),
+            "the root itself is never inherited"
+        );
+        assert_eq!(active.len(), 5);
+    }
+
+    #[test]
+    fn test_classify_directory_changes() {
+        assert_eq!(
+            HighlightKind::classify(ChangeKind::Modified, true),
+            HighlightKind::Descendant
+        );
+        assert_eq!(
+            HighlightKind::classify(ChangeKind::Created, true),
+            HighlightKind::Content
+        );
+        assert_eq!(
+            HighlightKind::classify(ChangeKind::Renamed, true),
+            HighlightKind::Content
+        );
+        assert_eq!(
+            HighlightKind::classify(ChangeKind::Modified, false),
+            HighlightKind::Content
+        );
+    }
+
     #[test]
     fn test_zero_duration_disables_highlights() {
         let mut tracker = HighlightTracker::new(Duration::from_secs(0));
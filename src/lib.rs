@@ -1,10 +1,40 @@
 #![forbid(unsafe_code)]
 //! LiveTree — a real-time directory tree watcher with flicker-free terminal rendering.
 
+pub mod action;
 pub mod cli;
+pub mod coalesce;
+pub mod config;
+pub mod control;
 pub mod event_loop;
+pub mod exit;
+pub mod export;
+pub mod external;
+pub mod frame;
+pub mod goto;
 pub mod highlight;
+pub mod live;
+pub mod ops;
+pub mod owners;
+pub mod palette;
+pub mod patch;
+pub mod paths;
+pub mod remote;
 pub mod render;
+pub mod report;
+pub mod schema;
+pub mod script;
+pub mod search;
+pub mod serve;
+pub mod split;
+pub mod stats;
+pub mod status;
 pub mod terminal;
+pub mod toast;
 pub mod tree;
+pub mod ttl;
+pub mod ui_state;
+pub mod validate;
 pub mod watcher;
+pub mod widget;
+pub mod workspace;
@@ -0,0 +1,324 @@
+//! Library handle that keeps a tree snapshot up to date in the background.
+//!
+//! `LiveTree` bundles the watcher, the tree builder, and a rebuild thread so
+//! GUI frontends can mirror livetree's model without re-implementing the
+//! plumbing used by the terminal event loop. `LiveTree::builder()` sets one
+//! up without assembling a `TreeConfig` by hand:
+//!
+//! ```no_run
+//! let live = livetree::live::LiveTree::builder()
+//!     .root(".")
+//!     .max_depth(3)
+//!     .ignore("*.log")
+//!     .build()?;
+//! for snapshot in live.subscribe() {
+//!     println!("{} entries", snapshot.total_entries);
+//! }
+//! # Ok::<(), String>(())
+//! ```
+
+use crate::patch::{self, PatchOp};
+use crate::render::{tree_to_lines, RenderConfig};
+use crate::tree::{
+    build_ignore_set, IgnorePattern, TreeBuilder, TreeConfig, TreeSnapshot, WalkdirTreeBuilder,
+};
+use crate::watcher::{start_supervised, SupervisedWatcher, WatchEvent, REVALIDATE_INTERVAL};
+use crossbeam_channel::{Receiver, Sender};
+use ratatui::text::Line;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// State shared between the handle and its rebuild thread.
+struct Shared {
+    snapshot: Mutex<Arc<TreeSnapshot>>,
+    subscribers: Mutex<Vec<Sender<Arc<TreeSnapshot>>>>,
+    patch_subscribers: Mutex<Vec<Sender<Arc<Vec<PatchOp>>>>>,
+}
+
+impl Shared {
+    /// Store a new snapshot and deliver it to every live subscriber,
+    /// dropping subscribers whose receiver has gone away.
+    fn publish(&self, snapshot: TreeSnapshot) {
+        let snapshot = Arc::new(snapshot);
+        let previous = match self.snapshot.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, Arc::clone(&snapshot)),
+            Err(_) => return,
+        };
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|tx| tx.send(Arc::clone(&snapshot)).is_ok());
+        }
+        if let Ok(mut subscribers) = self.patch_subscribers.lock() {
+            if subscribers.is_empty() {
+                return;
+            }
+            let ops = patch::diff(&previous, &snapshot);
+            if ops.is_empty() {
+                return;
+            }
+            let ops = Arc::new(ops);
+            subscribers.retain(|tx| tx.send(Arc::clone(&ops)).is_ok());
+        }
+    }
+}
+
+/// A watched directory whose snapshot is rebuilt after every debounced change.
+///
+/// Dropping the handle stops the watcher and joins the rebuild thread.
+pub struct LiveTree {
+    root: PathBuf,
+    shared: Arc<Shared>,
+    watcher: Option<SupervisedWatcher>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl LiveTree {
+    /// Configure a `LiveTree` step by step; see [`LiveTreeBuilder`].
+    pub fn builder() -> LiveTreeBuilder {
+        LiveTreeBuilder::default()
+    }
+
+    /// Build the initial snapshot of `root` and start watching it.
+    pub fn start(root: &Path, config: TreeConfig, debounce_ms: u64) -> Result<Self, String> {
+        let (watcher, fs_rx) = start_supervised(root, debounce_ms, REVALIDATE_INTERVAL)?;
+        let builder = WalkdirTreeBuilder;
+        let shared = Arc::new(Shared {
+            snapshot: Mutex::new(Arc::new(builder.build_tree(root, &config))),
+            subscribers: Mutex::new(Vec::new()),
+            patch_subscribers: Mutex::new(Vec::new()),
+        });
+
+        let worker = {
+            let shared = Arc::clone(&shared);
+            let root = root.to_path_buf();
+            thread::spawn(move || rebuild_loop(&root, &config, &builder, &fs_rx, &shared))
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            shared,
+            watcher: Some(watcher),
+            worker: Some(worker),
+        })
+    }
+
+    /// The watched root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The most recently built snapshot.
+    pub fn snapshot(&self) -> Arc<TreeSnapshot> {
+        self.shared
+            .snapshot
+            .lock()
+            .map(|s| Arc::clone(&s))
+            .unwrap_or_default()
+    }
+
+    /// Receive every snapshot built from now on (one per rebuild).
+    pub fn subscribe(&self) -> Receiver<Arc<TreeSnapshot>> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        if let Ok(mut subscribers) = self.shared.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
+    /// The current snapshot as styled tree lines, ready for a ratatui
+    /// `Paragraph` (or `render::line_to_plain_text`).
+    pub fn render_lines(&self, config: &RenderConfig) -> Vec<Line<'static>> {
+        tree_to_lines(&self.snapshot().entries, config, &HashSet::new())
+    }
+
+    /// Receive the delta from the previous snapshot for every rebuild that
+    /// changed the tree. Apply them to `snapshot()` taken before subscribing,
+    /// e.g. to keep a web frontend in sync without resending the whole tree.
+    pub fn subscribe_patches(&self) -> Receiver<Arc<Vec<PatchOp>>> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        if let Ok(mut subscribers) = self.shared.patch_subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+}
+
+impl Drop for LiveTree {
+    fn drop(&mut self) {
+        // Dropping the debouncer closes the event channel, which ends the worker.
+        drop(self.watcher.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Options for [`LiveTree::builder`]. Defaults match the command line: no
+/// depth limit, hidden files and symlinks skipped, the default ignores
+/// (`.git`, `node_modules`, …), and a 200 ms debounce.
+#[derive(Debug, Clone)]
+pub struct LiveTreeBuilder {
+    root: Option<PathBuf>,
+    max_depth: Option<usize>,
+    ignore: Vec<String>,
+    show_hidden: bool,
+    dirs_only: bool,
+    follow_symlinks: bool,
+    max_entries: Option<usize>,
+    debounce_ms: u64,
+}
+
+impl Default for LiveTreeBuilder {
+    fn default() -> Self {
+        Self {
+            root: None,
+            max_depth: None,
+            ignore: Vec::new(),
+            show_hidden: false,
+            dirs_only: false,
+            follow_symlinks: false,
+            max_entries: None,
+            debounce_ms: 200,
+        }
+    }
+}
+
+impl LiveTreeBuilder {
+    /// The directory to watch (required).
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Exclude entries matching a glob; may be called repeatedly.
+    pub fn ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore.push(pattern.into());
+        self
+    }
+
+    pub fn show_hidden(mut self, show: bool) -> Self {
+        self.show_hidden = show;
+        self
+    }
+
+    pub fn dirs_only(mut self, dirs_only: bool) -> Self {
+        self.dirs_only = dirs_only;
+        self
+    }
+
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Keep only the first `max` entries of each snapshot.
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    /// Milliseconds to wait for changes to settle before rebuilding (at
+    /// least 50, as on the command line).
+    pub fn debounce_ms(mut self, ms: u64) -> Self {
+        self.debounce_ms = ms.max(50);
+        self
+    }
+
+    /// The tree options these settings describe.
+    pub fn tree_config(&self) -> Result<TreeConfig, String> {
+        for pattern in &self.ignore {
+            IgnorePattern::parse(pattern)
+                .map_err(|e| format!("invalid ignore pattern '{pattern}': {e}"))?;
+        }
+        if self.max_depth == Some(0) {
+            return Err("max depth must be at least 1".to_string());
+        }
+        Ok(TreeConfig {
+            max_depth: self.max_depth,
+            show_hidden: self.show_hidden,
+            dirs_only: self.dirs_only,
+            follow_symlinks: self.follow_symlinks,
+            ignore_patterns: build_ignore_set(&self.ignore),
+            max_entries: self.max_entries,
+            stat: false,
+        })
+    }
+
+    /// Build the initial snapshot and start watching.
+    pub fn build(self) -> Result<LiveTree, String> {
+        let config = self.tree_config()?;
+        let root = self.root.ok_or("no root directory given")?;
+        LiveTree::start(&root, config, self.debounce_ms)
+    }
+}
+
+/// The async counterpart of `LiveTree` (with the `async` feature): calls
+/// `on_snapshot` with the initial snapshot and again after every change,
+/// until it returns `ControlFlow::Break`, the root is deleted, or the
+/// watcher stops. Walks run on tokio's blocking pool, so the caller's
+/// runtime threads are never blocked.
+#[cfg(feature = "async")]
+pub async fn run_async<F>(
+    root: &Path,
+    config: TreeConfig,
+    debounce_ms: u64,
+    mut on_snapshot: F,
+) -> Result<(), String>
+where
+    F: FnMut(Arc<TreeSnapshot>) -> std::ops::ControlFlow<()>,
+{
+    use crate::watcher::stream;
+
+    let mut events = stream::watch(root, debounce_ms)?;
+    let config = Arc::new(config);
+    let build = |root: PathBuf, config: Arc<TreeConfig>| async move {
+        tokio::task::spawn_blocking(move || WalkdirTreeBuilder.build_tree(&root, &config))
+            .await
+            .map(Arc::new)
+            .map_err(|e| format!("tree build failed: {e}"))
+    };
+
+    let snapshot = build(root.to_path_buf(), Arc::clone(&config)).await?;
+    if on_snapshot(snapshot).is_break() {
+        return Ok(());
+    }
+    while let Some(event) = events.next().await {
+        match event {
+            WatchEvent::Changed(_) | WatchEvent::Reattached => {
+                let snapshot = build(root.to_path_buf(), Arc::clone(&config)).await?;
+                if on_snapshot(snapshot).is_break() {
+                    break;
+                }
+            }
+            WatchEvent::RootDeleted => break,
+            WatchEvent::Error(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild and publish the snapshot for every change until the watcher stops
+/// or the root is deleted.
+fn rebuild_loop(
+    root: &Path,
+    config: &TreeConfig,
+    builder: &dyn TreeBuilder,
+    fs_rx: &Receiver<WatchEvent>,
+    shared: &Shared,
+) {
+    while let Ok(event) = fs_rx.recv() {
+        match event {
+            WatchEvent::Changed(_) | WatchEvent::Reattached => {
+                shared.publish(builder.build_tree(root, config))
+            }
+            WatchEvent::RootDeleted => break,
+            WatchEvent::Error(_) => {}
+        }
+    }
+}
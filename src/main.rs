@@ -1,52 +1,318 @@
 #![forbid(unsafe_code)]
-mod cli;
-mod event_loop;
-mod highlight;
-mod render;
-mod terminal;
-mod tree;
-mod watcher;
-
-use anyhow::{Context, Result};
+
+use anyhow::{anyhow, Context};
 use clap::Parser;
-use cli::Args;
-use render::RenderConfig;
-use tree::{build_ignore_set, TreeConfig};
+use crossbeam_channel::Receiver;
+use livetree::action::Keymap;
+use livetree::cli::{Args, Command};
+use livetree::config;
+use livetree::event_loop::ExitReason;
+use livetree::exit::ExitCode;
+use livetree::export;
+use livetree::remote::RemoteSource;
+use livetree::render::{Charset, Listing, RenderConfig};
+use livetree::report;
+use livetree::script::{self, StreamEnd, Wait};
+use livetree::serve::{Connection, Server};
+use livetree::tree::{
+    build_ignore_set, build_ignore_set_no_defaults, SourceTreeBuilder, TreeBuilder, TreeConfig,
+    WalkdirTreeBuilder,
+};
+use livetree::validate::{self, Severity, Startup, Subject};
+use livetree::watcher::{SupervisedWatcher, WatchEvent, WatcherOptions};
+use livetree::workspace::Workspace;
+use livetree::{control, event_loop, palette, paths, terminal, ui_state, watcher};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
 
-fn main() {
-    if let Err(e) = run_app() {
-        eprintln!("livetree: {e:#}");
-        std::process::exit(1);
+fn main() -> std::process::ExitCode {
+    match run_app() {
+        Ok(code) => code.into(),
+        Err((code, e)) => {
+            eprintln!("livetree: {e:#}");
+            code.into()
+        }
     }
 }
 
-fn run_app() -> Result<()> {
-    let args = Args::parse().validated();
+/// A fatal error together with the exit code it maps to.
+type Failure = (ExitCode, anyhow::Error);
+
+/// The config file a subcommand works on: its own argument, `--config`, or
+/// the default location.
+fn config_file(
+    file: &Option<std::path::PathBuf>,
+    args: &Args,
+) -> Result<std::path::PathBuf, Failure> {
+    file.clone()
+        .or_else(|| args.config.clone())
+        .or_else(config::default_config_path)
+        .ok_or_else(|| {
+            (
+                ExitCode::BadArgs,
+                anyhow!("no config file location known; pass a file"),
+            )
+        })
+}
+
+/// `livetree check-config`: print every problem in `file`.
+fn check_config(file: &std::path::Path) -> ExitCode {
+    let problems = validate::check_config(file);
+    for problem in &problems {
+        eprintln!("livetree: {problem}");
+    }
+    if problems.iter().any(|p| p.severity == Severity::Error) {
+        ExitCode::BadArgs
+    } else {
+        if !file.exists() {
+            println!("{}: not found, defaults apply", file.display());
+        } else {
+            println!("{}: OK", file.display());
+        }
+        ExitCode::Success
+    }
+}
 
-    let path = args
-        .path
-        .canonicalize()
-        .with_context(|| format!("{}: failed to resolve path", args.path.display()))?;
+/// Append `tracing` output to `file`, with a line per closed span giving its
+/// duration. `-v`/`-vv` raise the level; `LIVETREE_LOG` replaces the filter.
+fn init_logging(file: &std::path::Path, verbose: u8) -> anyhow::Result<()> {
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .with_context(|| format!("cannot open log file {}", file.display()))?;
+    let level = match verbose {
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_env("LIVETREE_LOG")
+        .unwrap_or_else(|_| EnvFilter::new(format!("livetree={level}")));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::sync::Mutex::new(log))
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .map_err(|e| anyhow!(e))
+}
 
-    anyhow::ensure!(path.is_dir(), "{}: Not a directory", path.display());
+fn run_app() -> Result<ExitCode, Failure> {
+    let mut args = Args::parse();
+    if let Some(file) = args.session.clone() {
+        let saved = Workspace::load(&file).map_err(|e| (ExitCode::BadArgs, anyhow!(e)))?;
+        if let Some(workspace) = saved {
+            workspace.apply(&mut args);
+        }
+    }
+    let args = args.validated();
+    if let Some(file) = &args.log_file {
+        init_logging(file, args.verbose).map_err(|e| (ExitCode::Failure, e))?;
+    }
+
+    match &args.command {
+        Some(Command::CheckConfig { file }) => {
+            return Ok(check_config(&config_file(file, &args)?));
+        }
+        Some(Command::InitConfig { file, force }) => {
+            let file = config_file(file, &args)?;
+            config::write_default(&file, *force).map_err(|e| (ExitCode::Failure, anyhow!(e)))?;
+            println!("wrote {}", file.display());
+            return Ok(ExitCode::Success);
+        }
+        None => {}
+    }
+
+    let (startup, problems) = validate::validate(&args);
+    for problem in &problems {
+        eprintln!("livetree: {problem}");
+    }
+    let Some(Startup {
+        root: path,
+        focus,
+        mut config,
+        report,
+        ignore,
+        split,
+    }) = startup
+    else {
+        let errors: Vec<_> = problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .collect();
+        let code = if errors.iter().any(|p| p.subject == Subject::Root) {
+            ExitCode::PathNotFound
+        } else {
+            ExitCode::BadArgs
+        };
+        return Err((
+            code,
+            anyhow!(
+                "{} problem{} found, not starting",
+                errors.len(),
+                if errors.len() == 1 { "" } else { "s" }
+            ),
+        ));
+    };
+
+    // A remote or served tree has no files here; nothing that reads them
+    // can work.
+    let local = args.remote.is_none() && args.connect.is_none();
 
     // Build configs
     let tree_config = TreeConfig {
         max_depth: args.max_depth,
-        show_hidden: args.show_hidden,
+        // A dotfile passed by name is shown.
+        show_hidden: args.show_hidden
+            || focus.as_deref().is_some_and(|f| {
+                f.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            }),
         dirs_only: args.dirs_only,
         follow_symlinks: args.follow_symlinks,
-        ignore_patterns: build_ignore_set(&args.ignore),
+        ignore_patterns: if args.no_default_ignores {
+            build_ignore_set_no_defaults(&ignore)
+        } else {
+            build_ignore_set(&ignore)
+        },
         max_entries: Some(args.max_entries),
+        stat: local && (args.permissions || args.owner),
     };
 
+    if let Some(report) = report {
+        print!("{}", report::run(&report, &path, &tree_config));
+        return Ok(ExitCode::Success);
+    }
+
+    if let Some(file) = &args.export {
+        let snapshot = WalkdirTreeBuilder.build_tree(&path, &tree_config);
+        export::write(&snapshot, &path, file, args.format)
+            .map_err(|e| (ExitCode::Failure, anyhow!(e).context("export failed")))?;
+        if file.as_os_str() != "-" {
+            println!("wrote {}", file.display());
+        }
+        return Ok(ExitCode::Success);
+    }
+
+    let watcher_options = WatcherOptions::new(args.debounce_ms);
+
+    if args.exit_on_change {
+        let (_watcher, fs_rx) = start_watcher(&path, &watcher_options)?;
+        return match script::wait_for_change(&path, &tree_config, &fs_rx) {
+            Wait::Changed(changes) => {
+                for change in changes {
+                    println!("{}", paths::strip_verbatim(&change.path).display());
+                }
+                Ok(ExitCode::Success)
+            }
+            Wait::RootDeleted => Err((
+                ExitCode::RootDeleted,
+                anyhow!("{} was deleted", paths::strip_verbatim(&path).display()),
+            )),
+            Wait::WatcherStopped => Err((
+                ExitCode::WatcherFailed,
+                anyhow!("watcher stopped unexpectedly"),
+            )),
+        };
+    }
+
+    if let Some(format) = args.stream {
+        let (_watcher, fs_rx) = start_watcher(&path, &watcher_options)?;
+        let mut stdout = std::io::stdout().lock();
+        return match script::stream(&path, &tree_config, &fs_rx, format, &mut stdout) {
+            Ok(StreamEnd::RootDeleted) => Ok(ExitCode::RootDeleted),
+            Ok(StreamEnd::WatcherStopped) => Err((
+                ExitCode::WatcherFailed,
+                anyhow!("watcher stopped unexpectedly"),
+            )),
+            // The reader went away (e.g. `| head`).
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(ExitCode::Success),
+            Err(e) => Err((
+                ExitCode::Failure,
+                anyhow!(e).context("cannot write changes"),
+            )),
+        };
+    }
+
+    // Where the tree comes from, and the events that say it changed.
+    let source = args.remote.clone().map(RemoteSource::new);
+    let source_builder = source.clone().map(SourceTreeBuilder);
+    let mut connection = None;
+    let (_watcher, _poller, fs_rx) = if let Some(endpoint) = &args.connect {
+        let (opened, fs_rx) =
+            Connection::open(endpoint).map_err(|e| (ExitCode::WatcherFailed, anyhow!(e)))?;
+        connection = Some(opened);
+        (None, None, fs_rx)
+    } else if let Some(source) = &source {
+        let (poller, fs_rx) = source.start_polling(args.poll_interval);
+        (None, Some(poller), fs_rx)
+    } else {
+        let (watcher, fs_rx) = start_watcher(&path, &watcher_options)?;
+        (Some(watcher), None, fs_rx)
+    };
+    // The directory shown beside the tree; --split is local only.
+    let (_split_watcher, split) = match split {
+        Some(dir) => {
+            let (watcher, split_rx) = start_watcher(&dir, &watcher_options)?;
+            (Some(watcher), Some((dir, split_rx)))
+        }
+        None => (None, None),
+    };
+    let tree_builder: &dyn TreeBuilder = match (&connection, &source_builder) {
+        (Some(connection), _) => connection,
+        (None, Some(builder)) => builder,
+        (None, None) => &WalkdirTreeBuilder,
+    };
+    let path = connection.as_ref().map_or(path, |c| c.root().to_path_buf());
+    let watched = match (&args.remote, &args.connect) {
+        (Some(remote), _) => remote.to_string(),
+        (None, Some(endpoint)) => format!("{} at {endpoint}", path.display()),
+        (None, None) => paths::strip_verbatim(&path).display().to_string(),
+    };
+
+    if let Some(endpoint) = &args.serve {
+        let server = Server::bind(endpoint).map_err(|e| (ExitCode::Failure, anyhow!(e)))?;
+        if !args.quiet {
+            eprintln!("livetree: serving {watched} on {endpoint}");
+        }
+        return match server.run(&path, &tree_config, tree_builder, &fs_rx) {
+            Ok(StreamEnd::RootDeleted) => Ok(ExitCode::RootDeleted),
+            Ok(StreamEnd::WatcherStopped) => Err((
+                ExitCode::WatcherFailed,
+                anyhow!("watcher stopped unexpectedly"),
+            )),
+            Err(e) => Err((ExitCode::Failure, anyhow!(e))),
+        };
+    }
+
+    let (_control, control_rx) = match &args.control {
+        Some(endpoint) => {
+            let (server, rx) =
+                control::listen(endpoint).map_err(|e| (ExitCode::Failure, anyhow!(e)))?;
+            (Some(server), Some(rx))
+        }
+        None => (None, None),
+    };
+
+    if args.safe_mode {
+        config.mouse.enabled = false;
+        if args.verbose > 0 && !args.quiet {
+            eprintln!("livetree: safe mode: built-in defaults, no config file or integrations");
+        }
+    }
+
     let (term_width, _) = terminal::terminal_size();
+    // Without VT support, skip escape-based features and draw plain ASCII.
+    let vt = terminal::enable_vt_mode();
+    if !vt && args.verbose > 0 && !args.quiet {
+        eprintln!("livetree: console has no VT support; using ASCII without colors");
+    }
 
     // Optionally set the terminal (window/pane) title so multiplexers like Zellij
     // can display a meaningful name. The title is formatted as
-    // "Live Tree <dir>", with HOME collapsed to "~", and truncated with an
-    // ellipsis if it would exceed the terminal width.
-    if !args.no_title {
+    // "Live Tree of <dir>", using the directory name only, and truncated with
+    // an ellipsis if it would exceed the terminal width.
+    if !args.no_title && vt {
         if let Some(title) = build_terminal_title(&path, term_width as usize) {
             use std::io::Write as _;
             let mut stdout = std::io::stdout();
@@ -54,50 +320,127 @@ fn run_app() -> Result<()> {
             let _ = stdout.flush();
         }
     }
-    let render_config = RenderConfig {
-        use_color: !args.no_color,
-        terminal_width: term_width,
-    };
+    let render_config = RenderConfig::builder()
+        .use_color(!args.no_color && vt)
+        .terminal_width(term_width)
+        .truncate_names(!args.no_truncate)
+        .charset(if vt { Charset::Unicode } else { Charset::Ascii })
+        .ambiguous_width(args.ambiguous_width)
+        .listing(if args.flat {
+            Listing::Flat
+        } else {
+            Listing::Tree
+        })
+        .build();
 
     if args.verbose > 0 && !args.quiet {
         eprintln!(
             "livetree: watching {} (debounce={}ms, color={})",
-            path.display(),
+            watched,
             args.debounce_ms,
             if render_config.use_color { "on" } else { "off" }
         );
     }
 
-    // Start filesystem watcher
-    let (_debouncer, fs_rx) = watcher::start_watcher(&path, args.debounce_ms)
-        .map_err(anyhow::Error::msg)
-        .context("failed to start watcher")?;
-
     // Initialize ratatui terminal (alternate screen, raw mode, panic hook)
-    let term = terminal::init().context("failed to initialize terminal")?;
+    let term = terminal::init()
+        .context("failed to initialize terminal")
+        .map_err(|e| (ExitCode::TerminalFailed, e))?;
+    if config.mouse.enabled {
+        let _ = terminal::set_mouse_capture(true);
+    }
 
     // Run the main event loop (blocks until quit)
-    event_loop::run(term, &path, &tree_config, &render_config, fs_rx, args.quiet);
+    let loop_config = event_loop::LoopConfig {
+        show_summary: local && args.summary,
+        show_heatmap: args.heatmap,
+        changers_window: args.changers_window,
+        hard_links: local && args.hard_links,
+        permissions: local && args.permissions,
+        owner: local && args.owner,
+        line_numbers: args.line_numbers,
+        relative_line_numbers: args.relative_line_numbers,
+        sticky_headers: !args.no_sticky_headers,
+        columns: args.columns,
+        mouse: config.mouse,
+        keymap: Keymap::default(),
+        stale_after: args.stale_after,
+        highlight: config.highlight,
+        status_template: args
+            .status_format
+            .clone()
+            .or(config.status.template)
+            .unwrap_or_default(),
+        ignore_patterns: args.ignore.clone(),
+        ignore_file: args.ignore_file.clone(),
+        default_ignores: !args.no_default_ignores,
+        history_file: palette::history_path(&path).filter(|_| local && !args.safe_mode),
+        announce: args.announce,
+        external_programs: local && !args.safe_mode,
+        lazy_depth: args.lazy,
+        trash: !args.no_trash,
+        state_file: ui_state::state_path(&path).filter(|_| local && !args.safe_mode),
+        session_file: args.session.clone().filter(|_| local),
+        read_only: !local,
+        focus,
+        control: control_rx,
+        split,
+        compare: args.compare,
+        max_fps: args.max_fps,
+        watcher_options: local.then_some(watcher_options),
+    };
+    let session = event_loop::run_with_tree_builder(
+        term,
+        &path,
+        &tree_config,
+        &render_config,
+        fs_rx,
+        tree_builder,
+        &loop_config,
+    );
 
+    // The terminal is gone after a hangup: restoring it or printing to it
+    // would only fail.
+    if session.reason == ExitReason::HungUp {
+        return Ok(session.reason.into());
+    }
     // Restore terminal state
     terminal::restore();
-    Ok(())
+    match session.reason {
+        ExitReason::WatcherStopped => match &args.connect {
+            Some(endpoint) => eprintln!("livetree: connection to {endpoint} closed"),
+            None => eprintln!("livetree: watcher stopped unexpectedly"),
+        },
+        ExitReason::RootDeleted => println!(
+            "livetree: {} was deleted; watched it for {session}",
+            paths::strip_verbatim(&path).display()
+        ),
+        _ => println!("livetree: watched {watched} for {session}"),
+    }
+    Ok(session.reason.into())
+}
+
+/// Start the filesystem watcher on `path`.
+fn start_watcher(
+    path: &std::path::Path,
+    options: &WatcherOptions,
+) -> Result<(SupervisedWatcher, Receiver<WatchEvent>), Failure> {
+    watcher::start_supervised_with(path, options, watcher::REVALIDATE_INTERVAL)
+        .map_err(anyhow::Error::msg)
+        .context("failed to start watcher")
+        .map_err(|e| (ExitCode::WatcherFailed, e))
 }
 
 /// Build a terminal title of the form "Live Tree <dir>", where <dir> is the
-/// directory name only, truncated with a middle ellipsis so it does not exceed
-/// `max_cols` characters.
+/// directory name only (or the whole path for a root such as `C:\`),
+/// truncated with a middle ellipsis so it does not exceed `max_cols`
+/// characters.
 fn build_terminal_title(path: &std::path::Path, max_cols: usize) -> Option<String> {
     if max_cols == 0 {
         return None;
     }
 
-    let display = path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| path.to_string_lossy().to_string());
-
-    let raw_title = format!("Live Tree of {}", display);
+    let raw_title = format!("Live Tree of {}", paths::display_name(path));
     let sanitized = sanitize_title(&raw_title);
     Some(truncate_middle(&sanitized, max_cols))
 }
@@ -0,0 +1,125 @@
+//! File operations on tree entries: creating files and directories,
+//! renaming, and deleting. Nothing here asks before acting; the event loop
+//! confirms deletions before calling `trash` or `delete`.
+//!
+//! Names are single path components, so an operation never reaches outside
+//! the directory it was given.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `name` if it can be used as a new entry name in a directory.
+pub fn check_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("name is empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("'{name}' is not a valid name"));
+    }
+    if name.contains('/') || name.contains(std::path::MAIN_SEPARATOR) {
+        return Err(format!("'{name}' must not contain a path separator"));
+    }
+    Ok(name)
+}
+
+/// Create an empty file `name` in `dir`, refusing to touch an existing one.
+pub fn create_file(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = dir.join(check_name(name)?);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| error(&path, e))?;
+    Ok(path)
+}
+
+/// Create a directory `name` in `dir`.
+pub fn create_dir(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = dir.join(check_name(name)?);
+    fs::create_dir(&path).map_err(|e| error(&path, e))?;
+    Ok(path)
+}
+
+/// Rename `path` to `new_name` in the same directory. An existing entry
+/// with that name is never replaced.
+pub fn rename(path: &Path, new_name: &str) -> Result<PathBuf, String> {
+    let parent = path.parent().ok_or("cannot rename the root")?;
+    let target = parent.join(check_name(new_name)?);
+    if target == path {
+        return Ok(target);
+    }
+    if target.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", display_name(&target)));
+    }
+    fs::rename(path, &target).map_err(|e| error(path, e))?;
+    Ok(target)
+}
+
+/// Delete `path`; a directory is deleted with everything in it. A symlink
+/// is removed itself, never what it points to.
+pub fn delete(path: &Path) -> Result<(), String> {
+    let meta = path.symlink_metadata().map_err(|e| error(path, e))?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .map_err(|e| error(path, e))
+}
+
+/// Move `path` to the OS trash: the freedesktop.org trash on Linux and
+/// BSD, the user's Trash on macOS, and the Recycle Bin on Windows.
+pub fn trash(path: &Path) -> Result<(), String> {
+    trash::delete(path)
+        .map_err(|e| format!("{}: cannot move to the trash: {}", display_name(path), e))
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn error(path: &Path, e: std::io::Error) -> String {
+    format!("{}: {}", display_name(path), e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn names_stay_inside_the_directory() {
+        assert_eq!(check_name(" notes.md "), Ok("notes.md"));
+        assert!(check_name("").is_err());
+        assert!(check_name("..").is_err());
+        assert!(check_name("a/b").is_err());
+    }
+
+    #[test]
+    fn create_rename_and_delete() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+
+        let file = create_file(root, "a.txt").unwrap();
+        assert!(file.is_file());
+        assert!(create_file(root, "a.txt")
+            .unwrap_err()
+            .starts_with("a.txt: "));
+
+        let dir = create_dir(root, "sub").unwrap();
+        fs::write(dir.join("inner.txt"), "x").unwrap();
+
+        let renamed = rename(&file, "b.txt").unwrap();
+        assert_eq!(renamed, root.join("b.txt"));
+        assert!(!file.exists());
+        assert_eq!(rename(&renamed, "sub").unwrap_err(), "sub already exists");
+
+        delete(&dir).unwrap();
+        delete(&renamed).unwrap();
+        assert_eq!(fs::read_dir(root).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,79 @@
+//! User and group names for the owner column (`--owner`).
+//!
+//! Every uid and gid is looked up once and remembered, so a tree of
+//! thousands of files owned by a handful of accounts costs a handful of
+//! passwd and group database lookups rather than one per entry and frame.
+
+use std::collections::HashMap;
+
+/// Cache of uid → user name and gid → group name. Ids with no database
+/// entry (e.g. files from another machine) show as the number itself.
+#[derive(Debug, Default)]
+pub struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of user `uid`.
+    pub fn user(&mut self, uid: u32) -> &str {
+        self.users.entry(uid).or_insert_with(|| lookup_user(uid))
+    }
+
+    /// Name of group `gid`.
+    pub fn group(&mut self, gid: u32) -> &str {
+        self.groups.entry(gid).or_insert_with(|| lookup_group(gid))
+    }
+
+    /// Number of ids looked up so far, users and groups together.
+    pub fn len(&self) -> usize {
+        self.users.len() + self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> String {
+    uzers::get_user_by_uid(uid)
+        .map(|u| u.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| uid.to_string())
+}
+
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> String {
+    uzers::get_group_by_gid(gid)
+        .map(|g| g.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| gid.to_string())
+}
+
+#[cfg(not(unix))]
+fn lookup_user(uid: u32) -> String {
+    uid.to_string()
+}
+
+#[cfg(not(unix))]
+fn lookup_group(gid: u32) -> String {
+    gid.to_string()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_looked_up_once() {
+        let mut names = OwnerNames::new();
+        assert_eq!(names.user(0), "root");
+        assert_eq!(names.user(0), "root");
+        // An id nobody has falls back to the number.
+        assert_eq!(names.group(4_000_000_000), "4000000000");
+        assert_eq!(names.len(), 2);
+    }
+}
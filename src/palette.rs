@@ -0,0 +1,625 @@
+//! The command palette (`:` / `g`): a one-line prompt that jumps to paths
+//! and entry numbers, runs commands for every runtime-adjustable setting
+//! (`set hidden on`, `duration 5s`, `ignore *.log`, …), and creates,
+//! renames, and deletes entries (`touch`, `mkdir`, `rename`, `delete`).
+//!
+//! Input that does not start with a command word is a jump target, so
+//! `src/main.rs` and `42` work as before. The palette completes command
+//! names, setting names, paths, and ignore patterns with Tab, validates the
+//! input as it is typed, and keeps a history of commands per watched root.
+
+use crate::cli::parse_duration;
+use crate::goto;
+use crate::ops;
+use crate::paths;
+use crate::tree::{IgnorePattern, TreeEntry};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Palette commands with their argument synopsis.
+pub const COMMANDS: [(&str, &str); 13] = [
+    ("goto", "<path|N>"),
+    ("find", "[TEXT]"),
+    ("set", "<setting> [on|off]"),
+    ("duration", "<DURATION>"),
+    ("ignore", "<GLOB>"),
+    ("unignore", "<GLOB>"),
+    ("export", "<FILE>"),
+    ("touch", "<NAME>"),
+    ("mkdir", "<NAME>"),
+    ("rename", "<NAME>"),
+    ("delete", ""),
+    ("clear", ""),
+    ("quit", ""),
+];
+
+/// A setting that can be switched on and off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    /// Hidden files (`.`).
+    Hidden,
+    /// Per-directory extension summary (`s`).
+    Summary,
+    /// Preview pane (`p`).
+    Preview,
+    /// Line-number gutter (`#`).
+    Numbers,
+    /// Gutter numbers relative to the selection (`%`).
+    Relative,
+    /// Follow mode (`F`).
+    Follow,
+    /// Stale-file marks (`S`).
+    Stale,
+    /// Heaviest-paths panel (`H`).
+    Heavy,
+    /// Activity heatmap (`T`).
+    Heatmap,
+    /// Top-changers panel (`C`).
+    Changers,
+    /// Pinned directory headers (`P`).
+    Sticky,
+    /// Compare mode in split view (`=`).
+    Compare,
+    /// Flat list of relative paths (`v`).
+    Flat,
+    /// Short trees packed into columns (`|`).
+    Columns,
+    /// Search matches listed best first (`o`).
+    Ranked,
+}
+
+impl Setting {
+    /// Every setting with its name in `set` commands.
+    pub const ALL: [(&'static str, Setting); 15] = [
+        ("hidden", Setting::Hidden),
+        ("summary", Setting::Summary),
+        ("preview", Setting::Preview),
+        ("numbers", Setting::Numbers),
+        ("relative", Setting::Relative),
+        ("follow", Setting::Follow),
+        ("stale", Setting::Stale),
+        ("heavy", Setting::Heavy),
+        ("heatmap", Setting::Heatmap),
+        ("changers", Setting::Changers),
+        ("sticky", Setting::Sticky),
+        ("compare", Setting::Compare),
+        ("flat", Setting::Flat),
+        ("columns", Setting::Columns),
+        ("ranked", Setting::Ranked),
+    ];
+}
+
+/// A parsed palette command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Jump to a path or entry number (also what bare input means).
+    Goto(String),
+    /// Search entry paths; empty text ends the search.
+    Search(String),
+    /// Turn a setting on or off; `None` toggles it.
+    Set(Setting, Option<bool>),
+    /// Change the highlight duration.
+    Duration(Duration),
+    /// Hide entries matching a glob.
+    Ignore(String),
+    /// Stop hiding entries matching a glob added with `-I` or `ignore`.
+    Unignore(String),
+    /// Write the tree to a file (format from the extension).
+    Export(String),
+    /// Create an empty file next to the selection (in it, for a directory).
+    Touch(String),
+    /// Create a directory where `Touch` would create a file.
+    Mkdir(String),
+    /// Rename the selected entry within its directory.
+    Rename(String),
+    /// Delete the selected entry, after confirmation.
+    Delete,
+    /// Clear change highlights.
+    Clear,
+    Quit,
+}
+
+/// Parse palette input. Anything that does not start with a command word is
+/// a `Goto`; prefix a path with `./` if its name is a command.
+pub fn parse(input: &str) -> Result<Command, String> {
+    let input = input.trim();
+    let (word, args) = split_word(input);
+    let Some(&(name, usage)) = COMMANDS.iter().find(|(n, _)| *n == word) else {
+        return Ok(Command::Goto(input.to_string()));
+    };
+    let usage = || format!("usage: {name} {usage}").trim_end().to_string();
+    let command = match name {
+        "goto" if !args.is_empty() => Command::Goto(args.to_string()),
+        "find" => Command::Search(args.to_string()),
+        "set" => {
+            let mut words = args.split_whitespace();
+            let setting = words.next().ok_or_else(usage)?;
+            let setting = Setting::ALL
+                .iter()
+                .find(|(n, _)| *n == setting)
+                .map(|&(_, s)| s)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown setting '{setting}' (one of: {})",
+                        names(&Setting::ALL).join(", ")
+                    )
+                })?;
+            let value = match words.next() {
+                None => None,
+                Some("on" | "true") => Some(true),
+                Some("off" | "false") => Some(false),
+                Some(value) => return Err(format!("expected on or off, not '{value}'")),
+            };
+            if words.next().is_some() {
+                return Err(usage());
+            }
+            Command::Set(setting, value)
+        }
+        "duration" if !args.is_empty() => Command::Duration(parse_duration(args)?),
+        "ignore" | "unignore" if !args.is_empty() => {
+            IgnorePattern::parse(args)
+                .map_err(|e| format!("invalid glob '{args}': {}", e.kind()))?;
+            if name == "ignore" {
+                Command::Ignore(args.to_string())
+            } else {
+                Command::Unignore(args.to_string())
+            }
+        }
+        "export" if !args.is_empty() => Command::Export(args.to_string()),
+        "touch" | "mkdir" | "rename" if !args.is_empty() => {
+            let entry = ops::check_name(args)?.to_string();
+            match name {
+                "touch" => Command::Touch(entry),
+                "mkdir" => Command::Mkdir(entry),
+                _ => Command::Rename(entry),
+            }
+        }
+        "delete" if args.is_empty() => Command::Delete,
+        "clear" if args.is_empty() => Command::Clear,
+        "quit" if args.is_empty() => Command::Quit,
+        _ => return Err(usage()),
+    };
+    Ok(command)
+}
+
+/// What to show next to the input while it is being typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feedback {
+    /// Nothing to add (a jump target, or a complete command).
+    None,
+    /// The command's synopsis, while its arguments are incomplete.
+    Usage(String),
+    /// The input cannot run as it stands.
+    Invalid(String),
+}
+
+/// Validate `input` as it is typed. Jump targets are only checked on Enter.
+pub fn feedback(input: &str) -> Feedback {
+    let trimmed = input.trim_start();
+    let (word, args) = split_word(trimmed);
+    let Some(&(name, usage)) = COMMANDS.iter().find(|(n, _)| *n == word) else {
+        return Feedback::None;
+    };
+    let synopsis = || Feedback::Usage(format!("{name} {usage}").trim_end().to_string());
+    if args.is_empty() && !usage.is_empty() {
+        return synopsis();
+    }
+    match parse(trimmed) {
+        Ok(_) if name == "set" && args.split_whitespace().count() == 1 => {
+            // "set hidden" is complete but may still grow into "set hidden on".
+            synopsis()
+        }
+        Ok(_) => Feedback::None,
+        Err(e) => {
+            let (index, partial) = last_word(args);
+            let growing = !input.ends_with(' ')
+                && word_choices(name, index)
+                    .iter()
+                    .any(|choice| choice.starts_with(partial));
+            if growing {
+                synopsis()
+            } else {
+                Feedback::Invalid(e)
+            }
+        }
+    }
+}
+
+/// What Tab completion can draw on besides the command tables.
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    /// The watched root, for path completion.
+    pub root: &'a Path,
+    pub show_hidden: bool,
+    /// Active ignore patterns, offered to `unignore`.
+    pub ignored: &'a [String],
+    /// The tree's entries when its paths are not on this machine; paths
+    /// complete from these instead of the disk.
+    pub listing: Option<&'a [TreeEntry]>,
+}
+
+impl Context<'_> {
+    /// `goto::complete` under the root, from the listing if there is one.
+    fn complete_path(&self, input: &str) -> Option<String> {
+        match self.listing {
+            Some(entries) => goto::complete_listed(self.root, entries, input, self.show_hidden),
+            None => goto::complete(self.root, input, self.show_hidden),
+        }
+    }
+}
+
+/// `input` extended as far as its last word is unambiguous, or `None` if
+/// there is nothing to add. A bare word completes as a path first and as a
+/// command name if no path matches.
+pub fn complete(input: &str, ctx: &Context<'_>) -> Option<String> {
+    let Some(space) = input.find(' ') else {
+        return ctx.complete_path(input).or_else(|| {
+            let commands = names(&COMMANDS);
+            let completed = common_prefix(input, &commands)?;
+            let has_args = COMMANDS
+                .iter()
+                .any(|(n, usage)| *n == completed && !usage.is_empty());
+            Some(if has_args {
+                format!("{completed} ")
+            } else {
+                completed
+            })
+        });
+    };
+    let (word, args) = input.split_at(space);
+    let args_start = input.len() - args.trim_start().len();
+    let (head, args) = input.split_at(args_start);
+    match word {
+        "goto" | "ignore" => ctx
+            .complete_path(args)
+            .map(|completed| format!("{head}{completed}")),
+        "export" => {
+            let cwd = std::env::current_dir().ok()?;
+            goto::complete(&cwd, args, ctx.show_hidden)
+                .map(|completed| format!("{head}{completed}"))
+        }
+        "unignore" => {
+            let ignored: Vec<&str> = ctx.ignored.iter().map(String::as_str).collect();
+            common_prefix(args, &ignored).map(|completed| format!("{head}{completed}"))
+        }
+        _ => {
+            let (index, partial) = last_word(args);
+            let choices = word_choices(word, index);
+            let completed = common_prefix(partial, &choices)?;
+            let unique = choices.contains(&completed.as_str())
+                && choices.iter().filter(|c| c.starts_with(&completed)).count() == 1;
+            let before = &input[..input.len() - partial.len()];
+            Some(format!(
+                "{before}{completed}{}",
+                if unique { " " } else { "" }
+            ))
+        }
+    }
+}
+
+/// State of an open palette.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    /// Text typed so far.
+    pub input: String,
+    /// Why the last command failed, shown until the input changes.
+    pub error: Option<String>,
+    /// History position while browsing with ↑/↓, and the input typed before
+    /// browsing started.
+    browsing: Option<(usize, String)>,
+}
+
+impl Palette {
+    /// A palette opened with `input` already typed (e.g. `export `).
+    pub fn with_input(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+        self.edited();
+    }
+
+    /// Append pasted text, dropping line breaks and other control characters.
+    pub fn paste(&mut self, text: &str) {
+        self.input.extend(text.chars().filter(|c| !c.is_control()));
+        self.edited();
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.edited();
+    }
+
+    /// Tab completion; see `complete`.
+    pub fn complete(&mut self, ctx: &Context<'_>) {
+        if let Some(completed) = complete(&self.input, ctx) {
+            self.input = completed;
+            self.edited();
+        }
+    }
+
+    /// Replace the input with the previous (older) history entry.
+    pub fn history_prev(&mut self, history: &History) {
+        let index = match &self.browsing {
+            Some((0, _)) => return,
+            Some((index, _)) => index - 1,
+            None if history.entries.is_empty() => return,
+            None => history.entries.len() - 1,
+        };
+        let draft = match self.browsing.take() {
+            Some((_, draft)) => draft,
+            None => std::mem::take(&mut self.input),
+        };
+        self.input = history.entries[index].clone();
+        self.browsing = Some((index, draft));
+        self.error = None;
+    }
+
+    /// Replace the input with the next (newer) history entry, or the input
+    /// typed before browsing once past the newest.
+    pub fn history_next(&mut self, history: &History) {
+        let Some((index, draft)) = self.browsing.take() else {
+            return;
+        };
+        match history.entries.get(index + 1) {
+            Some(entry) => {
+                self.input = entry.clone();
+                self.browsing = Some((index + 1, draft));
+            }
+            None => self.input = draft,
+        }
+        self.error = None;
+    }
+
+    /// The error from the last Enter, or live validation of the input.
+    pub fn feedback(&self) -> Feedback {
+        match &self.error {
+            Some(error) => Feedback::Invalid(error.clone()),
+            None => feedback(&self.input),
+        }
+    }
+
+    fn edited(&mut self) {
+        self.error = None;
+        self.browsing = None;
+    }
+}
+
+/// Most commands kept per watched root.
+const HISTORY_LIMIT: usize = 200;
+
+/// Commands run from the palette, oldest first, optionally saved to a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<String>,
+    file: Option<PathBuf>,
+}
+
+impl History {
+    /// History stored in `file`, one command per line. A missing or
+    /// unreadable file starts an empty history; `None` keeps it in memory.
+    pub fn load(file: Option<PathBuf>) -> Self {
+        let entries = file
+            .as_deref()
+            .and_then(|f| std::fs::read_to_string(f).ok())
+            .map(|text| {
+                text.lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { entries, file }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Record a command, moving an earlier copy to the end, and save.
+    pub fn push(&mut self, entry: &str) -> Result<(), String> {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return Ok(());
+        }
+        self.entries.retain(|e| e != entry);
+        self.entries.push(entry.to_string());
+        let excess = self.entries.len().saturating_sub(HISTORY_LIMIT);
+        self.entries.drain(..excess);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        let mut text = self.entries.join("\n");
+        text.push('\n');
+        std::fs::write(file, text).map_err(|e| format!("{}: {}", file.display(), e))
+    }
+}
+
+/// Where the palette history for `root` is kept:
+/// `$XDG_STATE_HOME/livetree/history/<dir name>-<hash of root>`, falling
+/// back to `~/.local/state`.
+pub fn history_path(root: &Path) -> Option<PathBuf> {
+    paths::state_file(root, "history")
+}
+
+/// Split off the first whitespace-separated word: (word, rest trimmed).
+fn split_word(input: &str) -> (&str, &str) {
+    match input.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (input, ""),
+    }
+}
+
+/// Index of the argument being typed and the part typed so far.
+fn last_word(args: &str) -> (usize, &str) {
+    let words = args.split_whitespace().count();
+    if args.is_empty() || args.ends_with(char::is_whitespace) {
+        (words, "")
+    } else {
+        let start = args.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        (words - 1, &args[start..])
+    }
+}
+
+/// Fixed choices for argument `index` of `command`.
+fn word_choices(command: &str, index: usize) -> Vec<&'static str> {
+    match (command, index) {
+        ("set", 0) => names(&Setting::ALL),
+        ("set", 1) => vec!["on", "off"],
+        _ => Vec::new(),
+    }
+}
+
+fn names<T>(table: &[(&'static str, T)]) -> Vec<&'static str> {
+    table.iter().map(|(name, _)| *name).collect()
+}
+
+/// The longest common prefix of the `choices` starting with `partial`, if
+/// it is longer than `partial`.
+fn common_prefix(partial: &str, choices: &[&str]) -> Option<String> {
+    let mut matching = choices.iter().filter(|c| c.starts_with(partial));
+    let first = matching.next()?;
+    let common = matching.fold(*first, |common, choice| {
+        let len = common
+            .char_indices()
+            .zip(choice.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, c), _)| i + c.len_utf8());
+        &common[..len]
+    });
+    (common.len() > partial.len()).then(|| common.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn context<'a>(root: &'a Path, ignored: &'a [String]) -> Context<'a> {
+        Context {
+            root,
+            show_hidden: false,
+            ignored,
+            listing: None,
+        }
+    }
+
+    #[test]
+    fn parses_commands_and_bare_targets() {
+        assert_eq!(
+            parse("src/main.rs"),
+            Ok(Command::Goto("src/main.rs".into()))
+        );
+        assert_eq!(parse(" 42 "), Ok(Command::Goto("42".into())));
+        assert_eq!(parse("goto ./set"), Ok(Command::Goto("./set".into())));
+        assert_eq!(
+            parse("set hidden on"),
+            Ok(Command::Set(Setting::Hidden, Some(true)))
+        );
+        assert_eq!(parse("set follow"), Ok(Command::Set(Setting::Follow, None)));
+        assert_eq!(
+            parse("duration 1500ms"),
+            Ok(Command::Duration(Duration::from_millis(1500)))
+        );
+        assert_eq!(parse("ignore *.log"), Ok(Command::Ignore("*.log".into())));
+        assert_eq!(parse("export t.md"), Ok(Command::Export("t.md".into())));
+        assert_eq!(parse("quit"), Ok(Command::Quit));
+        assert_eq!(parse("touch a.txt"), Ok(Command::Touch("a.txt".into())));
+        assert_eq!(parse("rename b.txt"), Ok(Command::Rename("b.txt".into())));
+        assert_eq!(parse("delete"), Ok(Command::Delete));
+
+        assert!(parse("set colour on")
+            .unwrap_err()
+            .contains("unknown setting"));
+        assert!(parse("set hidden maybe").is_err());
+        assert!(parse("ignore a[b").unwrap_err().contains("invalid glob"));
+        assert_eq!(parse("set").unwrap_err(), "usage: set <setting> [on|off]");
+        assert_eq!(parse("clear all").unwrap_err(), "usage: clear");
+        assert!(parse("mkdir a/b").unwrap_err().contains("path separator"));
+    }
+
+    #[test]
+    fn feedback_while_typing() {
+        assert_eq!(feedback("src/ma"), Feedback::None);
+        assert_eq!(
+            feedback("set "),
+            Feedback::Usage("set <setting> [on|off]".into())
+        );
+        assert_eq!(
+            feedback("set hid"),
+            Feedback::Usage("set <setting> [on|off]".into())
+        );
+        assert!(matches!(feedback("set hidx"), Feedback::Invalid(_)));
+        assert!(matches!(feedback("duration soon"), Feedback::Invalid(_)));
+        assert_eq!(feedback("duration 5s"), Feedback::None);
+    }
+
+    #[test]
+    fn completes_commands_settings_and_paths() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir(tmp.path().join("src")).unwrap();
+        let ignored = vec!["target".to_string(), "*.log".to_string()];
+        let ctx = context(tmp.path(), &ignored);
+
+        assert_eq!(complete("s", &ctx).as_deref(), Some("src/"));
+        assert_eq!(complete("se", &ctx).as_deref(), Some("set "));
+        assert_eq!(complete("qu", &ctx).as_deref(), Some("quit"));
+        assert_eq!(complete("set hi", &ctx).as_deref(), Some("set hidden "));
+        assert_eq!(complete("set hidden o", &ctx), None);
+        assert_eq!(
+            complete("set hidden of", &ctx).as_deref(),
+            Some("set hidden off ")
+        );
+        assert_eq!(complete("goto s", &ctx).as_deref(), Some("goto src/"));
+        assert_eq!(complete("ignore s", &ctx).as_deref(), Some("ignore src/"));
+        assert_eq!(
+            complete("unignore t", &ctx).as_deref(),
+            Some("unignore target")
+        );
+    }
+
+    #[test]
+    fn history_browsing_restores_the_draft() {
+        let mut history = History::default();
+        history.push("set hidden on").unwrap();
+        history.push("src").unwrap();
+        history.push("set hidden on").unwrap();
+        assert_eq!(history.entries(), ["src", "set hidden on"]);
+
+        let mut palette = Palette::default();
+        palette.push('x');
+        palette.history_prev(&history);
+        assert_eq!(palette.input, "set hidden on");
+        palette.history_prev(&history);
+        palette.history_prev(&history);
+        assert_eq!(palette.input, "src");
+        palette.history_next(&history);
+        palette.history_next(&history);
+        assert_eq!(palette.input, "x");
+    }
+
+    #[test]
+    fn history_is_saved_per_file() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("state/history");
+        let mut history = History::load(Some(file.clone()));
+        history.push("ignore *.tmp").unwrap();
+        assert_eq!(
+            History::load(Some(file)).entries(),
+            ["ignore *.tmp".to_string()]
+        );
+    }
+}
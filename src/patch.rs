@@ -0,0 +1,319 @@
+//! Snapshot deltas as RFC 6902-style JSON patch operations, so frontends can
+//! update incrementally instead of re-downloading large trees.
+//!
+//! Operations address `/entries/<index>` and `/total_entries` of the JSON
+//! snapshot produced by `snapshot_to_json`, and must be applied in order.
+//! `snapshot_from_json` and `patch_from_json` read both back, for clients
+//! on the other end of a connection.
+
+use crate::tree::{TreeEntry, TreeSnapshot};
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A single patch operation against a snapshot.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "OpRecord<'static>")]
+pub enum PatchOp {
+    /// Insert `entry` at `index`, shifting later entries down.
+    Add { index: usize, entry: TreeEntry },
+    /// Remove the entry at `index`.
+    Remove { index: usize },
+    /// Replace the entry at `index` (same path, changed fields).
+    Replace { index: usize, entry: TreeEntry },
+    /// Set `total_entries`.
+    SetTotal(usize),
+}
+
+/// A `PatchOp` as written on the wire.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum OpRecord<'a> {
+    Add {
+        path: String,
+        value: Cow<'a, TreeEntry>,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: ReplaceValue<'a>,
+    },
+}
+
+/// What a `replace` sets: an entry, or `/total_entries`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ReplaceValue<'a> {
+    Total(usize),
+    Entry(Cow<'a, TreeEntry>),
+}
+
+impl<'a> From<&'a PatchOp> for OpRecord<'a> {
+    fn from(op: &'a PatchOp) -> Self {
+        let path = |index| format!("/entries/{index}");
+        match op {
+            PatchOp::Add { index, entry } => OpRecord::Add {
+                path: path(index),
+                value: Cow::Borrowed(entry),
+            },
+            PatchOp::Remove { index } => OpRecord::Remove { path: path(index) },
+            PatchOp::Replace { index, entry } => OpRecord::Replace {
+                path: path(index),
+                value: ReplaceValue::Entry(Cow::Borrowed(entry)),
+            },
+            PatchOp::SetTotal(total) => OpRecord::Replace {
+                path: "/total_entries".to_string(),
+                value: ReplaceValue::Total(*total),
+            },
+        }
+    }
+}
+
+impl TryFrom<OpRecord<'_>> for PatchOp {
+    type Error = String;
+
+    fn try_from(op: OpRecord<'_>) -> Result<Self, String> {
+        let index = |path: &str| {
+            path.strip_prefix("/entries/")
+                .and_then(|i| i.parse().ok())
+                .ok_or_else(|| format!("unsupported patch path '{path}'"))
+        };
+        match op {
+            OpRecord::Add { path, value } => Ok(PatchOp::Add {
+                index: index(&path)?,
+                entry: value.into_owned(),
+            }),
+            OpRecord::Remove { path } => Ok(PatchOp::Remove {
+                index: index(&path)?,
+            }),
+            OpRecord::Replace {
+                path,
+                value: ReplaceValue::Total(total),
+            } if path == "/total_entries" => Ok(PatchOp::SetTotal(total)),
+            OpRecord::Replace {
+                path,
+                value: ReplaceValue::Entry(entry),
+            } => Ok(PatchOp::Replace {
+                index: index(&path)?,
+                entry: entry.into_owned(),
+            }),
+            OpRecord::Replace { path, .. } => Err(format!("wrong value for patch path '{path}'")),
+        }
+    }
+}
+
+impl Serialize for PatchOp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        OpRecord::from(self).serialize(serializer)
+    }
+}
+
+/// Compute the operations that turn `old` into `new`.
+///
+/// Entries are matched by path. Because both snapshots use the same sort
+/// order, surviving entries keep their relative order, so removals (from the
+/// back) followed by insertions (from the front) reproduce `new` exactly.
+pub fn diff(old: &TreeSnapshot, new: &TreeSnapshot) -> Vec<PatchOp> {
+    let old_paths: HashSet<&Path> = old.entries.iter().map(|e| e.path.as_path()).collect();
+    let new_paths: HashSet<&Path> = new.entries.iter().map(|e| e.path.as_path()).collect();
+    let mut ops = Vec::new();
+
+    for (index, entry) in old.entries.iter().enumerate().rev() {
+        if !new_paths.contains(entry.path.as_path()) {
+            ops.push(PatchOp::Remove { index });
+        }
+    }
+
+    let mut survivors = old
+        .entries
+        .iter()
+        .filter(|e| new_paths.contains(e.path.as_path()));
+    let mut replaces = Vec::new();
+    for (index, entry) in new.entries.iter().enumerate() {
+        if old_paths.contains(entry.path.as_path()) {
+            if let Some(previous) = survivors.next() {
+                if previous != entry {
+                    replaces.push(PatchOp::Replace {
+                        index,
+                        entry: entry.clone(),
+                    });
+                }
+            }
+        } else {
+            ops.push(PatchOp::Add {
+                index,
+                entry: entry.clone(),
+            });
+        }
+    }
+    ops.extend(replaces);
+
+    if old.total_entries != new.total_entries {
+        ops.push(PatchOp::SetTotal(new.total_entries));
+    }
+    ops
+}
+
+/// Apply operations produced by `diff` to `snapshot`.
+///
+/// # Panics
+///
+/// If an operation addresses an entry `snapshot` does not have, i.e. the
+/// patch was made for another snapshot. Use `try_apply` for patches that
+/// come from elsewhere.
+pub fn apply(snapshot: &mut TreeSnapshot, ops: &[PatchOp]) {
+    if let Err(e) = try_apply(snapshot, ops) {
+        panic!("{e}");
+    }
+}
+
+/// Apply operations to `snapshot`, stopping at the first one that
+/// addresses an entry it does not have. Operations before that one stay
+/// applied.
+pub fn try_apply(snapshot: &mut TreeSnapshot, ops: &[PatchOp]) -> Result<(), String> {
+    for op in ops {
+        let len = snapshot.entries.len();
+        match op {
+            PatchOp::Add { index, entry } if *index <= len => {
+                snapshot.entries.insert(*index, entry.clone())
+            }
+            PatchOp::Remove { index } if *index < len => {
+                snapshot.entries.remove(*index);
+            }
+            PatchOp::Replace { index, entry } if *index < len => {
+                snapshot.entries[*index] = entry.clone()
+            }
+            PatchOp::SetTotal(total) => snapshot.total_entries = *total,
+            PatchOp::Add { index, .. }
+            | PatchOp::Remove { index }
+            | PatchOp::Replace { index, .. } => {
+                return Err(format!(
+                    "patch addresses entry {index} of a snapshot with {len}"
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Encode a full snapshot as JSON (the document patches apply to).
+pub fn snapshot_to_json(snapshot: &TreeSnapshot) -> String {
+    serde_json::to_string(snapshot).expect("snapshots always serialize")
+}
+
+/// Encode operations as an RFC 6902 JSON patch array.
+pub fn patch_to_json(ops: &[PatchOp]) -> String {
+    serde_json::to_string(ops).expect("patches always serialize")
+}
+
+/// Decode a snapshot encoded by `snapshot_to_json`.
+pub fn snapshot_from_json(s: &str) -> Result<TreeSnapshot, String> {
+    serde_json::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Decode a patch encoded by `patch_to_json`.
+pub fn patch_from_json(s: &str) -> Result<Vec<PatchOp>, String> {
+    serde_json::from_str(s).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(name: &str, is_last: bool) -> TreeEntry {
+        let mut entry = TreeEntry::new(name, PathBuf::from(format!("/root/{name}")), 0, false);
+        entry.is_last = is_last;
+        entry
+    }
+
+    fn snapshot(entries: Vec<TreeEntry>) -> TreeSnapshot {
+        let total_entries = entries.len();
+        TreeSnapshot {
+            entries,
+            total_entries,
+        }
+    }
+
+    #[test]
+    fn diff_round_trips() {
+        let old = snapshot(vec![
+            entry("a", false),
+            entry("b", false),
+            entry("c", false),
+            entry("d", true),
+        ]);
+        let new = snapshot(vec![
+            entry("0", false),
+            entry("a", false),
+            entry("c", false),
+            entry("c2", false),
+            entry("d", false),
+            entry("e", true),
+        ]);
+        let ops = diff(&old, &new);
+        let mut patched = old.clone();
+        apply(&mut patched, &ops);
+        assert_eq!(patched, new);
+        assert!(ops.contains(&PatchOp::Replace {
+            index: 4,
+            entry: entry("d", false)
+        }));
+    }
+
+    #[test]
+    fn identical_snapshots_have_empty_patch() {
+        let s = snapshot(vec![entry("a", true)]);
+        assert!(diff(&s, &s).is_empty());
+    }
+
+    #[test]
+    fn patch_json_uses_rfc6902_ops() {
+        let old = snapshot(vec![entry("a", true)]);
+        let new = snapshot(vec![]);
+        assert_eq!(
+            patch_to_json(&diff(&old, &new)),
+            "[{\"op\":\"remove\",\"path\":\"/entries/0\"},\
+             {\"op\":\"replace\",\"path\":\"/total_entries\",\"value\":0}]"
+        );
+    }
+
+    #[test]
+    fn json_reads_back_what_was_written() {
+        let old = snapshot(vec![entry("a", false), entry("b", true)]);
+        let new = snapshot(vec![entry("b", false), entry("c", true)]);
+        let ops = diff(&old, &new);
+        assert_eq!(patch_from_json(&patch_to_json(&ops)).unwrap(), ops);
+        let mut patched = snapshot_from_json(&snapshot_to_json(&old)).unwrap();
+        try_apply(&mut patched, &ops).unwrap();
+        assert_eq!(patched, new);
+        assert!(try_apply(&mut patched, &[PatchOp::Remove { index: 2 }]).is_err());
+        let bad = r#"[{"op":"replace","path":"/entries/0","value":3}]"#;
+        let err = patch_from_json(bad).unwrap_err();
+        assert!(
+            err.starts_with("wrong value for patch path '/entries/0'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn entries_read_back_with_every_field() {
+        let mut original = entry("run \"me\"", false);
+        original.depth = 2;
+        original.is_symlink = true;
+        original.symlink_target = Some("../bin/run".to_string());
+        original.set_ancestor_last(1, true);
+        original.mode = Some(0o100755);
+        original.owner = Some((1000, 100));
+        let json = serde_json::to_string(&original).unwrap();
+        assert_eq!(serde_json::from_str::<TreeEntry>(&json).unwrap(), original);
+        let broken = serde_json::from_str::<TreeEntry>(r#"{"name":"a"}"#).unwrap_err();
+        assert!(
+            broken.to_string().contains("missing field `path`"),
+            "{broken}"
+        );
+    }
+}
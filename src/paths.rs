@@ -0,0 +1,163 @@
+//! Platform-aware path display: finding the user's home directory,
+//! collapsing it to `~`, and hiding the verbatim (`\\?\`) prefix that
+//! `canonicalize` adds on Windows. Also where per-root state files go.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+/// The user's home directory: `HOME`, or on Windows `USERPROFILE` (with
+/// `HOME` as a fallback for MSYS/Cygwin shells).
+pub fn home_dir() -> Option<PathBuf> {
+    let vars: &[&str] = if cfg!(windows) {
+        &["USERPROFILE", "HOME"]
+    } else {
+        &["HOME"]
+    };
+    vars.iter()
+        .filter_map(std::env::var_os)
+        .find(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `path` without a Windows verbatim prefix: `\\?\C:\src` becomes `C:\src`
+/// and `\\?\UNC\server\share` becomes `\\server\share`. Other paths are
+/// returned unchanged.
+pub fn strip_verbatim(path: &Path) -> Cow<'_, Path> {
+    let Some(text) = path.to_str() else {
+        return Cow::Borrowed(path);
+    };
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        return Cow::Owned(PathBuf::from(format!(r"\\{rest}")));
+    }
+    match text.strip_prefix(r"\\?\") {
+        // Only drive paths; other verbatim forms have no plain spelling.
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => Cow::Owned(PathBuf::from(rest)),
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// `path` for display, with `home` (if given and a prefix) shown as `~`:
+/// `~/src/app` on Unix, `~\src\app` on Windows.
+pub fn display_path(path: &Path, home: Option<&Path>) -> String {
+    let path = strip_verbatim(path);
+    let raw = path.to_string_lossy().into_owned();
+    let Some(home) = home.map(strip_verbatim) else {
+        return raw;
+    };
+    match path.strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~{MAIN_SEPARATOR}{}", rest.to_string_lossy()),
+        Err(_) => raw,
+    }
+}
+
+/// The last component of `path`, or the whole path for a root such as `/`,
+/// `C:\`, or `\\server\share`.
+pub fn display_name(path: &Path) -> String {
+    let path = strip_verbatim(path);
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// A per-root file under the state directory:
+/// `$XDG_STATE_HOME/livetree/<kind>/<dir name>-<hash of root>`, falling
+/// back to `~/.local/state`.
+pub fn state_file(root: &Path, kind: &str) -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".local").join("state")))?;
+    let name: String = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // FNV-1a, so the file name stays the same across builds.
+    let hash = root
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+    Some(
+        base.join("livetree")
+            .join(kind)
+            .join(format!("{name}-{hash:016x}")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn collapses_home() {
+        let home = Path::new("/home/me");
+        assert_eq!(display_path(Path::new("/home/me"), Some(home)), "~");
+        assert_eq!(
+            display_path(Path::new("/home/me/src/app"), Some(home)),
+            "~/src/app"
+        );
+        assert_eq!(
+            display_path(Path::new("/home/meow"), Some(home)),
+            "/home/meow"
+        );
+        assert_eq!(display_path(Path::new("/srv"), None), "/srv");
+        assert_eq!(display_name(Path::new("/")), "/");
+    }
+
+    #[test]
+    fn strips_verbatim_prefixes() {
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\C:\src")),
+            Path::new(r"C:\src")
+        );
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\UNC\server\share")),
+            Path::new(r"\\server\share")
+        );
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\Volume{x}\")),
+            Path::new(r"\\?\Volume{x}\")
+        );
+        assert_eq!(strip_verbatim(Path::new("/tmp")), Path::new("/tmp"));
+    }
+
+    #[cfg(windows)]
+    mod windows {
+        use super::super::*;
+
+        #[test]
+        fn collapses_home_with_backslashes() {
+            let home = Path::new(r"C:\Users\me");
+            assert_eq!(
+                display_path(Path::new(r"\\?\C:\Users\me\src\app"), Some(home)),
+                r"~\src\app"
+            );
+            assert_eq!(display_path(Path::new(r"C:\Users\me"), Some(home)), "~");
+            assert_eq!(display_path(Path::new(r"D:\data"), Some(home)), r"D:\data");
+        }
+
+        #[test]
+        fn drive_and_share_roots_are_shown_whole() {
+            assert_eq!(display_name(Path::new(r"\\?\C:\")), r"C:\");
+            assert_eq!(
+                display_name(Path::new(r"\\server\share\")),
+                r"\\server\share\"
+            );
+            assert_eq!(display_name(Path::new(r"C:\Users\me\proj")), "proj");
+        }
+    }
+}
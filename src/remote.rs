@@ -0,0 +1,417 @@
+//! Remote directories (`--remote user@host:/path`, `--docker
+//! container:/path`): the tree is listed over ssh or `docker exec` and
+//! polled for changes, then shown by the same event loop as a local one.
+//!
+//! Listing over ssh runs the system `ssh` client with `BatchMode`, so it
+//! needs key-based (or agent) authentication and picks up `~/.ssh/config`,
+//! where a `ControlMaster` setting keeps one connection open for every
+//! poll. Listing a container runs the `docker` CLI, which talks to the
+//! daemon `DOCKER_HOST` points at. On the other end only a POSIX shell and
+//! `find` are needed. Polling compares listings, so creations and removals
+//! are seen but changes to the contents of a file are not.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+use crate::tree::{virtual_entries, EntrySource, RawEntry, TreeConfig, VirtualEntry};
+use crate::watcher::{ChangeKind, PathChange, WatchEvent};
+
+/// How a remote tree is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// `ssh <host> <command>`.
+    Ssh,
+    /// `docker exec <container> sh -c <command>`.
+    Docker,
+}
+
+/// `[user@]host:path`, as `scp` takes it, or `container:path` for
+/// [`RemoteSpec::docker`]. A relative path is relative to the remote
+/// user's home directory, or to the container's working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSpec {
+    pub transport: Transport,
+    /// Destination passed to `ssh` (a host, `user@host`, or a `Host` alias
+    /// from the ssh config), or the container's name or id.
+    pub host: String,
+    /// Directory on the host or in the container.
+    pub path: PathBuf,
+}
+
+impl RemoteSpec {
+    /// Parse `container:path`, a path in a running Docker container.
+    pub fn docker(s: &str) -> Result<Self, String> {
+        let (container, path) = split_spec(s, "container:path")?;
+        Ok(RemoteSpec {
+            transport: Transport::Docker,
+            host: container.to_string(),
+            path: PathBuf::from(path),
+        })
+    }
+
+    /// The command-line flag that selects this kind of remote, for
+    /// messages.
+    pub fn flag(&self) -> &'static str {
+        match self.transport {
+            Transport::Ssh => "--remote",
+            Transport::Docker => "--docker",
+        }
+    }
+
+    /// Command that runs `script` with the POSIX shell at the other end.
+    fn command(&self, script: &str) -> Command {
+        let mut cmd = match self.transport {
+            Transport::Ssh => {
+                let mut cmd = Command::new("ssh");
+                cmd.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"])
+                    .arg(&self.host);
+                cmd
+            }
+            Transport::Docker => {
+                let mut cmd = Command::new("docker");
+                cmd.arg("exec").arg(&self.host).args(["sh", "-c"]);
+                cmd
+            }
+        };
+        cmd.arg(script).stdin(Stdio::null());
+        cmd
+    }
+}
+
+/// `s` split at its first `:` into a non-empty destination, which must
+/// not look like an option, and a non-empty path.
+fn split_spec<'a>(s: &'a str, expected: &str) -> Result<(&'a str, &'a str), String> {
+    let (host, path) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid remote '{s}' (expected {expected})"))?;
+    if host.is_empty() || host.starts_with('-') {
+        return Err(format!("invalid remote '{s}' (expected {expected})"));
+    }
+    if path.is_empty() {
+        return Err(format!("invalid remote '{s}': missing path"));
+    }
+    Ok((host, path))
+}
+
+impl FromStr for RemoteSpec {
+    type Err = String;
+
+    /// Parse `[user@]host:path`, a directory reached over ssh.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (host, path) = split_spec(s, "[user@]host:path")?;
+        Ok(RemoteSpec {
+            transport: Transport::Ssh,
+            host: host.to_string(),
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+impl fmt::Display for RemoteSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.path.display())
+    }
+}
+
+/// Shell command that prints every path below `path`, one per line, as
+/// `d ./dir` or `f ./file`.
+fn find_command(path: &Path) -> String {
+    format!(
+        "cd {} && find . -type d -exec printf 'd %s\\n' {{}} + ; find . ! -type d -exec printf 'f %s\\n' {{}} +",
+        shell_quote(&path.to_string_lossy())
+    )
+}
+
+/// `s` single-quoted for a POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// List the tree at `spec`. Directories that cannot be read on the other
+/// end are left out; failing to connect or to enter the root is an error.
+pub fn list_remote(spec: &RemoteSpec) -> Result<Vec<VirtualEntry>, String> {
+    let mut cmd = spec.command(&find_command(&spec.path));
+    let output = cmd
+        .output()
+        .map_err(|e| format!("cannot run {}: {e}", cmd.get_program().to_string_lossy()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // `find` also fails on a single unreadable directory, so a failure only
+    // counts when nothing was listed (not even the root itself).
+    if !output.status.success() && stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().last().unwrap_or("listing failed").trim();
+        return Err(format!("{spec}: {reason}"));
+    }
+    Ok(parse_listing(&stdout))
+}
+
+/// Parse `find_command` output. The root itself (`.`) and lines in any
+/// other form are skipped.
+pub fn parse_listing(output: &str) -> Vec<VirtualEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (kind, path) = line.split_once(' ')?;
+            let is_dir = match kind {
+                "d" => true,
+                "f" => false,
+                _ => return None,
+            };
+            let path = path.strip_prefix("./")?;
+            (!path.is_empty()).then(|| VirtualEntry {
+                path: PathBuf::from(path),
+                is_dir,
+            })
+        })
+        .collect()
+}
+
+/// Whether `entry` passes `config`'s filters, checking every directory
+/// above it as `tree::is_visible` does.
+fn is_listed(entry: &VirtualEntry, config: &TreeConfig) -> bool {
+    if config.dirs_only && !entry.is_dir {
+        return false;
+    }
+    if config
+        .max_depth
+        .is_some_and(|max| entry.path.components().count() > max)
+    {
+        return false;
+    }
+    entry
+        .path
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .all(|p| {
+            let hidden = !config.show_hidden
+                && p.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            let is_dir = p != entry.path || entry.is_dir;
+            !hidden && !config.ignore_patterns.is_match(p, is_dir)
+        })
+}
+
+/// The latest listing of a remote tree, shared by its source and poller so
+/// a rebuild after a poll does not list the tree a second time.
+type Listing = Arc<Mutex<Option<Vec<VirtualEntry>>>>;
+
+/// A remote tree as an entry source. The root passed to `entries` is only
+/// used for entry paths; what is listed is always the spec's directory.
+/// A tree that cannot be listed shows a single entry carrying the error.
+#[derive(Debug, Clone)]
+pub struct RemoteSource {
+    spec: RemoteSpec,
+    latest: Listing,
+}
+
+impl RemoteSource {
+    pub fn new(spec: RemoteSpec) -> Self {
+        Self {
+            spec,
+            latest: Listing::default(),
+        }
+    }
+
+    /// Start listing the tree every `interval` on a background thread,
+    /// reporting what was created and removed since the previous listing.
+    /// A failed listing is reported once, until listing works again.
+    pub fn start_polling(&self, interval: Duration) -> (RemotePoller, Receiver<WatchEvent>) {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let (stop, stopped) = crossbeam_channel::bounded::<()>(0);
+        let spec = self.spec.clone();
+        let latest = Arc::clone(&self.latest);
+        thread::spawn(move || poll(&spec, &latest, interval, &stopped, &tx));
+        (RemotePoller { _stop: stop }, rx)
+    }
+
+    fn listing(&self) -> Result<Vec<VirtualEntry>, String> {
+        if let Some(listing) = self.latest.lock().unwrap().clone() {
+            return Ok(listing);
+        }
+        let listing = list_remote(&self.spec)?;
+        *self.latest.lock().unwrap() = Some(listing.clone());
+        Ok(listing)
+    }
+}
+
+impl EntrySource for RemoteSource {
+    fn entries<'a>(
+        &'a self,
+        root: &'a Path,
+        config: &'a TreeConfig,
+    ) -> Box<dyn Iterator<Item = RawEntry> + 'a> {
+        match self.listing() {
+            Ok(listing) => {
+                let listed: Vec<VirtualEntry> = listing
+                    .into_iter()
+                    .filter(|e| is_listed(e, config))
+                    .collect();
+                Box::new(virtual_entries(root, &listed).into_iter())
+            }
+            Err(e) => {
+                let name = root.file_name().unwrap_or(root.as_os_str());
+                let mut entry = RawEntry::new(1, name.to_string_lossy(), root, true);
+                entry.error = Some(e);
+                Box::new(std::iter::once(entry))
+            }
+        }
+    }
+}
+
+/// Stops polling when dropped. A listing already running is left to
+/// finish in the background rather than delaying exit.
+pub struct RemotePoller {
+    _stop: Sender<()>,
+}
+
+fn poll(
+    spec: &RemoteSpec,
+    latest: &Listing,
+    interval: Duration,
+    stopped: &Receiver<()>,
+    tx: &Sender<WatchEvent>,
+) {
+    let mut failing: Option<String> = None;
+    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        let event = match list_remote(spec) {
+            Ok(listing) => {
+                failing = None;
+                let previous = latest.lock().unwrap().replace(listing.clone());
+                let changes = previous
+                    .map(|previous| listing_changes(&spec.path, &previous, &listing))
+                    .unwrap_or_default();
+                if changes.is_empty() {
+                    continue;
+                }
+                WatchEvent::Changed(changes)
+            }
+            Err(e) if failing.as_ref() == Some(&e) => continue,
+            Err(e) => {
+                failing = Some(e.clone());
+                WatchEvent::Error(e)
+            }
+        };
+        if tx.send(event).is_err() {
+            break;
+        }
+    }
+}
+
+/// What was removed and created between two listings of the tree at
+/// `root`, as changes to paths below it.
+fn listing_changes(root: &Path, old: &[VirtualEntry], new: &[VirtualEntry]) -> Vec<PathChange> {
+    let before: HashSet<&VirtualEntry> = old.iter().collect();
+    let after: HashSet<&VirtualEntry> = new.iter().collect();
+    let change = |entry: &VirtualEntry, kind| PathChange {
+        path: root.join(&entry.path),
+        kind,
+    };
+    old.iter()
+        .filter(|e| !after.contains(e))
+        .map(|e| change(e, ChangeKind::Removed))
+        .chain(
+            new.iter()
+                .filter(|e| !before.contains(e))
+                .map(|e| change(e, ChangeKind::Created)),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::IgnoreSet;
+
+    fn entry(path: &str, is_dir: bool) -> VirtualEntry {
+        VirtualEntry {
+            path: PathBuf::from(path),
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn specs_need_a_host_and_a_path() {
+        let spec: RemoteSpec = "deploy@web1:/srv/app".parse().unwrap();
+        assert_eq!(spec.host, "deploy@web1");
+        assert_eq!(spec.path, PathBuf::from("/srv/app"));
+        assert_eq!(spec.to_string(), "deploy@web1:/srv/app");
+        assert!("web1".parse::<RemoteSpec>().is_err());
+        assert!(":/srv".parse::<RemoteSpec>().is_err());
+        assert!("web1:".parse::<RemoteSpec>().is_err());
+        assert!("-oProxyCommand=x:/srv".parse::<RemoteSpec>().is_err());
+    }
+
+    #[test]
+    fn docker_specs_exec_a_shell_in_the_container() {
+        let spec = RemoteSpec::docker("web:/app/dist").unwrap();
+        assert_eq!(spec.transport, Transport::Docker);
+        assert_eq!(spec.flag(), "--docker");
+        let cmd = spec.command("true");
+        assert_eq!(cmd.get_program(), "docker");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["exec", "web", "sh", "-c", "true"]);
+        assert!(RemoteSpec::docker("web").is_err());
+    }
+
+    #[test]
+    fn paths_are_quoted_for_the_remote_shell() {
+        assert_eq!(
+            find_command(Path::new("/srv/it's here")),
+            "cd '/srv/it'\\''s here' && find . -type d -exec printf 'd %s\\n' {} + ; \
+             find . ! -type d -exec printf 'f %s\\n' {} +"
+        );
+    }
+
+    #[test]
+    fn listings_skip_the_root_and_stray_lines() {
+        let listing =
+            parse_listing("d .\nd ./src\nf ./src/main.rs\nfind: ./x: Permission denied\n");
+        assert_eq!(listing, [entry("src", true), entry("src/main.rs", false)]);
+    }
+
+    #[test]
+    fn filters_apply_to_every_ancestor() {
+        let config = TreeConfig {
+            max_depth: Some(2),
+            show_hidden: false,
+            dirs_only: false,
+            follow_symlinks: false,
+            ignore_patterns: IgnoreSet::new([crate::tree::IgnorePattern::parse("target").unwrap()])
+                .unwrap(),
+            max_entries: None,
+            stat: false,
+        };
+        assert!(is_listed(&entry("src/main.rs", false), &config));
+        assert!(!is_listed(&entry("src/a/b.rs", false), &config));
+        assert!(!is_listed(&entry(".git/config", false), &config));
+        assert!(!is_listed(&entry("target/debug", true), &config));
+    }
+
+    #[test]
+    fn changes_are_removals_then_creations() {
+        let old = [entry("a", false), entry("b", true)];
+        let new = [entry("b", true), entry("c", false)];
+        let changes = listing_changes(Path::new("/srv"), &old, &new);
+        assert_eq!(
+            changes,
+            [
+                PathChange {
+                    path: PathBuf::from("/srv/a"),
+                    kind: ChangeKind::Removed
+                },
+                PathChange {
+                    path: PathBuf::from("/srv/c"),
+                    kind: ChangeKind::Created
+                },
+            ]
+        );
+    }
+}
@@ -1,30 +1,314 @@
 //! Tree rendering using ratatui Line/Span styling.
 
-use crate::tree::TreeEntry;
+use crate::highlight::{Channel, HighlightKind, Highlights};
+use crate::palette::Feedback;
+use crate::search;
+use crate::stats::{Stats, HEAT_LEVELS};
+use crate::status::{Field, Segment, StatusTemplate, StatusValues};
+use crate::toast::{Toast, ToastLevel};
+use crate::tree::{Difference, DirDetails, DirSummary, HeavyReport, TreeEntry};
+use crate::watcher::ChangeKind;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-/// Configuration for the rendering pipeline.
-pub struct RenderConfig {
-    /// Whether to emit color styling.
-    pub use_color: bool,
-    /// Current terminal width in columns.
-    #[allow(dead_code)]
-    pub terminal_width: u16,
+/// How many columns the terminal gives East Asian "ambiguous" characters
+/// (`…`, `§`, `±`, box drawing, …): one in most locales, two in many CJK
+/// terminal setups. Wide characters such as CJK and emoji are always two.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmbiguousWidth {
+    #[default]
+    Narrow,
+    Wide,
+}
+
+impl AmbiguousWidth {
+    /// Display columns of `text`.
+    pub fn str_width(self, text: &str) -> usize {
+        match self {
+            AmbiguousWidth::Narrow => text.width(),
+            AmbiguousWidth::Wide => text.width_cjk(),
+        }
+    }
+
+    /// Display columns of `c` (zero for control characters).
+    pub fn char_width(self, c: char) -> usize {
+        match self {
+            AmbiguousWidth::Narrow => c.width(),
+            AmbiguousWidth::Wide => c.width_cjk(),
+        }
+        .unwrap_or(0)
+    }
+}
+
+impl FromStr for AmbiguousWidth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "1" => Ok(AmbiguousWidth::Narrow),
+            "2" => Ok(AmbiguousWidth::Wide),
+            other => Err(format!(
+                "invalid ambiguous width '{other}' (expected 1 or 2)"
+            )),
+        }
+    }
+}
+
+/// Characters used to draw tree connectors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Box drawing: `├── `, `└── `, `│`.
+    #[default]
+    Unicode,
+    /// ASCII of the same width (`|-- `, `` `-- ``, `|`), for consoles that
+    /// cannot show box drawing.
+    Ascii,
+}
+
+/// How entries are laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Listing {
+    /// Names indented below their directories, with connectors.
+    #[default]
+    Tree,
+    /// Paths relative to the root, one per line and not indented, as `fd`
+    /// lists them; directories end in a separator.
+    Flat,
 }
 
 // Color constants matching the original ANSI palette.
-const DIR_STYLE: Style = Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD);
-const SYMLINK_STYLE: Style = Style::new().fg(Color::Cyan);
-const ERROR_STYLE: Style = Style::new().fg(Color::Red);
-const PREFIX_STYLE: Style = Style::new().fg(Color::White);
+pub(crate) const DIR_STYLE: Style = Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD);
+pub(crate) const SYMLINK_STYLE: Style = Style::new().fg(Color::Cyan);
+// Green bold, as `ls --color` shows executables.
+const EXECUTABLE_STYLE: Style = Style::new().fg(Color::Green).add_modifier(Modifier::BOLD);
+pub(crate) const ERROR_STYLE: Style = Style::new().fg(Color::Red);
+pub(crate) const PREFIX_STYLE: Style = Style::new().fg(Color::White);
 const CHANGED_STYLE: Style = Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD);
 // Turquoise-green style for changed directories (distinct from default blue).
 const CHANGED_DIR_STYLE: Style = Style::new()
     .fg(Color::Rgb(64, 224, 208))
     .add_modifier(Modifier::BOLD);
+// Metadata-only changes (touch/chmod) get a quieter, non-bold variant.
+const METADATA_STYLE: Style = Style::new().fg(Color::Cyan).add_modifier(Modifier::ITALIC);
+const METADATA_DIR_STYLE: Style = Style::new()
+    .fg(Color::Rgb(64, 224, 208))
+    .add_modifier(Modifier::ITALIC);
+// Directories that merely contain changes: dim turquoise, no bold.
+const DESCENDANT_STYLE: Style = Style::new()
+    .fg(Color::Rgb(64, 224, 208))
+    .add_modifier(Modifier::DIM);
+// Directories standing in for a change that is collapsed or scrolled off:
+// dim turquoise, underlined to tell them from direct highlights.
+const INHERITED_STYLE: Style = Style::new()
+    .fg(Color::Rgb(64, 224, 208))
+    .add_modifier(Modifier::DIM.union(Modifier::UNDERLINED));
+// Search matches within names: yellow and underlined.
+const MATCHED_STYLE: Style = Style::new()
+    .fg(Color::Yellow)
+    .add_modifier(Modifier::UNDERLINED);
+
+/// Styles for tree lines when colors are on. Per-path highlight colors
+/// (from `Highlights::color_of`) still replace the foreground of the change
+/// styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub dir: Style,
+    pub symlink: Style,
+    /// A file with an execute bit set (only known when modes were read).
+    pub executable: Style,
+    pub error: Style,
+    /// Tree connectors.
+    pub prefix: Style,
+    /// A changed file, and a changed directory.
+    pub changed: Style,
+    pub changed_dir: Style,
+    /// A file or directory whose metadata alone changed.
+    pub metadata: Style,
+    pub metadata_dir: Style,
+    /// A directory that only contains changes.
+    pub descendant: Style,
+    /// A directory highlighted for a change below it that is collapsed or
+    /// out of view.
+    pub inherited: Style,
+    /// The characters of a name that match the search, patched over the
+    /// name's own style.
+    pub matched: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            dir: DIR_STYLE,
+            symlink: SYMLINK_STYLE,
+            executable: EXECUTABLE_STYLE,
+            error: ERROR_STYLE,
+            prefix: PREFIX_STYLE,
+            changed: CHANGED_STYLE,
+            changed_dir: CHANGED_DIR_STYLE,
+            metadata: METADATA_STYLE,
+            metadata_dir: METADATA_DIR_STYLE,
+            descendant: DESCENDANT_STYLE,
+            inherited: INHERITED_STYLE,
+            matched: MATCHED_STYLE,
+        }
+    }
+}
+
+/// Optional parts of a tree line after the name. All are on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+    /// ` -> target` after symlinks.
+    pub symlink_targets: bool,
+    /// ` [error]` after entries that could not be read.
+    pub errors: bool,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            symlink_targets: true,
+            errors: true,
+        }
+    }
+}
+
+/// Configuration for the rendering pipeline.
+///
+/// Build one with [`RenderConfig::builder`] (or start from `Default`); new
+/// options get defaults that keep the current output, so existing callers
+/// keep compiling.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RenderConfig {
+    /// Whether to emit color styling.
+    pub use_color: bool,
+    /// Columns available for a tree line; 0 means unknown (names are never
+    /// shortened).
+    pub terminal_width: u16,
+    /// Shorten names that would not fit in `terminal_width` by cutting
+    /// out their middle (keeping the extension) instead of clipping the end.
+    pub truncate_names: bool,
+    /// Characters for tree connectors.
+    pub charset: Charset,
+    /// Columns taken by ambiguous-width characters when fitting names.
+    pub ambiguous_width: AmbiguousWidth,
+    /// Styles used when `use_color` is on.
+    pub theme: Theme,
+    /// Which optional parts of each line are drawn.
+    pub columns: Columns,
+    /// Tree or flat list.
+    pub listing: Listing,
+    /// Text searched for in names; the matching characters are drawn in
+    /// the theme's `matched` style.
+    pub search: Option<String>,
+}
+
+impl Default for RenderConfig {
+    /// Colored, box-drawn lines with every column, and names shortened in
+    /// the middle once `terminal_width` is set.
+    fn default() -> Self {
+        Self {
+            use_color: true,
+            terminal_width: 0,
+            truncate_names: true,
+            charset: Charset::Unicode,
+            ambiguous_width: AmbiguousWidth::Narrow,
+            theme: Theme::default(),
+            columns: Columns::default(),
+            listing: Listing::Tree,
+            search: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    /// Start from the defaults and change only what you need.
+    pub fn builder() -> RenderConfigBuilder {
+        RenderConfigBuilder::default()
+    }
+
+    /// Whether connectors are drawn in ASCII.
+    fn ascii(&self) -> bool {
+        self.charset == Charset::Ascii
+    }
+}
+
+/// Options for [`RenderConfig::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct RenderConfigBuilder {
+    config: RenderConfig,
+}
+
+impl RenderConfigBuilder {
+    pub fn use_color(mut self, use_color: bool) -> Self {
+        self.config.use_color = use_color;
+        self
+    }
+
+    pub fn terminal_width(mut self, width: u16) -> Self {
+        self.config.terminal_width = width;
+        self
+    }
+
+    pub fn truncate_names(mut self, truncate: bool) -> Self {
+        self.config.truncate_names = truncate;
+        self
+    }
+
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.config.charset = charset;
+        self
+    }
+
+    pub fn ambiguous_width(mut self, widths: AmbiguousWidth) -> Self {
+        self.config.ambiguous_width = widths;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.config.theme = theme;
+        self
+    }
+
+    pub fn columns(mut self, columns: Columns) -> Self {
+        self.config.columns = columns;
+        self
+    }
+
+    pub fn listing(mut self, listing: Listing) -> Self {
+        self.config.listing = listing;
+        self
+    }
+
+    pub fn search(mut self, query: impl Into<String>) -> Self {
+        self.config.search = Some(query.into());
+        self
+    }
+
+    pub fn build(self) -> RenderConfig {
+        self.config
+    }
+}
+
+/// Tone a highlight style down as it ages: drop bold, then dim, then fall
+/// back to gray just before it expires.
+fn faded(style: Style, step: u8) -> Style {
+    match step {
+        0 => style,
+        1 => style.remove_modifier(Modifier::BOLD),
+        2 => style
+            .remove_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::DIM),
+        _ => style
+            .remove_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::DIM)
+            .fg(Color::DarkGray),
+    }
+}
 
 /// Sanitize control characters to avoid terminal control-sequence injection.
 fn sanitize_terminal_text(input: &str) -> String {
@@ -49,10 +333,14 @@ fn sanitize_terminal_text(input: &str) -> String {
 }
 
 /// Convert a slice of `TreeEntry` into styled ratatui `Line` objects.
-pub fn tree_to_lines(
+///
+/// `changed_paths` is any highlight lookup: a `HashSet<PathBuf>` highlights
+/// every contained path as a content change, while a
+/// `HashMap<PathBuf, HighlightKind>` distinguishes metadata-only changes.
+pub fn tree_to_lines<H: Highlights + ?Sized>(
     entries: &[TreeEntry],
     config: &RenderConfig,
-    changed_paths: &HashSet<PathBuf>,
+    changed_paths: &H,
 ) -> Vec<Line<'static>> {
     entries
         .iter()
@@ -61,113 +349,1028 @@ pub fn tree_to_lines(
 }
 
 /// Convert a single `TreeEntry` into a styled `Line`.
-fn entry_to_line(
+fn entry_to_line<H: Highlights + ?Sized>(
     entry: &TreeEntry,
     config: &RenderConfig,
-    changed_paths: &HashSet<PathBuf>,
+    changed_paths: &H,
 ) -> Line<'static> {
-    let is_changed = config.use_color && changed_paths.contains(&entry.path);
+    let change = if config.use_color {
+        changed_paths.kind_of(&entry.path)
+    } else {
+        None
+    };
+    let theme = &config.theme;
     let mut spans = Vec::new();
-    let safe_name = sanitize_terminal_text(&entry.name);
+    let executable = is_executable(entry);
+    // Tree-drawing characters, drawn from the entry's structure in the
+    // configured charset; a flat list has none.
+    let (mut safe_name, prefix) = match config.listing {
+        Listing::Tree if config.ascii() => (
+            sanitize_terminal_text(&entry.name),
+            ascii_prefix(&entry.prefix()),
+        ),
+        Listing::Tree => (sanitize_terminal_text(&entry.name), entry.prefix()),
+        Listing::Flat => (sanitize_terminal_text(&relative_path(entry)), String::new()),
+    };
+    if config.truncate_names && config.terminal_width > 0 {
+        let widths = config.ambiguous_width;
+        let columns = config.columns;
+        let suffix = match (&entry.error, &entry.symlink_target) {
+            (Some(err), _) if columns.errors => widths.str_width(&sanitize_terminal_text(err)) + 3,
+            (Some(_), _) => 0,
+            (None, Some(target)) if entry.is_symlink && columns.symlink_targets => {
+                widths.str_width(&sanitize_terminal_text(target)) + 4
+            }
+            _ => 0,
+        } + usize::from(executable);
+        let prefix_width = if config.ascii() {
+            prefix.chars().count()
+        } else {
+            widths.str_width(&prefix)
+        };
+        let budget = usize::from(config.terminal_width)
+            .saturating_sub(prefix_width + suffix)
+            .max(MIN_TRUNCATED_NAME);
+        safe_name = middle_truncate_as(&safe_name, budget, widths);
+    }
 
-    // Prefix (tree-drawing characters)
-    if !entry.prefix.is_empty() {
+    if !prefix.is_empty() {
         if config.use_color {
-            let prefix_style = PREFIX_STYLE;
-            spans.push(Span::styled(entry.prefix.clone(), prefix_style));
+            spans.push(Span::styled(prefix, theme.prefix));
         } else {
-            spans.push(Span::raw(entry.prefix.clone()));
+            spans.push(Span::raw(prefix));
         }
     }
 
     // Name + decorations
-    if is_changed {
+    let name = spans.len();
+    let target = entry
+        .symlink_target
+        .as_deref()
+        .filter(|_| entry.is_symlink && config.columns.symlink_targets);
+    if let Some(kind) = change {
         // Changed entries: directories use turquoise-green, others use cyan bold.
-        let style = if entry.is_dir {
-            CHANGED_DIR_STYLE
-        } else {
-            CHANGED_STYLE
+        let style = match (kind, entry.is_dir) {
+            (HighlightKind::Content, true) => theme.changed_dir,
+            (HighlightKind::Content, false) => theme.changed,
+            (HighlightKind::Metadata, true) => theme.metadata_dir,
+            (HighlightKind::Metadata, false) => theme.metadata,
+            (HighlightKind::Descendant, _) => theme.descendant,
+            (HighlightKind::Inherited, _) => theme.inherited,
         };
+        let style = match changed_paths.color_of(&entry.path) {
+            Some(color) if kind != HighlightKind::Descendant => style.fg(color),
+            _ => style,
+        };
+        let style = faded(style, changed_paths.fade_of(&entry.path));
         spans.push(Span::styled(safe_name.clone(), style));
-        if entry.is_symlink {
-            if let Some(ref target) = entry.symlink_target {
-                let safe_target = sanitize_terminal_text(target);
-                spans.push(Span::styled(format!(" -> {}", safe_target), style));
-            }
+        if let Some(target) = target {
+            let safe_target = sanitize_terminal_text(target);
+            spans.push(Span::styled(format!(" -> {}", safe_target), style));
         }
     } else if let Some(ref err) = entry.error {
-        let safe_err = sanitize_terminal_text(err);
-        let text = format!("{} [{}]", safe_name, safe_err);
+        let text = if config.columns.errors {
+            format!("{} [{}]", safe_name, sanitize_terminal_text(err))
+        } else {
+            safe_name
+        };
         if config.use_color {
-            spans.push(Span::styled(text, ERROR_STYLE));
+            spans.push(Span::styled(text, theme.error));
         } else {
             spans.push(Span::raw(text));
         }
     } else if entry.is_symlink {
         if config.use_color {
-            spans.push(Span::styled(safe_name.clone(), SYMLINK_STYLE));
+            spans.push(Span::styled(safe_name.clone(), theme.symlink));
         } else {
             spans.push(Span::raw(safe_name.clone()));
         }
-        if let Some(ref target) = entry.symlink_target {
+        if let Some(target) = target {
             let safe_target = sanitize_terminal_text(target);
             spans.push(Span::raw(format!(" -> {}", safe_target)));
         }
     } else if entry.is_dir {
         if config.use_color {
-            spans.push(Span::styled(safe_name, DIR_STYLE));
+            spans.push(Span::styled(safe_name, theme.dir));
         } else {
             spans.push(Span::raw(safe_name));
         }
+    } else if executable && config.use_color {
+        spans.push(Span::styled(safe_name, theme.executable));
     } else {
         spans.push(Span::raw(safe_name));
     }
+    if executable && entry.error.is_none() {
+        match spans.last().map(|s| s.style) {
+            Some(style) if config.use_color => spans.push(Span::styled("*", style)),
+            _ => spans.push(Span::raw("*")),
+        }
+    }
+    if let Some(query) = config.search.as_deref().filter(|_| config.use_color) {
+        mark_matches(&mut spans, name, entry, query, config);
+    }
+
+    Line::from(spans)
+}
+
+/// Split the name span at `index` so the characters of `entry`'s path
+/// matching the search `query` are drawn in the theme's `matched` style.
+/// A tree line shows only the name, so only matches in the name are
+/// marked; a name that was shortened or sanitized is left alone.
+fn mark_matches(
+    spans: &mut Vec<Span<'static>>,
+    index: usize,
+    entry: &TreeEntry,
+    query: &str,
+    config: &RenderConfig,
+) {
+    let Some(span) = spans.get(index) else {
+        return;
+    };
+    let path = entry.relative_path();
+    let Some(found) = search::fuzzy_match(&path, query) else {
+        return;
+    };
+    let shown = match config.listing {
+        Listing::Tree => path.rfind(std::path::MAIN_SEPARATOR).map_or(0, |i| i + 1),
+        Listing::Flat => 0,
+    };
+    if !span.content.starts_with(&path[shown..]) {
+        return;
+    }
+    let matched: Vec<usize> = found
+        .positions
+        .iter()
+        .filter_map(|&p| p.checked_sub(shown))
+        .collect();
+    let (content, style) = (span.content.to_string(), span.style);
+    let styled = |text: String, is_match: bool| {
+        let style = if is_match {
+            style.patch(config.theme.matched)
+        } else {
+            style
+        };
+        Span::styled(text, style)
+    };
+    // Runs of matched and unmatched characters, one span each.
+    let mut pieces = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in content.char_indices() {
+        let is_match = matched.binary_search(&i).is_ok();
+        if is_match != run_matched && !run.is_empty() {
+            pieces.push(styled(std::mem::take(&mut run), run_matched));
+        }
+        run_matched = is_match;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        pieces.push(styled(run, run_matched));
+    }
+    spans.splice(index..=index, pieces);
+}
+
+/// `entry`'s path below the root for flat lists, directories ending in a
+/// separator.
+fn relative_path(entry: &TreeEntry) -> String {
+    let mut path = entry.relative_path();
+    if entry.is_dir {
+        path.push(std::path::MAIN_SEPARATOR);
+    }
+    path
+}
+
+/// Whether `entry` is a regular file with an execute bit set. Always false
+/// when the tree was built without modes.
+fn is_executable(entry: &TreeEntry) -> bool {
+    !entry.is_dir && !entry.is_symlink && entry.mode.is_some_and(|m| m & 0o111 != 0)
+}
+
+/// `prefix` with its box-drawing characters replaced by ASCII of the same
+/// width: `├── ` becomes `|-- `, `└── ` becomes `` `-- ``, `│` becomes `|`.
+pub fn ascii_prefix(prefix: &str) -> String {
+    prefix
+        .chars()
+        .map(|c| match c {
+            '\u{2502}' | '\u{251c}' => '|',
+            '\u{2514}' => '`',
+            '\u{2500}' => '-',
+            c => c,
+        })
+        .collect()
+}
+
+/// Names are never shortened below this many columns; past that they clip.
+const MIN_TRUNCATED_NAME: usize = 8;
+
+/// Fit `name` into `max` columns by replacing its middle with `…`, keeping
+/// the extension: `a_very_long_file_name.txt` → `a_ver…name.txt`. Widths
+/// are display columns, so wide (CJK, emoji) characters count as two.
+pub fn middle_truncate(name: &str, max: usize) -> String {
+    middle_truncate_as(name, max, AmbiguousWidth::Narrow)
+}
+
+/// [`middle_truncate`] with ambiguous-width characters (including the `…`
+/// it inserts) counted as `widths` says.
+pub fn middle_truncate_as(name: &str, max: usize, widths: AmbiguousWidth) -> String {
+    if widths.str_width(name) <= max {
+        return name.to_string();
+    }
+    let ellipsis = widths.char_width('…');
+    if max < ellipsis {
+        return String::new();
+    }
+    // Keep the extension unless it would take up most of the room.
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 && widths.str_width(&name[dot..]) <= max / 2 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let keep = max - ellipsis - widths.str_width(ext);
+    let head = take_columns(stem.chars(), keep.div_ceil(2), widths);
+    let head_width = widths.str_width(&head);
+    let mut tail: Vec<char> = take_columns(stem.chars().rev(), keep - head_width, widths)
+        .chars()
+        .collect();
+    tail.reverse();
+    let mut out = head;
+    out.push('…');
+    out.extend(tail);
+    out.push_str(ext);
+    out
+}
+
+/// The longest run of `chars` that fits in `columns` display columns.
+fn take_columns(
+    chars: impl Iterator<Item = char>,
+    columns: usize,
+    widths: AmbiguousWidth,
+) -> String {
+    let mut used = 0;
+    chars
+        .take_while(|&c| {
+            used += widths.char_width(c);
+            used <= columns
+        })
+        .collect()
+}
+
+/// Build the dimmed composition column appended after a directory name,
+/// e.g. `  rs:34 toml:2`.
+pub fn summary_span(summary: &DirSummary, config: &RenderConfig) -> Span<'static> {
+    let text = format!("  {}", sanitize_terminal_text(&summary.to_string()));
+    if config.use_color {
+        Span::styled(text, Style::new().fg(Color::DarkGray))
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Marker appended after a collapsed directory's name.
+pub fn collapsed_span(config: &RenderConfig) -> Span<'static> {
+    let marker = if config.ascii() { " ..." } else { " …" };
+    if config.use_color {
+        Span::styled(marker, LABEL_STYLE)
+    } else {
+        Span::raw(marker)
+    }
+}
+
+/// Tag for names that differ from a sibling only in case, which cannot both
+/// exist on case-insensitive filesystems.
+pub fn case_collision_span(config: &RenderConfig) -> Span<'static> {
+    if config.use_color {
+        Span::styled(" (case collision)", Style::new().fg(Color::Yellow))
+    } else {
+        Span::raw(" (case collision)")
+    }
+}
+
+/// ` (hard link of <path>)` after a file that is a hard link to `original`
+/// (relative to the root), shown earlier in the tree.
+pub fn hard_link_span(original: &Path, config: &RenderConfig) -> Span<'static> {
+    let text = format!(
+        " (hard link of {})",
+        sanitize_terminal_text(&original.to_string_lossy())
+    );
+    if config.use_color {
+        Span::styled(text, Style::new().fg(Color::Magenta))
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Columns taken by the permissions column: ten mode characters and a space.
+pub const PERMISSIONS_WIDTH: usize = 11;
+
+/// `st_mode` as `ls -l` shows it, e.g. `drwxr-xr-x` or `-rwsr-x--T`.
+pub fn format_mode(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o010000 => 'p',
+        0o140000 => 's',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        _ => '-',
+    };
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    // (shift of the rwx triple, its special bit, and that bit's letter)
+    for (shift, special, letter) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(match (mode & special != 0, bits & 0o1 != 0) {
+            (true, true) => letter,
+            (true, false) => letter.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+/// Fixed-width permissions column in front of a tree line; blank when the
+/// mode is unknown (unreadable entries, the truncation notice).
+pub fn permissions_span(mode: Option<u32>, config: &RenderConfig) -> Span<'static> {
+    let text = match mode {
+        Some(mode) => format!("{} ", format_mode(mode)),
+        None => " ".repeat(PERMISSIONS_WIDTH),
+    };
+    if config.use_color {
+        Span::styled(text, LABEL_STYLE)
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Owner column in front of a tree line: user and group names, each padded
+/// to `widths` columns, then a space; blank when the owner is unknown.
+pub fn owner_span(
+    owner: Option<(&str, &str)>,
+    widths: (usize, usize),
+    config: &RenderConfig,
+) -> Span<'static> {
+    let pad = |name: &str, width: usize| {
+        let name = sanitize_terminal_text(name);
+        let fill = width.saturating_sub(config.ambiguous_width.str_width(&name));
+        format!("{name}{}", " ".repeat(fill))
+    };
+    let (user, group) = owner.unwrap_or(("", ""));
+    let text = format!("{} {} ", pad(user, widths.0), pad(group, widths.1));
+    if config.use_color {
+        Span::styled(text, LABEL_STYLE)
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Entry number shown in the line-number gutter, right-aligned to `width`
+/// digits; `None` leaves the gutter blank (e.g. for the truncation notice).
+pub fn gutter_span(number: Option<usize>, width: usize, config: &RenderConfig) -> Span<'static> {
+    let text = match number {
+        Some(n) => format!("{n:>width$} "),
+        None => " ".repeat(width + 1),
+    };
+    if config.use_color {
+        Span::styled(text, LABEL_STYLE)
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// One-column margin in front of each tree line while any entry is marked:
+/// a bar next to marked entries, blank elsewhere.
+pub fn mark_margin_span(marked: bool, config: &RenderConfig) -> Span<'static> {
+    match (marked, config.use_color) {
+        (false, _) => Span::raw(" "),
+        (true, true) if !config.ascii() => Span::styled("▌", Style::new().fg(Color::Magenta)),
+        (true, true) => Span::styled("*", Style::new().fg(Color::Magenta)),
+        (true, false) => Span::raw("*"),
+    }
+}
+
+/// Compare mode's marks and what they mean, in legend order.
+const DIFFERENCE_MARKS: [(Difference, &str, Color, &str); 4] = [
+    (Difference::Missing, "+", Color::Green, "only here"),
+    (Difference::Content, "~", Color::Yellow, "size/time differ"),
+    (Difference::Kind, "!", Color::Red, "kind differs"),
+    (
+        Difference::Within,
+        ".",
+        Color::DarkGray,
+        "differences inside",
+    ),
+];
+
+/// One-column margin in front of each tree line in compare mode: how the
+/// entry differs from the other tree, blank where it does not.
+pub fn compare_span(difference: Option<Difference>, config: &RenderConfig) -> Span<'static> {
+    let Some((_, mark, color, _)) = DIFFERENCE_MARKS
+        .iter()
+        .find(|(d, ..)| Some(*d) == difference)
+    else {
+        return Span::raw(" ");
+    };
+    if config.use_color {
+        Span::styled(*mark, Style::new().fg(*color))
+    } else {
+        Span::raw(*mark)
+    }
+}
 
+/// The legend for compare mode's marks, e.g. `+ only here  ~ size/time
+/// differ  ...`, or a note that the trees look the same.
+pub fn compare_legend_line(identical: bool, config: &RenderConfig) -> Line<'static> {
+    if identical {
+        return Line::from(" no differences ");
+    }
+    let mut spans = vec![Span::raw(" ")];
+    for (difference, _, _, meaning) in DIFFERENCE_MARKS {
+        spans.push(compare_span(Some(difference), config));
+        spans.push(Span::raw(format!(" {meaning} ")));
+    }
     Line::from(spans)
 }
 
-/// Build a line indicating that the displayed entries were truncated.
-pub fn truncation_line(shown: usize, total: usize) -> Line<'static> {
-    let msg = format!("... showing {} of {} entries (truncated)", shown, total);
+/// Format a byte count using binary units, e.g. `512 B` or `1.5 KiB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Format how long ago `time` was relative to `now`, e.g. `5m ago`.
+pub fn format_age(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or_default().as_secs();
+    match secs {
+        0..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Style for labels in the preview pane.
+const LABEL_STYLE: Style = Style::new().fg(Color::DarkGray);
+
+/// A `label: value` preview line.
+fn preview_field(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{label:<9}"), LABEL_STYLE),
+        Span::raw(sanitize_terminal_text(&value)),
+    ])
+}
+
+/// Display `path` relative to `base` for preview listings.
+fn relative_display(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Build the preview pane contents for a directory. `details` is `None`
+/// while the background computation is still running.
+pub fn dir_preview_lines(
+    dir: &Path,
+    details: Option<&DirDetails>,
+    now: SystemTime,
+) -> Vec<Line<'static>> {
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.to_string_lossy().to_string());
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{}/", sanitize_terminal_text(&name)),
+        DIR_STYLE,
+    ))];
+    let Some(details) = details else {
+        lines.push(Line::styled("computing…", LABEL_STYLE));
+        return lines;
+    };
+
+    lines.push(preview_field(
+        "children",
+        format!("{} dirs, {} files", details.child_dirs, details.child_files),
+    ));
+    lines.push(preview_field(
+        "total",
+        format!(
+            "{} in {} files",
+            human_size(details.total_size),
+            details.total_files
+        ),
+    ));
+    if let Some((path, time)) = &details.newest {
+        lines.push(preview_field(
+            "newest",
+            format!(
+                "{} ({})",
+                relative_display(path, dir),
+                format_age(*time, now)
+            ),
+        ));
+    }
+    if let Some((path, time)) = &details.oldest {
+        lines.push(preview_field(
+            "oldest",
+            format!(
+                "{} ({})",
+                relative_display(path, dir),
+                format_age(*time, now)
+            ),
+        ));
+    }
+    if !details.largest.is_empty() {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled("largest", LABEL_STYLE));
+        for (path, size) in &details.largest {
+            lines.push(Line::raw(format!(
+                "  {:>10}  {}",
+                human_size(*size),
+                sanitize_terminal_text(&relative_display(path, dir))
+            )));
+        }
+    }
+    lines
+}
+
+/// Build the heaviest-paths listing relative to `root`. `report` is `None`
+/// while the background computation is still running.
+pub fn heavy_report_lines(report: Option<&HeavyReport>, root: &Path) -> Vec<Line<'static>> {
+    let Some(report) = report else {
+        return vec![Line::styled("computing…", LABEL_STYLE)];
+    };
+    let mut lines = Vec::new();
+    for (label, items) in [("directories", &report.dirs), ("files", &report.files)] {
+        if items.is_empty() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::styled(label, LABEL_STYLE));
+        for (path, size) in items {
+            lines.push(Line::raw(format!(
+                "  {:>10}  {}",
+                human_size(*size),
+                sanitize_terminal_text(&relative_display(path, root))
+            )));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::styled("no files", LABEL_STYLE));
+    }
+    lines
+}
+
+/// Build the top-changers panel: directories by number of changes within
+/// the last `window`, most first.
+pub fn top_changers_lines(
+    top: &[(PathBuf, usize)],
+    root: &Path,
+    window: Duration,
+) -> Vec<Line<'static>> {
+    let window = format_window(window);
+    if top.is_empty() {
+        return vec![Line::styled(
+            format!("no changes in the last {window}"),
+            LABEL_STYLE,
+        )];
+    }
+    let mut lines = vec![Line::styled(
+        format!("changes in the last {window}"),
+        LABEL_STYLE,
+    )];
+    for (dir, count) in top {
+        let name = if dir == root {
+            "./".to_string()
+        } else {
+            format!("{}/", relative_display(dir, root))
+        };
+        lines.push(Line::raw(format!(
+            "  {count:>6}  {}",
+            sanitize_terminal_text(&name)
+        )));
+    }
+    lines
+}
+
+/// A window length in its largest whole unit, e.g. `5m` or `90s`.
+fn format_window(window: Duration) -> String {
+    let secs = window.as_secs();
+    match secs {
+        0 => format!("{}ms", window.as_millis()),
+        _ if secs.is_multiple_of(3600) => format!("{}h", secs / 3600),
+        _ if secs.is_multiple_of(60) => format!("{}m", secs / 60),
+        _ => format!("{secs}s"),
+    }
+}
+
+/// Build the debug overlay: frame count and rate, the last walk and frame
+/// times, the watcher event rate, and the snapshot's size.
+pub fn stats_lines(
+    stats: &Stats,
+    fps: usize,
+    entries: usize,
+    snapshot_bytes: usize,
+    now: Instant,
+) -> Vec<Line<'static>> {
+    let took = |d: Option<Duration>| d.map_or_else(|| "-".to_string(), |d| format!("{d:.1?}"));
+    vec![
+        preview_field(
+            "frames",
+            format!(
+                "{} ({fps} fps, {} skipped)",
+                stats.frames, stats.skipped_frames
+            ),
+        ),
+        preview_field("build", took(stats.last_build)),
+        preview_field("render", took(stats.last_render)),
+        preview_field("events", format!("{:.1}/s", stats.event_rate(now))),
+        preview_field(
+            "snapshot",
+            format!("{entries} entries, ~{}", human_size(snapshot_bytes as u64)),
+        ),
+    ]
+}
+
+/// Build the stale-files listing (age and path relative to `root`).
+pub fn stale_report_lines(
+    files: &[(PathBuf, SystemTime)],
+    root: &Path,
+    now: SystemTime,
+) -> Vec<Line<'static>> {
+    if files.is_empty() {
+        return vec![Line::styled("no stale files", LABEL_STYLE)];
+    }
+    files
+        .iter()
+        .map(|(path, modified)| {
+            Line::raw(format!(
+                "  {:>8}  {}",
+                format_age(*modified, now),
+                sanitize_terminal_text(&relative_display(path, root))
+            ))
+        })
+        .collect()
+}
+
+/// Style for files that have not been modified for a while.
+const STALE_STYLE: Style = Style::new().fg(Color::Yellow).add_modifier(Modifier::DIM);
+
+/// Mark a tree line as stale: dim yellow with color, a ` (stale)` suffix without.
+pub fn mark_stale(line: Line<'static>, config: &RenderConfig) -> Line<'static> {
+    if config.use_color {
+        line.patch_style(STALE_STYLE)
+    } else {
+        let mut line = line;
+        line.spans.push(Span::raw(" (stale)"));
+        line
+    }
+}
+
+// Heatmap tints, coolest first.
+const HEAT_STYLES: [Style; HEAT_LEVELS as usize] = [
+    Style::new().fg(Color::Rgb(250, 220, 120)),
+    Style::new().fg(Color::Rgb(255, 180, 60)),
+    Style::new().fg(Color::Rgb(255, 120, 30)),
+    Style::new()
+        .fg(Color::Rgb(235, 50, 35))
+        .add_modifier(Modifier::BOLD),
+];
+
+/// Tint a tree line by heat `level` (from `stats::Activity::level`); a
+/// ` (heat N/M)` suffix without colors.
+pub fn mark_heat(line: Line<'static>, level: u8, config: &RenderConfig) -> Line<'static> {
+    let level = level.min(HEAT_LEVELS - 1);
+    if config.use_color {
+        line.patch_style(HEAT_STYLES[usize::from(level)])
+    } else {
+        let mut line = line;
+        line.spans
+            .push(Span::raw(format!(" (heat {}/{HEAT_LEVELS})", level + 1)));
+        line
+    }
+}
+
+// Styles for the highlight layers other than watcher changes.
+const MARKED_STYLE: Style = Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD);
+const BASELINE_STYLE: Style = Style::new().fg(Color::Green).add_modifier(Modifier::ITALIC);
+
+/// Overlay highlight layers on a tree line, lowest precedence first. Without
+/// color, each layer appends a tag such as ` (marked)` instead.
+pub fn mark_channels(
+    line: Line<'static>,
+    channels: &[Channel],
+    config: &RenderConfig,
+) -> Line<'static> {
+    let mut line = line;
+    for channel in channels {
+        let (style, tag) = match channel {
+            Channel::Changes => continue,
+            // In color the matching characters stand out instead.
+            Channel::Search if config.use_color => continue,
+            Channel::Search => (Style::new(), " (match)"),
+            Channel::Marked => (MARKED_STYLE, " (marked)"),
+            Channel::Baseline => (BASELINE_STYLE, " (differs)"),
+        };
+        if config.use_color {
+            line = line.patch_style(style);
+        } else {
+            line.spans.push(Span::raw(tag));
+        }
+    }
+    line
+}
+
+/// Build the preview pane contents for a non-directory entry.
+pub fn file_preview_lines(
+    entry: &TreeEntry,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+    now: SystemTime,
+) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::raw(sanitize_terminal_text(&entry.name))];
+    if let Some(size) = size {
+        lines.push(preview_field("size", human_size(size)));
+    }
+    if let Some(modified) = modified {
+        lines.push(preview_field("modified", format_age(modified, now)));
+    }
+    if let Some(target) = &entry.symlink_target {
+        lines.push(preview_field("target", target.clone()));
+    }
+    if let Some(err) = &entry.error {
+        lines.push(Line::styled(sanitize_terminal_text(err), ERROR_STYLE));
+    }
+    lines
+}
+
+/// Build a line indicating that the displayed entries were truncated, and
+/// how deep the entries shown reach (deeper levels are dropped first).
+pub fn truncation_line(shown: usize, total: usize, depth: usize) -> Line<'static> {
+    let msg = format!(
+        "... showing {} of {} entries down to depth {} (truncated, ]/[ to change)",
+        shown, total, depth
+    );
     let safe_msg = sanitize_terminal_text(&msg);
     let style = Style::new().fg(Color::DarkGray);
     Line::from(Span::styled(safe_msg, style))
 }
 
+/// Build the line shown while a slow first scan is running, e.g.
+/// ` Scanning ~/src… 12345 entries  (vendor/lib)`.
+pub fn scanning_line(watched_path: &str, entries: usize, current: &Path) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!(
+                " Scanning {}… {entries} entries",
+                sanitize_terminal_text(watched_path)
+            ),
+            Style::new().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("  ({})", sanitize_terminal_text(&current.to_string_lossy())),
+            Style::new().fg(Color::DarkGray),
+        ),
+    ])
+}
+
 /// Build a styled status bar `Line`.
 pub fn status_bar_line(
     watched_path: &str,
     entry_info: &str,
     last_change: Option<&str>,
 ) -> Line<'static> {
-    let change_text = match last_change {
-        Some(ts) => format!("Last change: {}", sanitize_terminal_text(ts)),
-        None => "No changes yet".to_string(),
+    let values = StatusValues {
+        path: watched_path,
+        entries: entry_info,
+        last_change,
+        ..StatusValues::default()
     };
+    status_bar(&StatusTemplate::default(), &values).line
+}
 
-    let safe_path = sanitize_terminal_text(watched_path);
-    let safe_entry_info = sanitize_terminal_text(entry_info);
-    let text = format!(
-        " Watching: {}  |  {}  |  {}",
-        safe_path, safe_entry_info, change_text
-    );
+/// Longest last-changed file name shown in the status bar, in characters.
+const LAST_FILE_MAX_CHARS: usize = 24;
+
+/// A rendered status bar.
+pub struct StatusBar {
+    pub line: Line<'static>,
+    /// Index of the span holding the last changed file name, for clicks.
+    pub last_file_span: Option<usize>,
+}
+
+/// Build the status bar from `template`. A field with nothing to show
+/// (e.g. `{follow}` while follow mode is off) also drops the whitespace
+/// directly before it. The last changed file name is its own underlined span.
+pub fn status_bar(template: &StatusTemplate, values: &StatusValues) -> StatusBar {
+    let mut pieces: Vec<(String, bool)> = Vec::new();
+    for segment in template.segments() {
+        let (text, is_file) = match segment {
+            Segment::Text(text) => (text.clone(), false),
+            Segment::Field(field) => (status_field(*field, values), *field == Field::LastFile),
+        };
+        if text.is_empty() {
+            if pieces
+                .last()
+                .is_some_and(|(prev, file)| !file && prev.trim().is_empty())
+            {
+                pieces.pop();
+            }
+            continue;
+        }
+        pieces.push((text, is_file));
+    }
 
     let style = Style::new()
         .fg(Color::White)
         .bg(Color::DarkGray)
         .add_modifier(Modifier::BOLD);
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut last_file_span = None;
+    let mut plain = String::new();
+    for (text, is_file) in pieces {
+        if is_file {
+            if !plain.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut plain), style));
+            }
+            last_file_span = Some(spans.len());
+            spans.push(Span::styled(text, style.add_modifier(Modifier::UNDERLINED)));
+        } else {
+            plain.push_str(&text);
+        }
+    }
+    if !plain.is_empty() || spans.is_empty() {
+        spans.push(Span::styled(plain, style));
+    }
+    StatusBar {
+        line: Line::from(spans),
+        last_file_span,
+    }
+}
 
-    Line::from(Span::styled(text, style))
+/// Sanitized text for one status bar field.
+fn status_field(field: Field, values: &StatusValues) -> String {
+    match field {
+        Field::Path => sanitize_terminal_text(values.path),
+        Field::Entries => sanitize_terminal_text(values.entries),
+        Field::LastChange => match values.last_change {
+            Some(ts) => format!("Last change: {}", sanitize_terminal_text(ts)),
+            None => "No changes yet".to_string(),
+        },
+        Field::LastFile => values
+            .last_file
+            .map(|name| truncate_end(&sanitize_terminal_text(name), LAST_FILE_MAX_CHARS))
+            .unwrap_or_default(),
+        Field::Follow => if values.follow { "[follow]" } else { "" }.to_string(),
+        Field::Watcher => sanitize_terminal_text(values.watcher),
+        Field::Fps => values.fps.to_string(),
+        Field::Search => values
+            .search
+            .map(|(current, total)| format!("[{current}/{total}]"))
+            .unwrap_or_default(),
+    }
+}
+
+/// `text` cut to at most `max` columns, ending in `…` if it was cut.
+fn truncate_end(text: &str, max: usize) -> String {
+    if text.width() <= max {
+        return text.to_string();
+    }
+    let mut cut = take_columns(text.chars(), max.saturating_sub(1), AmbiguousWidth::Narrow);
+    cut.push('…');
+    cut
 }
 
 /// Build a help bar `Line` showing available keyboard shortcuts.
 pub fn help_bar_line() -> Line<'static> {
     let text =
-        " q: Quit  |  r: Reset  |  ↑↓/jk: Scroll  |  PgUp/PgDn: Page  |  Home/End  |  +/-: Highlight duration";
+        " q: Quit  |  r: Reset  |  ↑↓/jk: Move  |  PgUp/PgDn: Page  |  Home/End  |  +/-: Highlight duration  |  s: Summary  |  p: Preview  |  m: Mark  |  '/\": Next/prev mark  |  :/g: Commands  |  w: Export  |  a/A: New file/dir  |  R: Rename  |  D: Delete  |  #: Line numbers  |  L: Last change  |  F: Follow  |  Enter: Expand  |  f: Reveal  |  O: Open  |  !: Shell  |  H: Heaviest  |  S: Stale  |  T: Heatmap  |  C: Top changers  |  ]/[: Entries  |  F12: Stats";
     let style = Style::new().fg(Color::DarkGray);
     Line::from(Span::styled(text.to_string(), style))
 }
 
+/// The latest change in words, e.g. `created src/new.rs at 12:31:05`, with
+/// `(and N more)` when it arrived with others.
+pub fn announcement(kind: ChangeKind, path: &str, time: &str, others: usize) -> String {
+    let verb = match kind {
+        ChangeKind::Created => "created",
+        ChangeKind::Modified => "modified",
+        ChangeKind::Metadata => "changed metadata of",
+        ChangeKind::Removed => "deleted",
+        ChangeKind::Renamed => "renamed",
+    };
+    let mut text = format!("{verb} {} at {time}", sanitize_terminal_text(path));
+    if others > 0 {
+        text.push_str(&format!(" (and {others} more)"));
+    }
+    text
+}
+
+/// The announcement line: unstyled, and only changing when a new change
+/// arrives, so screen readers can follow it.
+pub fn announcement_line(announcement: Option<&str>) -> Line<'static> {
+    Line::raw(format!(" {}", announcement.unwrap_or("No changes yet")))
+}
+
+/// One toast, padded by a space on each side; errors are red, or prefixed
+/// with `error:` without colors.
+pub fn toast_line(toast: &Toast, config: &RenderConfig) -> Line<'static> {
+    let text = sanitize_terminal_text(&toast.text);
+    match (toast.level, config.use_color) {
+        (ToastLevel::Info, true) => Line::styled(
+            format!(" {text} "),
+            Style::new().fg(Color::Black).bg(Color::Cyan),
+        ),
+        (ToastLevel::Error, true) => Line::styled(
+            format!(" {text} "),
+            Style::new()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ),
+        (ToastLevel::Info, false) => Line::raw(format!(" {text} ")),
+        (ToastLevel::Error, false) => Line::raw(format!(" error: {text} ")),
+    }
+}
+
+/// Build the banner shown above the grayed-out tree once the root is deleted.
+pub fn root_deleted_line(
+    watched_path: &str,
+    waiting: bool,
+    config: &RenderConfig,
+) -> Line<'static> {
+    let action = if waiting {
+        "showing the last snapshot, press q to exit"
+    } else {
+        "press q to exit or w to wait"
+    };
+    let text = format!(
+        "Directory deleted: {} — {action}",
+        sanitize_terminal_text(watched_path)
+    );
+    if config.use_color {
+        Line::from(Span::styled(
+            text,
+            ERROR_STYLE.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        ))
+    } else {
+        Line::raw(text)
+    }
+}
+
+/// Build the command palette line that replaces the help bar while it is
+/// open: the input, then an error, the command synopsis, or key hints.
+pub fn palette_line(input: &str, feedback: &Feedback) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(" : ", Style::new().add_modifier(Modifier::BOLD)),
+        Span::raw(sanitize_terminal_text(input)),
+        Span::styled("▏", Style::new().add_modifier(Modifier::SLOW_BLINK)),
+    ];
+    let hint = Style::new().fg(Color::DarkGray);
+    spans.push(match feedback {
+        Feedback::Invalid(error) => {
+            Span::styled(format!("  {}", sanitize_terminal_text(error)), ERROR_STYLE)
+        }
+        Feedback::Usage(usage) => Span::styled(format!("  {usage}"), hint),
+        Feedback::None => Span::styled(
+            "  Tab: Complete  |  ↑↓: History  |  Enter: Run  |  Esc: Cancel",
+            hint,
+        ),
+    });
+    Line::from(spans)
+}
+
+/// The help bar while a deletion waits for confirmation; `trash` asks
+/// about moving the entry to the trash instead.
+pub fn delete_prompt_line(path: &str, is_dir: bool, trash: bool) -> Line<'static> {
+    let path = sanitize_terminal_text(path);
+    let question = match (trash, is_dir) {
+        (true, true) => format!(" Move {path}/ to the trash?"),
+        (true, false) => format!(" Move {path} to the trash?"),
+        (false, true) => format!(" Delete {path}/ and everything in it?"),
+        (false, false) => format!(" Delete {path}?"),
+    };
+    Line::from(vec![
+        Span::styled(question, ERROR_STYLE.add_modifier(Modifier::BOLD)),
+        Span::styled(
+            "  y: Delete  |  any other key: Cancel",
+            Style::new().fg(Color::DarkGray),
+        ),
+    ])
+}
+
 /// Extract plain text from a `Line` (useful for testing).
 #[allow(dead_code)]
 pub fn line_to_plain_text(line: &Line<'_>) -> String {
@@ -177,27 +1380,54 @@ pub fn line_to_plain_text(line: &Line<'_>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::path::PathBuf;
+
+    #[test]
+    fn aging_highlight_fades() {
+        use crate::highlight::{ActiveHighlight, HighlightKind};
+        use std::time::Duration;
+
+        let path = PathBuf::from("/tmp/a.txt");
+        let entry = TreeEntry::new("a.txt", path.clone(), 0, false);
+
+        let cfg = RenderConfig::builder()
+            .use_color(true)
+            .terminal_width(80)
+            .truncate_names(false)
+            .build();
+        let style_at = |fade, color| {
+            let active = HashMap::from([(
+                path.clone(),
+                ActiveHighlight {
+                    kind: HighlightKind::Content,
+                    age: Duration::ZERO,
+                    fade,
+                    color,
+                },
+            )]);
+            entry_to_line(&entry, &cfg, &active).spans[0].style
+        };
+
+        assert_eq!(style_at(0, None), CHANGED_STYLE);
+        assert!(!style_at(1, None).add_modifier.contains(Modifier::BOLD));
+        assert!(style_at(2, None).add_modifier.contains(Modifier::DIM));
+        assert_eq!(style_at(3, None).fg, Some(Color::DarkGray));
+        assert_eq!(style_at(0, Some(Color::Green)).fg, Some(Color::Green));
+    }
 
     #[test]
     fn changed_directory_uses_turquoise_style() {
         let path = PathBuf::from("/tmp/dir");
-        let entry = TreeEntry {
-            name: "dir".to_string(),
-            path: path.clone(),
-            depth: 1,
-            is_dir: true,
-            is_symlink: false,
-            symlink_target: None,
-            is_last: true,
-            prefix: "".to_string(),
-            error: None,
-        };
+        let entry = TreeEntry::new("dir", path.clone(), 0, true);
+
         let mut changed = HashSet::new();
         changed.insert(path.clone());
-        let cfg = RenderConfig {
-            use_color: true,
-            terminal_width: 80,
-        };
+        let cfg = RenderConfig::builder()
+            .use_color(true)
+            .terminal_width(80)
+            .truncate_names(false)
+            .build();
 
         let line = entry_to_line(&entry, &cfg, &changed);
         let plain = line_to_plain_text(&line);
@@ -209,9 +1439,113 @@ mod tests {
         // confirms rendering succeeds with changed-directory styling.
     }
 
+    #[test]
+    fn metadata_change_is_not_bold() {
+        let path = PathBuf::from("/tmp/touched.txt");
+        let entry = TreeEntry::new("touched.txt", path.clone(), 0, false);
+
+        let changed: HashMap<PathBuf, HighlightKind> =
+            [(path, HighlightKind::Metadata)].into_iter().collect();
+        let cfg = RenderConfig::builder()
+            .use_color(true)
+            .terminal_width(80)
+            .truncate_names(false)
+            .build();
+
+        let line = entry_to_line(&entry, &cfg, &changed);
+        let name_span = &line.spans[0];
+        assert_eq!(name_span.style.fg, Some(Color::Cyan));
+        assert!(!name_span.style.add_modifier.contains(Modifier::BOLD));
+        assert!(name_span.style.add_modifier.contains(Modifier::ITALIC));
+    }
+
+    #[test]
+    fn executables_get_a_marker_and_their_own_color() {
+        let mut entry = TreeEntry::new("build.sh", PathBuf::from("/tmp/build.sh"), 0, false);
+        entry.mode = Some(0o100755);
+        let cfg = RenderConfig::builder().use_color(true).build();
+        let line = entry_to_line(&entry, &cfg, &HashSet::new());
+        assert_eq!(line_to_plain_text(&line), "build.sh*");
+        assert_eq!(line.spans[0].style, EXECUTABLE_STYLE);
+
+        let mut plain = entry;
+        plain.mode = Some(0o100644);
+        assert_eq!(
+            line_to_plain_text(&entry_to_line(&plain, &cfg, &HashSet::new())),
+            "build.sh"
+        );
+    }
+
+    #[test]
+    fn format_mode_matches_ls() {
+        assert_eq!(format_mode(0o040755), "drwxr-xr-x");
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+        assert_eq!(format_mode(0o104750), "-rwsr-x---");
+        assert_eq!(format_mode(0o042770), "drwxrws---");
+        assert_eq!(format_mode(0o041777), "drwxrwxrwt");
+        assert_eq!(format_mode(0o101644), "-rw-r--r-T");
+    }
+
+    #[test]
+    fn human_size_uses_binary_units() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn format_age_picks_largest_unit() {
+        let now = SystemTime::now();
+        let ago = |secs| now - std::time::Duration::from_secs(secs);
+        assert_eq!(format_age(ago(5), now), "5s ago");
+        assert_eq!(format_age(ago(125), now), "2m ago");
+        assert_eq!(format_age(ago(7200), now), "2h ago");
+        assert_eq!(format_age(ago(3 * 86_400), now), "3d ago");
+    }
+
+    #[test]
+    fn middle_truncate_keeps_extension() {
+        assert_eq!(middle_truncate("short.rs", 20), "short.rs");
+        assert_eq!(
+            middle_truncate("a_very_long_file_name.txt", 14),
+            "a_ver…name.txt"
+        );
+        assert_eq!(
+            middle_truncate("a_very_long_file_name.txt", 14)
+                .chars()
+                .count(),
+            14
+        );
+        // No extension, or one too long to keep.
+        assert_eq!(middle_truncate("abcdefghijkl", 7), "abc…jkl");
+        assert_eq!(middle_truncate("x.abcdefghij", 7), "x.a…hij");
+    }
+
+    #[test]
+    fn middle_truncate_counts_wide_characters_as_two_columns() {
+        let name = "日本語のとても長いファイル名.md";
+        assert_eq!(middle_truncate(name, 12), "日本…ル名.md");
+        assert_eq!(middle_truncate(name, 11).width(), 10);
+        assert_eq!(middle_truncate("🎉🎉🎉🎉🎉🎉.txt", 10), "🎉…🎉.txt");
+        assert_eq!(truncate_end("日本語.md", 5), "日本…");
+    }
+
+    #[test]
+    fn wide_ambiguous_characters_shrink_the_name_budget() {
+        let name = "±±±±±±±±±±.txt";
+        assert_eq!(middle_truncate(name, 12), "±±±±…±±±.txt");
+        let wide = middle_truncate_as(name, 12, AmbiguousWidth::Wide);
+        assert_eq!(wide, "±…±±.txt");
+        assert!(AmbiguousWidth::Wide.str_width(&wide) <= 12);
+        assert_eq!("2".parse(), Ok(AmbiguousWidth::Wide));
+        assert!("3".parse::<AmbiguousWidth>().is_err());
+    }
+
     #[test]
     fn truncation_line_mentions_truncated() {
-        let line = truncation_line(1000, 5000);
+        let line = truncation_line(1000, 5000, 3);
         let text = line_to_plain_text(&line);
         assert!(
             text.contains("showing 1000 of 5000"),
@@ -221,5 +1555,6 @@ mod tests {
             text.contains("truncated"),
             "Truncation line should mention truncation"
         );
+        assert!(text.contains("down to depth 3"));
     }
 }
@@ -0,0 +1,125 @@
+//! One-shot reports printed instead of starting the live view
+//! (`--report heavy [--top N]`, `--report stale --older-than DURATION`).
+
+use crate::render::{heavy_report_lines, line_to_plain_text, stale_report_lines};
+use crate::tree::{heaviest, stale_files, TreeConfig};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Number of entries listed by `--report heavy` when no count is given.
+pub const DEFAULT_HEAVY_TOP: usize = 20;
+
+/// A report requested on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Report {
+    /// The `top` largest files and directories.
+    Heavy { top: usize },
+    /// Files not modified within `older_than`, oldest first.
+    Stale { older_than: Duration },
+}
+
+impl Report {
+    /// The report `--report <kind>` asks for, with the `--top` and
+    /// `--older-than` values given for it.
+    pub fn parse(
+        kind: &str,
+        top: Option<usize>,
+        older_than: Option<Duration>,
+    ) -> Result<Self, String> {
+        match kind {
+            "heavy" => {
+                if older_than.is_some() {
+                    return Err("--older-than only applies to --report stale".to_string());
+                }
+                match top {
+                    Some(0) => Err("--top must be at least 1".to_string()),
+                    top => Ok(Self::Heavy {
+                        top: top.unwrap_or(DEFAULT_HEAVY_TOP),
+                    }),
+                }
+            }
+            "stale" => {
+                if top.is_some() {
+                    return Err("--top only applies to --report heavy".to_string());
+                }
+                let older_than = older_than.ok_or(
+                    "--report stale needs --older-than, e.g. `--report stale --older-than 30d`",
+                )?;
+                Ok(Self::Stale { older_than })
+            }
+            other => Err(format!(
+                "unknown report '{other}' (available: heavy, stale)"
+            )),
+        }
+    }
+}
+
+/// Compute `report` for `root` and format it as plain text.
+pub fn run(report: &Report, root: &Path, config: &TreeConfig) -> String {
+    let lines = match report {
+        Report::Heavy { top } => {
+            let report = heaviest(
+                root,
+                config.show_hidden,
+                config.follow_symlinks,
+                &config.ignore_patterns,
+                *top,
+            );
+            heavy_report_lines(Some(&report), root)
+        }
+        Report::Stale { older_than } => {
+            let now = SystemTime::now();
+            let files = stale_files(
+                root,
+                config.show_hidden,
+                config.follow_symlinks,
+                &config.ignore_patterns,
+                *older_than,
+                now,
+            );
+            stale_report_lines(&files, root, now)
+        }
+    };
+    let mut out = String::new();
+    for line in &lines {
+        out.push_str(&line_to_plain_text(line));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_DAYS: Duration = Duration::from_secs(2 * 86_400);
+
+    #[test]
+    fn parses_heavy_with_optional_count() {
+        assert_eq!(
+            Report::parse("heavy", None, None).unwrap(),
+            Report::Heavy {
+                top: DEFAULT_HEAVY_TOP
+            }
+        );
+        assert_eq!(
+            Report::parse("heavy", Some(5), None).unwrap(),
+            Report::Heavy { top: 5 }
+        );
+        assert!(Report::parse("heavy", Some(0), None).is_err());
+        assert!(Report::parse("heavy", None, Some(TWO_DAYS)).is_err());
+        assert!(Report::parse("bogus", None, None).is_err());
+    }
+
+    #[test]
+    fn stale_requires_a_duration() {
+        assert_eq!(
+            Report::parse("stale", None, Some(TWO_DAYS)).unwrap(),
+            Report::Stale {
+                older_than: TWO_DAYS
+            }
+        );
+        assert!(Report::parse("stale", None, None).is_err());
+        assert!(Report::parse("stale", Some(5), Some(TWO_DAYS)).is_err());
+    }
+}
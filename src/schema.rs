@@ -0,0 +1,309 @@
+//! Schema validation for `config.toml`.
+//!
+//! Deserializing the config stops at the first problem and often cannot say
+//! where it is. `check` instead walks the file against a description of
+//! every known key and reports all unknown keys, mistyped values, invalid
+//! colors/durations/actions, and settings that contradict each other, each
+//! with the line it is on. Used at startup and by `livetree check-config`.
+
+use crate::cli::parse_duration;
+use crate::status::StatusTemplate;
+use ratatui::style::Color;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The expected shape of a config value.
+enum Schema {
+    Table(&'static [(&'static str, Schema)]),
+    Bool,
+    /// A click action name.
+    Click,
+    Color,
+    Duration,
+    /// A status bar template.
+    Template,
+}
+
+/// Names accepted for click actions.
+const CLICK_ACTIONS: [&str; 6] = ["none", "select", "expand", "open", "copy-path", "reveal"];
+
+const CLICK_BINDINGS: Schema = Schema::Table(&[
+    ("single", Schema::Click),
+    ("double", Schema::Click),
+    ("middle", Schema::Click),
+]);
+
+const KIND_STYLE: Schema =
+    Schema::Table(&[("color", Schema::Color), ("duration", Schema::Duration)]);
+
+const CONFIG: Schema = Schema::Table(&[
+    (
+        "mouse",
+        Schema::Table(&[
+            ("enabled", Schema::Bool),
+            ("file", CLICK_BINDINGS),
+            ("dir", CLICK_BINDINGS),
+        ]),
+    ),
+    (
+        "highlight",
+        Schema::Table(&[
+            ("created", KIND_STYLE),
+            ("modified", KIND_STYLE),
+            ("deleted", KIND_STYLE),
+        ]),
+    ),
+    ("status", Schema::Table(&[("template", Schema::Template)])),
+]);
+
+/// One problem found in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// 1-based line number, if the problem can be located.
+    pub line: Option<usize>,
+    pub message: String,
+    /// The file still loads, but a setting has no effect.
+    pub warning: bool,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Check config file text against the schema. An empty result means the
+/// file is valid.
+pub fn check(text: &str) -> Vec<Issue> {
+    let table: toml::Table = match toml::from_str(text) {
+        Ok(table) => table,
+        Err(e) => {
+            let line = e.span().map(|span| line_at(text, span.start));
+            return vec![Issue {
+                line,
+                message: e.message().to_string(),
+                warning: false,
+            }];
+        }
+    };
+    let mut issues = Vec::new();
+    let mut checker = Checker {
+        text,
+        issues: &mut issues,
+    };
+    if let Schema::Table(fields) = &CONFIG {
+        checker.table(&[], &table, fields);
+    }
+    checker.conflicts(&table);
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// Read and check a config file. A missing file is valid (defaults apply).
+pub fn check_file(path: &Path) -> Result<Vec<Issue>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(check(&text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+struct Checker<'a> {
+    text: &'a str,
+    issues: &'a mut Vec<Issue>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, path: &[&str], message: String, warning: bool) {
+        self.issues.push(Issue {
+            line: line_of(self.text, path),
+            message,
+            warning,
+        });
+    }
+
+    fn table(&mut self, path: &[&str], table: &toml::Table, fields: &[(&str, Schema)]) {
+        for (key, value) in table {
+            let mut key_path = path.to_vec();
+            key_path.push(key.as_str());
+            match fields.iter().find(|(name, _)| name == key) {
+                Some((_, schema)) => self.value(&key_path, value, schema),
+                None => {
+                    let names: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+                    let hint = match suggest(key, &names) {
+                        Some(name) => format!("did you mean '{name}'?"),
+                        None => format!("expected one of: {}", names.join(", ")),
+                    };
+                    self.report(
+                        &key_path,
+                        format!("unknown key '{}' ({hint})", key_path.join(".")),
+                        false,
+                    );
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, path: &[&str], value: &toml::Value, schema: &Schema) {
+        let name = path.join(".");
+        let result = match (schema, value) {
+            (Schema::Table(fields), toml::Value::Table(table)) => {
+                self.table(path, table, fields);
+                Ok(())
+            }
+            (Schema::Table(_), _) => Err(format!("'{name}' must be a table")),
+            (Schema::Bool, toml::Value::Boolean(_)) => Ok(()),
+            (Schema::Bool, _) => Err(format!("'{name}' must be true or false")),
+            (_, toml::Value::String(s)) => match schema {
+                Schema::Click if CLICK_ACTIONS.contains(&s.as_str()) => Ok(()),
+                Schema::Click => Err(format!(
+                    "invalid click action '{s}' for '{name}' (expected one of: {})",
+                    CLICK_ACTIONS.join(", ")
+                )),
+                Schema::Color => Color::from_str(s).map(|_| ()).map_err(|_| {
+                    format!("invalid color '{s}' for '{name}' (use a name such as green, #rrggbb, or 0-255)")
+                }),
+                Schema::Duration => parse_duration(s)
+                    .map(|_| ())
+                    .map_err(|e| format!("{e} for '{name}'")),
+                Schema::Template => StatusTemplate::from_str(s)
+                    .map(|_| ())
+                    .map_err(|e| format!("{e} for '{name}'")),
+                Schema::Table(_) | Schema::Bool => unreachable!("handled above"),
+            },
+            (_, _) => Err(format!("'{name}' must be a string")),
+        };
+        if let Err(message) = result {
+            self.report(path, message, false);
+        }
+    }
+
+    /// Settings that are valid on their own but cancel each other out.
+    fn conflicts(&mut self, table: &toml::Table) {
+        let Some(mouse) = table.get("mouse").and_then(|m| m.as_table()) else {
+            return;
+        };
+        if mouse.get("enabled").and_then(|e| e.as_bool()) == Some(false) {
+            for section in ["file", "dir"] {
+                if mouse.contains_key(section) {
+                    self.report(
+                        &["mouse", section],
+                        format!("'mouse.{section}' has no effect while 'mouse.enabled' is false"),
+                        true,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The closest of `names` to `key`, if it is a plausible typo.
+fn suggest<'a>(key: &str, names: &[&'a str]) -> Option<&'a str> {
+    names
+        .iter()
+        .map(|name| (edit_distance(key, name), *name))
+        .filter(|&(distance, _)| distance <= 2)
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between two short strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = (prev + usize::from(ca != cb)).min(row[j] + 1).min(cur + 1);
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// 1-based line containing byte offset `offset`.
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// 1-based line of the first header or key that defines `path` (or
+/// something inside it). Handles `[a.b]` headers and dotted keys.
+fn line_of(text: &str, path: &[&str]) -> Option<usize> {
+    let mut header: Vec<String> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let full = if let Some(name) = line.strip_prefix('[') {
+            let name = name.trim_start_matches('[');
+            let name = name.split(']').next().unwrap_or_default();
+            header = split_key(name);
+            header.clone()
+        } else if let Some((key, _)) = line.split_once('=') {
+            header.iter().cloned().chain(split_key(key)).collect()
+        } else {
+            continue;
+        };
+        if full.len() >= path.len() && full.iter().zip(path).all(|(a, b)| a == b) {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// Split a (possibly dotted, possibly quoted) TOML key into its parts.
+fn split_key(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"').trim_matches('\'').to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        let text = "[mouse]\nenabled = true\n\n[mouse.file]\nsingle = \"open\"\n\n\
+                    [highlight.created]\ncolor = \"green\"\nduration = \"5s\"\n\n\
+                    [status]\ntemplate = \"{path} | {fps}\"\n";
+        assert_eq!(check(text), Vec::new());
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_line() {
+        let text = "[mouse]\nenabeld = true\n\n[mouse.file]\nsingle = \"explode\"\n\n\
+                    [highlight.created]\ncolor = \"blurple\"\nduration = \"soon\"\n";
+        let issues: Vec<String> = check(text).iter().map(Issue::to_string).collect();
+        assert_eq!(issues.len(), 4, "{issues:?}");
+        assert!(
+            issues[0].starts_with("line 2: unknown key 'mouse.enabeld' (did you mean 'enabled'?)")
+        );
+        assert!(issues[1].starts_with("line 5: invalid click action 'explode'"));
+        assert!(issues[2].starts_with("line 8: invalid color 'blurple'"));
+        assert!(issues[3].starts_with("line 9: invalid duration"));
+    }
+
+    #[test]
+    fn dotted_keys_and_syntax_errors_are_located() {
+        let issues = check("# comment\nhighlight.created.colour = \"red\"\n");
+        assert_eq!(issues[0].line, Some(2));
+        assert!(issues[0].message.contains("did you mean 'color'?"));
+
+        let issues = check("[mouse]\nenabled = \n");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(2));
+    }
+
+    #[test]
+    fn disabled_mouse_with_bindings_is_a_warning() {
+        let issues = check("[mouse]\nenabled = false\n\n[mouse.dir]\ndouble = \"open\"\n");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].warning);
+        assert_eq!(issues[0].line, Some(4));
+    }
+}
@@ -0,0 +1,314 @@
+//! Watch modes for shell scripts and Makefiles, which print changes to
+//! stdout instead of drawing the tree: `--exit-on-change` blocks until the
+//! first change and exits, and `--stream` prints every change as it comes.
+
+use crate::paths;
+use crate::tree::{is_visible, TreeConfig};
+use crate::watcher::{ChangeKind, PathChange, WatchEvent};
+use crossbeam_channel::Receiver;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// How waiting for a change ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Wait {
+    /// The first batch of changes to paths the tree shows.
+    Changed(Vec<PathChange>),
+    RootDeleted,
+    /// The watcher's channel closed.
+    WatcherStopped,
+}
+
+/// Block until a debounced batch from `events` touches a path the tree
+/// would show.
+pub fn wait_for_change(root: &Path, config: &TreeConfig, events: &Receiver<WatchEvent>) -> Wait {
+    for event in events {
+        match event {
+            WatchEvent::Changed(changes) => {
+                let shown = visible_changes(root, config, changes);
+                if !shown.is_empty() {
+                    return Wait::Changed(shown);
+                }
+            }
+            WatchEvent::RootDeleted => return Wait::RootDeleted,
+            // A new directory at the root path may hold anything.
+            WatchEvent::Reattached => {
+                return Wait::Changed(vec![PathChange {
+                    path: root.to_path_buf(),
+                    kind: ChangeKind::Modified,
+                }])
+            }
+            WatchEvent::Error(e) => tracing::warn!(error = %e, "watcher error"),
+        }
+    }
+    Wait::WatcherStopped
+}
+
+/// How `--stream` prints changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// `<time>\t<kind>\t<path>` lines.
+    Text,
+    /// One JSON object per line: `{"time":…,"kind":…,"path":…}`.
+    Ndjson,
+}
+
+impl std::str::FromStr for StreamFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(StreamFormat::Text),
+            "ndjson" | "json" => Ok(StreamFormat::Ndjson),
+            other => Err(format!(
+                "invalid stream format '{other}' (expected text or ndjson)"
+            )),
+        }
+    }
+}
+
+/// Why a stream ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEnd {
+    RootDeleted,
+    /// The watcher's channel closed.
+    WatcherStopped,
+}
+
+/// Print every change from `events` to a path the tree would show, one
+/// line each, until the root is deleted or the watcher stops. The root's
+/// deletion and re-attachment are printed too, for the root itself.
+pub fn stream(
+    root: &Path,
+    config: &TreeConfig,
+    events: &Receiver<WatchEvent>,
+    format: StreamFormat,
+    out: &mut impl Write,
+) -> io::Result<StreamEnd> {
+    for event in events {
+        let time = rfc3339(SystemTime::now());
+        match event {
+            WatchEvent::Changed(changes) => {
+                for change in visible_changes(root, config, changes) {
+                    let kind = kind_name(change.kind);
+                    writeln!(out, "{}", stream_line(format, &time, kind, &change.path))?;
+                }
+            }
+            WatchEvent::RootDeleted => {
+                writeln!(out, "{}", stream_line(format, &time, "removed", root))?;
+                return Ok(StreamEnd::RootDeleted);
+            }
+            WatchEvent::Reattached => {
+                writeln!(out, "{}", stream_line(format, &time, "reattached", root))?;
+            }
+            WatchEvent::Error(e) => tracing::warn!(error = %e, "watcher error"),
+        }
+        out.flush()?;
+    }
+    Ok(StreamEnd::WatcherStopped)
+}
+
+/// One line of `--stream` output.
+pub fn stream_line(format: StreamFormat, time: &str, kind: &str, path: &Path) -> String {
+    let path = paths::strip_verbatim(path);
+    match format {
+        StreamFormat::Text => format!("{time}\t{kind}\t{}", path.display()),
+        StreamFormat::Ndjson => serde_json::to_string(&StreamRecord {
+            time,
+            kind,
+            path: path.to_string_lossy(),
+        })
+        .expect("stream lines always serialize"),
+    }
+}
+
+/// One line of `--stream=ndjson` output.
+#[derive(serde::Serialize)]
+struct StreamRecord<'a> {
+    time: &'a str,
+    kind: &'a str,
+    path: Cow<'a, str>,
+}
+
+fn kind_name(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Created => "created",
+        ChangeKind::Modified => "modified",
+        ChangeKind::Metadata => "metadata",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Renamed => "renamed",
+    }
+}
+
+/// `time` in UTC as RFC 3339 with milliseconds, e.g.
+/// `2024-05-01T12:31:05.120Z`.
+pub fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let of_day = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Year, month, and day of the `days`th day after 1970-01-01 (Howard
+/// Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The `changes` to paths the tree would show, sorted by path, with one
+/// entry per path carrying the last kind reported for it.
+pub fn visible_changes(
+    root: &Path,
+    config: &TreeConfig,
+    changes: Vec<PathChange>,
+) -> Vec<PathChange> {
+    let mut latest = BTreeMap::new();
+    for change in changes {
+        if is_visible(root, &change.path, config) {
+            latest.insert(change.path, change.kind);
+        }
+    }
+    latest
+        .into_iter()
+        .map(|(path, kind)| PathChange { path, kind })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::build_ignore_set;
+    use std::path::PathBuf;
+
+    fn change(path: &str, kind: ChangeKind) -> PathChange {
+        PathChange {
+            path: PathBuf::from(path),
+            kind,
+        }
+    }
+
+    fn config(ignore: &[&str]) -> TreeConfig {
+        let ignore: Vec<String> = ignore.iter().map(|p| p.to_string()).collect();
+        TreeConfig {
+            max_depth: None,
+            show_hidden: false,
+            dirs_only: false,
+            follow_symlinks: false,
+            ignore_patterns: build_ignore_set(&ignore),
+            max_entries: None,
+            stat: false,
+        }
+    }
+
+    #[test]
+    fn hidden_and_ignored_changes_are_dropped() {
+        let config = config(&["target"]);
+        let changes = vec![
+            change("/w/src/b.rs", ChangeKind::Created),
+            change("/w/target/debug/x", ChangeKind::Modified),
+            change("/w/.cache/y", ChangeKind::Modified),
+            change("/w/src/a.rs", ChangeKind::Modified),
+            change("/w/src/b.rs", ChangeKind::Modified),
+            change("/elsewhere/c.rs", ChangeKind::Modified),
+        ];
+        assert_eq!(
+            visible_changes(Path::new("/w"), &config, changes),
+            [
+                change("/w/src/a.rs", ChangeKind::Modified),
+                change("/w/src/b.rs", ChangeKind::Modified),
+            ]
+        );
+    }
+
+    #[test]
+    fn timestamps_are_rfc3339_utc() {
+        let at = |secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(secs);
+        assert_eq!(rfc3339(at(0)), "1970-01-01T00:00:00.000Z");
+        assert_eq!(rfc3339(at(951_827_696_789)), "2000-02-29T12:34:56.789Z");
+        assert_eq!(rfc3339(at(1_735_689_599_000)), "2024-12-31T23:59:59.000Z");
+    }
+
+    #[test]
+    fn stream_prints_a_line_per_shown_change() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        tx.send(WatchEvent::Changed(vec![
+            change("/w/b \"q\".txt", ChangeKind::Created),
+            change("/w/.git/HEAD", ChangeKind::Modified),
+            change("/w/a.txt", ChangeKind::Removed),
+        ]))
+        .unwrap();
+        tx.send(WatchEvent::RootDeleted).unwrap();
+        let mut out = Vec::new();
+        let end = stream(
+            Path::new("/w"),
+            &config(&[]),
+            &rx,
+            StreamFormat::Ndjson,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(end, StreamEnd::RootDeleted);
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().map(|l| &l[l.find(",").unwrap()..]).collect();
+        assert_eq!(
+            lines,
+            [
+                r#","kind":"removed","path":"/w/a.txt"}"#,
+                r#","kind":"created","path":"/w/b \"q\".txt"}"#,
+                r#","kind":"removed","path":"/w"}"#,
+            ]
+        );
+        assert_eq!(
+            stream_line(StreamFormat::Text, "T", "created", Path::new("/w/a b")),
+            "T\tcreated\t/w/a b"
+        );
+    }
+
+    #[test]
+    fn waiting_skips_batches_with_nothing_shown() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        tx.send(WatchEvent::Changed(vec![change(
+            "/w/.git/index",
+            ChangeKind::Modified,
+        )]))
+        .unwrap();
+        tx.send(WatchEvent::Error("boom".into())).unwrap();
+        tx.send(WatchEvent::Changed(vec![change(
+            "/w/a.txt",
+            ChangeKind::Created,
+        )]))
+        .unwrap();
+        let config = config(&[]);
+        assert_eq!(
+            wait_for_change(Path::new("/w"), &config, &rx),
+            Wait::Changed(vec![change("/w/a.txt", ChangeKind::Created)])
+        );
+        drop(tx);
+        assert_eq!(
+            wait_for_change(Path::new("/w"), &config, &rx),
+            Wait::WatcherStopped
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Searching the tree (`/`, or `find` in the palette): fuzzy matching of
+//! the query against each entry's path below the root, scored the way fzf
+//! scores it, kept up to date as the tree changes and stepped through with
+//! `n` and `N`.
+//!
+//! The query's characters must appear in the path in order, ignoring case,
+//! so `srcmnrs` matches `src/main.rs`. Matches score higher when their
+//! characters are consecutive or start words (after `/`, `_`, `-`, `.`, or
+//! a space, or at a lower-to-upper case change), and lower for every
+//! character skipped between them.
+
+use crate::tree::TreeEntry;
+
+/// Points for each matched character.
+const SCORE_MATCH: i64 = 16;
+/// Penalty for the first character skipped between two matches, and for
+/// each one after it.
+const GAP_START: i64 = -3;
+const GAP_EXTENSION: i64 = -1;
+/// Bonus for a match at the start of a word; more after a path separator.
+const BONUS_BOUNDARY: i64 = SCORE_MATCH / 2;
+const BONUS_SEPARATOR: i64 = BONUS_BOUNDARY + 1;
+/// Bonus for a match at a lower-to-upper case change (`camelCase`).
+const BONUS_CAMEL: i64 = BONUS_BOUNDARY - 1;
+/// Least bonus for a match right after another.
+const BONUS_CONSECUTIVE: i64 = -(GAP_START + GAP_EXTENSION);
+/// The first character's bonus counts this many times.
+const FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// How well a query matched a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    /// Byte offsets of the matched characters in the text.
+    pub positions: Vec<usize>,
+}
+
+/// Match `query` against `text`: the shortest stretch of `text` holding
+/// the query's characters in order, found by scanning forward for the
+/// first occurrence and back from its end, like fzf's first algorithm.
+pub fn fuzzy_match(text: &str, query: &str) -> Option<Match> {
+    let query: Vec<char> = query.chars().map(fold).collect();
+    if query.is_empty() {
+        return None;
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let folded: Vec<char> = chars.iter().map(|&(_, c)| fold(c)).collect();
+
+    // Forward: where the first occurrence of the whole query ends.
+    let mut next = 0;
+    let mut end = None;
+    for (i, &c) in folded.iter().enumerate() {
+        if c == query[next] {
+            next += 1;
+            if next == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    // Backward: the latest start that still holds the query.
+    let mut start = end;
+    let mut remaining = query.len();
+    for i in (0..=end).rev() {
+        if folded[i] == query[remaining - 1] {
+            remaining -= 1;
+            if remaining == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    let mut positions = Vec::with_capacity(query.len());
+    let mut in_gap = false;
+    // Length of the current run of matches, and the bonus it started with.
+    let mut run = 0;
+    let mut run_bonus = 0;
+    for i in start..=end {
+        if positions.len() < query.len() && folded[i] == query[positions.len()] {
+            let before = i.checked_sub(1).map(|j| chars[j].1);
+            let mut bonus = boundary_bonus(before, chars[i].1);
+            if run == 0 {
+                run_bonus = bonus;
+            } else {
+                // A run keeps the bonus of the word start it began at.
+                if bonus >= BONUS_BOUNDARY && bonus > run_bonus {
+                    run_bonus = bonus;
+                }
+                bonus = bonus.max(run_bonus).max(BONUS_CONSECUTIVE);
+            }
+            if positions.is_empty() {
+                bonus *= FIRST_CHAR_MULTIPLIER;
+            }
+            score += SCORE_MATCH + bonus;
+            positions.push(chars[i].0);
+            in_gap = false;
+            run += 1;
+        } else {
+            score += if in_gap { GAP_EXTENSION } else { GAP_START };
+            in_gap = true;
+            run = 0;
+            run_bonus = 0;
+        }
+    }
+    Some(Match { score, positions })
+}
+
+/// `c` in lower case, one character for one.
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Bonus for a match on `c` coming right after `before`.
+fn boundary_bonus(before: Option<char>, c: char) -> i64 {
+    match before {
+        None => BONUS_SEPARATOR,
+        Some('/' | '\\') => BONUS_SEPARATOR,
+        Some('_' | '-' | '.' | ' ') => BONUS_BOUNDARY,
+        Some(b) if b.is_lowercase() && c.is_uppercase() => BONUS_CAMEL,
+        Some(b) if !b.is_alphanumeric() && c.is_alphanumeric() => BONUS_BOUNDARY,
+        _ => 0,
+    }
+}
+
+/// The current search and the rows it matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    query: String,
+    /// Matching rows, in tree order.
+    rows: Vec<usize>,
+    /// The score of each row in `rows`.
+    scores: Vec<i64>,
+}
+
+impl Search {
+    /// A search for `query` among `entries`.
+    pub fn new(query: &str, entries: &[TreeEntry]) -> Self {
+        let mut search = Self {
+            query: query.to_string(),
+            ..Self::default()
+        };
+        search.refresh(entries);
+        search
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Matching rows, in tree order.
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+
+    /// Matching rows, best match first; equal scores stay in tree order.
+    pub fn ranked(&self) -> Vec<usize> {
+        let mut ranked: Vec<(usize, i64)> = self
+            .rows
+            .iter()
+            .copied()
+            .zip(self.scores.iter().copied())
+            .collect();
+        ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        ranked.into_iter().map(|(row, _)| row).collect()
+    }
+
+    /// Match the query against `entries` again, after the tree changed.
+    pub fn refresh(&mut self, entries: &[TreeEntry]) {
+        (self.rows, self.scores) = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| Some((i, fuzzy_match(&e.relative_path(), &self.query)?.score)))
+            .unzip();
+    }
+
+    /// The first match after (or before) row `from`, wrapping around the
+    /// ends of the tree.
+    pub fn next(&self, from: usize, forward: bool) -> Option<usize> {
+        if forward {
+            let after = self.rows.iter().find(|&&row| row > from);
+            after.or(self.rows.first()).copied()
+        } else {
+            let before = self.rows.iter().rev().find(|&&row| row < from);
+            before.or(self.rows.last()).copied()
+        }
+    }
+
+    /// Which match `row` is (1-based; the last one above it if it isn't
+    /// one) and how many there are, as shown in the status bar.
+    pub fn position(&self, row: usize) -> (usize, usize) {
+        (self.rows.partition_point(|&r| r <= row), self.rows.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(text: &str, query: &str) -> Option<Vec<usize>> {
+        fuzzy_match(text, query).map(|m| m.positions)
+    }
+
+    #[test]
+    fn queries_match_in_order_ignoring_case() {
+        assert_eq!(
+            positions("src/main.rs", "srcmnrs"),
+            Some(vec![0, 1, 2, 4, 7, 9, 10])
+        );
+        assert_eq!(positions("Cargo.TOML", "toml"), Some(vec![6, 7, 8, 9]));
+        assert_eq!(positions("ÉTÉ.txt", "été"), Some(vec![0, 2, 3]));
+        assert_eq!(positions("main.rs", "sr"), None);
+        assert_eq!(positions("main.rs", ""), None);
+        // The stretch is cut short from the end of the first occurrence.
+        assert_eq!(positions("aab", "ab"), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn word_starts_and_runs_score_higher() {
+        let score = |text| fuzzy_match(text, "mr").unwrap().score;
+        assert!(score("main_rs") > score("mirror"));
+        assert!(score("src/mr") > score("samr"));
+        let score = |text| fuzzy_match(text, "main").unwrap().score;
+        assert!(score("main.rs") > score("m_a_i_n"));
+        assert!(score("src/main.rs") > score("domain.rs"));
+    }
+
+    #[test]
+    fn matches_are_stepped_through_in_tree_order_and_ranked_by_score() {
+        let search = Search {
+            query: "x".to_string(),
+            rows: vec![2, 5, 9],
+            scores: vec![10, 30, 10],
+        };
+        assert_eq!(search.next(2, true), Some(5));
+        assert_eq!(search.next(9, true), Some(2));
+        assert_eq!(search.next(0, false), Some(9));
+        assert_eq!(search.position(5), (2, 3));
+        assert_eq!(search.position(1), (0, 3));
+        assert_eq!(search.ranked(), [5, 2, 9]);
+    }
+}
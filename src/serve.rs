@@ -0,0 +1,451 @@
+//! Sharing a live tree over a socket (`--serve`) and viewing it from
+//! another terminal or machine (`--connect`).
+//!
+//! The protocol is newline-delimited JSON, sent by the server only. A
+//! client gets the whole tree when it connects, then a patch (see `patch`)
+//! each time the tree changes, and `deleted` if the root is removed, after
+//! which the server exits:
+//!
+//! ```text
+//! {"type":"snapshot","root":"/srv/app","snapshot":{"entries":[…],"total_entries":3}}
+//! {"type":"patch","ops":[{"op":"add","path":"/entries/1","value":{…}}]}
+//! {"type":"deleted"}
+//! ```
+//!
+//! Messages of other types may be added later; clients skip them.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
+
+use crate::patch::{self, PatchOp};
+use crate::script::StreamEnd;
+use crate::tree::{TreeBuilder, TreeConfig, TreeSnapshot};
+use crate::watcher::{ChangeKind, PathChange, WatchEvent};
+
+/// How long a client may take to read a message before it is dropped, so
+/// one stalled viewer cannot hold up the others.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where a server listens: `host:port` for TCP, or a path (anything with a
+/// `/` or without a `:`) for a Unix socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            Err("invalid socket '' (expected host:port or a path)".to_string())
+        } else if s.contains('/') || !s.contains(':') {
+            Ok(Endpoint::Unix(PathBuf::from(s)))
+        } else {
+            Ok(Endpoint::Tcp(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Tcp(addr) => f.write_str(addr),
+            Endpoint::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A connected client or server, readable and writable.
+pub(crate) trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+enum Socket {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+/// A bound socket. A Unix socket file is removed again when the listener
+/// that bound it is dropped.
+pub(crate) struct Listener {
+    socket: Socket,
+    file: Option<PathBuf>,
+}
+
+impl Listener {
+    /// Listen on `endpoint`. A leftover Unix socket file that nothing is
+    /// listening on any more is replaced; any other file is left alone.
+    pub(crate) fn bind(endpoint: &Endpoint) -> Result<Self, String> {
+        let error = |e: io::Error| format!("cannot listen on {endpoint}: {e}");
+        match endpoint {
+            Endpoint::Tcp(addr) => Ok(Listener {
+                socket: Socket::Tcp(TcpListener::bind(addr).map_err(error)?),
+                file: None,
+            }),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                use std::os::unix::fs::FileTypeExt;
+                use std::os::unix::net::{UnixListener, UnixStream};
+                if let Ok(meta) = path.symlink_metadata() {
+                    if !meta.file_type().is_socket() {
+                        return Err(format!(
+                            "cannot listen on {endpoint}: it exists and is not a socket"
+                        ));
+                    }
+                    if UnixStream::connect(path).is_ok() {
+                        return Err(format!("{endpoint} is in use by another process"));
+                    }
+                    std::fs::remove_file(path).map_err(error)?;
+                }
+                Ok(Listener {
+                    socket: Socket::Unix(UnixListener::bind(path).map_err(error)?),
+                    file: Some(path.clone()),
+                })
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => Err(format!(
+                "cannot listen on {endpoint}: Unix sockets are not supported on this platform"
+            )),
+        }
+    }
+
+    /// Let only the owner connect to a Unix socket (mode 0600). TCP
+    /// sockets are left as they are.
+    pub(crate) fn restrict_to_owner(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        if let Some(file) = &self.file {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(file, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    /// Another handle to the same socket, e.g. to accept on another thread.
+    /// Only the original removes the socket file.
+    pub(crate) fn try_clone(&self) -> io::Result<Listener> {
+        let socket = match &self.socket {
+            Socket::Tcp(l) => Socket::Tcp(l.try_clone()?),
+            #[cfg(unix)]
+            Socket::Unix(l) => Socket::Unix(l.try_clone()?),
+        };
+        Ok(Listener { socket, file: None })
+    }
+
+    /// Wait for the next client.
+    pub(crate) fn accept(&self) -> io::Result<Box<dyn Stream>> {
+        match &self.socket {
+            Socket::Tcp(l) => {
+                let (stream, _) = l.accept()?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Socket::Unix(l) => {
+                let (stream, _) = l.accept()?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+}
+
+/// A bound socket, ready to serve a tree.
+pub struct Server {
+    listener: Listener,
+}
+
+impl Server {
+    /// Listen on `endpoint`. A leftover Unix socket file that nothing is
+    /// listening on any more is replaced (any other file is an error), and
+    /// the socket file is removed again when the server is dropped.
+    pub fn bind(endpoint: &Endpoint) -> Result<Self, String> {
+        Listener::bind(endpoint).map(|listener| Server { listener })
+    }
+
+    /// Serve the tree at `root` until the root is deleted or the watcher
+    /// stops: build it with `builder`, send it to every client that
+    /// connects, and rebuild it and send the patch after every event.
+    pub fn run(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        builder: &dyn TreeBuilder,
+        events: &Receiver<WatchEvent>,
+    ) -> Result<StreamEnd, String> {
+        let hub = Arc::new(Mutex::new(Hub {
+            root: root.to_path_buf(),
+            snapshot: builder.build_tree(root, config),
+            clients: Vec::new(),
+        }));
+        let listener = self
+            .listener
+            .try_clone()
+            .map_err(|e| format!("cannot accept clients: {e}"))?;
+        {
+            let hub = Arc::clone(&hub);
+            thread::spawn(move || loop {
+                match listener.accept() {
+                    Ok(client) => hub.lock().unwrap().add(client),
+                    Err(e) => tracing::warn!(error = %e, "cannot accept client"),
+                }
+            });
+        }
+        for event in events {
+            match event {
+                WatchEvent::Changed(_) | WatchEvent::Reattached => {
+                    let snapshot = builder.build_tree(root, config);
+                    hub.lock().unwrap().publish(snapshot);
+                }
+                WatchEvent::RootDeleted => {
+                    hub.lock().unwrap().broadcast(&encode(&Message::Deleted));
+                    return Ok(StreamEnd::RootDeleted);
+                }
+                WatchEvent::Error(e) => tracing::warn!(error = %e, "watcher error"),
+            }
+        }
+        Ok(StreamEnd::WatcherStopped)
+    }
+}
+
+/// The served tree and its clients, behind one lock so a client that
+/// connects during a rebuild gets either the old tree and the patch, or
+/// the new tree and no patch.
+struct Hub {
+    root: PathBuf,
+    snapshot: TreeSnapshot,
+    clients: Vec<Box<dyn Stream>>,
+}
+
+impl Hub {
+    fn add(&mut self, mut client: Box<dyn Stream>) {
+        let hello = encode(&Message::Snapshot {
+            root: self.root.to_string_lossy(),
+            snapshot: Cow::Borrowed(&self.snapshot),
+        });
+        if send(&mut client, &hello).is_ok() {
+            self.clients.push(client);
+        }
+    }
+
+    fn publish(&mut self, snapshot: TreeSnapshot) {
+        let ops = patch::diff(&self.snapshot, &snapshot);
+        self.snapshot = snapshot;
+        if !ops.is_empty() {
+            self.broadcast(&encode(&Message::Patch {
+                ops: Cow::Borrowed(&ops),
+            }));
+        }
+    }
+
+    /// Send `message` to every client, dropping those that are gone.
+    fn broadcast(&mut self, message: &str) {
+        self.clients
+            .retain_mut(|client| send(client, message).is_ok());
+    }
+}
+
+/// A message from the server: one JSON object per line, told apart by
+/// `"type"`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Message<'a> {
+    /// The whole tree, sent once to each new client.
+    Snapshot {
+        root: Cow<'a, str>,
+        snapshot: Cow<'a, TreeSnapshot>,
+    },
+    /// Changes since the last message.
+    Patch { ops: Cow<'a, [PatchOp]> },
+    /// The root was deleted; nothing follows.
+    Deleted,
+    /// A message from a newer server, which is skipped.
+    #[serde(other, skip_serializing)]
+    Unknown,
+}
+
+fn encode(message: &Message) -> String {
+    serde_json::to_string(message).expect("messages always serialize") + "\n"
+}
+
+fn send(client: &mut Box<dyn Stream>, message: &str) -> io::Result<()> {
+    client.write_all(message.as_bytes())?;
+    client.flush()
+}
+
+/// A tree received from a server, kept up to date by a reader thread. As a
+/// `TreeBuilder` it returns the latest tree whatever it is asked for: the
+/// server has already applied its own filters.
+pub struct Connection {
+    root: PathBuf,
+    snapshot: Arc<Mutex<TreeSnapshot>>,
+}
+
+impl Connection {
+    /// Connect to the server at `endpoint` and read the tree it sends.
+    /// Each patch after that is applied, then reported on the returned
+    /// channel as the changes it made; the channel closes when the server
+    /// goes away or sends something that cannot be applied.
+    pub fn open(endpoint: &Endpoint) -> Result<(Connection, Receiver<WatchEvent>), String> {
+        let error = |e: &dyn fmt::Display| format!("cannot connect to {endpoint}: {e}");
+        let stream: Box<dyn Read + Send> = match endpoint {
+            Endpoint::Tcp(addr) => Box::new(TcpStream::connect(addr).map_err(|e| error(&e))?),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                Box::new(std::os::unix::net::UnixStream::connect(path).map_err(|e| error(&e))?)
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => {
+                return Err(error(&"Unix sockets are not supported on this platform"))
+            }
+        };
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|e| error(&e))?;
+        let Message::Snapshot { root, snapshot } = message(&line).map_err(|e| error(&e))? else {
+            return Err(error(&"the server did not send a tree"));
+        };
+        let root = PathBuf::from(root.into_owned());
+        let snapshot = Arc::new(Mutex::new(snapshot.into_owned()));
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        {
+            let snapshot = Arc::clone(&snapshot);
+            thread::spawn(move || {
+                for line in reader.lines() {
+                    let event = match line.map_err(|e| e.to_string()).and_then(|l| {
+                        let mut snapshot = snapshot.lock().unwrap();
+                        receive(&l, &mut snapshot)
+                    }) {
+                        Ok(Some(event)) => event,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            tracing::warn!(error = %e, "connection closed");
+                            break;
+                        }
+                    };
+                    let last = matches!(event, WatchEvent::RootDeleted);
+                    if tx.send(event).is_err() || last {
+                        break;
+                    }
+                }
+            });
+        }
+        Ok((Connection { root, snapshot }, rx))
+    }
+
+    /// The root directory on the server.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl TreeBuilder for Connection {
+    fn build_tree(&self, _root: &Path, _config: &TreeConfig) -> TreeSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+}
+
+fn message(line: &str) -> Result<Message<'static>, String> {
+    serde_json::from_str(line).map_err(|e| format!("invalid message: {e}"))
+}
+
+/// Apply one message from the server to `snapshot`, returning the event it
+/// amounts to, if any.
+fn receive(line: &str, snapshot: &mut TreeSnapshot) -> Result<Option<WatchEvent>, String> {
+    match message(line)? {
+        Message::Patch { ops } => Ok(Some(WatchEvent::Changed(apply_changes(snapshot, &ops)?))),
+        Message::Deleted => Ok(Some(WatchEvent::RootDeleted)),
+        Message::Snapshot { .. } | Message::Unknown => Ok(None),
+    }
+}
+
+/// Apply `ops` to `snapshot`, listing the paths they added, removed, and
+/// replaced.
+fn apply_changes(snapshot: &mut TreeSnapshot, ops: &[PatchOp]) -> Result<Vec<PathChange>, String> {
+    let mut changes = Vec::new();
+    for op in ops {
+        let change = |path: &Path, kind| PathChange {
+            path: path.to_path_buf(),
+            kind,
+        };
+        match op {
+            PatchOp::Add { entry, .. } => changes.push(change(&entry.path, ChangeKind::Created)),
+            PatchOp::Replace { entry, .. } => {
+                changes.push(change(&entry.path, ChangeKind::Modified))
+            }
+            PatchOp::Remove { index } => {
+                if let Some(entry) = snapshot.entries.get(*index) {
+                    changes.push(change(&entry.path, ChangeKind::Removed));
+                }
+            }
+            PatchOp::SetTotal(_) => {}
+        }
+        patch::try_apply(snapshot, std::slice::from_ref(op))?;
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_unix_sockets_and_host_ports_are_tcp() {
+        let parse = |s: &str| s.parse::<Endpoint>().unwrap();
+        assert_eq!(
+            parse("localhost:7070"),
+            Endpoint::Tcp("localhost:7070".into())
+        );
+        assert_eq!(parse("live.sock"), Endpoint::Unix("live.sock".into()));
+        assert_eq!(parse("/tmp/a:b"), Endpoint::Unix("/tmp/a:b".into()));
+        assert!("".parse::<Endpoint>().is_err());
+    }
+
+    #[test]
+    fn patches_become_changes_and_bad_ones_are_refused() {
+        let mut snapshot = TreeSnapshot::default();
+        let added = r#"{"type":"patch","ops":[
+            {"op":"add","path":"/entries/0","value":{"name":"a","path":"/srv/a",
+             "depth":1,"is_dir":false,"is_symlink":false,"symlink_target":null,
+             "is_last":true,"prefix":"└── ","error":null,"mode":null,"owner":null}},
+            {"op":"replace","path":"/total_entries","value":1}]}"#
+            .replace('\n', "");
+        let Some(WatchEvent::Changed(changes)) = receive(&added, &mut snapshot).unwrap() else {
+            panic!("a patch is a change");
+        };
+        assert_eq!(changes[0].path, PathBuf::from("/srv/a"));
+        assert_eq!(changes[0].kind, ChangeKind::Created);
+        assert_eq!(snapshot.total_entries, 1);
+
+        let removal = r#"{"type":"patch","ops":[{"op":"remove","path":"/entries/0"}]}"#;
+        let Some(WatchEvent::Changed(changes)) = receive(removal, &mut snapshot).unwrap() else {
+            panic!("a patch is a change");
+        };
+        assert_eq!(changes[0].kind, ChangeKind::Removed);
+        assert!(receive(removal, &mut snapshot).is_err());
+        assert!(receive(r#"{"type":"later"}"#, &mut snapshot)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! Split view (`--split`): a second directory watched beside the first and
+//! drawn in its own pane, with its own selection and scroll position.
+//! The focused pane takes the movement keys; `Tab` switches focus.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Receiver;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+
+use crate::action::Action;
+use crate::config::HighlightConfig;
+use crate::highlight::{inherit_to_ancestors, HighlightKind, HighlightTracker};
+use crate::render::{compare_span, tree_to_lines, RenderConfig};
+use crate::tree::{build_tree, CompareSide, Difference, TreeConfig, TreeSnapshot};
+use crate::watcher::WatchEvent;
+
+/// The second tree of split view.
+pub struct SplitPane {
+    root: PathBuf,
+    /// Events from the watcher on `root`.
+    events: Receiver<WatchEvent>,
+    /// Cached snapshot; dropped on changes so the next frame rebuilds it.
+    snapshot: Option<TreeSnapshot>,
+    /// Set once `root` is deleted; the last snapshot stays on screen.
+    deleted: bool,
+    /// Index of the selected entry (clamped when drawn).
+    selected: usize,
+    /// First row shown.
+    offset: usize,
+    /// Directories whose contents are hidden.
+    collapsed: HashSet<PathBuf>,
+    /// Recent changes below `root`.
+    changes: HighlightTracker,
+    /// Whether keys move this pane's selection instead of the main tree's.
+    pub focused: bool,
+}
+
+impl SplitPane {
+    pub fn new(root: PathBuf, events: Receiver<WatchEvent>) -> Self {
+        Self {
+            root,
+            events,
+            snapshot: None,
+            deleted: false,
+            selected: 0,
+            offset: 0,
+            collapsed: HashSet::new(),
+            changes: HighlightTracker::default(),
+            focused: false,
+        }
+    }
+
+    /// The directory shown in this pane.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The watcher's events, to wait on alongside the main tree's.
+    pub fn events(&self) -> Receiver<WatchEvent> {
+        self.events.clone()
+    }
+
+    /// Apply an event from the watcher on `root`.
+    pub fn handle_watch_event(&mut self, event: WatchEvent, styles: &HighlightConfig) {
+        if self.deleted {
+            return;
+        }
+        match event {
+            WatchEvent::Changed(changes) => {
+                self.snapshot = None;
+                let now = Instant::now();
+                for change in changes {
+                    let kind = HighlightKind::classify(change.kind, change.path.is_dir());
+                    let style = styles.for_change(change.kind);
+                    self.changes.insert_styled(change.path, now, kind, style);
+                }
+            }
+            WatchEvent::RootDeleted => self.deleted = true,
+            WatchEvent::Reattached => self.snapshot = None,
+            // Logged by the watcher.
+            WatchEvent::Error(_) => {}
+        }
+    }
+
+    /// How long changes stay highlighted.
+    pub fn set_highlight_duration(&mut self, duration: Duration) {
+        self.changes.set_duration(duration);
+    }
+
+    /// When the highlights next fade or expire.
+    pub fn next_change(&self, now: Instant) -> Option<Instant> {
+        self.changes.next_change(now)
+    }
+
+    /// The tree as of the last change, built with `config` if needed.
+    pub fn snapshot(&mut self, config: &TreeConfig) -> &TreeSnapshot {
+        self.compare_side(config).snapshot
+    }
+
+    /// This pane's tree for comparing with the main one.
+    pub fn compare_side(&mut self, config: &TreeConfig) -> CompareSide<'_> {
+        let (root, collapsed) = (&self.root, &self.collapsed);
+        let snapshot = self.snapshot.get_or_insert_with(|| {
+            let mut snapshot = build_tree(root, config);
+            snapshot.collapse(collapsed);
+            snapshot
+        });
+        CompareSide {
+            root,
+            snapshot,
+            unlisted: collapsed,
+        }
+    }
+
+    /// Rebuild on the next frame, e.g. after the tree options changed.
+    pub fn invalidate(&mut self) {
+        if !self.deleted {
+            self.snapshot = None;
+        }
+    }
+
+    /// Apply a movement action to this pane's selection, `page` rows at a
+    /// time for page keys. Returns false for actions that are not about
+    /// moving, which the main tree handles.
+    pub fn navigate(&mut self, action: Action, page: usize) -> bool {
+        let page = page.max(1) as isize;
+        match action {
+            Action::MoveUp => self.move_selection(-1),
+            Action::MoveDown => self.move_selection(1),
+            Action::PageUp => self.move_selection(-page),
+            Action::PageDown => self.move_selection(page),
+            Action::Scroll(delta) => self.move_selection(delta),
+            Action::First => self.selected = 0,
+            Action::Last => self.selected = usize::MAX,
+            Action::Select(index) => self.selected = index,
+            Action::ToggleCollapse => self.toggle_collapsed(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Select the entry drawn `row` rows below the top of the pane.
+    pub fn select_row(&mut self, row: usize) {
+        let len = self.snapshot.as_ref().map_or(0, |s| s.entries.len());
+        if self.offset + row < len {
+            self.selected = self.offset + row;
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        self.selected = self.selected.saturating_add_signed(delta);
+    }
+
+    fn toggle_collapsed(&mut self) {
+        let Some(entry) = self
+            .snapshot
+            .as_ref()
+            .and_then(|s| s.entries.get(self.selected))
+            .filter(|e| e.is_dir)
+        else {
+            return;
+        };
+        let dir = entry.path.clone();
+        if !self.collapsed.remove(&dir) {
+            self.collapsed.insert(dir);
+        }
+        self.invalidate();
+    }
+
+    /// The `height` rows on screen, scrolled to keep the selection in view.
+    /// The selection is drawn reversed while the pane has the focus; with
+    /// `differences` (compare mode), each row starts with its mark.
+    pub fn lines(
+        &mut self,
+        config: &TreeConfig,
+        render_config: &RenderConfig,
+        height: usize,
+        differences: Option<&HashMap<PathBuf, Difference>>,
+    ) -> Vec<Line<'static>> {
+        let now = Instant::now();
+        let mut active = self.changes.active(now);
+        let focused = self.focused;
+        let len = self.snapshot(config).entries.len();
+        self.selected = self.selected.min(len.saturating_sub(1));
+        self.offset = self.offset.min(len.saturating_sub(height));
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if height > 0 && self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+        let (offset, selected) = (self.offset, self.selected);
+        let Some(snapshot) = self.snapshot.as_ref() else {
+            return Vec::new();
+        };
+        let window = snapshot.window(offset, height);
+        let shown: HashSet<&Path> = window.entries.iter().map(|e| e.path.as_path()).collect();
+        inherit_to_ancestors(&mut active, &self.root, |p| shown.contains(p));
+        let mut lines = tree_to_lines(window.entries, render_config, &active);
+        if let Some(line) = selected
+            .checked_sub(offset)
+            .and_then(|row| lines.get_mut(row))
+            .filter(|_| focused)
+        {
+            *line = std::mem::take(line).patch_style(Style::new().add_modifier(Modifier::REVERSED));
+        }
+        if let Some(differences) = differences {
+            for (line, entry) in lines.iter_mut().zip(window.entries) {
+                let difference = differences.get(&entry.path).copied();
+                line.spans
+                    .insert(0, compare_span(difference, render_config));
+            }
+        }
+        lines
+    }
+}
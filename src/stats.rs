@@ -0,0 +1,269 @@
+//! Performance counters shown by the debug overlay (`F12`), for tuning on
+//! large trees, and the per-path change counters behind the activity
+//! heatmap (`T`) and the top-changers panel (`C`).
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Watcher events are averaged over this window.
+pub const EVENT_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    /// Frames drawn since startup.
+    pub frames: u64,
+    /// Frames not drawn because they would have shown the same as the
+    /// one before.
+    pub skipped_frames: u64,
+    /// How long the last tree walk took.
+    pub last_build: Option<Duration>,
+    /// How long the last frame took to lay out and draw.
+    pub last_render: Option<Duration>,
+    /// Arrival time and path count of recent watcher batches.
+    events: VecDeque<(Instant, usize)>,
+}
+
+impl Stats {
+    pub fn record_frame(&mut self, took: Duration) {
+        self.frames += 1;
+        self.last_render = Some(took);
+    }
+
+    pub fn record_skipped_frame(&mut self) {
+        self.skipped_frames += 1;
+    }
+
+    pub fn record_build(&mut self, took: Duration) {
+        self.last_build = Some(took);
+    }
+
+    /// Count a watcher batch of `paths` changed paths arriving at `now`.
+    pub fn record_events(&mut self, paths: usize, now: Instant) {
+        self.events.push_back((now, paths));
+        while self
+            .events
+            .front()
+            .is_some_and(|&(at, _)| now.saturating_duration_since(at) >= EVENT_RATE_WINDOW)
+        {
+            self.events.pop_front();
+        }
+    }
+
+    /// Changed paths per second reported during `EVENT_RATE_WINDOW` before
+    /// `now`.
+    pub fn event_rate(&self, now: Instant) -> f64 {
+        let paths: usize = self
+            .events
+            .iter()
+            .filter(|&&(at, _)| now.saturating_duration_since(at) < EVENT_RATE_WINDOW)
+            .map(|&(_, paths)| paths)
+            .sum();
+        paths as f64 / EVENT_RATE_WINDOW.as_secs_f64()
+    }
+}
+
+/// A change counts half as much after this long, so the heatmap mostly
+/// reflects the last minute.
+pub const ACTIVITY_HALF_LIFE: Duration = Duration::from_secs(15);
+
+/// Number of heat levels `Activity::level` distinguishes.
+pub const HEAT_LEVELS: u8 = 4;
+
+/// Decayed change counts at which each heat level starts.
+const HEAT_THRESHOLDS: [f64; HEAT_LEVELS as usize] = [0.5, 2.0, 5.0, 12.0];
+
+/// How far back the top-changers panel counts by default.
+pub const DEFAULT_CHANGERS_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Recent changes: decaying per-path counters, where a change counts for
+/// the path and for every directory between it and the root, so busy
+/// subtrees heat up too; and the directory of each change within a window,
+/// for exact per-directory counts.
+#[derive(Debug)]
+pub struct Activity {
+    /// Score and when it was last decayed.
+    scores: HashMap<PathBuf, (f64, Instant)>,
+    /// The directory each change happened in, oldest first.
+    recent: VecDeque<(Instant, PathBuf)>,
+    /// How long changes stay in `recent`.
+    window: Duration,
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHANGERS_WINDOW)
+    }
+}
+
+impl Activity {
+    /// Counters whose per-directory counts cover the last `window`.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            scores: HashMap::new(),
+            recent: VecDeque::new(),
+            window,
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Count a change to `path` (below `root`) at `now`.
+    pub fn record(&mut self, root: &Path, path: &Path, now: Instant) {
+        if let Some(dir) = path.parent().filter(|dir| dir.starts_with(root)) {
+            self.recent.push_back((now, dir.to_path_buf()));
+        }
+        self.expire(now);
+        let paths = path
+            .ancestors()
+            .take_while(|p| *p != root && p.starts_with(root));
+        for p in paths {
+            let (score, at) = self.scores.entry(p.to_path_buf()).or_insert((0.0, now));
+            *score = decayed(*score, *at, now) + 1.0;
+            *at = now;
+        }
+    }
+
+    /// The decayed change count of `path` at `now`.
+    pub fn score(&self, path: &Path, now: Instant) -> f64 {
+        self.scores
+            .get(path)
+            .map_or(0.0, |&(score, at)| decayed(score, at, now))
+    }
+
+    /// How hot `path` is, from 0 to `HEAT_LEVELS - 1`, or `None` if it has
+    /// barely changed lately.
+    pub fn level(&self, path: &Path, now: Instant) -> Option<u8> {
+        let score = self.score(path, now);
+        HEAT_THRESHOLDS
+            .iter()
+            .rposition(|&threshold| score >= threshold)
+            .map(|level| level as u8)
+    }
+
+    /// The `n` directories with the most changes in the window before
+    /// `now`, with their counts, most changes first.
+    pub fn top_dirs(&self, n: usize, now: Instant) -> Vec<(PathBuf, usize)> {
+        let mut counts: HashMap<&Path, usize> = HashMap::new();
+        for (_, dir) in self.recent.iter().filter(|(at, _)| !self.expired(*at, now)) {
+            *counts.entry(dir).or_default() += 1;
+        }
+        let mut top: Vec<(PathBuf, usize)> = counts
+            .into_iter()
+            .map(|(dir, count)| (dir.to_path_buf(), count))
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(n);
+        top
+    }
+
+    /// When the oldest change in the window drops out of it, changing
+    /// `top_dirs`.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.recent.front().map(|&(at, _)| at + self.window)
+    }
+
+    /// Forget paths that have cooled down completely, and changes that
+    /// left the window.
+    pub fn prune(&mut self, now: Instant) {
+        self.scores
+            .retain(|_, &mut (score, at)| decayed(score, at, now) >= HEAT_THRESHOLDS[0] / 4.0);
+        self.expire(now);
+    }
+
+    pub fn clear(&mut self) {
+        self.scores.clear();
+        self.recent.clear();
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|&(at, _)| self.expired(at, now))
+        {
+            self.recent.pop_front();
+        }
+    }
+
+    fn expired(&self, at: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(at) >= self.window
+    }
+}
+
+fn decayed(score: f64, since: Instant, now: Instant) -> f64 {
+    let half_lives =
+        now.saturating_duration_since(since).as_secs_f64() / ACTIVITY_HALF_LIFE.as_secs_f64();
+    score * 0.5f64.powf(half_lives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_rate_covers_the_window() {
+        let now = Instant::now();
+        let mut stats = Stats::default();
+        stats.record_events(30, now);
+        stats.record_events(20, now + Duration::from_secs(5));
+        assert_eq!(stats.event_rate(now + Duration::from_secs(5)), 5.0);
+        assert_eq!(stats.event_rate(now + EVENT_RATE_WINDOW), 2.0);
+        assert_eq!(stats.event_rate(now + Duration::from_secs(60)), 0.0);
+    }
+
+    #[test]
+    fn activity_decays_and_rolls_up() {
+        let root = Path::new("/r");
+        let file = root.join("build/out.o");
+        let now = Instant::now();
+        let mut activity = Activity::default();
+        for _ in 0..6 {
+            activity.record(root, &file, now);
+        }
+        activity.record(root, &root.join("build/other.o"), now);
+
+        assert_eq!(activity.score(&file, now), 6.0);
+        assert_eq!(activity.score(&root.join("build"), now), 7.0);
+        assert_eq!(activity.score(root, now), 0.0);
+        assert_eq!(activity.level(&file, now), Some(2));
+        assert_eq!(activity.level(&root.join("build/other.o"), now), Some(0));
+
+        let later = now + ACTIVITY_HALF_LIFE;
+        assert_eq!(activity.score(&file, later), 3.0);
+        assert_eq!(activity.level(&file, later), Some(1));
+
+        activity.prune(now + ACTIVITY_HALF_LIFE * 10);
+        assert_eq!(activity.level(&root.join("build"), now), None);
+    }
+
+    #[test]
+    fn top_dirs_count_changes_in_the_window() {
+        let root = Path::new("/r");
+        let now = Instant::now();
+        let mut activity = Activity::new(Duration::from_secs(60));
+        activity.record(root, &root.join("a/1"), now);
+        activity.record(root, &root.join("b/1"), now);
+        activity.record(root, &root.join("b/2"), now + Duration::from_secs(30));
+        activity.record(root, &root.join("top.txt"), now + Duration::from_secs(30));
+
+        let at = now + Duration::from_secs(30);
+        assert_eq!(
+            activity.top_dirs(3, at),
+            [
+                (root.join("b"), 2),
+                (root.to_path_buf(), 1),
+                (root.join("a"), 1)
+            ]
+        );
+        assert_eq!(activity.next_expiry(), Some(now + Duration::from_secs(60)));
+
+        let later = now + Duration::from_secs(60);
+        assert_eq!(
+            activity.top_dirs(5, later),
+            [(root.to_path_buf(), 1), (root.join("b"), 1)]
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Status bar templates: a string such as
+//! `"{path} | {entries} | {last_change} | {watcher}"` parsed into literal
+//! text and fields, so the status bar can be reordered or trimmed.
+
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// The template used when none is configured; matches the classic layout.
+pub const DEFAULT_TEMPLATE: &str =
+    " Watching: {path}  |  {entries}  {search}  |  {last_change} {last_file}  {follow}";
+
+/// A value the status bar can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// The watched directory, with the home directory shown as `~`.
+    Path,
+    /// Entry count, with scroll position or truncation if relevant.
+    Entries,
+    /// `Last change: HH:MM:SS`, or `No changes yet`.
+    LastChange,
+    /// Name of the most recently changed file (clickable).
+    LastFile,
+    /// `[follow]` while follow mode is on.
+    Follow,
+    /// The filesystem notification backend (inotify, FSEvents, …).
+    Watcher,
+    /// Frames drawn during the last second.
+    Fps,
+    /// `[3/17]`: the selected search match and the number of matches.
+    Search,
+}
+
+impl Field {
+    /// Every field with its name in templates.
+    pub const ALL: [(&'static str, Field); 8] = [
+        ("path", Field::Path),
+        ("entries", Field::Entries),
+        ("last_change", Field::LastChange),
+        ("last_file", Field::LastFile),
+        ("follow", Field::Follow),
+        ("watcher", Field::Watcher),
+        ("fps", Field::Fps),
+        ("search", Field::Search),
+    ];
+}
+
+/// A piece of a parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+/// A parsed status bar template. `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct StatusTemplate {
+    segments: Vec<Segment>,
+}
+
+impl Default for StatusTemplate {
+    fn default() -> Self {
+        DEFAULT_TEMPLATE
+            .parse()
+            .expect("default status template is valid")
+    }
+}
+
+impl StatusTemplate {
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+impl FromStr for StatusTemplate {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(format!(
+                                    "unclosed '{{' in status template '{template}'"
+                                ))
+                            }
+                        }
+                    }
+                    let field = Field::ALL
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .map(|&(_, field)| field)
+                        .ok_or_else(|| {
+                            let known: Vec<&str> = Field::ALL.iter().map(|(n, _)| *n).collect();
+                            format!(
+                                "unknown status field '{{{name}}}' (known: {})",
+                                known.join(", ")
+                            )
+                        })?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => return Err(format!("unmatched '}}' in status template '{template}'")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+}
+
+impl TryFrom<String> for StatusTemplate {
+    type Error = String;
+
+    fn try_from(template: String) -> Result<Self, String> {
+        template.parse()
+    }
+}
+
+impl fmt::Display for StatusTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?,
+                Segment::Field(field) => {
+                    let name = Field::ALL.iter().find(|(_, f)| f == field).map(|(n, _)| n);
+                    write!(f, "{{{}}}", name.unwrap_or(&""))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Current values of the status bar fields.
+#[derive(Debug, Clone, Default)]
+pub struct StatusValues<'a> {
+    pub path: &'a str,
+    pub entries: &'a str,
+    /// Time of the last change.
+    pub last_change: Option<&'a str>,
+    pub last_file: Option<&'a str>,
+    pub follow: bool,
+    pub watcher: &'a str,
+    pub fps: usize,
+    /// The selected search match (1-based) and the number of matches.
+    pub search: Option<(usize, usize)>,
+}
+
+/// The `[status]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusConfig {
+    /// Status bar template; `--status-format` takes precedence.
+    pub template: Option<StatusTemplate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields_and_text() {
+        let template: StatusTemplate = "{path} | {fps} fps {{x}}".parse().unwrap();
+        assert_eq!(
+            template.segments(),
+            [
+                Segment::Field(Field::Path),
+                Segment::Text(" | ".into()),
+                Segment::Field(Field::Fps),
+                Segment::Text(" fps {x}".into()),
+            ]
+        );
+        assert_eq!(template.to_string(), "{path} | {fps} fps {{x}}");
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_stray_braces() {
+        let err = "{path} {bogus}".parse::<StatusTemplate>().unwrap_err();
+        assert!(err.contains("unknown status field '{bogus}'"), "{err}");
+        assert!("{path".parse::<StatusTemplate>().is_err());
+        assert!("path}".parse::<StatusTemplate>().is_err());
+    }
+}
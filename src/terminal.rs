@@ -1,26 +1,222 @@
 //! Terminal management via ratatui: init, restore, and size helpers.
 
-use crossterm::terminal;
-use ratatui::backend::CrosstermBackend;
+use crossterm::cursor::Show;
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
+use crossterm::terminal::{self, EnterAlternateScreen};
+use crossterm::ExecutableCommand;
+use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::Terminal;
 use std::io::{self, Stdout};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 
 /// The ratatui terminal type used throughout the application.
 pub type Term = Terminal<CrosstermBackend<Stdout>>;
 
 /// Initialize the terminal: enter alternate screen, enable raw mode,
-/// hide cursor, and install a panic hook that restores state.
+/// hide cursor, enable bracketed paste, and install a panic hook that
+/// restores state.
+///
+/// With bracketed paste, pasted text arrives as a single `Event::Paste`
+/// instead of one key press per character, so it cannot trigger bindings.
 pub fn init() -> io::Result<Term> {
     let terminal = ratatui::init();
+    io::stdout().execute(EnableBracketedPaste)?;
+    // ratatui's hook restores everything except mouse reporting and paste mode.
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = io::stdout().execute(DisableMouseCapture);
+        let _ = io::stdout().execute(DisableBracketedPaste);
+        hook(info);
+    }));
     Ok(terminal)
 }
 
+/// Make sure the console interprets escape sequences, and report whether it
+/// does. On Windows this turns on virtual terminal processing; legacy
+/// conhost (before Windows 10) cannot, and would print raw escape codes and
+/// mangle box-drawing characters. Other platforms always support them.
+pub fn enable_vt_mode() -> bool {
+    #[cfg(windows)]
+    {
+        crossterm::ansi_support::supports_ansi()
+    }
+    #[cfg(not(windows))]
+    {
+        true
+    }
+}
+
 /// Restore the terminal: exit alternate screen, disable raw mode, show cursor.
 pub fn restore() {
+    let _ = io::stdout().execute(DisableMouseCapture);
+    let _ = io::stdout().execute(DisableBracketedPaste);
     ratatui::restore();
 }
 
+/// Turn mouse event reporting on or off.
+pub fn set_mouse_capture(enabled: bool) -> io::Result<()> {
+    if enabled {
+        io::stdout().execute(EnableMouseCapture)?;
+    } else {
+        io::stdout().execute(DisableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Hand the terminal back to the shell (e.g. while an editor or a subshell
+/// runs): leave the alternate screen and raw mode, and show the cursor,
+/// which ratatui hides while drawing. Undo with `resume`.
+pub fn suspend() {
+    restore();
+    let _ = io::stdout().execute(Show);
+}
+
+/// Re-enter the TUI after `suspend`, forcing a full redraw on the next frame.
+pub fn resume<B: Backend>(term: &mut Terminal<B>, mouse_capture: bool) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    io::stdout().execute(EnableBracketedPaste)?;
+    set_mouse_capture(mouse_capture)?;
+    term.hide_cursor()?;
+    term.clear()
+}
+
+/// A signal asking livetree to stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopSignal {
+    /// SIGINT (on Windows also Ctrl+Break and closing the console).
+    Interrupt,
+    /// SIGTERM, e.g. from `kill` or a service manager.
+    Terminate,
+    /// SIGHUP: the terminal went away, e.g. its window was closed.
+    Hangup,
+}
+
+impl StopSignal {
+    const ALL: [StopSignal; 3] = [
+        StopSignal::Interrupt,
+        StopSignal::Terminate,
+        StopSignal::Hangup,
+    ];
+
+    fn code(self) -> usize {
+        self as usize + 1
+    }
+}
+
+/// The stop signal received so far, if any, shared by everything that has
+/// to wind down, and whether a SIGTSTP asked to suspend. Cloning shares
+/// the flags.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown(Arc<AtomicUsize>, Arc<AtomicBool>);
+
+impl Shutdown {
+    /// The latest stop signal received.
+    pub fn requested(&self) -> Option<StopSignal> {
+        let code = self.0.load(Ordering::SeqCst);
+        StopSignal::ALL.into_iter().find(|s| s.code() == code)
+    }
+
+    /// Ask to stop as if `signal` had been received.
+    pub fn request(&self, signal: StopSignal) {
+        self.0.store(signal.code(), Ordering::SeqCst);
+    }
+
+    /// Whether a SIGTSTP (e.g. `kill -TSTP`) arrived since the last call.
+    /// Ctrl+Z in the live view is a key press instead, since raw mode
+    /// turns off the terminal's signal keys.
+    pub fn take_suspend(&self) -> bool {
+        self.1.swap(false, Ordering::SeqCst)
+    }
+
+    /// Forget an interrupt, e.g. a Ctrl+C meant for a program run in the
+    /// foreground. Other signals are kept.
+    pub fn forgive_interrupt(&self) {
+        let _ = self.0.compare_exchange(
+            StopSignal::Interrupt.code(),
+            0,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+    }
+}
+
+/// Catch SIGINT, SIGTERM, and SIGHUP (Ctrl+C, Ctrl+Break, and console
+/// close on Windows) so they stop livetree through the same path as `q`,
+/// which restores the terminal, instead of killing it with the terminal
+/// left in raw mode. The handlers are installed on the first call; every
+/// call returns the same flag.
+pub fn shutdown_on_signals() -> Shutdown {
+    static SHUTDOWN: OnceLock<Shutdown> = OnceLock::new();
+    SHUTDOWN
+        .get_or_init(|| {
+            let shutdown = Shutdown::default();
+            if let Err(e) = install_handlers(&shutdown) {
+                tracing::warn!(error = %e, "cannot install signal handlers");
+            }
+            shutdown
+        })
+        .clone()
+}
+
+#[cfg(unix)]
+fn install_handlers(shutdown: &Shutdown) -> io::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGTSTP};
+    for (signal, stop) in [
+        (SIGINT, StopSignal::Interrupt),
+        (SIGTERM, StopSignal::Terminate),
+        (SIGHUP, StopSignal::Hangup),
+    ] {
+        signal_hook::flag::register_usize(signal, Arc::clone(&shutdown.0), stop.code())?;
+    }
+    signal_hook::flag::register(SIGTSTP, Arc::clone(&shutdown.1))?;
+    Ok(())
+}
+
+/// Whether the process can be stopped and continued by the shell's job
+/// control (`suspend_process`).
+pub const CAN_SUSPEND: bool = cfg!(unix);
+
+/// Stop the process until the shell continues it (`fg`), as Ctrl+Z does
+/// outside raw mode. Hand the terminal back with `suspend` first and take
+/// it again with `resume` after.
+#[cfg(unix)]
+pub fn suspend_process() -> io::Result<()> {
+    signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)
+}
+
+#[cfg(not(unix))]
+pub fn suspend_process() -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(unix))]
+fn install_handlers(shutdown: &Shutdown) -> io::Result<()> {
+    let shutdown = shutdown.clone();
+    ctrlc::set_handler(move || shutdown.request(StopSignal::Interrupt)).map_err(io::Error::other)
+}
+
 /// Get the current terminal size, falling back to (80, 24) if unavailable.
 pub fn terminal_size() -> (u16, u16) {
     terminal::size().unwrap_or((80, 24))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupts_are_forgiven_but_other_signals_kept() {
+        let shutdown = Shutdown::default();
+        assert_eq!(shutdown.requested(), None);
+        shutdown.request(StopSignal::Interrupt);
+        shutdown.forgive_interrupt();
+        assert_eq!(shutdown.requested(), None);
+        shutdown.request(StopSignal::Terminate);
+        shutdown.forgive_interrupt();
+        assert_eq!(shutdown.requested(), Some(StopSignal::Terminate));
+    }
+}
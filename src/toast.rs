@@ -0,0 +1,101 @@
+//! Transient notifications ("toasts") drawn in a corner of the tree pane,
+//! for errors and confirmations that must not be printed over the screen.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long an info toast stays up.
+pub const INFO_DURATION: Duration = Duration::from_secs(3);
+/// Errors stay up longer so they can be read.
+pub const ERROR_DURATION: Duration = Duration::from_secs(6);
+/// Toasts shown at once; older ones are dropped first.
+const MAX_TOASTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub text: String,
+    pub expires: Instant,
+}
+
+/// The toasts currently up, oldest first.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    items: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Show `text` for `INFO_DURATION`.
+    pub fn info(&mut self, text: impl Into<String>, now: Instant) {
+        self.push(ToastLevel::Info, text.into(), now + INFO_DURATION);
+    }
+
+    /// Show `text` as an error for `ERROR_DURATION`.
+    pub fn error(&mut self, text: impl Into<String>, now: Instant) {
+        self.push(ToastLevel::Error, text.into(), now + ERROR_DURATION);
+    }
+
+    /// A toast with the same text as one already up only extends it, so a
+    /// repeating error does not fill the corner.
+    fn push(&mut self, level: ToastLevel, text: String, expires: Instant) {
+        self.items.retain(|t| t.text != text);
+        self.items.push_back(Toast {
+            level,
+            text,
+            expires,
+        });
+        while self.items.len() > MAX_TOASTS {
+            self.items.pop_front();
+        }
+    }
+
+    /// Drop toasts that have expired by `now`.
+    pub fn prune(&mut self, now: Instant) {
+        self.items.retain(|t| t.expires > now);
+    }
+
+    /// When the next toast expires, if any are up.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.items.iter().map(|t| t.expires).min()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.items.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toasts_expire_dedupe_and_cap() {
+        let now = Instant::now();
+        let mut toasts = Toasts::default();
+        toasts.info("copied a.txt", now);
+        toasts.error("watcher: boom", now);
+        toasts.error("watcher: boom", now + Duration::from_secs(1));
+        assert_eq!(toasts.iter().count(), 2);
+        assert_eq!(toasts.next_expiry(), Some(now + INFO_DURATION));
+
+        toasts.prune(now + INFO_DURATION);
+        let left: Vec<&str> = toasts.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(left, ["watcher: boom"]);
+
+        for i in 0..5 {
+            toasts.info(format!("t{i}"), now);
+        }
+        let left: Vec<&str> = toasts.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(left, ["t2", "t3", "t4"]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Archive contents as virtual subtrees: the members of a `.zip`, `.tar`,
+//! `.tar.gz`, or `.tgz` file, laid out like a directory below the archive
+//! so they can be browsed in place. Read-only; only the member list is
+//! read, never the contents.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use super::source::{virtual_entries, EntrySource, RawEntry, VirtualEntry};
+use super::{TreeConfig, TreeSnapshot};
+
+/// Whether `path` has an archive extension livetree can list.
+pub fn is_archive(path: &Path) -> bool {
+    kind(path).is_some()
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn kind(path: &Path) -> Option<Kind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") {
+        Some(Kind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(Kind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Kind::TarGz)
+    } else {
+        None
+    }
+}
+
+/// Read the member list of the archive at `path`. Members whose paths
+/// climb out of the archive (`../x`) are left out.
+pub fn list_archive(path: &Path) -> Result<Vec<VirtualEntry>, String> {
+    let error = |e: &dyn std::fmt::Display| {
+        let name = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy();
+        format!("{name}: {e}")
+    };
+    let kind = kind(path).ok_or_else(|| error(&"not a zip or tar archive"))?;
+    let file = File::open(path).map_err(|e| error(&e))?;
+    let members = match kind {
+        Kind::Zip => {
+            let zip = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| error(&e))?;
+            zip.file_names()
+                .map(|name| (PathBuf::from(name), name.ends_with('/')))
+                .collect()
+        }
+        Kind::Tar => tar_members(BufReader::new(file)).map_err(|e| error(&e))?,
+        Kind::TarGz => tar_members(flate2::read::GzDecoder::new(BufReader::new(file)))
+            .map_err(|e| error(&e))?,
+    };
+    Ok(members
+        .into_iter()
+        .filter_map(|(path, is_dir)| {
+            let path = inner_path(&path)?;
+            Some(VirtualEntry { path, is_dir })
+        })
+        .collect())
+}
+
+fn tar_members(reader: impl Read) -> std::io::Result<Vec<(PathBuf, bool)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let is_dir = entry.header().entry_type().is_dir();
+        members.push((entry.path()?.into_owned(), is_dir));
+    }
+    Ok(members)
+}
+
+/// `path` with leading `/` and `./` dropped; `None` if it is empty or
+/// climbs out with `..`.
+fn inner_path(path: &Path) -> Option<PathBuf> {
+    let mut inner = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => inner.push(part),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            Component::ParentDir => return None,
+        }
+    }
+    (!inner.as_os_str().is_empty()).then_some(inner)
+}
+
+/// Lay out `members` as the subtree below `archive`, with depths counted
+/// from the archive (1 = top-level member) and paths joined onto it, ready
+/// for `TreeSnapshot::graft`. Directories implied by member paths are
+/// added, and siblings are sorted as the filesystem walk sorts them.
+pub fn archive_subtree(archive: &Path, members: &[VirtualEntry]) -> TreeSnapshot {
+    TreeSnapshot::from_raw(virtual_entries(archive, members), None)
+}
+
+/// Archive contents as an entry source: the root is the archive file, and
+/// its members are listed below it. An archive that cannot be read lists
+/// a single entry carrying the error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArchiveSource;
+
+impl EntrySource for ArchiveSource {
+    fn entries<'a>(
+        &'a self,
+        root: &'a Path,
+        _config: &'a TreeConfig,
+    ) -> Box<dyn Iterator<Item = RawEntry> + 'a> {
+        match list_archive(root) {
+            Ok(members) => Box::new(virtual_entries(root, &members).into_iter()),
+            Err(e) => {
+                let name = root.file_name().unwrap_or(root.as_os_str());
+                let mut entry = RawEntry::new(1, name.to_string_lossy(), root, false);
+                entry.error = Some(e);
+                Box::new(std::iter::once(entry))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::IgnoreSet;
+    use std::io::Write;
+
+    #[test]
+    fn tar_gz_members_become_a_sorted_subtree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let archive = tmp.path().join("release.tar.gz");
+        let gz = flate2::write::GzEncoder::new(
+            File::create(&archive).unwrap(),
+            flate2::Compression::fast(),
+        );
+        let mut tar = tar::Builder::new(gz);
+        for (name, data) in [("./bin/tool", "x"), ("README", "y"), ("bin/.hidden", "z")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            tar.append_data(&mut header, name, data.as_bytes()).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap().flush().unwrap();
+
+        let members = list_archive(&archive).unwrap();
+        let subtree = archive_subtree(&archive, &members);
+        let lines: Vec<String> = subtree
+            .entries
+            .iter()
+            .map(|e| format!("{}{}", e.prefix(), e.name))
+            .collect();
+        assert_eq!(
+            lines,
+            ["├── bin", "│   ├── tool", "│   └── .hidden", "└── README"]
+        );
+        assert_eq!(subtree.entries[1].path, archive.join("bin/tool"));
+        assert_eq!(subtree.entries[1].depth, 2);
+    }
+
+    #[test]
+    fn unreadable_archives_list_one_error_entry() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let archive = tmp.path().join("broken.zip");
+        std::fs::write(&archive, "not a zip").unwrap();
+        let config = TreeConfig {
+            max_depth: None,
+            show_hidden: false,
+            dirs_only: false,
+            follow_symlinks: false,
+            ignore_patterns: IgnoreSet::empty(),
+            max_entries: None,
+            stat: false,
+        };
+        let entries: Vec<RawEntry> = ArchiveSource.entries(&archive, &config).collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "broken.zip");
+        assert!(entries[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("broken.zip: "));
+    }
+
+    #[test]
+    fn escaping_members_are_dropped() {
+        assert_eq!(inner_path(Path::new("../etc/passwd")), None);
+        assert_eq!(
+            inner_path(Path::new("/abs/x")),
+            Some(PathBuf::from("abs/x"))
+        );
+        assert_eq!(inner_path(Path::new("./")), None);
+    }
+}
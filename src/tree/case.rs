@@ -0,0 +1,50 @@
+//! Names that differ only in case, which cannot coexist on case-insensitive
+//! filesystems (the default on macOS and Windows): cloning such a tree there
+//! silently loses one of them.
+
+use super::TreeEntry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Paths of entries that share their parent directory with another entry
+/// whose name is the same ignoring case, e.g. `README.md` and `readme.md`.
+pub fn case_collisions(entries: &[TreeEntry]) -> HashSet<PathBuf> {
+    let mut groups: HashMap<(Option<&Path>, String), Vec<&Path>> = HashMap::new();
+    for entry in entries {
+        groups
+            .entry((entry.path.parent(), entry.name.to_lowercase()))
+            .or_default()
+            .push(&entry.path);
+    }
+    groups
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::TreeSnapshot;
+
+    #[test]
+    fn only_siblings_collide() {
+        let snapshot = TreeSnapshot::from_outline([
+            (1, "README.md"),
+            (1, "docs"),
+            (2, "readme.md"),
+            (1, "Docs"),
+            (1, "readme.MD"),
+        ]);
+        let collisions = case_collisions(&snapshot.entries);
+        // `docs/readme.md` is in another directory; `docs` and `Docs`
+        // collide although one is a directory and the other a file.
+        let expected: HashSet<PathBuf> = ["README.md", "readme.MD", "docs", "Docs"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(collisions, expected);
+    }
+}
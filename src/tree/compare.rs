@@ -0,0 +1,169 @@
+//! Comparing two trees entry by entry, matched by their paths relative to
+//! their roots: a `diff -r` of the structure, as split view's compare mode
+//! shows it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::{TreeEntry, TreeSnapshot};
+
+/// How an entry differs from its counterpart in the other tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difference {
+    /// Nothing at the same relative path in the other tree.
+    Missing,
+    /// A file, directory, or symlink on one side and another kind of entry
+    /// on the other.
+    Kind,
+    /// Files of different sizes or modification times, or symlinks to
+    /// different targets.
+    Content,
+    /// A directory in both trees with differences below it.
+    Within,
+}
+
+/// One tree of a comparison.
+#[derive(Debug, Clone, Copy)]
+pub struct CompareSide<'a> {
+    pub root: &'a Path,
+    pub snapshot: &'a TreeSnapshot,
+    /// Directories whose contents are not listed (collapsed, or not loaded
+    /// yet): entries of the other tree below them are not reported missing.
+    pub unlisted: &'a HashSet<PathBuf>,
+}
+
+impl<'a> CompareSide<'a> {
+    /// Entries by their path relative to `root`.
+    fn index(&self) -> HashMap<&'a Path, &'a TreeEntry> {
+        self.snapshot
+            .entries
+            .iter()
+            .filter_map(|e| Some((e.path.strip_prefix(self.root).ok()?, e)))
+            .collect()
+    }
+
+    /// Whether the contents of the directory at `relative` are listed.
+    fn lists(&self, index: &HashMap<&Path, &TreeEntry>, relative: &Path) -> bool {
+        relative.as_os_str().is_empty()
+            || index
+                .get(relative)
+                .is_some_and(|dir| dir.is_dir && !self.unlisted.contains(&self.root.join(relative)))
+    }
+}
+
+/// The differing entries of both trees, by full path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comparison {
+    pub left: HashMap<PathBuf, Difference>,
+    pub right: HashMap<PathBuf, Difference>,
+}
+
+impl Comparison {
+    /// Whether the two trees look the same.
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty() && self.right.is_empty()
+    }
+}
+
+/// Compare `left` with `right`. Files in both are compared by size and
+/// modification time (to the second, which every filesystem keeps), not
+/// by content. Everything below an entry missing from the other side is
+/// missing too, and directories holding differences are marked `Within`.
+pub fn compare(left: &CompareSide<'_>, right: &CompareSide<'_>) -> Comparison {
+    let left_index = left.index();
+    let right_index = right.index();
+    let mut comparison = Comparison::default();
+    for (relative, entry) in relative_entries(left) {
+        match right_index.get(relative) {
+            Some(other) => {
+                if let Some(difference) = difference(entry, other) {
+                    comparison.left.insert(entry.path.clone(), difference);
+                    comparison.right.insert(other.path.clone(), difference);
+                }
+            }
+            None => {
+                if is_missing(relative, left, &comparison.left, right, &right_index) {
+                    comparison
+                        .left
+                        .insert(entry.path.clone(), Difference::Missing);
+                }
+            }
+        }
+    }
+    for (relative, entry) in relative_entries(right) {
+        if !left_index.contains_key(relative)
+            && is_missing(relative, right, &comparison.right, left, &left_index)
+        {
+            comparison
+                .right
+                .insert(entry.path.clone(), Difference::Missing);
+        }
+    }
+    mark_within(&mut comparison.left, left.root);
+    mark_within(&mut comparison.right, right.root);
+    comparison
+}
+
+/// Entries of `side` with their paths relative to its root, in tree order.
+fn relative_entries<'a>(
+    side: &CompareSide<'a>,
+) -> impl Iterator<Item = (&'a Path, &'a TreeEntry)> + 'a {
+    let root = side.root;
+    side.snapshot
+        .entries
+        .iter()
+        .filter_map(move |e| Some((e.path.strip_prefix(root).ok()?, e)))
+}
+
+/// Whether an entry of `side` at `relative`, with nothing there in `other`,
+/// is missing from `other` rather than just not listed there: its parent is
+/// listed in `other`, or is itself missing from it.
+fn is_missing(
+    relative: &Path,
+    side: &CompareSide<'_>,
+    found: &HashMap<PathBuf, Difference>,
+    other: &CompareSide<'_>,
+    other_index: &HashMap<&Path, &TreeEntry>,
+) -> bool {
+    let parent = relative.parent().unwrap_or(Path::new(""));
+    other.lists(other_index, parent)
+        || found.get(&side.root.join(parent)) == Some(&Difference::Missing)
+}
+
+/// How `a` differs from `b`, found at the same relative path.
+fn difference(a: &TreeEntry, b: &TreeEntry) -> Option<Difference> {
+    if a.is_dir != b.is_dir || a.is_symlink != b.is_symlink {
+        return Some(Difference::Kind);
+    }
+    if a.is_symlink {
+        return (a.symlink_target != b.symlink_target).then_some(Difference::Content);
+    }
+    if a.is_dir {
+        return None;
+    }
+    let stamp = |path: &Path| {
+        let metadata = std::fs::symlink_metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some((metadata.len(), modified.as_secs()))
+    };
+    match (stamp(&a.path), stamp(&b.path)) {
+        (Some(a), Some(b)) if a != b => Some(Difference::Content),
+        _ => None,
+    }
+}
+
+/// Mark the directories below `root` that hold a difference.
+fn mark_within(differences: &mut HashMap<PathBuf, Difference>, root: &Path) {
+    let differing: Vec<PathBuf> = differences.keys().cloned().collect();
+    for path in differing {
+        for ancestor in path.ancestors().skip(1) {
+            if ancestor == root || !ancestor.starts_with(root) {
+                break;
+            }
+            differences
+                .entry(ancestor.to_path_buf())
+                .or_insert(Difference::Within);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Directory details for the preview pane: child counts, total size,
+//! newest/oldest and largest files.
+
+use crossbeam_channel::Sender;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use super::ignore::IgnoreSet;
+use super::walk::is_excluded;
+
+/// Number of largest files kept in `DirDetails::largest`.
+const LARGEST_KEPT: usize = 5;
+
+/// Aggregated information about a directory and everything below it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirDetails {
+    /// Direct child directories.
+    pub child_dirs: usize,
+    /// Direct child files (including symlinks).
+    pub child_files: usize,
+    /// Files anywhere below the directory.
+    pub total_files: usize,
+    /// Summed size of all files below the directory, in bytes.
+    pub total_size: u64,
+    /// Most recently modified file below the directory.
+    pub newest: Option<(PathBuf, SystemTime)>,
+    /// Least recently modified file below the directory.
+    pub oldest: Option<(PathBuf, SystemTime)>,
+    /// Largest files below the directory, biggest first.
+    pub largest: Vec<(PathBuf, u64)>,
+}
+
+/// Walk `dir` with the same hidden/ignore filtering as the tree and collect
+/// its details.
+pub fn dir_details(
+    dir: &Path,
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: &IgnoreSet,
+) -> DirDetails {
+    let mut details = DirDetails::default();
+    let walker = WalkDir::new(dir)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(|entry| {
+            // Ignore patterns are relative to the watched root, not `dir`.
+            entry.depth() == 0 || !is_excluded(entry, root, show_hidden, ignore_patterns)
+        });
+
+    for entry in walker.flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        let is_dir = entry.file_type().is_dir();
+        if entry.depth() == 1 {
+            if is_dir {
+                details.child_dirs += 1;
+            } else {
+                details.child_files += 1;
+            }
+        }
+        if is_dir {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        details.total_files += 1;
+        details.total_size += meta.len();
+
+        if let Ok(modified) = meta.modified() {
+            if details.newest.as_ref().is_none_or(|(_, t)| modified > *t) {
+                details.newest = Some((entry.path().to_path_buf(), modified));
+            }
+            if details.oldest.as_ref().is_none_or(|(_, t)| modified < *t) {
+                details.oldest = Some((entry.path().to_path_buf(), modified));
+            }
+        }
+
+        details
+            .largest
+            .push((entry.path().to_path_buf(), meta.len()));
+        if details.largest.len() > LARGEST_KEPT * 4 {
+            keep_largest(&mut details.largest);
+        }
+    }
+    keep_largest(&mut details.largest);
+    details
+}
+
+/// Sort by size (descending) and keep the top `LARGEST_KEPT`.
+fn keep_largest(files: &mut Vec<(PathBuf, u64)>) {
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files.truncate(LARGEST_KEPT);
+}
+
+/// Compute details for `dir` on a background thread and send them on `tx`,
+/// tagged with `generation` so results made stale meanwhile can be dropped.
+pub fn spawn_dir_details(
+    dir: &Path,
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: IgnoreSet,
+    generation: u64,
+    tx: Sender<(u64, PathBuf, DirDetails)>,
+) {
+    let dir = dir.to_path_buf();
+    let root = root.to_path_buf();
+    thread::spawn(move || {
+        let details = dir_details(&dir, &root, show_hidden, follow_symlinks, &ignore_patterns);
+        let _ = tx.send((generation, dir, details));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn counts_sizes_and_largest() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src/nested")).unwrap();
+        fs::write(tmp.path().join("src/a.rs"), "a".repeat(10)).unwrap();
+        fs::write(tmp.path().join("src/nested/b.rs"), "b".repeat(30)).unwrap();
+        fs::write(tmp.path().join("src/c.rs"), "c".repeat(20)).unwrap();
+
+        let src = tmp.path().join("src");
+        let details = dir_details(&src, tmp.path(), false, false, &IgnoreSet::empty());
+        assert_eq!(details.child_dirs, 1);
+        assert_eq!(details.child_files, 2);
+        assert_eq!(details.total_files, 3);
+        assert_eq!(details.total_size, 60);
+        let largest: Vec<u64> = details.largest.iter().map(|(_, s)| *s).collect();
+        assert_eq!(largest, vec![30, 20, 10]);
+        assert!(details.newest.is_some() && details.oldest.is_some());
+    }
+}
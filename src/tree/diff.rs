@@ -0,0 +1,63 @@
+use super::{TreeEntry, TreeSnapshot};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Paths that differ between two snapshots, each list in display order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    /// Paths only in the newer snapshot.
+    pub added: Vec<PathBuf>,
+    /// Paths only in the older snapshot.
+    pub removed: Vec<PathBuf>,
+    /// Paths in both whose kind changed: file/directory/symlink, symlink
+    /// target, or read error.
+    pub changed: Vec<PathBuf>,
+}
+
+impl TreeDiff {
+    /// Whether the two snapshots have the same structure.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl TreeSnapshot {
+    /// What changed going from `self` to `other`, matching entries by path.
+    ///
+    /// Only structure is compared: an entry whose prefix or `is_last` flag
+    /// moved because a sibling came or went is not reported as changed. Use
+    /// `patch::diff` for edits that reproduce `other` exactly.
+    pub fn diff(&self, other: &TreeSnapshot) -> TreeDiff {
+        let old: HashMap<&Path, &TreeEntry> =
+            self.entries.iter().map(|e| (e.path.as_path(), e)).collect();
+        let new: HashMap<&Path, &TreeEntry> = other
+            .entries
+            .iter()
+            .map(|e| (e.path.as_path(), e))
+            .collect();
+        let mut diff = TreeDiff::default();
+        for entry in &other.entries {
+            match old.get(entry.path.as_path()) {
+                None => diff.added.push(entry.path.clone()),
+                Some(previous) if !same_kind(previous, entry) => {
+                    diff.changed.push(entry.path.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .entries
+            .iter()
+            .filter(|e| !new.contains_key(e.path.as_path()))
+            .map(|e| e.path.clone())
+            .collect();
+        diff
+    }
+}
+
+fn same_kind(a: &TreeEntry, b: &TreeEntry) -> bool {
+    a.is_dir == b.is_dir
+        && a.is_symlink == b.is_symlink
+        && a.symlink_target == b.symlink_target
+        && a.error == b.error
+}
@@ -0,0 +1,120 @@
+//! Heaviest files and directories below the root, for hunting disk usage.
+
+use crossbeam_channel::Sender;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use walkdir::WalkDir;
+
+use super::ignore::IgnoreSet;
+use super::walk::is_excluded;
+
+/// The largest files and directories (by cumulative size) below a root.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeavyReport {
+    /// Largest files, biggest first.
+    pub files: Vec<(PathBuf, u64)>,
+    /// Directories by total size of everything below them, biggest first.
+    /// The root itself is not included.
+    pub dirs: Vec<(PathBuf, u64)>,
+}
+
+/// Walk `root` with the tree's hidden/ignore filtering and keep the `top`
+/// largest files and directories.
+pub fn heaviest(
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: &IgnoreSet,
+    top: usize,
+) -> HeavyReport {
+    let mut files = Vec::new();
+    let mut dir_sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let walker = WalkDir::new(root)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !is_excluded(entry, root, show_hidden, ignore_patterns)
+        });
+
+    for entry in walker.flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            dir_sizes.entry(entry.path().to_path_buf()).or_insert(0);
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let size = meta.len();
+        for ancestor in entry.path().ancestors().skip(1) {
+            if ancestor == root {
+                break;
+            }
+            *dir_sizes.entry(ancestor.to_path_buf()).or_insert(0) += size;
+        }
+        files.push((entry.path().to_path_buf(), size));
+    }
+
+    HeavyReport {
+        files: keep_top(files, top),
+        dirs: keep_top(dir_sizes.into_iter().collect(), top),
+    }
+}
+
+/// Sort by size (descending, ties by path) and keep the first `top`.
+fn keep_top(mut items: Vec<(PathBuf, u64)>, top: usize) -> Vec<(PathBuf, u64)> {
+    items.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    items.truncate(top);
+    items
+}
+
+/// Compute the report on a background thread and send it on `tx`, tagged
+/// with `generation` so stale results can be discarded.
+pub fn spawn_heaviest(
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: IgnoreSet,
+    top: usize,
+    generation: u64,
+    tx: Sender<(u64, HeavyReport)>,
+) {
+    let root = root.to_path_buf();
+    thread::spawn(move || {
+        let report = heaviest(&root, show_hidden, follow_symlinks, &ignore_patterns, top);
+        let _ = tx.send((generation, report));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn ranks_files_and_cumulative_dirs() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+        fs::create_dir(tmp.path().join("c")).unwrap();
+        fs::write(tmp.path().join("a/b/big"), vec![0u8; 300]).unwrap();
+        fs::write(tmp.path().join("a/small"), vec![0u8; 10]).unwrap();
+        fs::write(tmp.path().join("c/mid"), vec![0u8; 200]).unwrap();
+
+        let report = heaviest(tmp.path(), false, false, &IgnoreSet::empty(), 2);
+        assert_eq!(
+            report.files,
+            vec![
+                (tmp.path().join("a/b/big"), 300),
+                (tmp.path().join("c/mid"), 200)
+            ]
+        );
+        assert_eq!(
+            report.dirs,
+            vec![(tmp.path().join("a"), 310), (tmp.path().join("a/b"), 300)]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Ignore patterns and the set they are matched with, and ignore files
+//! (`--ignore-file`): one pattern per line, like `-I`, with blank lines and
+//! `#` comments skipped.
+//!
+//! A pattern is a glob matched against paths relative to the root. A
+//! trailing `/` makes it match directories only (`build/`), and a leading
+//! `(?i)` makes it ignore case (`(?i)*.jpg`, which `--iglob` adds).
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Prefix marking a case-insensitive pattern.
+pub const CASE_INSENSITIVE: &str = "(?i)";
+
+/// A parsed ignore pattern.
+#[derive(Debug, Clone)]
+pub struct IgnorePattern {
+    pub glob: Glob,
+    /// Only directories match (the pattern ended with `/`).
+    pub dir_only: bool,
+}
+
+impl IgnorePattern {
+    pub fn parse(pattern: &str) -> Result<Self, globset::Error> {
+        let (case_insensitive, rest) = match pattern.strip_prefix(CASE_INSENSITIVE) {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let (dir_only, glob) = match rest.strip_suffix('/') {
+            Some(glob) if !glob.is_empty() => (true, glob),
+            _ => (false, rest),
+        };
+        let glob = GlobBuilder::new(glob)
+            .case_insensitive(case_insensitive)
+            .build()?;
+        Ok(Self { glob, dir_only })
+    }
+}
+
+/// Compiled ignore patterns.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreSet {
+    any: GlobSet,
+    dirs: GlobSet,
+}
+
+impl IgnoreSet {
+    /// A set that ignores nothing.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Compile `patterns`.
+    pub fn new(patterns: impl IntoIterator<Item = IgnorePattern>) -> Result<Self, globset::Error> {
+        let mut any = GlobSetBuilder::new();
+        let mut dirs = GlobSetBuilder::new();
+        for pattern in patterns {
+            if pattern.dir_only {
+                dirs.add(pattern.glob);
+            } else {
+                any.add(pattern.glob);
+            }
+        }
+        Ok(Self {
+            any: any.build()?,
+            dirs: dirs.build()?,
+        })
+    }
+
+    /// Whether `path` (relative to the root) is ignored.
+    pub fn is_match(&self, path: impl AsRef<Path>, is_dir: bool) -> bool {
+        let path = path.as_ref();
+        self.any.is_match(path) || (is_dir && self.dirs.is_match(path))
+    }
+}
+
+/// The patterns in an ignore file's `text`, with their 1-based line numbers.
+pub fn parse_ignore_file(text: &str) -> Vec<(usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| (n, line.to_string()))
+        .collect()
+}
+
+/// The patterns in `file`.
+pub fn read_ignore_file(file: &Path) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+    Ok(parse_ignore_file(&text)
+        .into_iter()
+        .map(|(_, pattern)| pattern)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_slash_and_case_prefix() {
+        let set = IgnoreSet::new(
+            ["build/", "(?i)*.jpg", "*.log"]
+                .iter()
+                .map(|p| IgnorePattern::parse(p).unwrap()),
+        )
+        .unwrap();
+        assert!(set.is_match("build", true));
+        assert!(!set.is_match("build", false));
+        assert!(set.is_match("IMG.JPG", false));
+        assert!(!set.is_match("OUT.LOG", false));
+        assert!(set.is_match("out.log", false));
+        assert!(IgnorePattern::parse("(?i)a[b/").is_err());
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let text = "# build output\ntarget\n\n  *.log  \n#*.tmp\n";
+        assert_eq!(
+            parse_ignore_file(text),
+            [(2, "target".to_string()), (4, "*.log".to_string())]
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Path → row lookup for a built snapshot.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::TreeSnapshot;
+
+/// Maps each entry's path to its index in `TreeSnapshot::entries`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathIndex {
+    rows: HashMap<PathBuf, usize>,
+}
+
+impl PathIndex {
+    /// Index every entry of `snapshot`.
+    pub fn new(snapshot: &TreeSnapshot) -> Self {
+        let rows = snapshot
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.path.clone(), i))
+            .collect();
+        Self { rows }
+    }
+
+    /// Row of the entry at `path`, if it is in the snapshot.
+    pub fn get(&self, path: &Path) -> Option<usize> {
+        self.rows.get(path).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+impl TreeSnapshot {
+    /// Build a path → row lookup for this snapshot.
+    pub fn path_index(&self) -> PathIndex {
+        PathIndex::new(self)
+    }
+}
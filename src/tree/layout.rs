@@ -1,95 +1,208 @@
-use super::walk::RawEntry;
-use super::TreeEntry;
+use super::archive::is_archive;
+use super::source::RawEntry;
+use super::{TreeEntry, TreeSnapshot};
+use std::path::{Path, PathBuf};
 
-/// Compute is_last flags and prefix strings for all entries.
-pub(super) fn compute_tree_structure(raw: &[RawEntry]) -> Vec<TreeEntry> {
-    let len = raw.len();
-    let mut entries = Vec::with_capacity(len);
-
-    for (i, (depth, name, path, is_dir, is_symlink, symlink_target, error)) in
-        raw.iter().enumerate()
+impl TreeSnapshot {
+    /// Lay out a snapshot from `(depth, name)` pairs in display order, without
+    /// touching the filesystem. An entry is a directory when the next one is
+    /// deeper, and paths are relative, joined from the nearest shallower
+    /// entries.
+    ///
+    /// Any sequence is accepted, including ones a walk never produces (depth
+    /// 0, jumps of several levels, duplicate names), so property tests and
+    /// fuzzers can check that layout and rendering hold up on them.
+    pub fn from_outline<I, S>(outline: I) -> TreeSnapshot
+    where
+        I: IntoIterator<Item = (usize, S)>,
+        S: Into<String>,
     {
-        let is_last = is_last_sibling(raw, i);
+        let outline: Vec<(usize, String)> = outline
+            .into_iter()
+            .map(|(depth, name)| (depth, name.into()))
+            .collect();
+        // Ancestors as (depth, name), innermost last.
+        let mut ancestors: Vec<(usize, &str)> = Vec::new();
+        let mut raw: Vec<RawEntry> = Vec::with_capacity(outline.len());
+        for (i, (depth, name)) in outline.iter().enumerate() {
+            while ancestors.last().is_some_and(|&(d, _)| d >= *depth) {
+                ancestors.pop();
+            }
+            let path: PathBuf = ancestors
+                .iter()
+                .map(|&(_, n)| n)
+                .chain([name.as_str()])
+                .collect();
+            let is_dir = outline.get(i + 1).is_some_and(|(next, _)| next > depth);
+            raw.push(RawEntry::new(*depth, name.clone(), path, is_dir));
+            ancestors.push((*depth, name));
+        }
+        TreeSnapshot::from_raw(raw, None)
+    }
 
-        entries.push(TreeEntry {
-            name: name.clone(),
-            path: path.clone(),
-            depth: *depth,
-            is_dir: *is_dir,
-            is_symlink: *is_symlink,
-            symlink_target: symlink_target.clone(),
-            is_last,
-            prefix: String::new(), // computed below
-            error: error.clone(),
+    /// Replace whatever is shown below the entry `dir` with `subtree`,
+    /// whose depths count from `dir` (as `build_subtree` and
+    /// `archive_subtree` return them), and lay out the connectors again.
+    /// `dir` may also be an archive file, which takes its members. Returns
+    /// `false`, leaving the snapshot unchanged, if `dir` is not one of its
+    /// directories or archives.
+    pub fn graft(&mut self, dir: &Path, subtree: TreeSnapshot) -> bool {
+        let Some(at) = self
+            .entries
+            .iter()
+            .position(|e| e.path == dir && (e.is_dir || is_archive(&e.path)))
+        else {
+            return false;
+        };
+        let depth = self.entries[at].depth;
+        let end = self.entries[at + 1..]
+            .iter()
+            .position(|e| e.depth <= depth)
+            .map_or(self.entries.len(), |n| at + 1 + n);
+        let removed = end - (at + 1);
+        let added = subtree.entries.into_iter().map(|mut e| {
+            e.depth += depth;
+            e
         });
+        self.entries.splice(at + 1..end, added);
+        self.total_entries = (self.total_entries + subtree.total_entries).saturating_sub(removed);
+        let last = last_siblings(self.entries.iter().map(|e| e.depth));
+        for (entry, is_last) in self.entries.iter_mut().zip(last) {
+            entry.is_last = is_last;
+        }
+        compute_last_ancestors(&mut self.entries);
+        true
     }
+}
 
-    // Compute prefixes using an ancestor_is_last stack
-    compute_prefixes(&mut entries);
-
-    entries
+/// One bit per ancestor depth, set when the ancestor at that depth is the
+/// last of its siblings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct LastAncestors {
+    /// Depths 1 to 64.
+    low: u64,
+    /// Depths past 64, only allocated for trees that deep.
+    high: Option<Box<[u64]>>,
 }
 
-/// Determine if entry at index `i` is the last sibling in its parent group.
-fn is_last_sibling(raw: &[RawEntry], i: usize) -> bool {
-    let depth = raw[i].0;
-    // Look ahead for next entry at the same or lesser depth
-    for next in raw.iter().skip(i + 1) {
-        let next_depth = next.0;
-        if next_depth == depth {
-            return false; // there's another sibling
+impl LastAncestors {
+    pub(super) fn get(&self, depth: usize) -> bool {
+        match depth.checked_sub(1) {
+            None => false,
+            Some(bit @ 0..64) => self.low & (1 << bit) != 0,
+            Some(bit) => self.high.as_ref().is_some_and(|high| {
+                let bit = bit - 64;
+                high.get(bit / 64)
+                    .is_some_and(|word| word & (1 << (bit % 64)) != 0)
+            }),
         }
-        if next_depth < depth {
-            return true; // parent's scope ended, we were last
+    }
+
+    pub(super) fn set(&mut self, depth: usize, last: bool) {
+        let (word, bit) = match depth.checked_sub(1) {
+            None => return,
+            Some(bit @ 0..64) => (&mut self.low, bit),
+            Some(_) if !last && self.high.is_none() => return,
+            Some(bit) => {
+                let bit = bit - 64;
+                let high = self.high.get_or_insert_with(Default::default);
+                if high.len() <= bit / 64 {
+                    let mut words = std::mem::take(high).into_vec();
+                    words.resize(bit / 64 + 1, 0);
+                    *high = words.into_boxed_slice();
+                }
+                (&mut high[bit / 64], bit % 64)
+            }
+        };
+        if last {
+            *word |= 1 << bit;
+        } else {
+            *word &= !(1 << bit);
         }
-        // next_depth > depth means it's a child of us, keep looking
     }
-    // Reached end of list — we are last
-    true
-}
 
-/// Compute prefix strings for all entries.
-/// Uses the is_last flag of ancestors to determine continuation lines.
-fn compute_prefixes(entries: &mut [TreeEntry]) {
-    // Track is_last for each depth level
-    // ancestor_is_last[d] = true means the ancestor at depth d was the last sibling
-    let mut ancestor_is_last: Vec<bool> = Vec::new();
+    /// Only the bits for depths below `depth`.
+    fn below(&self, depth: usize) -> Self {
+        let bits = depth.saturating_sub(1);
+        let low = match bits {
+            64.. => self.low,
+            bits => self.low & ((1 << bits) - 1),
+        };
+        let high = self.high.as_ref().and_then(|high| {
+            let bits = bits.checked_sub(64)?;
+            let mut words: Vec<u64> = high.iter().copied().take(bits.div_ceil(64)).collect();
+            if let Some(last) = words.last_mut().filter(|_| bits % 64 != 0) {
+                *last &= (1 << (bits % 64)) - 1;
+            }
+            while words.last() == Some(&0) {
+                words.pop();
+            }
+            (!words.is_empty()).then(|| words.into_boxed_slice())
+        });
+        LastAncestors { low, high }
+    }
 
-    for entry in entries.iter_mut() {
-        let depth = entry.depth;
+    pub(super) fn heap_size(&self) -> usize {
+        self.high
+            .as_ref()
+            .map_or(0, |high| std::mem::size_of_val(&**high))
+    }
+}
 
-        // Ensure ancestor stack is the right size
-        while ancestor_is_last.len() < depth {
-            ancestor_is_last.push(false);
-        }
-        ancestor_is_last.truncate(depth);
+/// Compute is_last flags and last ancestors for all entries.
+pub(super) fn compute_tree_structure(raw: Vec<RawEntry>) -> Vec<TreeEntry> {
+    let last = last_siblings(raw.iter().map(|r| r.depth));
+    lay_out(raw, &last)
+}
 
-        // Build prefix from ancestors
-        let mut prefix = String::new();
-        for d in 1..depth {
-            if d <= ancestor_is_last.len() && ancestor_is_last[d - 1] {
-                prefix.push_str("    ");
-            } else {
-                prefix.push_str("\u{2502}   "); // │
-            }
-        }
+/// Lay out `raw` with each entry's `is_last` flag taken from `last`, which
+/// may cover entries after the ones given.
+pub(super) fn lay_out(raw: Vec<RawEntry>, last: &[bool]) -> Vec<TreeEntry> {
+    let mut entries = Vec::with_capacity(raw.len());
+    for (raw, &is_last) in raw.into_iter().zip(last) {
+        entries.push(TreeEntry {
+            is_symlink: raw.is_symlink,
+            symlink_target: raw.symlink_target,
+            is_last,
+            error: raw.error,
+            mode: raw.mode,
+            owner: raw.owner,
+            ..TreeEntry::new(raw.name, raw.path, raw.depth, raw.is_dir)
+        });
+    }
 
-        // Add the connector for this entry
-        if depth > 0 {
-            if entry.is_last {
-                prefix.push_str("\u{2514}\u{2500}\u{2500} "); // └──
-            } else {
-                prefix.push_str("\u{251c}\u{2500}\u{2500} "); // ├──
-            }
-        }
+    compute_last_ancestors(&mut entries);
 
-        entry.prefix = prefix;
+    entries
+}
 
-        // Record whether this entry is_last at its depth for children
-        if ancestor_is_last.len() < depth {
-            ancestor_is_last.push(entry.is_last);
-        } else if depth > 0 {
-            ancestor_is_last[depth - 1] = entry.is_last;
-        }
+/// Whether each entry is the last sibling in its parent group, given the
+/// entries' depths in display order: no later entry has its depth before
+/// one shallower does.
+///
+/// One backward pass: `later[d]` says whether an entry at depth `d` comes
+/// later with nothing shallower in between, and an entry at depth `d`
+/// hides everything deeper after it from the entries before it.
+pub(super) fn last_siblings<I>(depths: I) -> Vec<bool>
+where
+    I: DoubleEndedIterator<Item = usize> + ExactSizeIterator,
+{
+    let mut last = vec![true; depths.len()];
+    let mut later: Vec<bool> = Vec::new();
+    for (is_last, depth) in last.iter_mut().rev().zip(depths.rev()) {
+        *is_last = !later.get(depth).copied().unwrap_or(false);
+        later.resize(depth + 1, false);
+        later[depth] = true;
+    }
+    last
+}
+
+/// Record for each entry which of its ancestors are the last of their
+/// siblings, from the `is_last` flags of the entries before it.
+fn compute_last_ancestors(entries: &mut [TreeEntry]) {
+    let mut last = LastAncestors::default();
+    for entry in entries.iter_mut() {
+        entry.last_ancestors = last.below(entry.depth);
+        last.set(entry.depth, entry.is_last);
     }
 }
@@ -0,0 +1,75 @@
+//! Files that appear more than once in the tree because they are hard links
+//! to the same inode, as build caches and package stores often create.
+//! Needs each file's device and inode number, so it is Unix-only and run
+//! only when asked for (`--hard-links`).
+
+use super::TreeEntry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Maps each file that is a hard link to an earlier entry (in tree order)
+/// to that entry's path. Directories, symlinks, and files that cannot be
+/// read are skipped. Always empty on platforms without inode numbers.
+#[cfg(unix)]
+pub fn hard_links(entries: &[TreeEntry]) -> HashMap<PathBuf, PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut first: HashMap<(u64, u64), &PathBuf> = HashMap::new();
+    let mut links = HashMap::new();
+    for entry in entries.iter().filter(|e| !e.is_dir && !e.is_symlink) {
+        let Ok(meta) = entry.path.symlink_metadata() else {
+            continue;
+        };
+        if meta.nlink() < 2 {
+            continue;
+        }
+        match first.get(&(meta.dev(), meta.ino())) {
+            Some(original) => {
+                links.insert(entry.path.clone(), (*original).clone());
+            }
+            None => {
+                first.insert((meta.dev(), meta.ino()), &entry.path);
+            }
+        }
+    }
+    links
+}
+
+#[cfg(not(unix))]
+pub fn hard_links(_entries: &[TreeEntry]) -> HashMap<PathBuf, PathBuf> {
+    HashMap::new()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tree::build_tree;
+    use crate::tree::{IgnoreSet, TreeConfig};
+    use std::fs;
+
+    #[test]
+    fn later_links_point_to_the_first() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::create_dir(root.join("cache")).unwrap();
+        fs::write(root.join("cache/blob"), "x").unwrap();
+        fs::hard_link(root.join("cache/blob"), root.join("a.o")).unwrap();
+        fs::hard_link(root.join("cache/blob"), root.join("b.o")).unwrap();
+        fs::write(root.join("c.o"), "x").unwrap();
+
+        let config = TreeConfig {
+            max_depth: None,
+            show_hidden: false,
+            dirs_only: false,
+            follow_symlinks: false,
+            ignore_patterns: IgnoreSet::empty(),
+            max_entries: None,
+            stat: false,
+        };
+        let snapshot = build_tree(root, &config);
+        let links = hard_links(&snapshot.entries);
+        let blob = root.join("cache/blob");
+        let expected = HashMap::from([(root.join("a.o"), blob.clone()), (root.join("b.o"), blob)]);
+        assert_eq!(links, expected);
+    }
+}
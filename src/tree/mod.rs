@@ -1,16 +1,54 @@
 //! Tree building, filtering, sorting, and layout computation.
 
+mod archive;
+mod case;
+mod compare;
+mod details;
+mod diff;
+mod heavy;
+mod ignore;
+mod index;
 mod layout;
+mod links;
+mod source;
+mod stale;
+mod summary;
 pub(crate) mod walk;
+mod window;
 
-use globset::GlobSet;
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
-pub use walk::{build_ignore_set, build_tree};
+use layout::LastAncestors;
+
+pub use archive::{archive_subtree, is_archive, list_archive, ArchiveSource};
+pub use case::case_collisions;
+pub use compare::{compare, CompareSide, Comparison, Difference};
+pub use details::{spawn_dir_details, DirDetails};
+pub use diff::TreeDiff;
+pub use heavy::{heaviest, spawn_heaviest, HeavyReport};
+pub use ignore::{parse_ignore_file, read_ignore_file, IgnorePattern, IgnoreSet, CASE_INSENSITIVE};
+pub use index::PathIndex;
+pub use links::hard_links;
+pub use source::{
+    virtual_entries, EntrySource, RawEntry, SourceTreeBuilder, VirtualEntry, WalkProgress,
+};
+pub use stale::{is_stale, stale_files};
+pub use summary::{spawn_summarize, DirSummary, SummaryMap};
+pub use walk::{
+    build_ignore_set, build_ignore_set_no_defaults, build_subtree, build_tree,
+    build_tree_with_preview, build_tree_with_progress, is_visible,
+};
+pub use window::Window;
 
 /// A single entry in the rendered directory tree.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// The box-drawing prefix is not stored: [`TreeEntry::prefix`] draws it
+/// from `depth`, `is_last`, and which ancestors were last among their
+/// siblings.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(into = "EntryRecord", from = "EntryRecord")]
 pub struct TreeEntry {
     /// Display name (filename component only).
     pub name: String,
@@ -26,13 +64,171 @@ pub struct TreeEntry {
     pub symlink_target: Option<String>,
     /// Whether this is the last sibling in its parent group.
     pub is_last: bool,
-    /// Pre-computed box-drawing prefix string for tree display.
-    pub prefix: String,
+    /// Which ancestors are the last of their siblings, drawn as blank
+    /// columns instead of `│` in the prefix.
+    last_ancestors: LastAncestors,
     /// Error message if the entry could not be read (e.g. permission denied).
     pub error: Option<String>,
+    /// Unix file type and permission bits (`st_mode`), when the tree was
+    /// built with `TreeConfig::stat` on a Unix platform.
+    #[serde(default)]
+    pub mode: Option<u32>,
+    /// Owning user and group ids, read alongside `mode`.
+    #[serde(default)]
+    pub owner: Option<(u32, u32)>,
+}
+
+impl TreeEntry {
+    /// An entry with nothing but its place in the tree filled in: not a
+    /// symlink, no error or metadata, last among its siblings, no prefix.
+    pub fn new(
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        depth: usize,
+        is_dir: bool,
+    ) -> Self {
+        let mut name = name.into();
+        name.shrink_to_fit();
+        let mut path = path.into();
+        path.shrink_to_fit();
+        Self {
+            name,
+            path,
+            depth,
+            is_dir,
+            is_symlink: false,
+            symlink_target: None,
+            is_last: true,
+            last_ancestors: LastAncestors::default(),
+            error: None,
+            mode: None,
+            owner: None,
+        }
+    }
+
+    /// Path below the root, from the last `depth` components of `path`
+    /// and ending in the display name: what a flat list shows and what
+    /// searches match.
+    pub fn relative_path(&self) -> String {
+        let mut parents: Vec<_> = self
+            .path
+            .components()
+            .rev()
+            .skip(1)
+            .take(self.depth.saturating_sub(1))
+            .collect();
+        parents.reverse();
+        let mut path: PathBuf = parents.into_iter().collect();
+        path.push(&self.name);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Box-drawing prefix for tree display: a `│   ` or blank column per
+    /// ancestor below the root, then `├── ` or `└── `. Empty at depth 0.
+    pub fn prefix(&self) -> String {
+        let mut prefix = String::with_capacity(self.depth * 10);
+        for depth in 1..self.depth {
+            if self.last_ancestors.get(depth) {
+                prefix.push_str("    ");
+            } else {
+                prefix.push_str("\u{2502}   "); // │
+            }
+        }
+        if self.depth > 0 {
+            if self.is_last {
+                prefix.push_str("\u{2514}\u{2500}\u{2500} "); // └──
+            } else {
+                prefix.push_str("\u{251c}\u{2500}\u{2500} "); // ├──
+            }
+        }
+        prefix
+    }
+
+    /// Whether the ancestor at `depth` (1 to `self.depth - 1`) is the last
+    /// of its siblings. Layout works this out; entries built by hand have
+    /// no last ancestors unless set with `set_ancestor_last`.
+    pub fn ancestor_is_last(&self, depth: usize) -> bool {
+        self.last_ancestors.get(depth)
+    }
+
+    /// Record whether the ancestor at `depth` is the last of its siblings.
+    pub fn set_ancestor_last(&mut self, depth: usize, last: bool) {
+        self.last_ancestors.set(depth, last);
+    }
+
+    /// Take the last ancestors from a prefix as `prefix` draws it (in
+    /// either charset), for decoding entries that were encoded with one.
+    pub(crate) fn set_ancestors_from_prefix(&mut self, prefix: &str) {
+        let columns: Vec<char> = prefix.chars().collect();
+        for (i, column) in columns
+            .chunks(4)
+            .enumerate()
+            .take(self.depth.saturating_sub(1))
+        {
+            self.set_ancestor_last(i + 1, column[0] == ' ');
+        }
+    }
+}
+
+/// How a `TreeEntry` is serialized: every field spelled out, including the
+/// drawn prefix. Paths are written lossily so non-UTF-8 names still encode.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EntryRecord {
+    name: String,
+    path: String,
+    depth: usize,
+    is_dir: bool,
+    is_symlink: bool,
+    symlink_target: Option<String>,
+    is_last: bool,
+    prefix: String,
+    error: Option<String>,
+    #[serde(default)]
+    mode: Option<u32>,
+    #[serde(default)]
+    owner: Option<(u32, u32)>,
+}
+
+impl From<TreeEntry> for EntryRecord {
+    fn from(e: TreeEntry) -> Self {
+        let prefix = e.prefix();
+        Self {
+            name: e.name,
+            path: e.path.to_string_lossy().into_owned(),
+            depth: e.depth,
+            is_dir: e.is_dir,
+            is_symlink: e.is_symlink,
+            symlink_target: e.symlink_target,
+            is_last: e.is_last,
+            prefix,
+            error: e.error,
+            mode: e.mode,
+            owner: e.owner,
+        }
+    }
+}
+
+impl From<EntryRecord> for TreeEntry {
+    fn from(r: EntryRecord) -> Self {
+        let mut entry = Self {
+            is_symlink: r.is_symlink,
+            symlink_target: r.symlink_target,
+            is_last: r.is_last,
+            error: r.error,
+            mode: r.mode,
+            owner: r.owner,
+            ..TreeEntry::new(r.name, PathBuf::from(r.path), r.depth, r.is_dir)
+        };
+        entry.set_ancestors_from_prefix(&r.prefix);
+        entry
+    }
 }
 
 /// Configuration for tree building.
+///
+/// When serialized, `ignore_patterns` is skipped: a compiled
+/// `IgnoreSet` cannot be turned back into patterns, so it deserializes empty.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct TreeConfig {
     /// Maximum traversal depth (`None` for unlimited).
     pub max_depth: Option<usize>,
@@ -42,15 +238,23 @@ pub struct TreeConfig {
     pub dirs_only: bool,
     /// Whether to follow symbolic links during traversal.
     pub follow_symlinks: bool,
-    /// Glob patterns for entries to exclude.
-    pub ignore_patterns: GlobSet,
+    /// Patterns for entries to exclude.
+    #[serde(skip)]
+    pub ignore_patterns: IgnoreSet,
     /// Optional maximum number of entries to include in the built tree.
-    /// When `Some(n)`, only the first `n` entries (after filtering/sorting) are kept.
+    /// When `Some(n)`, the deepest levels are dropped first: the first level
+    /// is always complete, and the level that no longer fits keeps its
+    /// first entries in tree order.
     pub max_entries: Option<usize>,
+    /// Whether to read each entry's metadata during the walk to fill in
+    /// `TreeEntry::mode` and `TreeEntry::owner`. Costs one extra stat per
+    /// entry; Unix only.
+    #[serde(default)]
+    pub stat: bool,
 }
 
 /// Snapshot of the built tree along with basic metadata.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct TreeSnapshot {
     /// Entries that will be rendered (possibly truncated).
     pub entries: Vec<TreeEntry>,
@@ -58,6 +262,46 @@ pub struct TreeSnapshot {
     pub total_entries: usize,
 }
 
+impl TreeSnapshot {
+    /// Hide everything below the given (collapsed) directories. The
+    /// directories themselves stay visible; `total_entries` shrinks by the
+    /// number of hidden entries.
+    pub fn collapse(&mut self, collapsed: &HashSet<PathBuf>) {
+        if collapsed.is_empty() {
+            return;
+        }
+        let before = self.entries.len();
+        self.entries.retain(|e| {
+            !e.path
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| collapsed.contains(ancestor))
+        });
+        self.total_entries = self
+            .total_entries
+            .saturating_sub(before - self.entries.len());
+    }
+
+    /// Rough number of bytes the snapshot occupies: the entry vector plus
+    /// the strings each entry owns.
+    pub fn estimated_size(&self) -> usize {
+        let heap: usize = self
+            .entries
+            .iter()
+            .map(|e| {
+                e.name.capacity()
+                    + e.path.capacity()
+                    + e.last_ancestors.heap_size()
+                    + e.symlink_target.as_ref().map_or(0, String::capacity)
+                    + e.error.as_ref().map_or(0, String::capacity)
+            })
+            .sum();
+        std::mem::size_of::<Self>()
+            + self.entries.capacity() * std::mem::size_of::<TreeEntry>()
+            + heap
+    }
+}
+
 impl Deref for TreeSnapshot {
     type Target = [TreeEntry];
     fn deref(&self) -> &[TreeEntry] {
@@ -66,8 +310,52 @@ impl Deref for TreeSnapshot {
 }
 
 /// Abstraction over tree construction so it can be swapped or mocked.
+///
+/// This is the extension point for other backends: implement it directly,
+/// or implement [`EntrySource`] and use [`SourceTreeBuilder`] to get the
+/// shared layout (connectors, `max_entries`) for free. It is stable API;
+/// methods added later will have default implementations.
 pub trait TreeBuilder {
     fn build_tree(&self, root: &Path, config: &TreeConfig) -> TreeSnapshot;
+
+    /// Like `build_tree`, calling `progress(entries_so_far, current_path)`
+    /// as the walk goes, so slow walks (e.g. over network filesystems) can
+    /// show progress. Builders that cannot report progress call it once
+    /// when done, which is what the default implementation does.
+    fn build_tree_with_progress(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        progress: &mut dyn FnMut(usize, &Path),
+    ) -> TreeSnapshot {
+        let snapshot = self.build_tree(root, config);
+        progress(snapshot.total_entries, root);
+        snapshot
+    }
+
+    /// Like `build_tree_with_progress`, with a `WalkProgress` that can also
+    /// lay out the top of the tree walked so far, so a slow first walk can
+    /// show something before it finishes. The default implementation
+    /// reports progress without a preview.
+    fn build_tree_with_preview(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        progress: &mut dyn FnMut(&WalkProgress),
+    ) -> TreeSnapshot {
+        self.build_tree_with_progress(root, config, &mut |count, current| {
+            progress(&WalkProgress::new(count, current, &[]))
+        })
+    }
+
+    /// Build the tree below `dir`, a directory inside `root`, with depths
+    /// counted from `dir`; used to load collapsed subtrees on demand. The
+    /// default implementation builds `dir` as a root of its own, so ignore
+    /// patterns match relative to `dir`.
+    fn build_subtree(&self, root: &Path, dir: &Path, config: &TreeConfig) -> TreeSnapshot {
+        let _ = root;
+        self.build_tree(dir, config)
+    }
 }
 
 /// Default `TreeBuilder` that delegates to the walkdir-based implementation.
@@ -77,4 +365,26 @@ impl TreeBuilder for WalkdirTreeBuilder {
     fn build_tree(&self, root: &Path, config: &TreeConfig) -> TreeSnapshot {
         build_tree(root, config)
     }
+
+    fn build_tree_with_progress(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        progress: &mut dyn FnMut(usize, &Path),
+    ) -> TreeSnapshot {
+        build_tree_with_progress(root, config, progress)
+    }
+
+    fn build_tree_with_preview(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        progress: &mut dyn FnMut(&WalkProgress),
+    ) -> TreeSnapshot {
+        build_tree_with_preview(root, config, progress)
+    }
+
+    fn build_subtree(&self, root: &Path, dir: &Path, config: &TreeConfig) -> TreeSnapshot {
+        build_subtree(root, dir, config)
+    }
 }
@@ -0,0 +1,240 @@
+//! Entry sources: backends that list entries for the shared layout and
+//! rendering pipeline. The filesystem walk is one; archive listings,
+//! remote listings, and in-memory fixtures plug in the same way by
+//! implementing [`EntrySource`] and wrapping it in [`SourceTreeBuilder`].
+//!
+//! `EntrySource`, `RawEntry`, and `TreeBuilder` are stable API: new trait
+//! methods come with default implementations and new `RawEntry` fields
+//! with defaults in [`RawEntry::new`], so sources written against one
+//! release keep compiling on the next.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::layout::{compute_tree_structure, last_siblings, lay_out};
+use super::walk::truncate_deepest_first;
+use super::{TreeBuilder, TreeConfig, TreeSnapshot};
+
+/// One entry as a source lists it: a `TreeEntry` without the connectors
+/// (`is_last` and the last ancestors the prefix is drawn from), which the
+/// layout works out.
+///
+/// Create one with [`RawEntry::new`] and set the optional fields after.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RawEntry {
+    /// Nesting depth (1 = direct child of the root).
+    pub depth: usize,
+    /// Display name (last path component).
+    pub name: String,
+    /// Full path. Sources other than the filesystem may use paths that do
+    /// not exist on disk, e.g. members joined onto an archive's path.
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    /// Why the entry could not be read, shown after its name.
+    pub error: Option<String>,
+    /// Unix `st_mode`, if known.
+    pub mode: Option<u32>,
+    /// Owning `(uid, gid)`, if known.
+    pub owner: Option<(u32, u32)>,
+}
+
+impl RawEntry {
+    /// An entry with nothing but its place in the tree filled in.
+    pub fn new(
+        depth: usize,
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        is_dir: bool,
+    ) -> Self {
+        Self {
+            depth,
+            name: name.into(),
+            path: path.into(),
+            is_dir,
+            is_symlink: false,
+            symlink_target: None,
+            error: None,
+            mode: None,
+            owner: None,
+        }
+    }
+}
+
+/// A backend that lists the entries below a root.
+///
+/// Entries come in display order: each one directly after its parent's
+/// earlier children and their subtrees, siblings in the order they are to
+/// be shown, and depths never more than one deeper than the entry before.
+/// Which of `config`'s filters apply (`show_hidden`, `ignore_patterns`,
+/// `max_depth`, `dirs_only`) is up to the source; `tree::is_visible`
+/// covers hidden files and ignores. `max_entries` is applied by
+/// [`SourceTreeBuilder`], which also counts what it cut.
+pub trait EntrySource {
+    fn entries<'a>(
+        &'a self,
+        root: &'a Path,
+        config: &'a TreeConfig,
+    ) -> Box<dyn Iterator<Item = RawEntry> + 'a>;
+}
+
+/// A `TreeBuilder` over any [`EntrySource`]: it lays out what the source
+/// lists, so the result renders, diffs, and patches like a walked tree.
+#[derive(Debug, Clone, Default)]
+pub struct SourceTreeBuilder<S>(pub S);
+
+impl<S: EntrySource> TreeBuilder for SourceTreeBuilder<S> {
+    fn build_tree(&self, root: &Path, config: &TreeConfig) -> TreeSnapshot {
+        self.build_tree_with_progress(root, config, &mut |_, _| {})
+    }
+
+    fn build_tree_with_progress(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        progress: &mut dyn FnMut(usize, &Path),
+    ) -> TreeSnapshot {
+        self.build_tree_with_preview(root, config, &mut |walked| {
+            progress(walked.count, walked.current)
+        })
+    }
+
+    fn build_tree_with_preview(
+        &self,
+        root: &Path,
+        config: &TreeConfig,
+        progress: &mut dyn FnMut(&WalkProgress),
+    ) -> TreeSnapshot {
+        let mut raw = Vec::new();
+        for entry in self.0.entries(root, config) {
+            raw.push(entry);
+            let current = &raw[raw.len() - 1].path;
+            progress(&WalkProgress::new(raw.len(), current, &raw));
+        }
+        TreeSnapshot::from_raw(raw, config.max_entries)
+    }
+}
+
+/// How far a walk has got, as `TreeBuilder::build_tree_with_preview`
+/// reports it after every entry.
+#[derive(Debug, Clone, Copy)]
+pub struct WalkProgress<'a> {
+    /// Entries found so far.
+    pub count: usize,
+    /// The path just visited.
+    pub current: &'a Path,
+    walked: &'a [RawEntry],
+}
+
+impl<'a> WalkProgress<'a> {
+    /// Progress after `count` entries, `walked` being those entries in
+    /// display order, or empty if the builder cannot show them early.
+    pub fn new(count: usize, current: &'a Path, walked: &'a [RawEntry]) -> Self {
+        Self {
+            count,
+            current,
+            walked,
+        }
+    }
+
+    /// The first `rows` entries walked so far, laid out as if the walk
+    /// ended here, with `total_entries` set to `count`. Entries come in
+    /// display order, so these are the top rows of the finished tree,
+    /// though connectors can still change for entries whose later siblings
+    /// have not been walked yet, and `max_entries` is not applied. Empty
+    /// when the builder gave no entries.
+    pub fn preview(&self, rows: usize) -> TreeSnapshot {
+        let last = last_siblings(self.walked.iter().map(|r| r.depth));
+        let shown = &self.walked[..rows.min(self.walked.len())];
+        TreeSnapshot {
+            entries: lay_out(shown.to_vec(), &last),
+            total_entries: self.count,
+        }
+    }
+}
+
+impl TreeSnapshot {
+    /// Lay out entries listed in display order (see [`EntrySource`]),
+    /// keeping at most `max_entries` of them by dropping the deepest levels
+    /// first. `total_entries` counts every entry given.
+    pub fn from_raw(mut raw: Vec<RawEntry>, max_entries: Option<usize>) -> TreeSnapshot {
+        let total_entries = raw.len();
+        if let Some(max) = max_entries {
+            truncate_deepest_first(&mut raw, max);
+        }
+        TreeSnapshot {
+            entries: compute_tree_structure(raw),
+            total_entries,
+        }
+    }
+}
+
+/// One path in a flat listing, such as an archive's member list or a
+/// remote `find`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VirtualEntry {
+    /// Path relative to the listed root, without `..` components.
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// Lay out a flat listing as entries below `root`, in display order, with
+/// depths counted from `root` (1 = top level) and paths joined onto it.
+/// Directories implied by deeper paths are added, and siblings are sorted
+/// as the filesystem walk sorts them.
+pub fn virtual_entries(root: &Path, listing: &[VirtualEntry]) -> Vec<RawEntry> {
+    let mut top = Node::default();
+    for entry in listing {
+        let parts: Vec<String> = entry
+            .path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some((last, dirs)) = parts.split_last() else {
+            continue;
+        };
+        let mut node = &mut top;
+        for dir in dirs {
+            node = node.dirs.entry(dir.clone()).or_default();
+        }
+        if entry.is_dir {
+            node.dirs.entry(last.clone()).or_default();
+        } else {
+            node.files.push(last.clone());
+        }
+    }
+    let mut raw = Vec::new();
+    top.flatten(root, 1, &mut raw);
+    raw
+}
+
+#[derive(Default)]
+struct Node {
+    dirs: BTreeMap<String, Node>,
+    files: Vec<String>,
+}
+
+impl Node {
+    fn flatten(&self, path: &Path, depth: usize, out: &mut Vec<RawEntry>) {
+        let mut dirs: Vec<(&String, &Node)> = self.dirs.iter().collect();
+        dirs.sort_by_key(|(name, _)| sort_key(name));
+        for (name, node) in dirs {
+            let dir = path.join(name);
+            out.push(RawEntry::new(depth, name.as_str(), dir.clone(), true));
+            node.flatten(&dir, depth + 1, out);
+        }
+        let mut files: Vec<&String> = self.files.iter().collect();
+        files.sort_by_key(|name| sort_key(name));
+        files.dedup();
+        for name in files {
+            out.push(RawEntry::new(depth, name.as_str(), path.join(name), false));
+        }
+    }
+}
+
+/// Dotfiles last, then case-insensitive, as `walk::sort_cmp` orders names.
+fn sort_key(name: &str) -> (bool, String) {
+    (name.starts_with('.'), name.to_lowercase())
+}
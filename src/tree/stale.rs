@@ -0,0 +1,81 @@
+//! Files that have not been modified for a while.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+use super::ignore::IgnoreSet;
+use super::walk::is_excluded;
+
+/// Whether a file last modified at `modified` is older than `older_than` at `now`.
+pub fn is_stale(modified: SystemTime, older_than: Duration, now: SystemTime) -> bool {
+    now.duration_since(modified)
+        .is_ok_and(|age| age > older_than)
+}
+
+/// Files below `root` (with the tree's hidden/ignore filtering) not modified
+/// within `older_than` of `now`, oldest first.
+pub fn stale_files(
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: &IgnoreSet,
+    older_than: Duration,
+    now: SystemTime,
+) -> Vec<(PathBuf, SystemTime)> {
+    let walker = WalkDir::new(root)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !is_excluded(entry, root, show_hidden, ignore_patterns)
+        });
+
+    let mut files: Vec<(PathBuf, SystemTime)> = walker
+        .flatten()
+        .filter(|entry| !entry.file_type().is_dir())
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            is_stale(modified, older_than, now).then(|| (entry.into_path(), modified))
+        })
+        .collect();
+    files.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn lists_only_files_older_than_threshold() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(tmp.path().join("fresh.txt"), "").unwrap();
+        fs::write(tmp.path().join("old.txt"), "").unwrap();
+
+        // Pretend it is two days from now: both files are a couple of days
+        // old, so a one-day threshold catches them and a week does not.
+        let later = SystemTime::now() + Duration::from_secs(2 * 86_400);
+        let day = Duration::from_secs(86_400);
+        let stale = stale_files(tmp.path(), false, false, &IgnoreSet::empty(), day, later);
+        assert_eq!(stale.len(), 2);
+        let week = Duration::from_secs(7 * 86_400);
+        assert!(stale_files(tmp.path(), false, false, &IgnoreSet::empty(), week, later).is_empty());
+    }
+
+    #[test]
+    fn future_timestamps_are_not_stale() {
+        let now = SystemTime::now();
+        assert!(!is_stale(
+            now + Duration::from_secs(60),
+            Duration::ZERO,
+            now
+        ));
+        assert!(is_stale(
+            now - Duration::from_secs(60),
+            Duration::from_secs(30),
+            now
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+//! Per-directory composition summaries (file counts by extension).
+
+use crossbeam_channel::Sender;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use walkdir::WalkDir;
+
+use super::ignore::IgnoreSet;
+use super::walk::is_excluded;
+
+/// Maximum number of extensions shown in a formatted summary.
+const MAX_SHOWN: usize = 4;
+
+/// File counts by extension for everything below a directory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirSummary {
+    counts: HashMap<String, usize>,
+}
+
+impl DirSummary {
+    /// Record one file with the given extension.
+    fn add(&mut self, ext: &str) {
+        *self.counts.entry(ext.to_string()).or_insert(0) += 1;
+    }
+
+    /// Extension counts ordered by descending count, then by name.
+    pub fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<(&str, usize)> =
+            self.counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked
+    }
+
+    /// Whether no files with an extension were found.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+impl fmt::Display for DirSummary {
+    /// Formats as e.g. `rs:34 toml:2`, keeping only the most common extensions.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranked = self.ranked();
+        for (i, (ext, count)) in ranked.iter().take(MAX_SHOWN).enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{ext}:{count}")?;
+        }
+        if ranked.len() > MAX_SHOWN {
+            f.write_str(" …")?;
+        }
+        Ok(())
+    }
+}
+
+/// Summaries keyed by directory path.
+pub type SummaryMap = HashMap<PathBuf, DirSummary>;
+
+/// Walk the whole tree below `root` (ignoring depth and entry limits) and
+/// count files by extension for every directory, including the root.
+/// Each file counts towards all of its ancestor directories.
+pub fn summarize(
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: &IgnoreSet,
+) -> SummaryMap {
+    let mut map = SummaryMap::new();
+    let walker = WalkDir::new(root)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(|entry| !is_excluded(entry, root, show_hidden, ignore_patterns));
+
+    for entry in walker.flatten() {
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let Some(ext) = entry.path().extension() else {
+            continue;
+        };
+        let ext = ext.to_string_lossy().to_lowercase();
+        for ancestor in entry.path().ancestors().skip(1) {
+            map.entry(ancestor.to_path_buf()).or_default().add(&ext);
+            if ancestor == root {
+                break;
+            }
+        }
+    }
+    map
+}
+
+/// Compute summaries on a background thread and send them, tagged with
+/// `generation`, on `tx` once finished.
+pub fn spawn_summarize(
+    root: &Path,
+    show_hidden: bool,
+    follow_symlinks: bool,
+    ignore_patterns: IgnoreSet,
+    generation: u64,
+    tx: Sender<(u64, SummaryMap)>,
+) {
+    let root = root.to_path_buf();
+    thread::spawn(move || {
+        let map = summarize(&root, show_hidden, follow_symlinks, &ignore_patterns);
+        let _ = tx.send((generation, map));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn counts_roll_up_to_ancestors() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src/bin")).unwrap();
+        fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+        fs::write(tmp.path().join("src/bin/main.rs"), "").unwrap();
+        fs::write(tmp.path().join("Cargo.toml"), "").unwrap();
+        fs::write(tmp.path().join("LICENSE"), "").unwrap();
+
+        let map = summarize(tmp.path(), false, false, &IgnoreSet::empty());
+        assert_eq!(map[tmp.path()].to_string(), "rs:2 toml:1");
+        assert_eq!(map[&tmp.path().join("src")].to_string(), "rs:2");
+        assert_eq!(map[&tmp.path().join("src/bin")].to_string(), "rs:1");
+    }
+
+    #[test]
+    fn display_keeps_most_common_extensions() {
+        let mut summary = DirSummary::default();
+        for ext in ["a", "b", "b", "c", "d", "e", "e", "e"] {
+            summary.add(ext);
+        }
+        assert_eq!(summary.to_string(), "e:3 b:2 a:1 c:1 …");
+    }
+}
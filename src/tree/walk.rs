@@ -1,37 +1,36 @@
-use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use walkdir::{DirEntry, WalkDir};
 
-use super::layout::compute_tree_structure;
+use super::ignore::{IgnorePattern, IgnoreSet};
+use super::source::{RawEntry, WalkProgress};
 use super::{TreeConfig, TreeSnapshot};
 
-/// Raw entry data collected during filesystem traversal, before layout computation.
-pub(super) type RawEntry = (
-    usize,
-    String,
-    PathBuf,
-    bool,
-    bool,
-    Option<String>,
-    Option<String>,
-);
-
 const DEFAULT_IGNORES: &[&str] = &[".git", "node_modules", "__pycache__", ".DS_Store"];
 
-/// Build a GlobSet from user patterns plus the default ignore list.
+/// Build an IgnoreSet from user patterns plus the default ignore list.
 /// Invalid patterns are skipped and reported to stderr.
-pub fn build_ignore_set(user_patterns: &[String]) -> GlobSet {
-    let mut builder = GlobSetBuilder::new();
+pub fn build_ignore_set(user_patterns: &[String]) -> IgnoreSet {
+    ignore_set(DEFAULT_IGNORES, user_patterns)
+}
+
+/// Build an IgnoreSet from user patterns alone, so `.git`, `node_modules`,
+/// and the rest of the default ignore list are walked like anything else.
+pub fn build_ignore_set_no_defaults(user_patterns: &[String]) -> IgnoreSet {
+    ignore_set(&[], user_patterns)
+}
+
+fn ignore_set(defaults: &[&str], user_patterns: &[String]) -> IgnoreSet {
+    let mut patterns = Vec::new();
     let mut invalid = Vec::new();
-    for pattern in DEFAULT_IGNORES {
-        if let Ok(g) = Glob::new(pattern) {
-            builder.add(g);
+    for pattern in defaults {
+        if let Ok(p) = IgnorePattern::parse(pattern) {
+            patterns.push(p);
         }
     }
     for pattern in user_patterns {
-        match Glob::new(pattern) {
-            Ok(g) => {
-                builder.add(g);
+        match IgnorePattern::parse(pattern) {
+            Ok(p) => {
+                patterns.push(p);
             }
             Err(_) => {
                 invalid.push(pattern.clone());
@@ -39,20 +38,59 @@ pub fn build_ignore_set(user_patterns: &[String]) -> GlobSet {
         }
     }
     if !invalid.is_empty() {
-        eprintln!(
-            "livetree: invalid ignore pattern(s), skipped: {:?}",
-            invalid
-        );
+        tracing::warn!(?invalid, "invalid ignore patterns skipped");
     }
-    builder.build().unwrap_or_else(|e| {
-        eprintln!("livetree: failed to build ignore set: {}", e);
-        GlobSet::empty()
+    IgnoreSet::new(patterns).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "failed to build ignore set");
+        IgnoreSet::empty()
     })
 }
 
 /// Build the tree from a root path.
 pub fn build_tree(root: &Path, config: &TreeConfig) -> TreeSnapshot {
-    let mut walker = WalkDir::new(root)
+    build_tree_with_progress(root, config, &mut |_, _| {})
+}
+
+/// Build the tree from a root path, calling `progress` with the number of
+/// entries found so far and the path just visited after every entry.
+pub fn build_tree_with_progress(
+    root: &Path,
+    config: &TreeConfig,
+    progress: &mut dyn FnMut(usize, &Path),
+) -> TreeSnapshot {
+    build_tree_with_preview(root, config, &mut |walked| {
+        progress(walked.count, walked.current)
+    })
+}
+
+/// Build the tree from a root path, calling `progress` after every entry
+/// with a `WalkProgress` that can preview the top of the tree so far.
+pub fn build_tree_with_preview(
+    root: &Path,
+    config: &TreeConfig,
+    progress: &mut dyn FnMut(&WalkProgress),
+) -> TreeSnapshot {
+    let _span = tracing::debug_span!("walk", root = %root.display()).entered();
+    walk(root, root, config, progress)
+}
+
+/// Build the tree below `dir`, a directory inside `root`, for grafting onto
+/// a snapshot of `root` (see `TreeSnapshot::graft`). Depths count from
+/// `dir` (1 = its children) and `config.max_depth` limits them, while
+/// ignore patterns still match paths relative to `root`.
+pub fn build_subtree(root: &Path, dir: &Path, config: &TreeConfig) -> TreeSnapshot {
+    let _span = tracing::debug_span!("walk", root = %dir.display()).entered();
+    walk(root, dir, config, &mut |_| {})
+}
+
+/// Walk `start` (`root` or a directory below it).
+fn walk(
+    root: &Path,
+    start: &Path,
+    config: &TreeConfig,
+    progress: &mut dyn FnMut(&WalkProgress),
+) -> TreeSnapshot {
+    let mut walker = WalkDir::new(start)
         .follow_links(config.follow_symlinks)
         .sort_by(sort_cmp);
 
@@ -67,26 +105,9 @@ pub fn build_tree(root: &Path, config: &TreeConfig) -> TreeSnapshot {
     let show_hidden = config.show_hidden;
     let ignore_patterns = config.ignore_patterns.clone();
     let root = root.to_path_buf();
-    let iter = walker.into_iter().filter_entry(move |entry| {
-        let name = entry.file_name().to_string_lossy();
-        // Always allow root
-        if entry.depth() == 0 {
-            return true;
-        }
-        // Filter hidden entries (prevents descending into .git, etc.)
-        if !show_hidden && name.starts_with('.') {
-            return false;
-        }
-        // Filter ignored patterns: match path relative to root so e.g. "target/**" works
-        let path_to_match = entry
-            .path()
-            .strip_prefix(&root)
-            .unwrap_or_else(|_| entry.path());
-        if ignore_patterns.is_match(path_to_match) {
-            return false;
-        }
-        true
-    });
+    let iter = walker
+        .into_iter()
+        .filter_entry(move |entry| !is_excluded(entry, &root, show_hidden, &ignore_patterns));
 
     for entry_result in iter {
         match entry_result {
@@ -118,14 +139,22 @@ pub fn build_tree(root: &Path, config: &TreeConfig) -> TreeSnapshot {
                     None
                 };
 
-                raw_entries.push((
-                    depth,
-                    file_name,
-                    path,
-                    is_dir,
-                    is_symlink,
-                    symlink_target,
-                    None,
+                let (mode, owner) = if config.stat {
+                    entry_stat(&entry)
+                } else {
+                    (None, None)
+                };
+
+                let mut raw = RawEntry::new(depth, file_name, path, is_dir);
+                raw.is_symlink = is_symlink;
+                raw.symlink_target = symlink_target;
+                raw.mode = mode;
+                raw.owner = owner;
+                raw_entries.push(raw);
+                progress(&WalkProgress::new(
+                    raw_entries.len(),
+                    entry.path(),
+                    &raw_entries,
                 ));
             }
             Err(e) => {
@@ -144,25 +173,113 @@ pub fn build_tree(root: &Path, config: &TreeConfig) -> TreeSnapshot {
                 } else {
                     e.to_string()
                 };
-                raw_entries.push((depth, name, path, true, false, None, Some(error_msg)));
+                let mut raw = RawEntry::new(depth, name, path, true);
+                raw.error = Some(error_msg);
+                raw_entries.push(raw);
+                let current = &raw_entries[raw_entries.len() - 1].path;
+                progress(&WalkProgress::new(raw_entries.len(), current, &raw_entries));
             }
         }
     }
 
-    // Apply max_entries cap if configured
-    let total_entries = raw_entries.len();
-    if let Some(max) = config.max_entries {
-        if raw_entries.len() > max {
-            raw_entries.truncate(max);
-        }
+    // Cap at max_entries, then compute is_last and prefixes
+    let snapshot = TreeSnapshot::from_raw(raw_entries, config.max_entries);
+    tracing::debug!(
+        shown = snapshot.entries.len(),
+        total = snapshot.total_entries,
+        "walk finished"
+    );
+    snapshot
+}
+
+/// The entry's `st_mode` and `(uid, gid)`, or `None`s if its metadata
+/// cannot be read. With `follow_symlinks` off these are the link's own.
+#[cfg(unix)]
+fn entry_stat(entry: &DirEntry) -> (Option<u32>, Option<(u32, u32)>) {
+    use std::os::unix::fs::MetadataExt;
+    match entry.metadata() {
+        Ok(meta) => (Some(meta.mode()), Some((meta.uid(), meta.gid()))),
+        Err(_) => (None, None),
+    }
+}
+
+#[cfg(not(unix))]
+fn entry_stat(_entry: &DirEntry) -> (Option<u32>, Option<(u32, u32)>) {
+    (None, None)
+}
+
+/// Keep at most `max` entries by dropping the deepest levels first: levels
+/// are kept whole from the top while they fit, and the first level that
+/// does not fit is cut in tree order. The first level is always kept
+/// complete, even when it alone exceeds `max`.
+pub(super) fn truncate_deepest_first(entries: &mut Vec<RawEntry>, max: usize) {
+    if entries.len() <= max {
+        return;
+    }
+    let deepest = entries.iter().map(|e| e.depth).max().unwrap_or(0);
+    let mut per_depth = vec![0usize; deepest + 1];
+    for entry in entries.iter() {
+        per_depth[entry.depth] += 1;
+    }
+    let mut kept = 0;
+    let mut cut = 1;
+    while cut <= deepest && kept + per_depth[cut] <= max {
+        kept += per_depth[cut];
+        cut += 1;
     }
+    let mut budget = if cut == 1 { usize::MAX } else { max - kept };
+    entries.retain(|entry| match entry.depth.cmp(&cut) {
+        std::cmp::Ordering::Less => true,
+        std::cmp::Ordering::Equal if budget > 0 => {
+            budget -= 1;
+            true
+        }
+        _ => false,
+    });
+}
 
-    // Now compute is_last and prefixes
-    let entries = compute_tree_structure(&raw_entries);
-    TreeSnapshot {
-        entries,
-        total_entries,
+/// Whether a walked entry is hidden or matches an ignore pattern.
+/// The root itself (depth 0) is never excluded.
+pub(super) fn is_excluded(
+    entry: &DirEntry,
+    root: &Path,
+    show_hidden: bool,
+    ignore_patterns: &IgnoreSet,
+) -> bool {
+    // Always allow root
+    if entry.depth() == 0 {
+        return false;
+    }
+    // Filter hidden entries (prevents descending into .git, etc.)
+    if !show_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
     }
+    // Filter ignored patterns: match path relative to root so e.g. "target/**" works
+    let path_to_match = entry
+        .path()
+        .strip_prefix(root)
+        .unwrap_or_else(|_| entry.path());
+    ignore_patterns.is_match(path_to_match, entry.file_type().is_dir())
+}
+
+/// Whether `path` (below `root`) is walked: neither it nor any directory
+/// between it and `root` is hidden (unless `show_hidden`) or ignored. Lets
+/// watcher events be filtered the way the tree is.
+pub fn is_visible(root: &Path, path: &Path, config: &TreeConfig) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    relative
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .all(|p| {
+            let hidden = !config.show_hidden
+                && p.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            // Everything above `path` is a directory; `path` itself may be gone.
+            let is_dir = p != relative || path.is_dir();
+            !hidden && !config.ignore_patterns.is_match(p, is_dir)
+        })
 }
 
 /// Comparison function for walkdir sorting.
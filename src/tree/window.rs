@@ -0,0 +1,98 @@
+//! Paging through a built snapshot: the rows a view shows, with the
+//! directories enclosing the first of them.
+
+use super::{TreeEntry, TreeSnapshot};
+
+/// Consecutive rows of a snapshot, as `TreeSnapshot::window` returns them.
+///
+/// Each entry draws its own connectors (`TreeEntry::prefix`), so the rows
+/// render correctly on their own; `ancestors` names the directories the
+/// first row sits in, which scrolled away above it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Window<'a> {
+    /// Index of the first row in the snapshot.
+    pub offset: usize,
+    /// The rows, fewer than asked for at the end of the snapshot.
+    pub entries: &'a [TreeEntry],
+    /// The directories enclosing the first row, outermost first.
+    pub ancestors: Vec<&'a TreeEntry>,
+}
+
+impl TreeSnapshot {
+    /// Up to `count` rows starting at `offset`; empty past the end.
+    pub fn window(&self, offset: usize, count: usize) -> Window<'_> {
+        let start = offset.min(self.entries.len());
+        let end = start.saturating_add(count).min(self.entries.len());
+        Window {
+            offset: start,
+            entries: &self.entries[start..end],
+            ancestors: self
+                .ancestors(start)
+                .into_iter()
+                .map(|i| &self.entries[i])
+                .collect(),
+        }
+    }
+
+    /// Indices of the directories enclosing the entry at `index`,
+    /// outermost first. Empty for top-level entries and past the end.
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        let Some(entry) = self.entries.get(index) else {
+            return Vec::new();
+        };
+        let mut depth = entry.depth;
+        let mut ancestors = Vec::new();
+        for (i, e) in self.entries[..index].iter().enumerate().rev() {
+            if depth <= 1 {
+                break;
+            }
+            if e.depth < depth {
+                ancestors.push(i);
+                depth = e.depth;
+            }
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Number of entries discovered, including any cut by `max_entries`.
+    pub fn total(&self) -> usize {
+        self.total_entries
+    }
+
+    /// Number of entries kept, which `window` pages through.
+    pub fn visible(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_clamp_and_name_the_enclosing_directories() {
+        let snapshot = TreeSnapshot::from_outline([
+            (1, "src"),
+            (2, "tree"),
+            (3, "mod.rs"),
+            (3, "walk.rs"),
+            (2, "main.rs"),
+            (1, "README.md"),
+        ]);
+        let window = snapshot.window(3, 2);
+        assert_eq!(window.offset, 3);
+        let names: Vec<&str> = window.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["walk.rs", "main.rs"]);
+        let ancestors: Vec<&str> = window.ancestors.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(ancestors, ["src", "tree"]);
+
+        assert_eq!(snapshot.ancestors(4), [0]);
+        assert!(snapshot.ancestors(5).is_empty());
+        assert_eq!(snapshot.window(4, 10).entries.len(), 2);
+        let past = snapshot.window(99, 3);
+        assert_eq!((past.offset, past.entries.len()), (6, 0));
+        assert!(past.ancestors.is_empty());
+        assert_eq!((snapshot.total(), snapshot.visible()), (6, 6));
+    }
+}
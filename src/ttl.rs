@@ -0,0 +1,234 @@
+//! A keyed set whose entries expire after a per-entry or default lifetime.
+//!
+//! Underpins change highlights and anything else that needs "remember this
+//! for a while": fades, ghosts of deleted entries, rename tags, sticky marks.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// One entry of a `TtlSet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtlEntry<V> {
+    /// When the entry was (last) inserted.
+    pub inserted: Instant,
+    /// Lifetime override; the set's default applies otherwise.
+    pub ttl: Option<Duration>,
+    /// Data stored with the key (e.g. a highlight kind).
+    pub value: V,
+}
+
+/// Keys with an insertion time, an optional per-entry lifetime, and a value.
+/// A set without a default lifetime keeps entries until they are removed,
+/// unless they were inserted with their own lifetime.
+#[derive(Debug, Clone)]
+pub struct TtlSet<K, V = ()> {
+    entries: HashMap<K, TtlEntry<V>>,
+    default_ttl: Option<Duration>,
+}
+
+impl<K, V> Default for TtlSet<K, V> {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl<K, V> TtlSet<K, V> {
+    /// Entries expire `ttl` after insertion unless given their own lifetime.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            default_ttl: Some(ttl),
+        }
+    }
+
+    /// Entries never expire unless given their own lifetime.
+    pub fn unbounded() -> Self {
+        Self {
+            entries: HashMap::new(),
+            default_ttl: None,
+        }
+    }
+
+    /// The lifetime of entries inserted without their own.
+    pub fn default_ttl(&self) -> Option<Duration> {
+        self.default_ttl
+    }
+
+    /// Change the default lifetime; applies to existing entries too.
+    pub fn set_default_ttl(&mut self, ttl: Option<Duration>) {
+        self.default_ttl = ttl;
+    }
+
+    /// Effective lifetime of `entry` (`None` = never expires).
+    pub fn ttl_of(&self, entry: &TtlEntry<V>) -> Option<Duration> {
+        entry.ttl.or(self.default_ttl)
+    }
+
+    /// Time left for `entry` at `now`; `None` if it never expires.
+    pub fn remaining(&self, entry: &TtlEntry<V>, now: Instant) -> Option<Duration> {
+        self.ttl_of(entry)
+            .map(|ttl| ttl.saturating_sub(now.saturating_duration_since(entry.inserted)))
+    }
+
+    /// Whether `entry` is still alive at `now`.
+    pub fn is_live(&self, entry: &TtlEntry<V>, now: Instant) -> bool {
+        self.ttl_of(entry)
+            .is_none_or(|ttl| now.saturating_duration_since(entry.inserted) < ttl)
+    }
+
+    /// Number of stored entries, including expired ones not yet pruned.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The earliest instant at which a stored entry expires.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.entries
+            .values()
+            .filter_map(|e| self.ttl_of(e).map(|ttl| e.inserted + ttl))
+            .min()
+    }
+}
+
+impl<K: Eq + Hash, V> TtlSet<K, V> {
+    /// Insert (or refresh) `key` with the default lifetime.
+    pub fn insert(&mut self, key: K, value: V, now: Instant) {
+        self.insert_with_ttl(key, value, now, None);
+    }
+
+    /// Insert (or refresh) `key` with its own lifetime (`None` = default).
+    pub fn insert_with_ttl(&mut self, key: K, value: V, now: Instant, ttl: Option<Duration>) {
+        self.entries.insert(
+            key,
+            TtlEntry {
+                inserted: now,
+                ttl,
+                value,
+            },
+        );
+    }
+
+    /// The stored entry for `key`, even if expired but not yet pruned.
+    pub fn get<Q: Eq + Hash + ?Sized>(&self, key: &Q) -> Option<&TtlEntry<V>>
+    where
+        K: Borrow<Q>,
+    {
+        self.entries.get(key)
+    }
+
+    /// The entry for `key` if it is alive at `now`.
+    pub fn get_live<Q: Eq + Hash + ?Sized>(&self, key: &Q, now: Instant) -> Option<&TtlEntry<V>>
+    where
+        K: Borrow<Q>,
+    {
+        self.entries.get(key).filter(|e| self.is_live(e, now))
+    }
+
+    /// Whether `key` is alive at `now`.
+    pub fn contains<Q: Eq + Hash + ?Sized>(&self, key: &Q, now: Instant) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get_live(key, now).is_some()
+    }
+
+    /// Remove `key`, returning its entry.
+    pub fn remove<Q: Eq + Hash + ?Sized>(&mut self, key: &Q) -> Option<TtlEntry<V>>
+    where
+        K: Borrow<Q>,
+    {
+        self.entries.remove(key)
+    }
+
+    /// Drop entries that have expired at `now`.
+    pub fn prune(&mut self, now: Instant) {
+        let default_ttl = self.default_ttl;
+        self.entries.retain(|_, e| {
+            e.ttl
+                .or(default_ttl)
+                .is_none_or(|ttl| now.saturating_duration_since(e.inserted) < ttl)
+        });
+    }
+
+    /// Live entries in arbitrary order.
+    pub fn iter_live(&self, now: Instant) -> impl Iterator<Item = (&K, &TtlEntry<V>)> {
+        self.entries
+            .iter()
+            .filter(move |(_, e)| self.is_live(e, now))
+    }
+
+    /// Live entries ordered by remaining lifetime, soonest to expire first;
+    /// entries that never expire come last.
+    pub fn by_remaining(&self, now: Instant) -> Vec<(&K, &TtlEntry<V>, Option<Duration>)> {
+        let mut live: Vec<_> = self
+            .iter_live(now)
+            .map(|(k, e)| (k, e, self.remaining(e, now)))
+            .collect();
+        live.sort_by_key(|(_, _, remaining)| (remaining.is_none(), *remaining));
+        live
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_expire_after_default_or_own_ttl() {
+        let mut set = TtlSet::new(Duration::from_secs(3));
+        let t0 = Instant::now();
+        set.insert("a", (), t0);
+        set.insert_with_ttl("b", (), t0, Some(Duration::from_secs(10)));
+
+        let later = t0 + Duration::from_secs(5);
+        assert!(!set.contains(&"a", later));
+        assert!(set.contains(&"b", later));
+        set.prune(later);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.next_expiry(), Some(t0 + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn unbounded_set_keeps_entries() {
+        let mut set: TtlSet<&str> = TtlSet::unbounded();
+        let t0 = Instant::now();
+        set.insert("a", (), t0);
+        set.prune(t0 + Duration::from_secs(86_400));
+        assert!(set.contains(&"a", t0 + Duration::from_secs(86_400)));
+        assert_eq!(set.next_expiry(), None);
+    }
+
+    #[test]
+    fn iterates_by_remaining_lifetime() {
+        let mut set = TtlSet::unbounded();
+        let t0 = Instant::now();
+        set.insert("forever", 0, t0);
+        set.insert_with_ttl("long", 1, t0, Some(Duration::from_secs(9)));
+        set.insert_with_ttl("short", 2, t0, Some(Duration::from_secs(4)));
+
+        let order: Vec<_> = set
+            .by_remaining(t0 + Duration::from_secs(1))
+            .into_iter()
+            .map(|(k, e, remaining)| (*k, e.value, remaining.map(|d| d.as_secs())))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("short", 2, Some(3)),
+                ("long", 1, Some(8)),
+                ("forever", 0, None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! UI state kept between sessions: toggles, collapsed and loaded
+//! directories, and the selection, saved per watched root on exit and
+//! restored on the next launch in the same directory.
+
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What is restored. Paths are relative to the watched root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub show_hidden: bool,
+    pub show_summary: bool,
+    pub show_preview: bool,
+    pub show_heavy: bool,
+    pub show_stale: bool,
+    pub line_numbers: bool,
+    pub relative_line_numbers: bool,
+    pub flat: bool,
+    pub columns: bool,
+    pub follow: bool,
+    pub show_heatmap: bool,
+    pub show_changers: bool,
+    pub highlight_duration_secs: u64,
+    pub collapsed: Vec<PathBuf>,
+    /// Directories walked on demand with `--lazy`.
+    pub loaded: Vec<PathBuf>,
+    pub selected: Option<PathBuf>,
+    /// First visible row of the tree.
+    pub scroll_offset: usize,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            show_hidden: false,
+            show_summary: false,
+            show_preview: false,
+            show_heavy: false,
+            show_stale: false,
+            line_numbers: false,
+            relative_line_numbers: false,
+            flat: false,
+            columns: false,
+            follow: false,
+            show_heatmap: false,
+            show_changers: false,
+            highlight_duration_secs: 3,
+            collapsed: Vec::new(),
+            loaded: Vec::new(),
+            selected: None,
+            scroll_offset: 0,
+        }
+    }
+}
+
+impl UiState {
+    /// The state saved in `file`, if there is a readable one.
+    pub fn load(file: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(file).ok()?;
+        match toml::from_str(&text) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                tracing::warn!(file = %file.display(), error = %e, "ignoring unreadable UI state");
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, file: &Path) -> Result<(), String> {
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(file, text).map_err(|e| format!("{}: {}", file.display(), e))
+    }
+}
+
+/// Where the UI state for `root` is kept:
+/// `$XDG_STATE_HOME/livetree/ui/<dir name>-<hash of root>.toml`.
+pub fn state_path(root: &Path) -> Option<PathBuf> {
+    paths::state_file(root, "ui").map(|file| file.with_extension("toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips_and_tolerates_missing_keys() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("ui").join("state.toml");
+        let state = UiState {
+            show_hidden: true,
+            collapsed: vec![PathBuf::from("src/tree")],
+            selected: Some(PathBuf::from("src/main.rs")),
+            scroll_offset: 4,
+            ..UiState::default()
+        };
+        state.save(&file).unwrap();
+        assert_eq!(UiState::load(&file), Some(state));
+
+        std::fs::write(&file, "line_numbers = true\n").unwrap();
+        let partial = UiState::load(&file).unwrap();
+        assert!(partial.line_numbers);
+        assert_eq!(partial.highlight_duration_secs, 3);
+        assert_eq!(UiState::load(&dir.path().join("missing.toml")), None);
+    }
+}
@@ -0,0 +1,634 @@
+//! Startup checks, run before the terminal is touched so that every problem
+//! with the arguments, the watched root, and the config file is reported at
+//! once — each with a suggested fix — instead of failing on the first.
+
+use crate::cli::Args;
+use crate::config::{default_config_path, Config};
+use crate::report::Report;
+use crate::schema;
+use crate::tree::{parse_ignore_file, IgnorePattern};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How serious a startup problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// livetree will not start.
+    Error,
+    /// livetree starts, but probably not the way the user expects.
+    Warning,
+}
+
+/// What a startup problem is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    /// The watched root directory.
+    Root,
+    /// Command-line flags or the config file.
+    Usage,
+}
+
+/// A startup problem with an optional suggested fix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    pub subject: Subject,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Problem {
+    fn error(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            subject: Subject::Usage,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn warning(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            subject: Subject::Usage,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+impl Problem {
+    fn about_root(self) -> Self {
+        Self {
+            subject: Subject::Root,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{label}: {}", self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything resolved during validation that the rest of startup needs.
+#[derive(Debug)]
+pub struct Startup {
+    /// The canonicalized root directory.
+    pub root: PathBuf,
+    /// The file named on the command line, when it was a file: `root` is
+    /// its directory, and the live view selects and follows it.
+    pub focus: Option<PathBuf>,
+    /// The loaded configuration file (or defaults).
+    pub config: Config,
+    /// The report requested with `--report`, if any.
+    pub report: Option<Report>,
+    /// The `-I` patterns followed by those in `--ignore-file`.
+    pub ignore: Vec<String>,
+    /// The canonicalized `--split` directory.
+    pub split: Option<PathBuf>,
+}
+
+/// Run every startup check. Returns `Some(Startup)` only if no problem is an
+/// error; the problem list includes warnings either way.
+pub fn validate(args: &Args) -> (Option<Startup>, Vec<Problem>) {
+    let mut problems = Vec::new();
+
+    let (root, focus) = if let Some(remote) = &args.remote {
+        problems.extend(check_remote(remote.flag(), args));
+        (Some(remote.path.clone()), None)
+    } else if args.connect.is_some() {
+        problems.extend(check_remote("--connect", args));
+        // The server says which root it watches once connected.
+        (Some(PathBuf::new()), None)
+    } else {
+        match check_root(&args.path) {
+            Ok((root, focus)) => {
+                problems.extend(check_filesystem(&root));
+                (Some(root), focus)
+            }
+            Err(problem) => {
+                problems.push(problem);
+                (None, None)
+            }
+        }
+    };
+    if let Some(file) = &focus {
+        problems.extend(check_focus(file, args));
+    }
+    let split = args
+        .split
+        .as_deref()
+        .and_then(|dir| check_split(dir).map_err(|p| problems.push(p)).ok());
+
+    let file = args.config.clone().or_else(default_config_path);
+    let config = match file.filter(|_| !args.safe_mode) {
+        Some(file) => {
+            let issues = check_config(&file);
+            let usable = !issues.iter().any(|p| p.severity == Severity::Error);
+            problems.extend(issues);
+            usable
+                .then(|| {
+                    Config::load(&file)
+                        .map_err(|e| problems.push(Problem::error(e, CONFIG_HINT)))
+                        .ok()
+                })
+                .flatten()
+        }
+        None => Some(Config::default()),
+    };
+
+    let report = match &args.report {
+        None => None,
+        Some(kind) => match Report::parse(kind, args.top, args.older_than) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                problems.push(Problem::error(
+                    e,
+                    "use `--report heavy [--top N]` or `--report stale --older-than <DURATION>`",
+                ));
+                None
+            }
+        },
+    };
+
+    problems.extend(check_ignore_patterns(&args.ignore));
+    let mut ignore = args.ignore.clone();
+    if let Some(file) = &args.ignore_file {
+        match check_ignore_file(file) {
+            Ok(patterns) => ignore.extend(patterns),
+            Err(issues) => problems.extend(issues),
+        }
+    }
+    problems.extend(check_flags(args, report.as_ref()));
+
+    let has_errors = problems.iter().any(|p| p.severity == Severity::Error);
+    let startup = match (root, config) {
+        (Some(root), Some(config)) if !has_errors => Some(Startup {
+            root,
+            focus,
+            config,
+            report,
+            ignore,
+            split,
+        }),
+        _ => None,
+    };
+    (startup, problems)
+}
+
+const CONFIG_HINT: &str = "fix the file, or pass --config with another file";
+
+/// Schema problems in the config file at `file`, located by line.
+pub fn check_config(file: &Path) -> Vec<Problem> {
+    match schema::check_file(file) {
+        Ok(issues) => issues
+            .into_iter()
+            .map(|issue| {
+                let message = match issue.line {
+                    Some(line) => format!("{}:{line}: {}", file.display(), issue.message),
+                    None => format!("{}: {}", file.display(), issue.message),
+                };
+                if issue.warning {
+                    Problem::warning(
+                        message,
+                        "remove the setting or change the one it contradicts",
+                    )
+                } else {
+                    Problem::error(message, CONFIG_HINT)
+                }
+            })
+            .collect(),
+        Err(e) => vec![Problem::error(e, CONFIG_HINT)],
+    }
+}
+
+/// The root must exist, be a directory, and be readable. A file stands for
+/// its directory and is returned as the second value, to be focused.
+pub fn check_root(path: &Path) -> Result<(PathBuf, Option<PathBuf>), Problem> {
+    let path = path.canonicalize().map_err(|e| {
+        Problem::error(
+            format!("{}: failed to resolve path: {e}", path.display()),
+            "check the spelling, or omit the path to watch the current directory",
+        )
+        .about_root()
+    })?;
+    let (root, focus) = if path.is_dir() {
+        (path, None)
+    } else {
+        match path.parent() {
+            Some(parent) => (parent.to_path_buf(), Some(path)),
+            None => {
+                return Err(Problem::error(
+                    format!("{}: Not a directory", path.display()),
+                    "pass a directory",
+                )
+                .about_root())
+            }
+        }
+    };
+    if let Err(e) = std::fs::read_dir(&root) {
+        return Err(Problem::error(
+            format!("{}: cannot read directory: {e}", root.display()),
+            "check its permissions, or run as a user who can read it",
+        )
+        .about_root());
+    }
+    Ok((root, focus))
+}
+
+/// The `--split` directory must exist, be a directory, and be readable.
+fn check_split(path: &Path) -> Result<PathBuf, Problem> {
+    let hint = "pass a directory to show beside the tree";
+    let dir = path
+        .canonicalize()
+        .map_err(|e| Problem::error(format!("--split {}: {e}", path.display()), hint))?;
+    if !dir.is_dir() {
+        return Err(Problem::error(
+            format!("--split {}: Not a directory", dir.display()),
+            hint,
+        ));
+    }
+    std::fs::read_dir(&dir).map_err(|e| {
+        Problem::error(
+            format!("--split {}: cannot read directory: {e}", dir.display()),
+            "check its permissions, or run as a user who can read it",
+        )
+    })?;
+    Ok(dir)
+}
+
+/// A file argument only works for the live view; the other modes report on
+/// whole directories.
+fn check_focus(file: &Path, args: &Args) -> Vec<Problem> {
+    let mode = if args.report.is_some() {
+        "--report"
+    } else if args.export.is_some() {
+        "--export"
+    } else if args.exit_on_change {
+        "--exit-on-change"
+    } else if args.stream.is_some() {
+        "--stream"
+    } else {
+        return Vec::new();
+    };
+    let hint = match file.parent() {
+        Some(parent) => format!("pass its directory instead: {}", parent.display()),
+        None => "pass a directory".to_string(),
+    };
+    vec![Problem::error(
+        format!("{}: {mode} needs a directory, not a file", file.display()),
+        hint,
+    )]
+}
+
+/// A tree from `remote` (`--remote`, `--docker`, or `--connect`) is only
+/// shown in the live view, and only by name: the other modes and the
+/// columns and views that read files need it local.
+fn check_remote(remote: &str, args: &Args) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let exclusive = [
+        ("--report", args.report.is_some()),
+        ("--export", args.export.is_some()),
+        ("--exit-on-change", args.exit_on_change),
+        ("--stream", args.stream.is_some()),
+        ("--lazy", args.lazy.is_some()),
+    ];
+    for (flag, _) in exclusive.iter().filter(|(_, set)| *set) {
+        problems.push(Problem::error(
+            format!("{flag} does not work with {remote}"),
+            format!("drop {flag}, or run livetree where the files are"),
+        ));
+    }
+    let ignored = [
+        ("--summary", args.summary),
+        ("--hard-links", args.hard_links),
+        ("--permissions", args.permissions),
+        ("--owner", args.owner),
+    ];
+    for (flag, _) in ignored.iter().filter(|(_, set)| *set) {
+        problems.push(Problem::warning(
+            format!("{flag} has no effect with {remote}, which only lists names"),
+            format!("drop {flag}"),
+        ));
+    }
+    if args.poll_interval.is_zero() && args.remote.is_some() {
+        problems.push(Problem::error(
+            "--poll-interval 0 would list the remote tree without pause",
+            "use an interval of a second or more, e.g. --poll-interval 5s",
+        ));
+    }
+    problems
+}
+
+/// Every `-I` pattern must be a valid glob.
+pub fn check_ignore_patterns(patterns: &[String]) -> Vec<Problem> {
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            IgnorePattern::parse(pattern).err().map(|e| {
+                Problem::error(
+                    format!("invalid ignore pattern '{pattern}': {}", e.kind()),
+                    "quote the pattern and check its brackets and braces, e.g. -I '*.log'",
+                )
+            })
+        })
+        .collect()
+}
+
+/// The patterns in an `--ignore-file`, or why it cannot be used: it is
+/// unreadable, or has invalid globs (reported with their line numbers).
+pub fn check_ignore_file(file: &Path) -> Result<Vec<String>, Vec<Problem>> {
+    let text = std::fs::read_to_string(file).map_err(|e| {
+        vec![Problem::error(
+            format!("cannot read ignore file {}: {e}", file.display()),
+            "check the path passed to --ignore-file",
+        )]
+    })?;
+    let patterns = parse_ignore_file(&text);
+    let problems: Vec<Problem> = patterns
+        .iter()
+        .filter_map(|(line, pattern)| {
+            IgnorePattern::parse(pattern).err().map(|e| {
+                Problem::error(
+                    format!(
+                        "{}:{line}: invalid ignore pattern '{pattern}': {}",
+                        file.display(),
+                        e.kind()
+                    ),
+                    "check its brackets and braces, or comment the line out with #",
+                )
+            })
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(patterns.into_iter().map(|(_, pattern)| pattern).collect())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Flag values and combinations that cannot do what the user wants.
+pub fn check_flags(args: &Args, report: Option<&Report>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if args.max_depth == Some(0) {
+        problems.push(Problem::error(
+            "-L 0 would show nothing below the root",
+            "use -L 1 or higher, or omit -L for unlimited depth",
+        ));
+    }
+    if args.max_entries == 0 {
+        problems.push(Problem::error(
+            "--max-entries 0 would show an empty tree",
+            "use a positive limit, e.g. --max-entries 1000",
+        ));
+    }
+    if args.lazy == Some(0) {
+        problems.push(Problem::error(
+            "--lazy 0 would not even list the root's entries",
+            "use --lazy 1 or higher",
+        ));
+    }
+    if args.dirs_only && matches!(report, Some(Report::Stale { .. })) {
+        problems.push(Problem::error(
+            "--report stale lists files, but --dirs-only hides all files",
+            "drop --dirs-only",
+        ));
+    }
+    if args.export.is_some() && report.is_some() {
+        problems.push(Problem::error(
+            "--export and --report each print once and exit",
+            "use one of them",
+        ));
+    }
+    if args.safe_mode && args.config.is_some() {
+        problems.push(Problem::warning(
+            "--config is ignored with --safe-mode",
+            "drop --safe-mode to use the config file",
+        ));
+    }
+    if args.exit_on_change && (report.is_some() || args.export.is_some()) {
+        problems.push(Problem::error(
+            "--exit-on-change waits for a change, but --export and --report print once and exit",
+            "use one of them",
+        ));
+    }
+    if args.stream.is_some() && (args.exit_on_change || report.is_some() || args.export.is_some()) {
+        problems.push(Problem::error(
+            "--stream prints changes until interrupted, but --exit-on-change, --export, and --report exit early",
+            "use one of them",
+        ));
+    }
+    if args.serve.is_some()
+        && (args.exit_on_change
+            || args.stream.is_some()
+            || report.is_some()
+            || args.export.is_some())
+    {
+        problems.push(Problem::error(
+            "--serve sends changes until interrupted, but --exit-on-change, --stream, --export, and --report do something else",
+            "use one of them",
+        ));
+    }
+    if args.control.is_some() {
+        let headless = [
+            ("--serve", args.serve.is_some()),
+            ("--stream", args.stream.is_some()),
+            ("--exit-on-change", args.exit_on_change),
+            ("--export", args.export.is_some()),
+            ("--report", report.is_some()),
+        ];
+        if let Some((flag, _)) = headless.iter().find(|(_, set)| *set) {
+            problems.push(Problem::warning(
+                format!(
+                    "--control has no effect with {flag}, which runs without the interactive view"
+                ),
+                "drop --control",
+            ));
+        }
+    }
+    if args.lazy.is_some() && args.serve.is_some() {
+        problems.push(Problem::warning(
+            "--lazy has no effect with --serve, which sends the whole tree",
+            "drop --lazy",
+        ));
+    }
+    if args.lazy.is_some() && (report.is_some() || args.export.is_some()) {
+        problems.push(Problem::warning(
+            "--lazy has no effect with --report or --export, which walk the whole tree",
+            "drop --lazy",
+        ));
+    }
+    if args.hard_links && cfg!(not(unix)) {
+        problems.push(Problem::warning(
+            "--hard-links needs inode numbers, which only Unix systems report",
+            "drop --hard-links",
+        ));
+    }
+    if args.permissions && cfg!(not(unix)) {
+        problems.push(Problem::warning(
+            "--permissions needs Unix mode bits, which this platform does not report",
+            "drop --permissions",
+        ));
+    }
+    if args.owner && cfg!(not(unix)) {
+        problems.push(Problem::warning(
+            "--owner needs Unix user and group ids, which this platform does not report",
+            "drop --owner",
+        ));
+    }
+    if args.summary && report.is_some() {
+        problems.push(Problem::warning(
+            "--summary has no effect with --report",
+            "drop --summary, or drop --report to start the live view",
+        ));
+    }
+    problems
+}
+
+/// Filesystem types where the native watcher only sees changes made by
+/// this machine (network and host-shared mounts).
+const REMOTE_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "9p",
+    "afs",
+    "ceph",
+    "vboxsf",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "fuse.glusterfs",
+    "davfs",
+];
+
+/// Warn when the root is on a filesystem the native backend cannot fully watch.
+pub fn check_filesystem(root: &Path) -> Option<Problem> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let fs_type = fs_type_of(root, &mounts)?;
+    REMOTE_FS_TYPES.contains(&fs_type.as_str()).then(|| {
+        Problem::warning(
+            format!(
+                "{} is on a {fs_type} filesystem; changes made from other machines will not be detected",
+                root.display()
+            ),
+            "run livetree on the machine that owns the files",
+        )
+        .about_root()
+    })
+}
+
+/// Filesystem type of the mount containing `path`, from `/proc/mounts`-style text.
+fn fs_type_of(path: &Path, mounts: &str) -> Option<String> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let mount_point = unescape_mount_path(fields.next()?);
+            let fs_type = fields.next()?;
+            path.starts_with(&mount_point)
+                .then(|| (mount_point, fs_type.to_string()))
+        })
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Decode the octal escapes (`\040` for space, …) used in `/proc/mounts`.
+fn unescape_mount_path(field: &str) -> PathBuf {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        let escaped = rest.get(pos + 1..pos + 4);
+        match escaped.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                out.push(char::from(byte));
+                rest = &rest[pos + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    PathBuf::from(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTS: &str = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+server:/export /mnt/share nfs4 rw 0 0
+/dev/sdb1 /mnt/share/local\\040disk ext4 rw 0 0
+";
+
+    #[test]
+    fn picks_longest_matching_mount() {
+        assert_eq!(
+            fs_type_of(Path::new("/home/me"), MOUNTS).as_deref(),
+            Some("ext4")
+        );
+        assert_eq!(
+            fs_type_of(Path::new("/mnt/share/project"), MOUNTS).as_deref(),
+            Some("nfs4")
+        );
+        assert_eq!(
+            fs_type_of(Path::new("/mnt/share/local disk/x"), MOUNTS).as_deref(),
+            Some("ext4")
+        );
+    }
+
+    #[test]
+    fn a_file_root_watches_its_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let file = root.join("app.log");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(check_root(&file).unwrap(), (root.clone(), Some(file)));
+        assert_eq!(check_root(&root).unwrap(), (root, None));
+    }
+
+    #[test]
+    fn ignore_file_globs_are_checked_by_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join(".livetreeignore");
+        std::fs::write(&file, "# comment\ntarget\n\na[b\n").unwrap();
+        let problems = check_ignore_file(&file).unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0]
+            .message
+            .contains(":4: invalid ignore pattern 'a[b'"));
+
+        std::fs::write(&file, "target\n*.log\n").unwrap();
+        assert_eq!(check_ignore_file(&file).unwrap(), ["target", "*.log"]);
+        assert!(check_ignore_file(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn invalid_glob_is_reported() {
+        let problems = check_ignore_patterns(&["*.log".to_string(), "a[b".to_string()]);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("a[b"));
+    }
+
+    #[test]
+    fn problem_display_includes_hint() {
+        let p = Problem::error("bad", "do better");
+        assert_eq!(p.to_string(), "error: bad\n  hint: do better");
+    }
+}
@@ -1,28 +1,129 @@
-//! Filesystem watcher using `notify-debouncer-full` with crossbeam channels.
+//! Filesystem watcher using `notify-debouncer-full` with crossbeam channels,
+//! or with the `async` feature, a `Stream` for tokio applications (`stream`).
 
-use crossbeam_channel::{self, Receiver, Sender};
+#[cfg(feature = "async")]
+pub mod stream;
+
+use crossbeam_channel::{self, Receiver, RecvTimeoutError, Sender};
+use notify::event::{AccessKind, AccessMode, EventKind, ModifyKind};
 use notify::RecommendedWatcher;
 use notify::RecursiveMode;
-use notify_debouncer_full::{new_debouncer, Debouncer, RecommendedCache};
-use std::collections::HashSet;
+use notify_debouncer_full::{new_debouncer, DebouncedEvent, Debouncer, RecommendedCache};
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
+/// What happened to a changed path, as far as the backend can tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// The path was created.
+    Created,
+    /// File contents changed (or the backend could not be more specific).
+    Modified,
+    /// Only metadata changed (permissions, timestamps, e.g. `chmod`/`touch`).
+    Metadata,
+    /// The path was removed.
+    Removed,
+    /// The path was renamed (reported for both the old and the new name).
+    Renamed,
+}
+
+impl ChangeKind {
+    /// Map a `notify` event kind; returns `None` for non-mutating events
+    /// such as opening or reading a file.
+    fn from_event_kind(kind: &EventKind) -> Option<Self> {
+        match kind {
+            EventKind::Create(_) => Some(ChangeKind::Created),
+            EventKind::Remove(_) => Some(ChangeKind::Removed),
+            EventKind::Modify(ModifyKind::Metadata(_)) => Some(ChangeKind::Metadata),
+            EventKind::Modify(ModifyKind::Name(_)) => Some(ChangeKind::Renamed),
+            EventKind::Modify(_) => Some(ChangeKind::Modified),
+            EventKind::Access(AccessKind::Close(AccessMode::Write)) => Some(ChangeKind::Modified),
+            EventKind::Access(_) => None,
+            EventKind::Any | EventKind::Other => Some(ChangeKind::Modified),
+        }
+    }
+
+    /// Whether only metadata (not contents or structure) changed.
+    pub fn is_metadata_only(self) -> bool {
+        self == ChangeKind::Metadata
+    }
+}
+
+/// A single changed path together with the kind of change.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PathChange {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+}
+
 /// Events emitted by the filesystem watcher.
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum WatchEvent {
-    /// One or more files/directories changed, with their paths.
-    Changed(Vec<PathBuf>),
+    /// One or more files/directories changed, with their paths and change kinds.
+    Changed(Vec<PathChange>),
     /// The watched root directory was deleted.
     RootDeleted,
+    /// The root turned out to be a different directory (e.g. its volume was
+    /// remounted) and the watcher was re-created on it; earlier events may
+    /// have been missed.
+    Reattached,
     /// A watcher error occurred.
     Error(String),
 }
 
+/// Options for the filesystem watcher.
+///
+/// notify fixes the backends' own tuning (FSEvents latency, the
+/// ReadDirectoryChangesW buffer size), so only the debounce is offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatcherOptions {
+    /// How long events for a path are collected before they are sent.
+    pub debounce: Duration,
+}
+
+impl WatcherOptions {
+    /// Options with a `debounce_ms` debounce.
+    pub fn new(debounce_ms: u64) -> Self {
+        Self {
+            debounce: Duration::from_millis(debounce_ms),
+        }
+    }
+}
+
+impl Default for WatcherOptions {
+    fn default() -> Self {
+        Self::new(200)
+    }
+}
+
 /// Handle for the active watcher; must be kept alive while receiving events.
 pub type WatcherHandle = Debouncer<RecommendedWatcher, RecommendedCache>;
 
+/// Name of the platform's filesystem notification backend.
+pub fn backend_name() -> &'static str {
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        "inotify"
+    } else if cfg!(target_os = "macos") {
+        "FSEvents"
+    } else if cfg!(windows) {
+        "ReadDirectoryChangesW"
+    } else if cfg!(any(
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    )) {
+        "kqueue"
+    } else {
+        "polling"
+    }
+}
+
 /// Trait abstraction for filesystem watching so it can be swapped or mocked.
 #[allow(dead_code)]
 pub trait FsWatcher {
@@ -38,42 +139,63 @@ pub fn start_watcher(
     path: &Path,
     debounce_ms: u64,
 ) -> Result<(WatcherHandle, Receiver<WatchEvent>), String> {
+    start_watcher_with(path, &WatcherOptions::new(debounce_ms))
+}
+
+/// `start_watcher` with backend tuning.
+pub fn start_watcher_with(
+    path: &Path,
+    options: &WatcherOptions,
+) -> Result<(WatcherHandle, Receiver<WatchEvent>), String> {
+    let (tx, rx): (Sender<WatchEvent>, Receiver<WatchEvent>) = crossbeam_channel::unbounded();
+    let debouncer = start_with(path, options, move |event| {
+        let _ = tx.send(event);
+    })?;
+    Ok((debouncer, rx))
+}
+
+/// Start watching `path`, handing every event to `send` on the debouncer's
+/// thread.
+pub(crate) fn start_with(
+    path: &Path,
+    options: &WatcherOptions,
+    send: impl Fn(WatchEvent) + Send + 'static,
+) -> Result<WatcherHandle, String> {
     // Verify path exists before attempting to watch
     if !path.exists() {
         return Err(format!("Path does not exist: {}", path.display()));
     }
-
-    let (tx, rx): (Sender<WatchEvent>, Receiver<WatchEvent>) = crossbeam_channel::unbounded();
     let root_path = path.to_path_buf();
 
     let mut debouncer = new_debouncer(
-        Duration::from_millis(debounce_ms),
+        options.debounce,
         None,
-        move |result: Result<Vec<notify_debouncer_full::DebouncedEvent>, Vec<notify::Error>>| {
+        move |result: Result<Vec<DebouncedEvent>, Vec<notify::Error>>| {
+            let _span = tracing::debug_span!("watcher_batch").entered();
             match result {
                 Ok(events) => {
+                    tracing::trace!(events = events.len(), "debounced batch");
                     // Only treat as root deleted when metadata says "not found"
                     match std::fs::metadata(&root_path) {
                         Ok(_) => {
-                            let paths: Vec<PathBuf> = events
-                                .iter()
-                                .flat_map(|e| e.paths.iter().cloned())
-                                .collect::<HashSet<_>>()
-                                .into_iter()
-                                .collect();
-                            let _ = tx.send(WatchEvent::Changed(paths));
+                            let changes = collect_changes(&events);
+                            if !changes.is_empty() {
+                                send(WatchEvent::Changed(changes));
+                            }
                         }
                         Err(e) if e.kind() == ErrorKind::NotFound => {
-                            let _ = tx.send(WatchEvent::RootDeleted);
+                            send(WatchEvent::RootDeleted);
                         }
                         Err(e) => {
-                            let _ = tx.send(WatchEvent::Error(format!("{}", e)));
+                            tracing::warn!(error = %e, "cannot read watched root");
+                            send(WatchEvent::Error(format!("{}", e)));
                         }
                     }
                 }
                 Err(errors) => {
                     for error in errors {
-                        let _ = tx.send(WatchEvent::Error(format!("{}", error)));
+                        tracing::warn!(%error, "watcher error");
+                        send(WatchEvent::Error(format!("{}", error)));
                     }
                 }
             }
@@ -85,7 +207,130 @@ pub fn start_watcher(
         .watch(path, RecursiveMode::Recursive)
         .map_err(|e| format!("Failed to watch path {}: {}", path.display(), e))?;
 
-    Ok((debouncer, rx))
+    Ok(debouncer)
+}
+
+/// How often a supervised watcher checks that its root is still the
+/// directory it started watching.
+pub const REVALIDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A watcher that re-creates itself when its root is replaced by another
+/// directory at the same path, such as after a remount, and would otherwise
+/// go blind. Dropping it stops watching and closes the event channel.
+pub struct SupervisedWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for SupervisedWatcher {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Start watching `path` like `start_watcher`, checking every `interval`
+/// that the root is still the same directory. When it is not, the watcher
+/// is re-created on the new one and `WatchEvent::Reattached` is sent.
+pub fn start_supervised(
+    path: &Path,
+    debounce_ms: u64,
+    interval: Duration,
+) -> Result<(SupervisedWatcher, Receiver<WatchEvent>), String> {
+    start_supervised_with(path, &WatcherOptions::new(debounce_ms), interval)
+}
+
+/// `start_supervised` with backend tuning.
+pub fn start_supervised_with(
+    path: &Path,
+    options: &WatcherOptions,
+    interval: Duration,
+) -> Result<(SupervisedWatcher, Receiver<WatchEvent>), String> {
+    let options = *options;
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let forward = |tx: Sender<WatchEvent>| {
+        move |event| {
+            let _ = tx.send(event);
+        }
+    };
+    let mut watcher = start_with(path, &options, forward(tx.clone()))?;
+    let mut identity = root_identity(path);
+    let (stop, stopped) = crossbeam_channel::bounded::<()>(0);
+    let root = path.to_path_buf();
+    let thread = thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            let current = root_identity(&root);
+            // A missing root is reported by the watcher itself.
+            if current.is_none() || current == identity {
+                continue;
+            }
+            identity = current;
+            tracing::info!(root = %root.display(), "root replaced, re-creating watcher");
+            match start_with(&root, &options, forward(tx.clone())) {
+                Ok(new) => {
+                    watcher = new;
+                    let _ = tx.send(WatchEvent::Reattached);
+                }
+                Err(e) => {
+                    let _ = tx.send(WatchEvent::Error(e));
+                }
+            }
+        }
+        drop(watcher);
+    });
+    Ok((
+        SupervisedWatcher {
+            stop: Some(stop),
+            thread: Some(thread),
+        },
+        rx,
+    ))
+}
+
+/// Device and inode of `path`, which change when another filesystem is
+/// mounted there or the directory is replaced.
+#[cfg(unix)]
+fn root_identity(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+/// Not tracked on this platform, so the watcher is never re-created.
+#[cfg(not(unix))]
+fn root_identity(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Flatten a debounced batch into one change per path.
+///
+/// The last event for a path wins, except that a metadata-only event never
+/// overrides a content or structural change in the same batch, and a
+/// creation is kept so short-lived (temp) files can still be recognised.
+fn collect_changes(events: &[DebouncedEvent]) -> Vec<PathChange> {
+    let mut by_path: HashMap<PathBuf, ChangeKind> = HashMap::new();
+    for event in events {
+        let Some(kind) = ChangeKind::from_event_kind(&event.kind) else {
+            continue;
+        };
+        for path in &event.paths {
+            by_path
+                .entry(path.clone())
+                .and_modify(|existing| {
+                    let keep = *existing == ChangeKind::Created
+                        || (kind.is_metadata_only() && !existing.is_metadata_only());
+                    if !keep {
+                        *existing = kind;
+                    }
+                })
+                .or_insert(kind);
+        }
+    }
+    by_path
+        .into_iter()
+        .map(|(path, kind)| PathChange { path, kind })
+        .collect()
 }
 
 /// Default watcher implementation backed by `notify` + `notify-debouncer-full`.
@@ -101,3 +346,50 @@ impl FsWatcher for NotifyFsWatcher {
         start_watcher(path, debounce_ms)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, MetadataKind, RenameMode};
+    use notify::Event;
+    use std::time::Instant;
+
+    fn debounced(kind: EventKind, path: &str) -> DebouncedEvent {
+        DebouncedEvent::new(
+            Event::new(kind).add_path(PathBuf::from(path)),
+            Instant::now(),
+        )
+    }
+
+    #[test]
+    fn metadata_does_not_override_content_change() {
+        let events = [
+            debounced(EventKind::Modify(ModifyKind::Data(DataChange::Any)), "/a"),
+            debounced(
+                EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)),
+                "/a",
+            ),
+            debounced(
+                EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)),
+                "/b",
+            ),
+            debounced(EventKind::Create(CreateKind::File), "/c"),
+            debounced(EventKind::Modify(ModifyKind::Name(RenameMode::From)), "/c"),
+            debounced(EventKind::Access(AccessKind::Read), "/d"),
+        ];
+        let mut changes = collect_changes(&events);
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let kinds: Vec<(&str, ChangeKind)> = changes
+            .iter()
+            .map(|c| (c.path.to_str().unwrap(), c.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("/a", ChangeKind::Modified),
+                ("/b", ChangeKind::Metadata),
+                ("/c", ChangeKind::Created),
+            ]
+        );
+    }
+}
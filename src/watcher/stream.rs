@@ -0,0 +1,46 @@
+//! Watch events as a `futures_core::Stream`, for tokio applications.
+//!
+//! The debouncer already runs on its own thread; events are handed straight
+//! to a tokio channel from there, so no extra blocking thread is needed.
+
+use super::{start_with, WatchEvent, WatcherHandle, WatcherOptions};
+use futures_core::Stream;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Debounced events for a watched directory. Watching stops when the stream
+/// is dropped.
+pub struct WatchStream {
+    rx: UnboundedReceiver<WatchEvent>,
+    _watcher: WatcherHandle,
+}
+
+impl WatchStream {
+    /// The next event, or `None` once the watcher has stopped.
+    pub async fn next(&mut self) -> Option<WatchEvent> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for WatchStream {
+    type Item = WatchEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<WatchEvent>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Start watching `path` recursively; the async counterpart of
+/// `watcher::start_watcher`. Does not need to be called inside a runtime.
+pub fn watch(path: &Path, debounce_ms: u64) -> Result<WatchStream, String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let watcher = start_with(path, &WatcherOptions::new(debounce_ms), move |event| {
+        let _ = tx.send(event);
+    })?;
+    Ok(WatchStream {
+        rx,
+        _watcher: watcher,
+    })
+}
@@ -0,0 +1,198 @@
+//! Embeddable tree pane for other ratatui applications.
+//!
+//! `LiveTreeWidget` draws a `TreeSnapshot` (for example from
+//! `live::LiveTree::snapshot`) the way the livetree binary does, with change
+//! highlights and a selection; `LiveTreeState` keeps the scroll offset and
+//! selection between frames.
+
+use crate::highlight::Highlights;
+use crate::render::{tree_to_lines, RenderConfig};
+use crate::tree::{TreeEntry, TreeSnapshot};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Paragraph, StatefulWidget, Widget};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Scroll offset and selection of a `LiveTreeWidget`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveTreeState {
+    offset: usize,
+    selected: Option<usize>,
+}
+
+impl LiveTreeState {
+    /// Index of the first visible entry.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Index of the selected entry, if any. Clamped to the snapshot on render.
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+    }
+
+    /// Move the selection down one entry (selecting the first if none is).
+    pub fn select_next(&mut self) {
+        self.selected = Some(self.selected.map_or(0, |i| i.saturating_add(1)));
+    }
+
+    /// Move the selection up one entry (selecting the first if none is).
+    pub fn select_previous(&mut self) {
+        self.selected = Some(self.selected.map_or(0, |i| i.saturating_sub(1)));
+    }
+
+    /// The selected entry of `snapshot`.
+    pub fn selected_entry<'s>(&self, snapshot: &'s TreeSnapshot) -> Option<&'s TreeEntry> {
+        snapshot.entries.get(self.selected?)
+    }
+}
+
+/// A tree pane drawing one snapshot; see the module docs.
+pub struct LiveTreeWidget<'a> {
+    snapshot: &'a TreeSnapshot,
+    highlights: Option<&'a dyn Highlights>,
+    block: Option<Block<'a>>,
+    selected_style: Style,
+    config: RenderConfig,
+}
+
+impl<'a> LiveTreeWidget<'a> {
+    /// A pane for `snapshot` drawn with the default `RenderConfig`, no
+    /// highlights, and the selection shown in reverse video.
+    pub fn new(snapshot: &'a TreeSnapshot) -> Self {
+        Self {
+            snapshot,
+            highlights: None,
+            block: None,
+            selected_style: Style::new().add_modifier(Modifier::REVERSED),
+            config: RenderConfig::default(),
+        }
+    }
+
+    /// Highlight changed entries, e.g. with a `HighlightTracker`'s
+    /// `active()` map.
+    pub fn highlights(mut self, highlights: &'a dyn Highlights) -> Self {
+        self.highlights = Some(highlights);
+        self
+    }
+
+    /// Draw the tree inside `block`.
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Style patched onto the selected entry's line.
+    pub fn selected_style(mut self, style: Style) -> Self {
+        self.selected_style = style;
+        self
+    }
+
+    /// Colors, charset, theme, and columns for the tree lines. Its
+    /// `terminal_width` is replaced by the width of the drawing area.
+    pub fn render_config(mut self, config: RenderConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl StatefulWidget for LiveTreeWidget<'_> {
+    type State = LiveTreeState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut LiveTreeState) {
+        let inner = match &self.block {
+            Some(block) => block.inner(area),
+            None => area,
+        };
+        let config = RenderConfig {
+            terminal_width: inner.width,
+            ..self.config
+        };
+        let entries = &self.snapshot.entries;
+        let no_highlights = HashSet::<PathBuf>::new();
+        let highlights = self.highlights.unwrap_or(&no_highlights);
+        let mut lines = tree_to_lines(entries, &config, highlights);
+
+        // Keep the selection on an entry and in view.
+        let height = usize::from(inner.height);
+        state.selected = state
+            .selected
+            .filter(|_| !entries.is_empty())
+            .map(|i| i.min(entries.len() - 1));
+        if let Some(selected) = state.selected {
+            if selected < state.offset {
+                state.offset = selected;
+            } else if height > 0 && selected >= state.offset + height {
+                state.offset = selected + 1 - height;
+            }
+            lines[selected] = std::mem::take(&mut lines[selected]).patch_style(self.selected_style);
+        }
+        state.offset = state.offset.min(entries.len().saturating_sub(height));
+
+        let offset = u16::try_from(state.offset).unwrap_or(u16::MAX);
+        let mut paragraph = Paragraph::new(lines).scroll((offset, 0));
+        if let Some(block) = self.block {
+            paragraph = paragraph.block(block);
+        }
+        paragraph.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlight::HighlightKind;
+    use crate::render::Charset;
+    use std::collections::HashMap;
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf[(x, y)].symbol())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn selection_scrolls_into_view_and_is_clamped() {
+        let snapshot = TreeSnapshot::from_outline((0..10).map(|i| (1, format!("f{i}"))));
+        let mut state = LiveTreeState::default();
+        state.select(Some(7));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 3));
+        LiveTreeWidget::new(&snapshot).render(buf.area, &mut buf, &mut state);
+        assert_eq!(state.offset(), 5);
+        assert_eq!(row(&buf, 2), "├── f7");
+        assert!(buf[(0, 2)].modifier.contains(Modifier::REVERSED));
+
+        state.select(Some(99));
+        LiveTreeWidget::new(&snapshot).render(buf.area, &mut buf, &mut state);
+        assert_eq!(state.selected(), Some(9));
+        assert_eq!(row(&buf, 2), "└── f9");
+        assert_eq!(
+            state.selected_entry(&snapshot).map(|e| e.name.as_str()),
+            Some("f9")
+        );
+    }
+
+    #[test]
+    fn block_and_highlights_are_drawn() {
+        let snapshot = TreeSnapshot::from_outline([(1, "a.txt"), (1, "b.txt")]);
+        let highlights = HashMap::from([(PathBuf::from("b.txt"), HighlightKind::Content)]);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 4));
+        LiveTreeWidget::new(&snapshot)
+            .block(Block::bordered().title("Tree"))
+            .highlights(&highlights)
+            .render_config(RenderConfig::builder().charset(Charset::Ascii).build())
+            .render(buf.area, &mut buf, &mut LiveTreeState::default());
+        assert_eq!(row(&buf, 0), "┌Tree──────────────┐");
+        assert_eq!(row(&buf, 1), "│|-- a.txt         │");
+        assert!(!buf[(5, 1)].modifier.contains(Modifier::BOLD));
+        assert!(buf[(5, 2)].modifier.contains(Modifier::BOLD));
+    }
+}
@@ -0,0 +1,123 @@
+//! Workspaces kept in session files (`--session`): the watched root, the
+//! split pane, and the view options, saved on exit and restored together
+//! by the next `livetree --session <file>`.
+
+use crate::cli::Args;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What a session file holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// The directory shown beside the tree.
+    pub split: Option<PathBuf>,
+    pub compare: bool,
+    pub show_hidden: bool,
+    pub dirs_only: bool,
+    pub max_depth: Option<usize>,
+    /// `-I` patterns, including those added from the palette.
+    pub ignore: Vec<String>,
+    pub line_numbers: bool,
+    pub relative_line_numbers: bool,
+    pub flat: bool,
+    pub columns: bool,
+    pub sticky_headers: bool,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            split: None,
+            compare: false,
+            show_hidden: false,
+            dirs_only: false,
+            max_depth: None,
+            ignore: Vec::new(),
+            line_numbers: false,
+            relative_line_numbers: false,
+            flat: false,
+            columns: false,
+            sticky_headers: true,
+        }
+    }
+}
+
+impl Workspace {
+    /// The workspace saved in `file`; `None` if there is no such file yet.
+    pub fn load(file: &Path) -> Result<Option<Self>, String> {
+        let text = match std::fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {}", file.display(), e)),
+        };
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("{}: {}", file.display(), e))
+    }
+
+    pub fn save(&self, file: &Path) -> Result<(), String> {
+        if let Some(parent) = file.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(file, text).map_err(|e| format!("{}: {}", file.display(), e))
+    }
+
+    /// Use the workspace's root, split pane, and options in place of those
+    /// given on the command line.
+    pub fn apply(self, args: &mut Args) {
+        args.path = self.root;
+        args.split = self.split;
+        args.compare = self.compare && args.split.is_some();
+        args.show_hidden = self.show_hidden;
+        args.dirs_only = self.dirs_only;
+        args.max_depth = self.max_depth;
+        args.ignore = self.ignore;
+        args.iglob.clear();
+        args.line_numbers = self.line_numbers;
+        args.relative_line_numbers = self.relative_line_numbers;
+        args.flat = self.flat;
+        args.columns = self.columns;
+        args.no_sticky_headers = !self.sticky_headers;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn workspaces_round_trip_and_replace_the_command_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("work.toml");
+        assert_eq!(Workspace::load(&file), Ok(None));
+
+        let workspace = Workspace {
+            root: PathBuf::from("/src/app"),
+            split: Some(PathBuf::from("/src/app/target")),
+            compare: true,
+            max_depth: Some(3),
+            ignore: vec!["*.log".to_string()],
+            ..Workspace::default()
+        };
+        workspace.save(&file).unwrap();
+        assert_eq!(Workspace::load(&file), Ok(Some(workspace.clone())));
+
+        let mut args = Args::parse_from(["livetree", "-a", "-I", "*.tmp", "elsewhere"]);
+        workspace.apply(&mut args);
+        assert_eq!(args.path, PathBuf::from("/src/app"));
+        assert_eq!(args.split, Some(PathBuf::from("/src/app/target")));
+        assert!(args.compare && !args.show_hidden && !args.no_sticky_headers);
+        assert_eq!(
+            (args.max_depth, args.ignore),
+            (Some(3), vec!["*.log".to_string()])
+        );
+
+        std::fs::write(&file, "root = 3\n").unwrap();
+        assert!(Workspace::load(&file).is_err());
+    }
+}
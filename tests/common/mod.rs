@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use livetree::render::RenderConfig;
 use livetree::tree::{build_ignore_set, TreeConfig};
 use std::fs;
@@ -13,23 +15,26 @@ pub fn default_tree_config() -> TreeConfig {
         follow_symlinks: false,
         ignore_patterns: build_ignore_set(&[]),
         max_entries: None,
+        stat: false,
     }
 }
 
 /// RenderConfig with color disabled.
 pub fn no_color_render_config(width: u16) -> RenderConfig {
-    RenderConfig {
-        use_color: false,
-        terminal_width: width,
-    }
+    RenderConfig::builder()
+        .use_color(false)
+        .terminal_width(width)
+        .truncate_names(false)
+        .build()
 }
 
 /// RenderConfig with color enabled.
 pub fn color_render_config(width: u16) -> RenderConfig {
-    RenderConfig {
-        use_color: true,
-        terminal_width: width,
-    }
+    RenderConfig::builder()
+        .use_color(true)
+        .terminal_width(width)
+        .truncate_names(false)
+        .build()
 }
 
 /// Create a directory structure from a list of relative paths.
@@ -60,15 +65,15 @@ pub fn make_entry(
     prefix: &str,
     error: Option<&str>,
 ) -> livetree::tree::TreeEntry {
-    livetree::tree::TreeEntry {
-        name: name.to_string(),
-        path: PathBuf::from(format!("/tmp/test/{}", name)),
+    let mut entry = livetree::tree::TreeEntry::new(
+        name,
+        PathBuf::from(format!("/tmp/test/{}", name)),
         depth,
         is_dir,
-        is_symlink,
-        symlink_target: None,
-        is_last,
-        prefix: prefix.to_string(),
-        error: error.map(|s| s.to_string()),
-    }
+    );
+    entry.is_symlink = is_symlink;
+    entry.is_last = is_last;
+    assert_eq!(entry.prefix(), prefix);
+    entry.error = error.map(|s| s.to_string());
+    entry
 }
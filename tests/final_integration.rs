@@ -133,7 +133,7 @@ fn test_full_lifecycle() {
         for entry in entries.iter() {
             if entry.depth > 0 {
                 assert!(
-                    !entry.prefix.is_empty(),
+                    !entry.prefix().is_empty(),
                     "FAIL: Entry '{}' at depth {} has empty prefix",
                     entry.name,
                     entry.depth
@@ -145,10 +145,11 @@ fn test_full_lifecycle() {
         // Render to ratatui Lines for visual inspection
         let lines = tree_to_lines(
             &entries,
-            &RenderConfig {
-                use_color: false,
-                terminal_width: 80,
-            },
+            &RenderConfig::builder()
+                .use_color(false)
+                .terminal_width(80)
+                .truncate_names(false)
+                .build(),
             &HashSet::new(),
         );
         let output: String = lines
@@ -280,10 +281,11 @@ fn test_full_lifecycle() {
 
         let tree_cfg = default_tree_config();
         let entries = build_tree(render_tmp.path(), &tree_cfg);
-        let render_cfg = RenderConfig {
-            use_color: false,
-            terminal_width: 80,
-        };
+        let render_cfg = RenderConfig::builder()
+            .use_color(false)
+            .terminal_width(80)
+            .truncate_names(false)
+            .build();
 
         // Render to ratatui Lines
         let lines = tree_to_lines(&entries, &render_cfg, &HashSet::new());
@@ -436,10 +438,11 @@ fn test_performance_large_directory() {
     let start = Instant::now();
     let lines = tree_to_lines(
         &entries,
-        &RenderConfig {
-            use_color: true,
-            terminal_width: 120,
-        },
+        &RenderConfig::builder()
+            .use_color(true)
+            .terminal_width(120)
+            .truncate_names(false)
+            .build(),
         &HashSet::new(),
     );
     let render_duration = start.elapsed();
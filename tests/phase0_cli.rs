@@ -1,11 +1,10 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 use tempfile::TempDir;
 
 #[test]
 fn test_help_flag() {
-    Command::cargo_bin("livetree")
-        .unwrap()
+    cargo_bin_cmd!("livetree")
         .arg("--help")
         .assert()
         .success()
@@ -22,8 +21,7 @@ fn test_help_flag() {
 
 #[test]
 fn test_version_flag() {
-    Command::cargo_bin("livetree")
-        .unwrap()
+    cargo_bin_cmd!("livetree")
         .arg("--version")
         .assert()
         .success()
@@ -32,8 +30,7 @@ fn test_version_flag() {
 
 #[test]
 fn test_nonexistent_path_exits_with_error() {
-    Command::cargo_bin("livetree")
-        .unwrap()
+    cargo_bin_cmd!("livetree")
         .arg("/this/path/does/not/exist")
         .assert()
         .failure()
@@ -41,17 +38,299 @@ fn test_nonexistent_path_exits_with_error() {
 }
 
 #[test]
-fn test_file_path_exits_with_error() {
+fn test_file_path_is_rejected_by_one_shot_modes() {
     let tmp = TempDir::new().unwrap();
     let file = tmp.path().join("afile.txt");
     std::fs::write(&file, "hello").unwrap();
 
-    Command::cargo_bin("livetree")
-        .unwrap()
+    cargo_bin_cmd!("livetree")
+        .args(["--export", "-"])
         .arg(file.to_str().unwrap())
         .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--export needs a directory, not a file",
+        ));
+}
+
+#[test]
+fn test_remote_rejects_one_shot_modes_and_bad_specs() {
+    cargo_bin_cmd!("livetree")
+        .args(["--remote", "web1:/srv/app", "--export", "-"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--export does not work with --remote",
+        ));
+    cargo_bin_cmd!("livetree")
+        .args(["--remote", "web1"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("expected [user@]host:path"));
+    cargo_bin_cmd!("livetree")
+        .args(["--docker", "web:/app", "--stream"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--stream does not work with --docker",
+        ));
+}
+
+#[test]
+fn test_connect_and_serve_reject_other_modes() {
+    cargo_bin_cmd!("livetree")
+        .args(["--connect", "live.sock", "--export", "-"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    cargo_bin_cmd!("livetree")
+        .args(["--serve", "live.sock", "--stream", "."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--serve sends changes until interrupted",
+        ));
+}
+
+#[test]
+fn test_control_refuses_tcp_addresses() {
+    cargo_bin_cmd!("livetree")
+        .args(["--control", "0.0.0.0:7070", "."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the control socket must be a Unix socket path",
+        ));
+}
+
+#[test]
+fn test_malformed_config_exits_with_error() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[mouse.file]\nsingle = \"explode\"\n").unwrap();
+
+    cargo_bin_cmd!("livetree")
+        .arg("--config")
+        .arg(&config)
+        .arg(tmp.path())
+        .assert()
         .failure()
-        .stderr(predicate::str::contains("Not a directory"));
+        .stderr(predicate::str::contains("config.toml"))
+        .stderr(predicate::str::contains("explode"));
+}
+
+#[test]
+fn test_safe_mode_skips_the_config_file() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[mouse.file]\nsingle = \"explode\"\n").unwrap();
+
+    cargo_bin_cmd!("livetree")
+        .args(["--safe-mode", "--export", "-", "--config"])
+        .arg(&config)
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.toml"))
+        .stderr(predicate::str::contains(
+            "--config is ignored with --safe-mode",
+        ))
+        .stderr(predicate::str::contains("explode").not());
+}
+
+#[test]
+fn test_log_file_receives_walk_timings() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("root");
+    std::fs::create_dir(&root).unwrap();
+    std::fs::write(root.join("a.txt"), "").unwrap();
+    let log = tmp.path().join("livetree.log");
+
+    cargo_bin_cmd!("livetree")
+        .args(["-v", "--export", "-", "--log-file"])
+        .arg(&log)
+        .arg(&root)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("walk").not());
+    let text = std::fs::read_to_string(&log).unwrap();
+    assert!(text.contains("walk finished shown=1"), "{text}");
+    assert!(text.contains("close time.busy="), "{text}");
+}
+
+#[test]
+fn test_exit_codes_distinguish_failure_modes() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "bogus = 1\n").unwrap();
+
+    cargo_bin_cmd!("livetree")
+        .arg("/this/path/does/not/exist")
+        .assert()
+        .code(3);
+    cargo_bin_cmd!("livetree")
+        .arg("--config")
+        .arg(&config)
+        .arg(tmp.path())
+        .assert()
+        .code(2);
+    cargo_bin_cmd!("livetree")
+        .arg("--no-such-flag")
+        .assert()
+        .code(2);
+    cargo_bin_cmd!("livetree")
+        .args(["--report", "heavy", "--top", "5"])
+        .arg(tmp.path())
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn test_report_heavy_prints_largest_and_exits() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("data")).unwrap();
+    std::fs::write(tmp.path().join("data/big.bin"), vec![0u8; 4096]).unwrap();
+    std::fs::write(tmp.path().join("small.txt"), "x").unwrap();
+
+    cargo_bin_cmd!("livetree")
+        .args(["--report", "heavy", "--top", "1"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("4.0 KiB  data/big.bin"))
+        .stdout(predicate::str::contains("4.0 KiB  data\n"))
+        .stdout(predicate::str::contains("small.txt").not());
+
+    // Without --top the path is not taken for a count.
+    cargo_bin_cmd!("livetree")
+        .args(["--report", "heavy"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("4.0 KiB  data/big.bin"))
+        .stdout(predicate::str::contains("1 B  small.txt"));
+}
+
+#[test]
+fn test_report_stale_lists_old_files() {
+    let tmp = TempDir::new().unwrap();
+    let old = tmp.path().join("old.log");
+    std::fs::write(&old, "").unwrap();
+    std::fs::write(tmp.path().join("fresh.log"), "").unwrap();
+    let forty_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(40 * 86_400);
+    std::fs::File::options()
+        .write(true)
+        .open(&old)
+        .unwrap()
+        .set_modified(forty_days_ago)
+        .unwrap();
+
+    cargo_bin_cmd!("livetree")
+        .args(["--report", "stale", "--older-than", "30d"])
+        .arg(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("40d ago  old.log"))
+        .stdout(predicate::str::contains("fresh.log").not());
+}
+
+#[test]
+fn test_invalid_stale_after_is_rejected() {
+    cargo_bin_cmd!("livetree")
+        .args(["--stale-after", "soon", "."])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid duration"));
+}
+
+#[test]
+fn test_check_config_reports_problems_with_lines() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(
+        &config,
+        "[mouse]\nenabeld = true\n\n[highlight.created]\ncolor = \"blurple\"\n",
+    )
+    .unwrap();
+    cargo_bin_cmd!("livetree")
+        .arg("check-config")
+        .arg(&config)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(":2: unknown key 'mouse.enabeld'"))
+        .stderr(predicate::str::contains(":5: invalid color 'blurple'"));
+
+    std::fs::write(&config, "[mouse]\nenabled = true\n").unwrap();
+    cargo_bin_cmd!("livetree")
+        .arg("check-config")
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK"));
+}
+
+#[test]
+fn test_init_config_writes_a_valid_file_once() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config = tmp.path().join("nested/config.toml");
+    cargo_bin_cmd!("livetree")
+        .arg("init-config")
+        .arg(&config)
+        .assert()
+        .success();
+    assert!(std::fs::read_to_string(&config)
+        .unwrap()
+        .contains("[mouse.file]"));
+    cargo_bin_cmd!("livetree")
+        .arg("init-config")
+        .arg(&config)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+    cargo_bin_cmd!("livetree")
+        .args(["init-config", "--force"])
+        .arg(&config)
+        .assert()
+        .success();
+    cargo_bin_cmd!("livetree")
+        .arg("check-config")
+        .arg(&config)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_invalid_status_format_is_rejected() {
+    cargo_bin_cmd!("livetree")
+        .args(["--status-format", "{path} | {nope}", "."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("unknown status field '{nope}'"));
+}
+
+#[test]
+fn test_startup_problems_are_reported_together() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    cargo_bin_cmd!("livetree")
+        .args([
+            "-L",
+            "0",
+            "-I",
+            "a[b",
+            "--dirs-only",
+            "--report",
+            "stale",
+            "--older-than",
+            "1d",
+        ])
+        .arg(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("-L 0"))
+        .stderr(predicate::str::contains("invalid ignore pattern 'a[b'"))
+        .stderr(predicate::str::contains("--dirs-only hides all files"))
+        .stderr(predicate::str::contains("hint: drop --dirs-only"))
+        .stderr(predicate::str::contains("3 problems found"));
 }
 
 // NOTE: test_valid_directory_prints_watching was removed because
@@ -106,3 +385,161 @@ fn test_quiet_resets_verbose() {
     assert!(args.quiet);
     assert_eq!(args.verbose, 0, "quiet should reset verbosity to 0");
 }
+
+#[test]
+fn test_export_writes_markdown_and_json() {
+    let root = TempDir::new().unwrap();
+    std::fs::create_dir(root.path().join("src")).unwrap();
+    std::fs::write(root.path().join("src/main.rs"), "").unwrap();
+    let out = TempDir::new().unwrap();
+
+    let md = out.path().join("tree.md");
+    cargo_bin_cmd!("livetree")
+        .arg("--export")
+        .arg(&md)
+        .arg(root.path())
+        .assert()
+        .success();
+    let text = std::fs::read_to_string(&md).unwrap();
+    assert!(text.contains("\n  - `src/`\n    - `main.rs`\n"), "{text}");
+
+    let json = out.path().join("tree.json");
+    cargo_bin_cmd!("livetree")
+        .arg("--export")
+        .arg(&json)
+        .arg(root.path())
+        .assert()
+        .success();
+    assert!(std::fs::read_to_string(&json)
+        .unwrap()
+        .starts_with("{\"entries\":["));
+}
+
+#[test]
+fn test_no_default_ignores_shows_node_modules() {
+    let root = TempDir::new().unwrap();
+    std::fs::create_dir(root.path().join("node_modules")).unwrap();
+    let out = TempDir::new().unwrap();
+    let md = out.path().join("tree.md");
+
+    cargo_bin_cmd!("livetree")
+        .arg("--export")
+        .arg(&md)
+        .arg(root.path())
+        .assert()
+        .success();
+    assert!(!std::fs::read_to_string(&md)
+        .unwrap()
+        .contains("node_modules"));
+
+    cargo_bin_cmd!("livetree")
+        .args(["--no-default-ignores", "--export"])
+        .arg(&md)
+        .arg(root.path())
+        .assert()
+        .success();
+    assert!(std::fs::read_to_string(&md)
+        .unwrap()
+        .contains("`node_modules/`"));
+}
+
+#[test]
+fn test_export_format_overrides_extension() {
+    let root = TempDir::new().unwrap();
+    std::fs::create_dir(root.path().join("src")).unwrap();
+    std::fs::write(root.path().join("src/<main>.rs"), "").unwrap();
+
+    cargo_bin_cmd!("livetree")
+        .args(["--export", "-", "--format", "html"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<!DOCTYPE html>"))
+        .stdout(predicate::str::contains(
+            "<summary class=\"dir\">src/</summary><ul>\n<li id=\"entry-src-main-rs\">&lt;main&gt;.rs</li>",
+        ));
+
+    cargo_bin_cmd!("livetree")
+        .args(["--format", "html"])
+        .arg(root.path())
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_exit_on_change_prints_the_first_change_and_exits() {
+    let root = TempDir::new().unwrap();
+    std::fs::create_dir(root.path().join("build")).unwrap();
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    // Keep changing files until livetree exits, as it may start watching
+    // after the first writes.
+    let writer = {
+        let dir = root.path().to_path_buf();
+        let done = done.clone();
+        std::thread::spawn(move || {
+            for i in 0..100 {
+                if done.load(std::sync::atomic::Ordering::SeqCst) {
+                    break;
+                }
+                std::fs::write(dir.join("build/out.o"), i.to_string()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(400));
+                std::fs::write(dir.join("notes.txt"), i.to_string()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        })
+    };
+
+    let output = cargo_bin_cmd!("livetree")
+        .args(["--exit-on-change", "-I", "build", "--debounce", "50"])
+        .arg(root.path())
+        .timeout(std::time::Duration::from_secs(30))
+        .output()
+        .unwrap();
+    done.store(true, std::sync::atomic::Ordering::SeqCst);
+    writer.join().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.lines().any(|l| l.ends_with("notes.txt")), "{stdout}");
+    assert!(!stdout.contains("out.o"), "{stdout}");
+}
+
+#[test]
+fn test_stream_prints_changes_as_json_lines() {
+    use std::io::BufRead;
+
+    let root = TempDir::new().unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("livetree"))
+        .args(["--stream=ndjson", "--debounce", "50"])
+        .arg(root.path())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in stdout.lines() {
+            if tx.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Keep writing until a line arrives, as livetree may start watching
+    // after the first writes.
+    let mut line = None;
+    for i in 0..60 {
+        std::fs::write(root.path().join("a.txt"), i.to_string()).unwrap();
+        if let Ok(received) = rx.recv_timeout(std::time::Duration::from_millis(500)) {
+            line = Some(received);
+            break;
+        }
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let line = line.expect("a change is streamed");
+    assert!(line.starts_with("{\"time\":\""), "{line}");
+    assert!(line.contains("\"kind\":\""), "{line}");
+    assert!(line.ends_with("a.txt\"}"), "{line}");
+}
@@ -1,10 +1,12 @@
 mod common;
 
 use common::{create_fixture, default_tree_config};
-use globset::GlobSet;
 use livetree::tree::{
-    build_ignore_set, build_tree, TreeBuilder, TreeConfig, TreeEntry, WalkdirTreeBuilder,
+    build_ignore_set, build_subtree, build_tree, compare, CompareSide, Difference, EntrySource,
+    IgnoreSet, RawEntry, SourceTreeBuilder, TreeBuilder, TreeConfig, TreeEntry, TreeSnapshot,
+    WalkdirTreeBuilder,
 };
+use std::path::Path;
 use tempfile::TempDir;
 
 fn default_config() -> TreeConfig {
@@ -29,7 +31,7 @@ fn test_directories_before_files() {
         assert!(
             last_dir < first_file,
             "All directories should sort before files. Got: {:?}",
-            top.iter().map(|e| &e.name).collect::<Vec<_>>()
+            top.iter().map(|e| e.name.as_str()).collect::<Vec<_>>()
         );
     }
 }
@@ -66,8 +68,8 @@ fn test_dotfiles_shown_with_all_flag() {
     let tmp = create_fixture(&[".hidden", "visible.txt"]);
     let mut cfg = default_config();
     cfg.show_hidden = true;
-    // Use empty GlobSet to avoid .hidden being caught by default ignores
-    cfg.ignore_patterns = GlobSet::empty();
+    // Use an empty IgnoreSet to avoid .hidden being caught by default ignores
+    cfg.ignore_patterns = IgnoreSet::empty();
     let snapshot = build_tree(tmp.path(), &cfg);
     let names: Vec<&str> = snapshot
         .entries
@@ -155,6 +157,34 @@ fn test_custom_ignore_pattern() {
     assert!(names.contains(&"main.rs"));
 }
 
+#[test]
+fn test_dir_only_and_case_insensitive_ignore_patterns() {
+    let tmp = create_fixture(&[
+        "out/",
+        "out/a.txt",
+        "src/",
+        "src/out",
+        "Logo.PNG",
+        "icon.png",
+    ]);
+    let mut cfg = default_config();
+    cfg.ignore_patterns = build_ignore_set(&["**/out/".to_string(), "(?i)*.png".to_string()]);
+    let snapshot = build_tree(tmp.path(), &cfg);
+    let paths: Vec<String> = snapshot
+        .entries
+        .iter()
+        .filter(|e| e.depth > 0)
+        .map(|e| {
+            e.path
+                .strip_prefix(tmp.path())
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    assert_eq!(paths, ["src", "src/out"]);
+}
+
 #[test]
 fn test_walkdir_tree_builder_trait_matches_free_function() {
     let tmp = create_fixture(&["src/", "src/main.rs", "README.md"]);
@@ -167,6 +197,90 @@ fn test_walkdir_tree_builder_trait_matches_free_function() {
     assert_eq!(via_trait, via_function);
 }
 
+/// An in-memory listing, as a test fixture or a remote backend would be.
+struct Listing(Vec<(usize, &'static str, bool)>);
+
+impl EntrySource for Listing {
+    fn entries<'a>(
+        &'a self,
+        root: &'a Path,
+        _config: &'a TreeConfig,
+    ) -> Box<dyn Iterator<Item = RawEntry> + 'a> {
+        Box::new(self.0.iter().map(move |&(depth, path, is_dir)| {
+            let name = path.rsplit('/').next().unwrap();
+            RawEntry::new(depth, name, root.join(path), is_dir)
+        }))
+    }
+}
+
+#[test]
+fn test_entry_sources_share_the_walk_layout() {
+    let listing = Listing(vec![
+        (1, "src", true),
+        (2, "src/main.rs", false),
+        (1, "README.md", false),
+    ]);
+    let root = Path::new("/remote");
+    let mut seen = 0;
+    let snapshot = SourceTreeBuilder(listing).build_tree_with_progress(
+        root,
+        &default_config(),
+        &mut |count, _| seen = count,
+    );
+    let lines: Vec<String> = snapshot
+        .entries
+        .iter()
+        .map(|e| format!("{}{}", e.prefix(), e.name))
+        .collect();
+    assert_eq!(lines, ["├── src", "│   └── main.rs", "└── README.md"]);
+    assert_eq!(snapshot.entries[1].path, root.join("src/main.rs"));
+    assert_eq!(seen, 3);
+
+    // The same fixture on disk walks to the same layout.
+    let tmp = create_fixture(&["src/", "src/main.rs", "README.md"]);
+    let walked = build_tree(tmp.path(), &default_config());
+    let walked: Vec<String> = walked
+        .entries
+        .iter()
+        .map(|e| format!("{}{}", e.prefix(), e.name))
+        .collect();
+    assert_eq!(lines, walked);
+
+    // max_entries is applied for the source, deepest level first.
+    let capped = SourceTreeBuilder(Listing(vec![
+        (1, "src", true),
+        (2, "src/main.rs", false),
+        (1, "README.md", false),
+    ]))
+    .build_tree(
+        root,
+        &TreeConfig {
+            max_entries: Some(2),
+            ..default_config()
+        },
+    );
+    assert_eq!(capped.entries.len(), 2);
+    assert_eq!(capped.total_entries, 3);
+}
+
+#[test]
+fn test_build_progress_reports_each_entry() {
+    let tmp = create_fixture(&["src/", "src/main.rs", "README.md"]);
+    let mut seen = Vec::new();
+    let snapshot = WalkdirTreeBuilder.build_tree_with_progress(
+        tmp.path(),
+        &default_config(),
+        &mut |count, path| seen.push((count, path.to_path_buf())),
+    );
+
+    assert_eq!(
+        seen.iter().map(|(count, _)| *count).collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+    assert_eq!(seen[1].1, tmp.path().join("src/main.rs"));
+    assert_eq!(snapshot, build_tree(tmp.path(), &default_config()));
+}
+
 // --- Dirs Only ---
 
 #[test]
@@ -192,21 +306,21 @@ fn test_prefix_simple_tree() {
 
     let a_entry = entries.iter().find(|e| e.name == "a").unwrap();
     assert!(
-        a_entry.prefix.contains('\u{251c}'),
+        a_entry.prefix().contains('\u{251c}'),
         "Dir 'a' should use \u{251c}\u{2500}\u{2500} (got: {:?})",
-        a_entry.prefix
+        a_entry.prefix()
     );
 
     let b_entry = entries.iter().find(|e| e.name == "b.txt").unwrap();
     assert!(
-        b_entry.prefix.contains('\u{2514}'),
+        b_entry.prefix().contains('\u{2514}'),
         "'b.txt' should use \u{2514}\u{2500}\u{2500} (got: {:?})",
-        b_entry.prefix
+        b_entry.prefix()
     );
 
     let deep = entries.iter().find(|e| e.name == "deep.txt").unwrap();
     assert!(
-        deep.prefix.contains('\u{2514}'),
+        deep.prefix().contains('\u{2514}'),
         "Nested last child should use \u{2514}\u{2500}\u{2500}"
     );
 }
@@ -224,9 +338,9 @@ fn test_prefix_deeply_nested() {
     );
     // Prefix should have continuation lines
     assert!(
-        d.prefix.len() > 8,
+        d.prefix().len() > 8,
         "Deep prefix should be long: {:?}",
-        d.prefix
+        d.prefix()
     );
 }
 
@@ -283,3 +397,247 @@ fn test_symlink_detected() {
         "Symlink should be flagged as is_symlink"
     );
 }
+
+// --- Entry limit ---
+
+#[test]
+fn test_max_entries_drops_deepest_levels_first() {
+    let tmp = create_fixture(&[
+        "a/",
+        "a/x/",
+        "a/x/deep1.txt",
+        "a/x/deep2.txt",
+        "a/f1.txt",
+        "b/",
+        "b/f2.txt",
+        "top.txt",
+    ]);
+    let names = |max: usize| {
+        let config = TreeConfig {
+            max_entries: Some(max),
+            ..default_config()
+        };
+        let snapshot = build_tree(tmp.path(), &config);
+        assert_eq!(snapshot.total_entries, 8);
+        snapshot
+            .entries
+            .iter()
+            .map(|e| e.name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    // Levels 1 and 2 fit; level 3 keeps its first entry.
+    assert_eq!(
+        names(7),
+        ["a", "x", "deep1.txt", "f1.txt", "b", "f2.txt", "top.txt"]
+    );
+    assert_eq!(names(4), ["a", "x", "b", "top.txt"]);
+    // The first level is never cut.
+    assert_eq!(names(2), ["a", "b", "top.txt"]);
+}
+
+// --- Collapsing ---
+
+#[test]
+fn test_collapse_hides_descendants_only() {
+    let tmp = create_fixture(&["src/", "src/lib/", "src/lib/a.rs", "src/main.rs", "z.txt"]);
+    let mut snapshot = build_tree(tmp.path(), &default_config());
+    let collapsed: std::collections::HashSet<_> = [tmp.path().join("src")].into();
+    snapshot.collapse(&collapsed);
+
+    let names: Vec<&str> = snapshot.entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["src", "z.txt"]);
+    assert_eq!(snapshot.total_entries, 2);
+}
+
+#[test]
+fn test_grafting_subtrees_matches_a_full_walk() {
+    let tmp = create_fixture(&[
+        "a/",
+        "a/x/",
+        "a/x/deep.txt",
+        "a/f.txt",
+        "b/",
+        "b/g.txt",
+        "top.txt",
+    ]);
+    let shallow = TreeConfig {
+        max_depth: Some(1),
+        ..default_config()
+    };
+    let mut snapshot = build_tree(tmp.path(), &shallow);
+    assert_eq!(snapshot.total_entries, 3);
+    for dir in ["a", "a/x", "b"] {
+        let dir = tmp.path().join(dir);
+        let subtree = build_subtree(tmp.path(), &dir, &shallow);
+        assert!(snapshot.graft(&dir, subtree));
+    }
+    assert!(!snapshot.graft(&tmp.path().join("top.txt"), TreeSnapshot::default()));
+    assert_eq!(snapshot, build_tree(tmp.path(), &default_config()));
+}
+
+#[test]
+fn test_entries_take_a_fixed_size_whatever_their_depth() {
+    // Bytes a snapshot takes per entry besides the text of its name and
+    // path: the same for a wide tree and for one 40 levels deep, where a
+    // stored prefix would take 160 bytes at the bottom.
+    let overhead = |snapshot: &TreeSnapshot| {
+        let text: usize = snapshot
+            .iter()
+            .map(|e| e.name.len() + e.path.as_os_str().len())
+            .sum();
+        (snapshot.estimated_size() - text) / snapshot.len()
+    };
+    let wide: Vec<String> = (0..500).map(|i| format!("f{i:03}.txt")).collect();
+    let wide = create_fixture(&wide.iter().map(String::as_str).collect::<Vec<_>>());
+    let mut deep = Vec::new();
+    let mut dir = String::new();
+    for _ in 0..40 {
+        dir.push_str("d/");
+        deep.push(dir.clone());
+        deep.push(format!("{dir}f.txt"));
+    }
+    let deep = create_fixture(&deep.iter().map(String::as_str).collect::<Vec<_>>());
+
+    let wide = build_tree(wide.path(), &default_config());
+    let deep = build_tree(deep.path(), &default_config());
+    assert_eq!(wide.len(), 500);
+    assert_eq!(deep.len(), 80);
+    assert!(overhead(&wide) <= 160, "{}", overhead(&wide));
+    assert!(overhead(&deep) <= 160, "{}", overhead(&deep));
+}
+
+#[test]
+fn test_layout_of_huge_flat_directory_is_linear() {
+    // Quadratic sibling scans took minutes here; one pass takes milliseconds.
+    let count = 200_000;
+    let start = std::time::Instant::now();
+    let snapshot = TreeSnapshot::from_outline((0..count).map(|i| (1, format!("file{i:06}.txt"))));
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+    assert_eq!(snapshot.len(), count);
+    assert!(snapshot[..count - 1].iter().all(|e| !e.is_last));
+    assert!(snapshot[count - 1].is_last);
+    assert_eq!(snapshot[count - 1].prefix(), "└── ");
+}
+
+#[test]
+fn test_walk_previews_the_top_of_the_tree_so_far() {
+    let tmp = create_fixture(&["a/", "a/x.txt", "a/y.txt", "b/", "b/z.txt", "c.txt"]);
+    let mut previews = Vec::new();
+    let snapshot =
+        WalkdirTreeBuilder.build_tree_with_preview(tmp.path(), &default_config(), &mut |walked| {
+            previews.push((walked.count, walked.preview(2)))
+        });
+    assert_eq!(previews.len(), snapshot.len());
+
+    let names = |s: &TreeSnapshot| s.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    let (count, after_three) = &previews[2];
+    assert_eq!(*count, 3);
+    assert_eq!(after_three.total_entries, 3);
+    assert_eq!(names(after_three), ["a", "x.txt"]);
+    assert_eq!(names(&previews[0].1), ["a"]);
+    // Once walked, the rows shown are the finished tree's top rows.
+    assert_eq!(previews.last().unwrap().1, {
+        let mut top = snapshot.clone();
+        top.entries.truncate(2);
+        top
+    });
+
+    // Builders that cannot show entries early report progress only.
+    struct Opaque;
+    impl TreeBuilder for Opaque {
+        fn build_tree(&self, root: &Path, config: &TreeConfig) -> TreeSnapshot {
+            build_tree(root, config)
+        }
+    }
+    let mut last = None;
+    Opaque.build_tree_with_preview(tmp.path(), &default_config(), &mut |walked| {
+        last = Some((walked.count, walked.preview(10).len()))
+    });
+    assert_eq!(last, Some((6, 0)));
+}
+
+// --- Comparing trees ---
+
+#[test]
+fn test_compare_marks_missing_retyped_and_resized_entries() {
+    let left = create_fixture(&[
+        "same.txt",
+        "sized.txt",
+        "kind",
+        "only/inner.txt",
+        "sub/a.txt",
+    ]);
+    let right = create_fixture(&["same.txt", "sized.txt", "kind/", "sub/a.txt", "sub/b.txt"]);
+    std::fs::write(left.path().join("sized.txt"), "abc").unwrap();
+    // Same modification times everywhere, so only sizes tell files apart.
+    let time = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1 << 30);
+    for file in ["same.txt", "sized.txt", "sub/a.txt"] {
+        for root in [left.path(), right.path()] {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(root.join(file))
+                .unwrap();
+            file.set_modified(time).unwrap();
+        }
+    }
+    let (left_tree, right_tree) = (
+        build_tree(left.path(), &default_config()),
+        build_tree(right.path(), &default_config()),
+    );
+    let none = std::collections::HashSet::new();
+    let side = |root, snapshot| CompareSide {
+        root,
+        snapshot,
+        unlisted: &none,
+    };
+    let comparison = compare(
+        &side(left.path(), &left_tree),
+        &side(right.path(), &right_tree),
+    );
+
+    let marks = |differences: &std::collections::HashMap<std::path::PathBuf, Difference>,
+                 root: &Path| {
+        let mut marks: Vec<(String, Difference)> = differences
+            .iter()
+            .map(|(path, d)| {
+                let relative = path.strip_prefix(root).unwrap();
+                (relative.to_string_lossy().replace('\\', "/"), *d)
+            })
+            .collect();
+        marks.sort_by(|a, b| a.0.cmp(&b.0));
+        marks
+    };
+    assert_eq!(
+        marks(&comparison.left, left.path()),
+        [
+            ("kind".to_string(), Difference::Kind),
+            ("only".to_string(), Difference::Missing),
+            ("only/inner.txt".to_string(), Difference::Missing),
+            ("sized.txt".to_string(), Difference::Content),
+        ]
+    );
+    assert_eq!(
+        marks(&comparison.right, right.path()),
+        [
+            ("kind".to_string(), Difference::Kind),
+            ("sized.txt".to_string(), Difference::Content),
+            ("sub".to_string(), Difference::Within),
+            ("sub/b.txt".to_string(), Difference::Missing),
+        ]
+    );
+
+    // Nothing is missing below a directory the other side does not list.
+    let collapsed = std::collections::HashSet::from([left.path().join("sub")]);
+    let comparison = compare(
+        &CompareSide {
+            unlisted: &collapsed,
+            ..side(left.path(), &left_tree)
+        },
+        &side(right.path(), &right_tree),
+    );
+    assert!(!comparison
+        .right
+        .contains_key(&right.path().join("sub/b.txt")));
+}
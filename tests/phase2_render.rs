@@ -2,10 +2,12 @@ mod common;
 
 use common::{color_render_config, make_entry, no_color_render_config};
 use livetree::render::{
-    help_bar_line, line_to_plain_text, status_bar_line, tree_to_lines, RenderConfig,
+    help_bar_line, line_to_plain_text, status_bar, status_bar_line, tree_to_lines, Charset,
+    Columns, Listing, RenderConfig, Theme,
 };
-use livetree::tree::TreeEntry;
-use ratatui::style::{Color, Modifier};
+use livetree::status::{StatusTemplate, StatusValues};
+use livetree::tree::{TreeEntry, TreeSnapshot};
+use ratatui::style::{Color, Modifier, Style};
 use std::collections::HashSet;
 use std::path::PathBuf;
 
@@ -88,21 +90,13 @@ fn test_tree_to_lines_symlink_with_color() {
     let link_path = tmp.path().join("link.txt");
     std::os::unix::fs::symlink(&target_path, &link_path).unwrap();
 
-    let entry = TreeEntry {
-        name: "link.txt".to_string(),
-        path: link_path.clone(),
-        depth: 1,
-        is_dir: false,
-        is_symlink: true,
-        symlink_target: Some(
-            std::fs::read_link(&link_path)
-                .map(|t| t.to_string_lossy().to_string())
-                .unwrap_or_else(|_| "?".to_string()),
-        ),
-        is_last: true,
-        prefix: "\u{2514}\u{2500}\u{2500} ".to_string(),
-        error: None,
-    };
+    let mut entry = TreeEntry::new("link.txt", link_path.clone(), 1, false);
+    entry.is_symlink = true;
+    entry.symlink_target = Some(
+        std::fs::read_link(&link_path)
+            .map(|t| t.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "?".to_string()),
+    );
     let config = color_config();
     let lines = tree_to_lines(&[entry], &config, &HashSet::new());
     let line = &lines[0];
@@ -251,6 +245,44 @@ fn test_status_bar_line_no_change() {
     );
 }
 
+#[test]
+fn test_status_bar_follows_template() {
+    let template: StatusTemplate = "{entries} | {watcher} | {last_change} {last_file} {follow}"
+        .parse()
+        .unwrap();
+    let values = StatusValues {
+        path: "/tmp/test",
+        entries: "10 entries",
+        last_change: Some("12:00:00"),
+        last_file: Some("main.rs"),
+        watcher: "inotify",
+        ..StatusValues::default()
+    };
+    let bar = status_bar(&template, &values);
+    assert_eq!(
+        line_to_plain_text(&bar.line),
+        "10 entries | inotify | Last change: 12:00:00 main.rs"
+    );
+    let file_span = &bar.line.spans[bar.last_file_span.unwrap()];
+    assert_eq!(file_span.content, "main.rs");
+    assert!(file_span.style.add_modifier.contains(Modifier::UNDERLINED));
+
+    // Empty fields drop the whitespace before them.
+    let bar = status_bar(
+        &template,
+        &StatusValues {
+            last_file: None,
+            follow: true,
+            ..values
+        },
+    );
+    assert_eq!(
+        line_to_plain_text(&bar.line),
+        "10 entries | inotify | Last change: 12:00:00 [follow]"
+    );
+    assert_eq!(bar.last_file_span, None);
+}
+
 // --- Test 9: status_bar_line has styling ---
 #[test]
 fn test_status_bar_line_has_style() {
@@ -410,17 +442,9 @@ fn test_unchanged_entry_keeps_normal_style() {
 
 #[test]
 fn test_tree_to_lines_sanitizes_control_chars() {
-    let entry = TreeEntry {
-        name: "bad\u{001B}[31mname".to_string(),
-        path: PathBuf::from("/tmp/bad"),
-        depth: 1,
-        is_dir: false,
-        is_symlink: true,
-        symlink_target: Some("line1\nline2".to_string()),
-        is_last: true,
-        prefix: "└── ".to_string(),
-        error: None,
-    };
+    let mut entry = TreeEntry::new("bad\u{001B}[31mname", PathBuf::from("/tmp/bad"), 1, false);
+    entry.is_symlink = true;
+    entry.symlink_target = Some("line1\nline2".to_string());
     let config = no_color_config();
     let lines = tree_to_lines(&[entry], &config, &HashSet::new());
     let text = line_to_plain_text(&lines[0]);
@@ -447,3 +471,303 @@ fn test_status_bar_sanitizes_control_chars() {
     assert!(text.contains("\\x1B"));
     assert!(text.contains("\\tUTC"));
 }
+
+// --- Directory that only contains changes gets the dimmer style ---
+#[test]
+fn test_descendant_directory_highlight_is_dim_not_bold() {
+    use livetree::highlight::HighlightKind;
+    use std::collections::HashMap;
+
+    let entry = make_entry("src", 1, true, false, false, "├── ", None);
+    let config = color_config();
+    let changed: HashMap<PathBuf, HighlightKind> =
+        [(entry.path.clone(), HighlightKind::Descendant)]
+            .into_iter()
+            .collect();
+    let lines = tree_to_lines(&[entry], &config, &changed);
+
+    let name_span = lines[0]
+        .spans
+        .iter()
+        .find(|s| s.content.as_ref() == "src")
+        .unwrap();
+    assert!(name_span.style.add_modifier.contains(Modifier::DIM));
+    assert!(
+        !name_span.style.add_modifier.contains(Modifier::BOLD),
+        "Contains-changes directory should not use the bold direct-change style"
+    );
+}
+
+// --- Long names are shortened in the middle to fit the width ---
+#[test]
+fn test_long_name_is_middle_truncated_to_width() {
+    let entries = vec![make_entry(
+        "a_rather_long_generated_report_name.csv",
+        1,
+        false,
+        false,
+        true,
+        "└── ",
+        None,
+    )];
+    let mut config = no_color_render_config(24);
+    config.truncate_names = true;
+    let lines = tree_to_lines(&entries, &config, &HashSet::new());
+    let text = line_to_plain_text(&lines[0]);
+    assert_eq!(text.chars().count(), 24, "{text:?}");
+    assert!(text.starts_with("└── a_rather"), "{text:?}");
+    assert!(text.contains('…') && text.ends_with("name.csv"), "{text:?}");
+
+    let clipped = tree_to_lines(&entries, &no_color_render_config(24), &HashSet::new());
+    assert!(line_to_plain_text(&clipped[0]).ends_with("report_name.csv"));
+}
+
+// --- Wide (CJK, emoji) names are measured in display columns ---
+#[test]
+fn test_wide_names_are_truncated_by_display_width() {
+    let entries = vec![
+        make_entry(
+            "日本語のとても長いファイル名.md",
+            1,
+            false,
+            false,
+            false,
+            "├── ",
+            None,
+        ),
+        make_entry(
+            "emoji-🎉🎉🎉🎉🎉🎉🎉🎉.txt",
+            1,
+            false,
+            false,
+            true,
+            "└── ",
+            None,
+        ),
+    ];
+    let mut config = no_color_render_config(20);
+    config.truncate_names = true;
+    let lines = tree_to_lines(&entries, &config, &HashSet::new());
+    for (line, ext) in lines.iter().zip([".md", ".txt"]) {
+        let text = line_to_plain_text(line);
+        assert!(line.width() <= 20, "{text:?} is {} columns", line.width());
+        assert!(line.width() >= 19, "{text:?} wastes room");
+        assert!(text.contains('…') && text.ends_with(ext), "{text:?}");
+    }
+}
+
+/// Small deterministic generator so the outline test needs no extra crates.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
+
+#[test]
+fn test_arbitrary_outlines_lay_out_and_render_consistently() {
+    const NAMES: [&str; 6] = [
+        "a",
+        "b.rs",
+        "",
+        "日本語.txt",
+        "very_long_file_name.tar.gz",
+        ".",
+    ];
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..500 {
+        let len = rng.below(24) as usize;
+        // Depths wander freely, including 0 and jumps of several levels.
+        let outline: Vec<(usize, &str)> = (0..len)
+            .map(|_| (rng.below(6) as usize, NAMES[rng.below(6) as usize]))
+            .collect();
+        let snapshot = TreeSnapshot::from_outline(outline.iter().copied());
+        assert_eq!(snapshot.entries.len(), outline.len());
+
+        for (i, entry) in snapshot.entries.iter().enumerate() {
+            let later_sibling = snapshot.entries[i + 1..]
+                .iter()
+                .take_while(|e| e.depth >= entry.depth)
+                .any(|e| e.depth == entry.depth);
+            assert_eq!(entry.is_last, !later_sibling, "{outline:?} at {i}");
+            assert_eq!(
+                entry.prefix().chars().count(),
+                4 * entry.depth,
+                "{outline:?} at {i}"
+            );
+            let connector = if entry.is_last {
+                "└── "
+            } else {
+                "├── "
+            };
+            assert!(
+                entry.depth == 0 || entry.prefix().ends_with(connector),
+                "{outline:?} at {i}"
+            );
+        }
+
+        for width in [0, 1, 5, 12, 40, 120] {
+            for truncate_names in [false, true] {
+                let config = RenderConfig::builder()
+                    .use_color(width % 2 == 0)
+                    .terminal_width(width)
+                    .truncate_names(truncate_names)
+                    .build();
+                let lines = tree_to_lines(&snapshot.entries, &config, &HashSet::new());
+                assert_eq!(lines.len(), snapshot.entries.len());
+                for (line, entry) in lines.iter().zip(&snapshot.entries) {
+                    let text = line_to_plain_text(line);
+                    assert!(text.starts_with(&entry.prefix()), "{outline:?}: {text:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_from_outline_infers_directories_and_paths() {
+    let snapshot = TreeSnapshot::from_outline([(1, "src"), (2, "main.rs"), (1, "README.md")]);
+    let dirs: Vec<bool> = snapshot.entries.iter().map(|e| e.is_dir).collect();
+    assert_eq!(dirs, [true, false, false]);
+    assert_eq!(snapshot.entries[1].path, PathBuf::from("src/main.rs"));
+    assert_eq!(snapshot.entries[1].prefix(), "│   └── ");
+}
+
+#[test]
+fn test_prefixes_are_drawn_from_structure_at_any_depth() {
+    // A chain 70 levels deep whose level 66 gets a later sibling.
+    let outline: Vec<(usize, String)> = (1..=70)
+        .map(|depth| (depth, format!("d{depth}")))
+        .chain([(66, "sibling".to_string())])
+        .collect();
+    let snapshot = TreeSnapshot::from_outline(outline);
+
+    let deepest = &snapshot.entries[69];
+    assert!(deepest.ancestor_is_last(65));
+    assert!(!deepest.ancestor_is_last(66));
+    let expected = format!("{}│   {}└── ", "    ".repeat(65), "    ".repeat(3));
+    assert_eq!(deepest.prefix(), expected);
+    assert_eq!(
+        snapshot.entries[70].prefix(),
+        format!("{}└── ", "    ".repeat(65))
+    );
+
+    let mut by_hand = TreeEntry::new("x", "a/b/x", 3, false);
+    by_hand.is_last = false;
+    by_hand.set_ancestor_last(1, true);
+    assert_eq!(by_hand.prefix(), "    │   ├── ");
+}
+
+#[test]
+fn test_ascii_config_replaces_box_drawing_connectors() {
+    let snapshot = TreeSnapshot::from_outline([(1, "src"), (2, "main.rs"), (1, "README.md")]);
+    let mut config = no_color_config();
+    config.charset = Charset::Ascii;
+    let text: Vec<String> = tree_to_lines(&snapshot.entries, &config, &HashSet::new())
+        .iter()
+        .map(line_to_plain_text)
+        .collect();
+    assert_eq!(text, ["|-- src", "|   `-- main.rs", "`-- README.md"]);
+}
+
+#[test]
+fn test_flat_layout_lists_relative_paths_with_the_same_styles() {
+    let snapshot = TreeSnapshot::from_outline([(1, "src"), (2, "main.rs"), (1, "README.md")]);
+    let config = RenderConfig::builder()
+        .charset(Charset::Ascii)
+        .listing(Listing::Flat)
+        .build();
+    let changed: HashSet<PathBuf> = [PathBuf::from("src/main.rs")].into();
+    let lines = tree_to_lines(&snapshot.entries, &config, &changed);
+    let text: Vec<String> = lines.iter().map(line_to_plain_text).collect();
+    let sep = std::path::MAIN_SEPARATOR;
+    assert_eq!(
+        text,
+        [
+            format!("src{sep}"),
+            format!("src{sep}main.rs"),
+            "README.md".to_string()
+        ]
+    );
+    assert_eq!(lines[0].spans.len(), 1);
+    assert_eq!(lines[0].spans[0].style, Theme::default().dir);
+    assert_eq!(lines[1].spans[0].style, Theme::default().changed);
+}
+
+#[test]
+fn test_search_matches_are_drawn_in_the_matched_style() {
+    let snapshot = TreeSnapshot::from_outline([(1, "Main"), (2, "main.rs")]);
+    let spans = |config: &mut RenderConfig| {
+        config.search = Some("MAINRS".to_string());
+        let lines = tree_to_lines(&snapshot.entries, config, &HashSet::new());
+        lines[1]
+            .spans
+            .iter()
+            .map(|s| (s.content.to_string(), s.style))
+            .collect::<Vec<_>>()
+    };
+    let matched = Theme::default().matched;
+    assert_eq!(
+        spans(&mut color_config()),
+        [
+            ("    └── ".to_string(), Theme::default().prefix),
+            ("main".to_string(), matched),
+            (".".to_string(), Style::default()),
+            ("rs".to_string(), matched)
+        ]
+    );
+    // The whole path is matched; the shortest stretch holding the query
+    // is in the entry's own name, not its directory.
+    let mut flat = color_config();
+    flat.listing = Listing::Flat;
+    let sep = std::path::MAIN_SEPARATOR;
+    assert_eq!(
+        spans(&mut flat),
+        [
+            (format!("Main{sep}"), Style::default()),
+            ("main".to_string(), matched),
+            (".".to_string(), Style::default()),
+            ("rs".to_string(), matched)
+        ]
+    );
+    assert_eq!(spans(&mut no_color_config()).len(), 2);
+}
+
+#[test]
+fn test_render_config_builder_applies_theme_and_columns() {
+    let mut link = make_entry("link", 1, false, true, false, "├── ", None);
+    link.symlink_target = Some("target.txt".to_string());
+    let broken = make_entry("locked", 1, true, false, true, "└── ", Some("denied"));
+    let entries = vec![link, broken];
+
+    let default_text: Vec<String> = tree_to_lines(&entries, &no_color_config(), &HashSet::new())
+        .iter()
+        .map(line_to_plain_text)
+        .collect();
+    assert_eq!(
+        default_text,
+        ["├── link -> target.txt", "└── locked [denied]"]
+    );
+
+    let theme = Theme {
+        symlink: Style::new().fg(Color::Magenta),
+        ..Theme::default()
+    };
+    let config = RenderConfig::builder()
+        .terminal_width(80)
+        .theme(theme)
+        .columns(Columns {
+            symlink_targets: false,
+            errors: false,
+        })
+        .build();
+    assert!(config.use_color && config.truncate_names);
+    let lines = tree_to_lines(&entries, &config, &HashSet::new());
+    let text: Vec<String> = lines.iter().map(line_to_plain_text).collect();
+    assert_eq!(text, ["├── link", "└── locked"]);
+    assert_eq!(lines[0].spans[1].style.fg, Some(Color::Magenta));
+}
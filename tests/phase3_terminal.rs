@@ -4,3 +4,95 @@ fn test_terminal_size_returns_nonzero() {
     assert!(w > 0, "Width should be > 0 (fallback is 80)");
     assert!(h > 0, "Height should be > 0 (fallback is 24)");
 }
+
+/// Run the binary on a pseudoterminal until it draws the tree, send it
+/// `signal`, and return its exit code, everything it wrote, and the
+/// terminal's slave side.
+#[cfg(unix)]
+fn stop_with(signal: nix::sys::signal::Signal) -> (Option<i32>, String, std::os::fd::OwnedFd) {
+    use nix::pty::{openpty, Winsize};
+    use nix::sys::signal::kill;
+    use nix::sys::termios::{tcgetattr, LocalFlags};
+    use nix::unistd::Pid;
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("marker.txt"), "").unwrap();
+    let size = Winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let pty = openpty(&size, None).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_livetree"))
+        .arg("--safe-mode")
+        .arg(tmp.path())
+        .env("TERM", "xterm-256color")
+        .stdin(Stdio::from(pty.slave.try_clone().unwrap()))
+        .stdout(Stdio::from(pty.slave.try_clone().unwrap()))
+        .stderr(Stdio::from(pty.slave.try_clone().unwrap()))
+        .spawn()
+        .unwrap();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut master = std::fs::File::from(pty.master);
+    std::thread::spawn(move || {
+        let mut buf = [0; 4096];
+        while let Ok(n @ 1..) = master.read(&mut buf) {
+            if tx.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    let mut output = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !String::from_utf8_lossy(&output).contains("marker.txt") && Instant::now() < deadline {
+        if let Ok(chunk) = rx.recv_timeout(Duration::from_millis(50)) {
+            output.extend(chunk);
+        }
+    }
+    assert!(
+        !tcgetattr(&pty.slave)
+            .unwrap()
+            .local_flags
+            .contains(LocalFlags::ICANON),
+        "the tree is drawn in raw mode"
+    );
+    kill(Pid::from_raw(child.id() as i32), signal).unwrap();
+    let status = child.wait().unwrap();
+    // What the binary wrote on the way out.
+    while let Ok(chunk) = rx.recv_timeout(Duration::from_millis(200)) {
+        output.extend(chunk);
+    }
+    (
+        status.code(),
+        String::from_utf8_lossy(&output).into_owned(),
+        pty.slave,
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sigterm_restores_the_terminal_and_exits_143() {
+    use nix::sys::termios::{tcgetattr, LocalFlags};
+
+    let (code, output, slave) = stop_with(nix::sys::signal::Signal::SIGTERM);
+    assert_eq!(code, Some(143), "{output}");
+    let flags = tcgetattr(&slave).unwrap().local_flags;
+    assert!(flags.contains(LocalFlags::ICANON | LocalFlags::ECHO));
+    let left = output
+        .rfind("\x1b[?1049l")
+        .expect("left the alternate screen");
+    assert!(left > output.rfind("\x1b[?1049h").unwrap());
+    assert!(output.contains("livetree: watched"), "{output}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sighup_exits_129() {
+    let (code, output, _slave) = stop_with(nix::sys::signal::Signal::SIGHUP);
+    assert_eq!(code, Some(129), "{output}");
+}
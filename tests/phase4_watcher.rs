@@ -1,4 +1,7 @@
-use livetree::watcher::{start_watcher, FsWatcher, NotifyFsWatcher, WatchEvent};
+use livetree::watcher::{
+    start_supervised, start_watcher, start_watcher_with, ChangeKind, FsWatcher, NotifyFsWatcher,
+    WatchEvent, WatcherOptions,
+};
 use std::fs;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -24,6 +27,22 @@ fn test_watcher_detects_file_creation() {
     drop(watcher);
 }
 
+#[test]
+fn test_start_watcher_with_options() {
+    let dir = TempDir::new().unwrap();
+    let options = WatcherOptions {
+        debounce: Duration::from_millis(100),
+    };
+    let (watcher, rx) = start_watcher_with(dir.path(), &options).unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    fs::write(dir.path().join("newfile.txt"), b"hello").unwrap();
+
+    let event = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(matches!(event, WatchEvent::Changed(_)), "got {:?}", event);
+    drop(watcher);
+}
+
 #[test]
 fn test_watcher_detects_file_deletion() {
     let dir = TempDir::new().unwrap();
@@ -168,7 +187,8 @@ fn test_watcher_changed_paths_contain_created_file() {
     let event = rx.recv_timeout(Duration::from_secs(2)).unwrap();
     match event {
         WatchEvent::Changed(paths) => {
-            let path_set: std::collections::HashSet<PathBuf> = paths.into_iter().collect();
+            let path_set: std::collections::HashSet<PathBuf> =
+                paths.into_iter().map(|c| c.path).collect();
             // Canonicalize to handle macOS /var -> /private/var symlink
             let canonical_target = target.canonicalize().unwrap();
             let canonical_dir = dir.path().canonicalize().unwrap();
@@ -184,6 +204,40 @@ fn test_watcher_changed_paths_contain_created_file() {
     }
 }
 
+#[test]
+#[cfg(unix)]
+fn test_supervised_watcher_reattaches_to_replaced_root() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir(&root).unwrap();
+    let (_watcher, rx) = start_supervised(&root, 100, Duration::from_millis(100)).unwrap();
+
+    std::thread::sleep(Duration::from_millis(200));
+    fs::rename(&root, dir.path().join("old")).unwrap();
+    fs::create_dir(&root).unwrap();
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(3);
+    loop {
+        let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok(WatchEvent::Reattached) => break,
+            Ok(_) => continue,
+            Err(e) => panic!("no Reattached event: {e}"),
+        }
+    }
+
+    // Changes in the new directory are seen again.
+    fs::write(root.join("after.txt"), b"hello").unwrap();
+    let event = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    match event {
+        WatchEvent::Changed(changes) => assert!(
+            changes.iter().any(|c| c.path.ends_with("after.txt")),
+            "{changes:?}"
+        ),
+        other => panic!("Expected Changed, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_notify_watcher_trait_start_works() {
     let dir = TempDir::new().unwrap();
@@ -199,3 +253,30 @@ fn test_notify_watcher_trait_start_works() {
         "Expected Changed event from trait watcher"
     );
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_watcher_tags_chmod_as_metadata_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let file_path = dir.path().join("script.sh");
+    fs::write(&file_path, b"#!/bin/sh").unwrap();
+
+    let (_watcher, rx) = start_watcher(dir.path(), 100).unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    fs::set_permissions(&file_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let event = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    match event {
+        WatchEvent::Changed(changes) => {
+            let change = changes
+                .iter()
+                .find(|c| c.path == file_path)
+                .expect("chmod'ed file should be reported");
+            assert_eq!(change.kind, ChangeKind::Metadata);
+        }
+        other => panic!("Expected Changed, got {:?}", other),
+    }
+}
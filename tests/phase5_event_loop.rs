@@ -2,7 +2,7 @@ mod common;
 
 use common::default_tree_config;
 use livetree::render::{tree_to_lines, RenderConfig};
-use livetree::tree::build_tree;
+use livetree::tree::{build_tree, TreeConfig};
 use std::collections::HashSet;
 use std::time::Duration;
 use tempfile::TempDir;
@@ -40,10 +40,11 @@ fn test_full_pipeline_build_render() {
     let cfg = default_tree_config();
     let entries = build_tree(tmp.path(), &cfg);
 
-    let rcfg = RenderConfig {
-        use_color: false,
-        terminal_width: 80,
-    };
+    let rcfg = RenderConfig::builder()
+        .use_color(false)
+        .terminal_width(80)
+        .truncate_names(false)
+        .build();
 
     let lines = tree_to_lines(&entries.entries, &rcfg, &HashSet::new());
 
@@ -100,3 +101,1452 @@ fn test_watcher_triggers_rebuild() {
 
     drop(watcher);
 }
+
+// --- Headless event loop ---
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use livetree::event_loop::{run_headless, AnnouncePosition, HeadlessEvent, LoopConfig};
+use livetree::watcher::{ChangeKind, PathChange, WatchEvent};
+use livetree::workspace::Workspace;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::Modifier;
+
+fn loop_config() -> LoopConfig {
+    LoopConfig::default()
+}
+
+fn key(c: char) -> HeadlessEvent {
+    HeadlessEvent::Input(Event::Key(KeyEvent::new(
+        KeyCode::Char(c),
+        KeyModifiers::NONE,
+    )))
+}
+
+fn row_text(buffer: &Buffer, y: u16) -> String {
+    (0..buffer.area.width)
+        .map(|x| buffer[(x, y)].symbol())
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn run(dir: &std::path::Path, events: Vec<HeadlessEvent>) -> Vec<Buffer> {
+    run_with(dir, events, &loop_config())
+}
+
+/// `run` with options of its own.
+fn run_with(dir: &std::path::Path, events: Vec<HeadlessEvent>, config: &LoopConfig) -> Vec<Buffer> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for event in events {
+        tx.send(event).unwrap();
+    }
+    drop(tx);
+    run_headless(
+        TestBackend::new(60, 10),
+        dir,
+        &default_tree_config(),
+        &common::no_color_render_config(60),
+        rx,
+        config,
+    )
+}
+
+#[test]
+fn test_headless_keys_move_selection_and_quit() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let frames = run(tmp.path(), vec![key('j'), key('q'), key('j')]);
+
+    // Initial frame plus one for `j`; `q` stops before the trailing `j`.
+    assert_eq!(frames.len(), 2);
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 1).contains("b.txt"));
+    assert!(last[(4, 1)].modifier.contains(Modifier::REVERSED));
+    assert!(!last[(4, 0)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_fs_event_rebuilds_tree() {
+    let tmp = common::create_fixture(&["a.txt"]);
+    std::fs::write(tmp.path().join("new.txt"), "").unwrap();
+    let change = WatchEvent::Changed(vec![PathChange {
+        path: tmp.path().join("new.txt"),
+        kind: ChangeKind::Created,
+    }]);
+    let frames = run(tmp.path(), vec![HeadlessEvent::Fs(change)]);
+
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 1).contains("new.txt"));
+}
+
+#[test]
+fn test_headless_root_deleted_shows_message() {
+    let tmp = common::create_fixture(&["a.txt"]);
+    let frames = run(
+        tmp.path(),
+        vec![HeadlessEvent::Fs(WatchEvent::RootDeleted), key('j')],
+    );
+
+    assert!(row_text(frames.last().unwrap(), 0).starts_with("Directory deleted"));
+}
+
+#[test]
+fn test_headless_root_deleted_keeps_last_snapshot_until_quit() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    // Wide enough for the banner, which includes the temp dir path.
+    let frames = run_wide(
+        tmp.path(),
+        vec![
+            HeadlessEvent::Fs(WatchEvent::RootDeleted),
+            key('w'),
+            key('j'),
+            key('q'),
+            key('j'),
+        ],
+    );
+
+    // Initial frame, deletion, `w`, and `j`; `q` exits.
+    assert_eq!(frames.len(), 4);
+    assert!(row_text(&frames[1], 0).ends_with("press q to exit or w to wait"));
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).ends_with("showing the last snapshot, press q to exit"));
+    assert!(row_text(last, 1).contains("a.txt"));
+    assert!(row_text(last, 2).contains("b.txt"));
+    assert!(last[(4, 2)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_stale_toggle_marks_old_files() {
+    let tmp = common::create_fixture(&["old.txt", "new.txt"]);
+    let ten_days_ago = std::time::SystemTime::now() - Duration::from_secs(10 * 86_400);
+    std::fs::File::options()
+        .write(true)
+        .open(tmp.path().join("old.txt"))
+        .unwrap()
+        .set_modified(ten_days_ago)
+        .unwrap();
+
+    let frames = run_with(
+        tmp.path(),
+        vec![key('S')],
+        &LoopConfig {
+            stale_after: Duration::from_secs(86_400),
+            ..loop_config()
+        },
+    );
+
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 0), "├── new.txt");
+    assert_eq!(row_text(last, 1), "└── old.txt (stale)");
+}
+
+fn special(code: KeyCode) -> HeadlessEvent {
+    HeadlessEvent::Input(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+}
+
+#[test]
+fn test_headless_goto_completes_and_selects_offscreen_entry() {
+    let mut paths: Vec<String> = (0..12).map(|i| format!("src/f{i:02}.rs")).collect();
+    paths.push("src/zz_target.rs".to_string());
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let tmp = common::create_fixture(&paths);
+
+    let typed = || {
+        vec![
+            key(':'),
+            key('s'),
+            special(KeyCode::Tab),
+            key('z'),
+            special(KeyCode::Tab),
+        ]
+    };
+    let frames = run(tmp.path(), typed());
+    assert!(row_text(frames.last().unwrap(), 9).contains(" : src/zz_target.rs"));
+
+    let frames = run(
+        tmp.path(),
+        typed()
+            .into_iter()
+            .chain([special(KeyCode::Enter)])
+            .collect(),
+    );
+    let last = frames.last().unwrap();
+    let row = (0..8)
+        .find(|&y| row_text(last, y).contains("zz_target.rs"))
+        .expect("target scrolled into view");
+    assert!(last[(8, row)].modifier.contains(Modifier::REVERSED));
+    assert!(row_text(last, 9).contains("q: Quit"));
+}
+
+#[test]
+fn test_headless_goto_reports_missing_entry() {
+    let tmp = common::create_fixture(&["a.txt"]);
+    let events = vec![key('g'), key('n'), key('o'), special(KeyCode::Enter)];
+    let frames = run(tmp.path(), events);
+
+    assert!(row_text(frames.last().unwrap(), 9).contains("no such entry"));
+}
+
+#[test]
+fn test_headless_mark_toggles_and_outlives_changes() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let change = WatchEvent::Changed(vec![PathChange {
+        path: tmp.path().join("b.txt"),
+        kind: ChangeKind::Modified,
+    }]);
+    let frames = run(
+        tmp.path(),
+        vec![
+            key('m'),
+            key('j'),
+            key('m'),
+            key('j'),
+            key('m'),
+            key('m'),
+            HeadlessEvent::Fs(change),
+            key('r'),
+        ],
+    );
+
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 0), "*├── a.txt (marked)");
+    assert_eq!(row_text(last, 1), "*├── b.txt (marked)");
+    assert_eq!(row_text(last, 2), " └── c.txt");
+}
+
+fn run_wide(dir: &std::path::Path, events: Vec<HeadlessEvent>) -> Vec<Buffer> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for event in events {
+        tx.send(event).unwrap();
+    }
+    drop(tx);
+    run_headless(
+        TestBackend::new(200, 10),
+        dir,
+        &default_tree_config(),
+        &common::no_color_render_config(200),
+        rx,
+        &loop_config(),
+    )
+}
+
+fn modified(path: std::path::PathBuf) -> HeadlessEvent {
+    HeadlessEvent::Fs(WatchEvent::Changed(vec![PathChange {
+        path,
+        kind: ChangeKind::Modified,
+    }]))
+}
+
+#[test]
+fn test_headless_status_bar_names_last_change_and_jumps_to_it() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let frames = run_wide(tmp.path(), vec![modified(tmp.path().join("c.txt"))]);
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 8).ends_with(" c.txt"));
+    // Without follow mode the selection stays put.
+    assert!(last[(4, 0)].modifier.contains(Modifier::REVERSED));
+
+    let frames = run_wide(
+        tmp.path(),
+        vec![modified(tmp.path().join("c.txt")), key('L')],
+    );
+    let last = frames.last().unwrap();
+    assert!(last[(4, 2)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_follow_mode_selects_each_change() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let frames = run_wide(
+        tmp.path(),
+        vec![
+            key('F'),
+            modified(tmp.path().join("c.txt")),
+            modified(tmp.path().join("b.txt")),
+        ],
+    );
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 8).ends_with("b.txt  [follow]"));
+    assert!(last[(4, 1)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_clicking_last_change_selects_it() {
+    use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let frames = run_wide(tmp.path(), vec![modified(tmp.path().join("c.txt"))]);
+    let status = row_text(frames.last().unwrap(), 8);
+    let column = status.rfind("c.txt").unwrap() as u16 + 1;
+
+    let click = HeadlessEvent::Input(Event::Mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column,
+        row: 8,
+        modifiers: KeyModifiers::NONE,
+    }));
+    let frames = run_wide(tmp.path(), vec![modified(tmp.path().join("c.txt")), click]);
+    let last = frames.last().unwrap();
+    assert!(last[(4, 2)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_paste_is_not_read_as_keys() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    let paste = |text: &str| HeadlessEvent::Input(Event::Paste(text.to_string()));
+    // A pasted "jq" neither moves the selection nor quits.
+    let frames = run(tmp.path(), vec![paste("jq"), key('j')]);
+    assert_eq!(frames.len(), 2);
+    assert!(frames.last().unwrap()[(4, 1)]
+        .modifier
+        .contains(Modifier::REVERSED));
+
+    // With the palette open, pasted text becomes its input.
+    let frames = run(tmp.path(), vec![key(':'), paste("b.txt\n")]);
+    assert!(row_text(frames.last().unwrap(), 9).contains(" : b.txt"));
+}
+
+#[test]
+fn test_headless_line_numbers_and_goto_entry_number() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let frames = run(
+        tmp.path(),
+        vec![key('#'), key(':'), key('3'), special(KeyCode::Enter)],
+    );
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).starts_with("1 ├── a.txt"));
+    assert!(row_text(last, 2).starts_with("3 └── c.txt"));
+    assert!(last[(6, 2)].modifier.contains(Modifier::REVERSED));
+
+    let frames = run(
+        tmp.path(),
+        vec![key(':'), key('9'), special(KeyCode::Enter)],
+    );
+    assert!(row_text(frames.last().unwrap(), 9).contains("no entry 9 (1–3)"));
+}
+
+#[test]
+fn test_headless_counts_repeat_motions() {
+    let names: Vec<String> = (1..=20).map(|i| format!("{i:02}.txt")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let tmp = common::create_fixture(&names);
+    let frames = run(
+        tmp.path(),
+        vec![
+            key('1'),
+            key('2'),
+            key('j'),
+            key('5'),
+            key('k'),
+            key('j'),
+            key('1'),
+            key('5'),
+            special(KeyCode::Home),
+        ],
+    );
+    let selected = |frame: &Buffer| {
+        (0..8)
+            .find(|&y| frame[(4, y)].modifier.contains(Modifier::REVERSED))
+            .map(|y| row_text(frame, y))
+    };
+    // Digits wait for the motion without a frame of their own.
+    assert_eq!(frames.len(), 5);
+    assert_eq!(selected(&frames[1]).as_deref(), Some("├── 13.txt"));
+    assert_eq!(selected(&frames[2]).as_deref(), Some("├── 08.txt"));
+    assert_eq!(selected(&frames[3]).as_deref(), Some("├── 09.txt"));
+    assert_eq!(selected(&frames[4]).as_deref(), Some("├── 15.txt"));
+}
+
+#[test]
+fn test_headless_relative_line_numbers_count_from_the_selection() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt", "d.txt"]);
+    let frames = run(tmp.path(), vec![key('j'), key('%'), key('#')]);
+    let gutter = |frame: &Buffer| {
+        (0..4)
+            .map(|y| row_text(frame, y)[..1].to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(gutter(&frames[2]), ["1", "0", "1", "2"]);
+    assert_eq!(gutter(&frames[3]), ["1", "2", "1", "2"]);
+}
+
+fn typed(text: &str) -> impl Iterator<Item = HeadlessEvent> + '_ {
+    text.chars().map(key)
+}
+
+#[test]
+fn test_headless_search_steps_through_matches() {
+    let tmp = common::create_fixture(&["alpha.rs", "beta.txt", "Gamma.RS", "src/main.rs"]);
+    let mut events = vec![key('/')];
+    events.extend(typed("rs"));
+    events.extend([special(KeyCode::Enter), key('n'), key('n'), key('N')]);
+    let frames = run(tmp.path(), events);
+    let steps = &frames[frames.len() - 4..];
+    // The first match below the selection, then on down, wrapping around.
+    let selected: Vec<String> = steps
+        .iter()
+        .map(|frame| {
+            let y = (0..8)
+                .find(|&y| frame[(4, y)].modifier.contains(Modifier::REVERSED))
+                .unwrap();
+            row_text(frame, y)
+        })
+        .collect();
+    assert_eq!(
+        selected,
+        [
+            "│   └── main.rs (match)",
+            "├── alpha.rs (match)",
+            "└── Gamma.RS (match)",
+            "├── alpha.rs (match)",
+        ]
+    );
+    assert!(row_text(&steps[0], 8).contains("5 entries  [1/3]  |"));
+    assert!(row_text(&steps[3], 8).contains("5 entries  [2/3]  |"));
+    assert_eq!(row_text(&steps[0], 3), "├── beta.txt");
+}
+
+#[test]
+fn test_headless_ranked_view_lists_fuzzy_matches_best_first() {
+    let tmp = common::create_fixture(&["src/main.rs", "sorcerer/manners.rs", "scream.rs"]);
+    let mut events = vec![key('o'), key('/')];
+    events.extend(typed("srcmnrs"));
+    events.extend([special(KeyCode::Enter), key('o')]);
+    let frames = run(tmp.path(), events);
+    let ranked = &frames[frames.len() - 2];
+    let sep = std::path::MAIN_SEPARATOR;
+    let rows: Vec<String> = (0..3).map(|y| row_text(ranked, y)).collect();
+    assert_eq!(
+        rows,
+        [
+            format!("src{sep}main.rs (match)"),
+            format!("sorcerer{sep}manners.rs (match)"),
+            String::new(),
+        ]
+    );
+    assert!(ranked[(4, 0)].modifier.contains(Modifier::REVERSED));
+    assert!(row_text(ranked, 8).contains("2 entries  [1/2]  |"));
+    // Leaving the view brings the tree back, the selection kept.
+    let tree = frames.last().unwrap();
+    assert_eq!(row_text(tree, 3), "│   └── main.rs (match)");
+    assert!(tree[(8, 3)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_palette_commands_change_settings() {
+    let tmp = common::create_fixture(&["a.txt", "b.log", ".env"]);
+    let mut events = vec![key(':')];
+    events.extend(typed("set hid"));
+    events.extend([special(KeyCode::Tab)]);
+    events.extend(typed("on"));
+    events.extend([special(KeyCode::Enter), key(':')]);
+    events.extend(typed("ignore *.log"));
+    events.extend([special(KeyCode::Enter)]);
+    let frames = run(tmp.path(), events);
+    let last = frames.last().unwrap();
+    let rows: Vec<String> = (0..8).map(|y| row_text(last, y)).collect();
+    assert!(rows.iter().any(|r| r.contains(".env")), "{rows:?}");
+    assert!(!rows.iter().any(|r| r.contains("b.log")), "{rows:?}");
+
+    let mut events = vec![key(':')];
+    events.extend(typed("set colour"));
+    let frames = run(tmp.path(), events);
+    assert!(row_text(frames.last().unwrap(), 9).contains("unknown setting 'colour'"));
+}
+
+#[test]
+fn test_headless_palette_history_is_kept_per_file() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    let state = TempDir::new().unwrap();
+    let config = LoopConfig {
+        history_file: Some(state.path().join("history")),
+        ..loop_config()
+    };
+    let run_with_history = |events: Vec<HeadlessEvent>| run_with(tmp.path(), events, &config);
+
+    let mut events = vec![key(':')];
+    events.extend(typed("b.txt"));
+    events.push(special(KeyCode::Enter));
+    run_with_history(events);
+
+    // A later session recalls the command with ↑.
+    let frames = run_with_history(vec![
+        key(':'),
+        special(KeyCode::Up),
+        special(KeyCode::Enter),
+    ]);
+    let last = frames.last().unwrap();
+    assert!(last[(4, 1)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_w_exports_the_tree() {
+    let tmp = common::create_fixture(&["a.txt", "dir/b.txt"]);
+    let out = TempDir::new().unwrap();
+    let file = out.path().join("tree.txt");
+    let mut events = vec![key('w')];
+    events.extend(typed(&file.to_string_lossy()));
+    events.push(special(KeyCode::Enter));
+    run(tmp.path(), events);
+
+    let text = std::fs::read_to_string(&file).unwrap();
+    assert!(
+        text.contains("├── dir\n│   └── b.txt\n└── a.txt\n"),
+        "{text}"
+    );
+}
+
+// Needs a case-sensitive filesystem to create both names.
+#[cfg(target_os = "linux")]
+#[test]
+fn test_headless_case_collisions_are_flagged() {
+    let tmp = common::create_fixture(&["README.md", "readme.md", "z.txt"]);
+    let frames = run(tmp.path(), vec![key('q')]);
+    let last = frames.last().unwrap();
+    let rows: Vec<String> = (0..3).map(|y| row_text(last, y)).collect();
+    assert_eq!(
+        rows,
+        [
+            "├── readme.md (case collision)",
+            "├── README.md (case collision)",
+            "└── z.txt"
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_headless_hard_links_are_flagged_with_hard_links() {
+    let tmp = common::create_fixture(&["lib/a.so", "z.so"]);
+    std::fs::hard_link(tmp.path().join("lib/a.so"), tmp.path().join("b.so")).unwrap();
+    let frames = run_with(
+        tmp.path(),
+        vec![key('q')],
+        &LoopConfig {
+            hard_links: true,
+            ..loop_config()
+        },
+    );
+    let last = frames.last().unwrap();
+    let rows: Vec<String> = (0..4).map(|y| row_text(last, y)).collect();
+    assert_eq!(
+        rows,
+        [
+            "├── lib",
+            "│   └── a.so",
+            "├── b.so (hard link of lib/a.so)",
+            "└── z.so"
+        ]
+    );
+
+    // Without the flag no inodes are read.
+    let frames = run(tmp.path(), vec![key('q')]);
+    assert_eq!(row_text(frames.last().unwrap(), 2), "├── b.so");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_headless_permissions_column_and_executable_marker() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = common::create_fixture(&["bin/", "build.sh", "notes.txt"]);
+    let set_mode = |path: &str, mode: u32| {
+        std::fs::set_permissions(tmp.path().join(path), std::fs::Permissions::from_mode(mode))
+            .unwrap()
+    };
+    set_mode("bin", 0o755);
+    set_mode("build.sh", 0o750);
+    set_mode("notes.txt", 0o644);
+    let (tx, rx) = crossbeam_channel::unbounded();
+    tx.send(key('q')).unwrap();
+    let frames = run_headless(
+        TestBackend::new(60, 10),
+        tmp.path(),
+        &TreeConfig {
+            stat: true,
+            ..default_tree_config()
+        },
+        &common::no_color_render_config(60),
+        rx,
+        &LoopConfig {
+            permissions: true,
+            ..loop_config()
+        },
+    );
+    let last = frames.last().unwrap();
+    let rows: Vec<String> = (0..3).map(|y| row_text(last, y)).collect();
+    assert_eq!(
+        rows,
+        [
+            "drwxr-xr-x ├── bin",
+            "-rwxr-x--- ├── build.sh*",
+            "-rw-r--r-- └── notes.txt"
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_headless_owner_column_sits_between_permissions_and_tree() {
+    use std::os::unix::fs::MetadataExt;
+
+    let tmp = common::create_fixture(&["a.txt"]);
+    let meta = std::fs::metadata(tmp.path().join("a.txt")).unwrap();
+    let mut names = livetree::owners::OwnerNames::new();
+    let user = names.user(meta.uid()).to_string();
+    let owner = format!("{user} {}", names.group(meta.gid()));
+    let (tx, rx) = crossbeam_channel::unbounded();
+    tx.send(key('q')).unwrap();
+    let frames = run_headless(
+        TestBackend::new(60, 10),
+        tmp.path(),
+        &TreeConfig {
+            stat: true,
+            ..default_tree_config()
+        },
+        &common::no_color_render_config(60),
+        rx,
+        &LoopConfig {
+            permissions: true,
+            owner: true,
+            ..loop_config()
+        },
+    );
+    let row = row_text(frames.last().unwrap(), 0);
+    let mode = livetree::render::format_mode(meta.mode());
+    assert_eq!(row, format!("{mode} {owner} └── a.txt"));
+}
+
+#[test]
+fn test_headless_enter_opens_and_closes_an_archive() {
+    let tmp = common::create_fixture(&["pkg.tar"]);
+    let mut tar = tar::Builder::new(std::fs::File::create(tmp.path().join("pkg.tar")).unwrap());
+    for name in ["lib/a.txt", "b.txt"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(1);
+        header.set_cksum();
+        tar.append_data(&mut header, name, &b"x"[..]).unwrap();
+    }
+    tar.finish().unwrap();
+
+    let frames = run(
+        tmp.path(),
+        vec![special(KeyCode::Enter), special(KeyCode::Enter)],
+    );
+    let opened = &frames[frames.len() - 2];
+    let rows: Vec<String> = (0..4).map(|y| row_text(opened, y)).collect();
+    assert_eq!(
+        rows,
+        [
+            "└── pkg.tar",
+            "    ├── lib",
+            "    │   └── a.txt",
+            "    └── b.txt"
+        ]
+    );
+    let closed = frames.last().unwrap();
+    assert_eq!(row_text(closed, 0), "└── pkg.tar");
+    assert_eq!(row_text(closed, 1), "");
+}
+
+#[test]
+fn test_headless_read_only_refuses_file_operations() {
+    let tmp = common::create_fixture(&["a.txt"]);
+    let frames = run_with(
+        tmp.path(),
+        vec![key('D'), key('y'), key('q')],
+        &LoopConfig {
+            read_only: true,
+            ..loop_config()
+        },
+    );
+    assert!(tmp.path().join("a.txt").exists());
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).ends_with("a.txt"));
+    assert_eq!(
+        row_text(last, 7),
+        format!("{:>60}", "error: not available for a remote tree ").trim_end()
+    );
+}
+
+/// A remote tree: its entries are not on this machine.
+struct Remote;
+
+impl livetree::tree::TreeBuilder for Remote {
+    fn build_tree(
+        &self,
+        root: &std::path::Path,
+        _config: &TreeConfig,
+    ) -> livetree::tree::TreeSnapshot {
+        let mut entry =
+            livetree::tree::TreeEntry::new("remote_only.rs", root.join("remote_only.rs"), 1, false);
+        entry.is_last = true;
+        livetree::tree::TreeSnapshot {
+            entries: vec![entry],
+            total_entries: 1,
+        }
+    }
+}
+
+/// `run_with` on the `Remote` tree, read-only.
+fn run_remote(
+    dir: &std::path::Path,
+    events: Vec<HeadlessEvent>,
+    config: LoopConfig,
+) -> Vec<Buffer> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for event in events {
+        tx.send(event).unwrap();
+    }
+    drop(tx);
+    livetree::event_loop::run_headless_with_tree_builder(
+        TestBackend::new(60, 10),
+        dir,
+        &default_tree_config(),
+        &common::no_color_render_config(60),
+        rx,
+        &Remote,
+        &LoopConfig {
+            read_only: true,
+            ..config
+        },
+    )
+}
+
+#[test]
+fn test_headless_read_only_completes_from_the_listing() {
+    let tmp = common::create_fixture(&["local_only.rs"]);
+    let events = vec![
+        key(':'),
+        key('l'),
+        special(KeyCode::Tab),
+        special(KeyCode::Backspace),
+        key('r'),
+        special(KeyCode::Tab),
+    ];
+    let frames = run_remote(tmp.path(), events, loop_config());
+    assert!(row_text(frames.last().unwrap(), 9).contains(" : remote_only.rs"));
+}
+
+#[test]
+fn test_headless_remote_creations_are_not_taken_for_temp_files() {
+    // The created path is only on the remote side, never on this disk.
+    let tmp = common::create_fixture(&["local_only.rs"]);
+    let created = HeadlessEvent::Fs(WatchEvent::Changed(vec![PathChange {
+        path: tmp.path().join("remote_only.rs"),
+        kind: ChangeKind::Created,
+    }]));
+    let frames = run_remote(
+        tmp.path(),
+        vec![created],
+        LoopConfig {
+            announce: Some(AnnouncePosition::Top),
+            ..loop_config()
+        },
+    );
+    let line = row_text(frames.last().unwrap(), 0);
+    assert!(line.starts_with(" created remote_only.rs at "), "{line}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_headless_control_socket_drives_the_view() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let tmp = common::create_fixture(&["a.txt", ".env", "dir/inner.txt"]);
+    let socket_dir = TempDir::new().unwrap();
+    let socket = socket_dir.path().join("control.sock");
+    let endpoint = socket.to_str().unwrap().parse().unwrap();
+    let (_server, control) = livetree::control::listen(&endpoint).unwrap();
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let (tx, rx) = crossbeam_channel::unbounded::<HeadlessEvent>();
+    let client = std::thread::spawn(move || {
+        let mut stream = UnixStream::connect(&socket).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut answers = Vec::new();
+        for command in ["set-depth 1", "toggle-hidden", "scroll 2", "bogus", "quit"] {
+            writeln!(stream, "{command}").unwrap();
+            let mut answer = String::new();
+            reader.read_line(&mut answer).unwrap();
+            answers.push(answer.trim_end().to_string());
+        }
+        drop(tx);
+        answers
+    });
+    let frames = run_headless(
+        TestBackend::new(60, 10),
+        tmp.path(),
+        &default_tree_config(),
+        &common::no_color_render_config(60),
+        rx,
+        &LoopConfig {
+            control: Some(control),
+            ..loop_config()
+        },
+    );
+    assert_eq!(
+        client.join().unwrap(),
+        ["ok", "ok", "ok", "error: unknown command 'bogus'", "ok"]
+    );
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 0), "├── dir");
+    assert_eq!(row_text(last, 1), "├── a.txt");
+    assert_eq!(row_text(last, 2), "└── .env");
+}
+
+#[test]
+fn test_headless_watcher_notices_show_as_toasts() {
+    let tmp = common::create_fixture(&["a.txt"]);
+    let frames = run(
+        tmp.path(),
+        vec![
+            HeadlessEvent::Fs(WatchEvent::Reattached),
+            HeadlessEvent::Fs(WatchEvent::Error("inotify limit reached".to_string())),
+        ],
+    );
+    assert_eq!(row_text(&frames[0], 7), "");
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).ends_with("a.txt"));
+    assert_eq!(
+        row_text(last, 6),
+        format!("{:>60}", "re-attached watcher ").trim_end()
+    );
+    assert_eq!(
+        row_text(last, 7),
+        format!("{:>60}", "error: watcher: inotify limit reached ").trim_end()
+    );
+    assert!(row_text(last, 9).starts_with(" q: Quit"));
+}
+
+#[test]
+fn test_headless_announcement_line_describes_latest_change() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    let events = vec![HeadlessEvent::Fs(WatchEvent::Changed(vec![
+        PathChange {
+            path: tmp.path().join("a.txt"),
+            kind: ChangeKind::Modified,
+        },
+        PathChange {
+            path: tmp.path().join("b.txt"),
+            kind: ChangeKind::Created,
+        },
+    ]))];
+    let frames = run_with(
+        tmp.path(),
+        events,
+        &LoopConfig {
+            announce: Some(AnnouncePosition::Top),
+            ..loop_config()
+        },
+    );
+    assert_eq!(row_text(&frames[0], 0), " No changes yet");
+    assert!(row_text(&frames[0], 1).ends_with("a.txt"));
+    let last = frames.last().unwrap();
+    let line = row_text(last, 0);
+    assert!(line.starts_with(" created b.txt at "), "{line}");
+    assert!(line.ends_with(" (and 1 more)"), "{line}");
+}
+
+#[test]
+fn test_headless_f12_toggles_the_stats_overlay() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    let frames = run(
+        tmp.path(),
+        vec![
+            special(KeyCode::F(12)),
+            HeadlessEvent::Fs(WatchEvent::Changed(vec![PathChange {
+                path: tmp.path().join("a.txt"),
+                kind: ChangeKind::Modified,
+            }])),
+            special(KeyCode::F(12)),
+        ],
+    );
+    assert!(!row_text(&frames[0], 0).contains("Stats"));
+
+    let shown = &frames[1];
+    assert!(
+        row_text(shown, 0).contains("┌ Stats "),
+        "{}",
+        row_text(shown, 0)
+    );
+    assert!(row_text(shown, 0).starts_with("├── a.txt"));
+    assert!(row_text(shown, 1).contains("│frames   1 ("));
+    assert!(row_text(shown, 2).contains("│build    "));
+    assert!(row_text(shown, 4).contains("│events   0.0/s"));
+    assert!(row_text(shown, 5).contains("│snapshot 2 entries, ~"));
+
+    let after_change = &frames[2];
+    assert!(row_text(after_change, 1).contains("│frames   2 ("));
+    assert!(row_text(after_change, 4).contains("│events   0.1/s"));
+
+    assert!(!row_text(frames.last().unwrap(), 0).contains("Stats"));
+}
+
+#[test]
+fn test_headless_brackets_change_the_entry_limit() {
+    let mut paths = vec!["a.txt".to_string(), "d/".to_string()];
+    paths.extend((0..15).map(|i| format!("d/f{i:02}.txt")));
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let tmp = common::create_fixture(&paths);
+    let (tx, rx) = crossbeam_channel::unbounded();
+    for event in [key(']'), key('[')] {
+        tx.send(event).unwrap();
+    }
+    drop(tx);
+    let frames = run_headless(
+        TestBackend::new(80, 20),
+        tmp.path(),
+        &TreeConfig {
+            max_entries: Some(10),
+            ..default_tree_config()
+        },
+        &common::no_color_render_config(80),
+        rx,
+        &loop_config(),
+    );
+    assert_eq!(frames.len(), 3);
+    // The top level stays complete; `d` loses its last files.
+    assert!(row_text(&frames[0], 8).ends_with("f07.txt"));
+    assert!(row_text(&frames[0], 9).ends_with("a.txt"));
+    assert_eq!(
+        row_text(&frames[0], 10),
+        "... showing 10 of 17 entries down to depth 2 (truncated, ]/[ to change)"
+    );
+
+    assert!(row_text(&frames[1], 16).ends_with("a.txt"));
+    assert!(row_text(&frames[1], 17).ends_with("showing up to 20 entries"));
+    assert!(row_text(&frames[2], 10).starts_with("... showing 10 of 17"));
+}
+
+#[test]
+fn test_headless_lazy_loading_walks_directories_when_expanded() {
+    use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+    let tmp = common::create_fixture(&["src/", "src/lib/", "src/lib/a.rs", "src/main.rs", "z.txt"]);
+    let click = || {
+        HeadlessEvent::Input(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 5,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        }))
+    };
+    let mut events = vec![click(), click()];
+    events.push(key(':'));
+    events.extend(typed("src/lib/a.rs"));
+    events.push(special(KeyCode::Enter));
+    let frames = run_with(
+        tmp.path(),
+        events,
+        &LoopConfig {
+            lazy_depth: Some(1),
+            ..loop_config()
+        },
+    );
+    let rows = |frame: &Buffer, n: u16| (0..n).map(|y| row_text(frame, y)).collect::<Vec<_>>();
+    assert_eq!(rows(&frames[0], 3), ["├── src …", "└── z.txt", ""]);
+
+    // A double click walks one more level below `src`.
+    assert_eq!(
+        rows(&frames[2], 5),
+        [
+            "├── src",
+            "│   ├── lib …",
+            "│   └── main.rs",
+            "└── z.txt",
+            ""
+        ]
+    );
+
+    // Jumping to a path loads the directories on the way.
+    let last = frames.last().unwrap();
+    assert_eq!(
+        rows(last, 5),
+        [
+            "├── src",
+            "│   ├── lib",
+            "│   │   └── a.rs",
+            "│   └── main.rs",
+            "└── z.txt"
+        ]
+    );
+    assert!(last[(12, 2)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_ui_state_is_restored_next_session() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt", ".env"]);
+    let state_dir = TempDir::new().unwrap();
+    let config = LoopConfig {
+        state_file: Some(state_dir.path().join("ui.toml")),
+        ..loop_config()
+    };
+    let session = |events: Vec<HeadlessEvent>| run_with(tmp.path(), events, &config);
+
+    session(vec![key('#'), key('.'), key('j')]);
+    let saved = std::fs::read_to_string(state_dir.path().join("ui.toml")).unwrap();
+    assert!(saved.contains("selected = \"b.txt\""), "{saved}");
+
+    let frames = session(Vec::new());
+    let first = &frames[0];
+    assert!(row_text(first, 0).starts_with("1 ├── a.txt"));
+    assert!(row_text(first, 3).starts_with("4 └── .env"));
+    assert!(first[(6, 1)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_session_file_saves_the_workspace_on_exit() {
+    let tmp = common::create_fixture(&["a.txt", ".env"]);
+    let session_dir = TempDir::new().unwrap();
+    let file = session_dir.path().join("sessions/work.toml");
+    let config = LoopConfig {
+        session_file: Some(file.clone()),
+        ..loop_config()
+    };
+    run_with(tmp.path(), vec![key('.'), key('#')], &config);
+
+    let workspace = Workspace::load(&file).unwrap().unwrap();
+    assert_eq!(workspace.root, tmp.path());
+    assert!(workspace.show_hidden && workspace.line_numbers);
+    assert_eq!(workspace.split, None);
+}
+
+#[test]
+fn test_headless_marks_have_a_margin_and_can_be_cycled() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt", "d.txt", ".cfg/"]);
+    let selected_row = |frame: &Buffer| {
+        (0..5)
+            .find(|&y| frame[(2, y)].modifier.contains(Modifier::REVERSED))
+            .unwrap()
+    };
+    let frames = run(
+        tmp.path(),
+        vec![
+            key('j'),
+            key('m'),
+            key('j'),
+            key('j'),
+            key('m'),
+            key('\''),
+            key('\''),
+            key('"'),
+            key('.'),
+        ],
+    );
+    let marked = &frames[5];
+    assert_eq!(row_text(marked, 0), " ├── a.txt");
+    assert_eq!(row_text(marked, 1), "*├── b.txt (marked)");
+    assert_eq!(row_text(marked, 3), "*└── d.txt (marked)");
+    let rows: Vec<u16> = frames[5..9].iter().map(selected_row).collect();
+    assert_eq!(rows, [3, 1, 3, 1]);
+
+    // Marks are kept by path when the tree is rebuilt with more entries.
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 0), " ├── .cfg");
+    assert_eq!(row_text(last, 2), "*├── b.txt (marked)");
+}
+
+#[test]
+fn test_headless_file_operations_create_rename_and_confirm_deletes() {
+    let tmp = common::create_fixture(&["a.txt", "src/"]);
+    let mut events = vec![key('a')];
+    events.extend(typed("new.txt"));
+    events.push(special(KeyCode::Enter));
+    events.push(key('R'));
+    events.extend(typed("2"));
+    events.push(special(KeyCode::Enter));
+    events.push(key('A'));
+    events.extend(typed("docs"));
+    events.push(special(KeyCode::Enter));
+    events.extend([key('D'), key('n'), key('D')]);
+    let frames = run(tmp.path(), events);
+
+    // The first entry is src/, so the file is created in it.
+    assert!(!tmp.path().join("src/new.txt").exists());
+    assert!(tmp.path().join("src/new.txt2").is_file());
+    assert!(tmp.path().join("src/docs").is_dir());
+    let renaming = frames
+        .iter()
+        .any(|f| row_text(f, 9).starts_with(" : rename new.txt▏"));
+    assert!(renaming, "R opens the palette with the current name");
+
+    // `n` cancels; the second `D` is still waiting for its answer.
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 1), "│   ├── docs");
+    assert!(row_text(last, 9).starts_with(" Move src/docs/ to the trash?"));
+
+    let frames = run_with(
+        tmp.path(),
+        vec![key('j'), key('j'), key('D'), key('y')],
+        &LoopConfig {
+            trash: false,
+            ..loop_config()
+        },
+    );
+    assert!(row_text(&frames[3], 9).starts_with(" Delete src/new.txt2?"));
+    assert!(!tmp.path().join("src/new.txt2").exists());
+    assert_eq!(row_text(frames.last().unwrap(), 1), "│   └── docs");
+}
+
+#[test]
+#[cfg(all(unix, not(target_os = "macos")))]
+fn test_headless_delete_moves_entries_to_the_trash() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    let data = TempDir::new_in(tmp.path().parent().unwrap()).unwrap();
+    // Only this test reads XDG_DATA_HOME (through the trash crate).
+    std::env::set_var("XDG_DATA_HOME", data.path());
+    let frames = run(tmp.path(), vec![key('D'), key('y')]);
+
+    assert!(!tmp.path().join("a.txt").exists());
+    let trashed = data.path().join("Trash/files/a.txt");
+    assert!(trashed.is_file(), "a.txt is in the trash");
+    let info = std::fs::read_to_string(data.path().join("Trash/info/a.txt.trashinfo")).unwrap();
+    assert!(info.contains("Path="), "{info}");
+    assert_eq!(row_text(frames.last().unwrap(), 0), "└── b.txt");
+}
+
+#[test]
+fn test_headless_ignore_file_is_reread_when_it_changes() {
+    let tmp = common::create_fixture(&["a.txt", "b.log", "c.txt"]);
+    let state = TempDir::new().unwrap();
+    let file = state.path().join("ignore");
+    std::fs::write(&file, "# logs\n*.log\n").unwrap();
+    let tree_config = TreeConfig {
+        ignore_patterns: livetree::tree::build_ignore_set(&["*.log".to_string()]),
+        ..default_tree_config()
+    };
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let writer = {
+        let file = file.clone();
+        std::thread::spawn(move || {
+            // Let the loop read the file first.
+            std::thread::sleep(Duration::from_millis(300));
+            std::fs::write(&file, "a.txt\n").unwrap();
+            tx.send(modified(file)).unwrap();
+            tx.send(key(':')).unwrap();
+            for event in typed("unignore a.txt") {
+                tx.send(event).unwrap();
+            }
+            tx.send(special(KeyCode::Enter)).unwrap();
+        })
+    };
+    let frames = run_headless(
+        TestBackend::new(60, 10),
+        tmp.path(),
+        &tree_config,
+        &common::no_color_render_config(60),
+        rx,
+        &LoopConfig {
+            ignore_file: Some(file.clone()),
+            ..loop_config()
+        },
+    );
+    writer.join().unwrap();
+
+    let first = &frames[0];
+    assert_eq!(row_text(first, 0), "├── a.txt");
+    assert_eq!(row_text(first, 1), "└── c.txt");
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 0), "├── b.log");
+    assert_eq!(row_text(last, 1), "└── c.txt");
+    assert!(row_text(last, 9).contains("'a.txt' comes from"));
+}
+
+#[test]
+fn test_headless_offscreen_change_highlights_visible_ancestors() {
+    let files: Vec<String> = (0..10).map(|i| format!("a/f{i}.txt")).collect();
+    let paths: Vec<&str> = files.iter().map(String::as_str).collect();
+    let tmp = common::create_fixture(&paths);
+    let run_colored = |changed: &str| {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        tx.send(modified(tmp.path().join(changed))).unwrap();
+        drop(tx);
+        run_headless(
+            TestBackend::new(60, 10),
+            tmp.path(),
+            &default_tree_config(),
+            &common::color_render_config(60),
+            rx,
+            &loop_config(),
+        )
+    };
+
+    // a/f9.txt is below the eight tree rows, so `a/` lights up for it.
+    let frames = run_colored("a/f9.txt");
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).ends_with("a"));
+    assert!(last[(4, 0)].modifier.contains(Modifier::UNDERLINED));
+
+    // A change on screen is highlighted where it is, not on its parent.
+    let frames = run_colored("a/f1.txt");
+    let last = frames.last().unwrap();
+    assert!(!last[(4, 0)].modifier.contains(Modifier::UNDERLINED));
+}
+
+#[test]
+fn test_headless_sticky_headers_pin_the_enclosing_directories() {
+    let files: Vec<String> = (0..20).map(|i| format!("a/b/f{i:02}.txt")).collect();
+    let paths: Vec<&str> = files.iter().map(String::as_str).collect();
+    let tmp = common::create_fixture(&paths);
+    let down = |then: Vec<HeadlessEvent>| (0..12).map(|_| key('j')).chain(then).collect();
+
+    // Eight tree rows; f10 is selected on the last, with a/ and b/ pinned
+    // over the rows scrolled past.
+    let frames = run(tmp.path(), down(Vec::new()));
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).ends_with("a"));
+    assert!(row_text(last, 1).ends_with("b"));
+    assert!(last[(8, 1)].modifier.contains(Modifier::BOLD));
+    assert!(row_text(last, 2).ends_with("f05.txt"));
+    assert!(row_text(last, 7).ends_with("f10.txt"));
+    assert!(last[(12, 7)].modifier.contains(Modifier::REVERSED));
+
+    // Moving up onto a covered row scrolls it out from under the headers.
+    let frames = run(tmp.path(), down((0..6).map(|_| key('k')).collect()));
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 2).ends_with("f04.txt"));
+    assert!(last[(12, 2)].modifier.contains(Modifier::REVERSED));
+
+    // `P` unpins them.
+    let frames = run(tmp.path(), down(vec![key('P')]));
+    let last = frames.last().unwrap();
+    assert!(row_text(last, 0).ends_with("f03.txt"));
+}
+
+#[test]
+fn test_headless_zoom_shows_a_directory_as_the_root() {
+    let tmp = common::create_fixture(&["src/lib/a.rs", "src/main.rs", "z.txt"]);
+    let frames = run(
+        tmp.path(),
+        vec![
+            key('l'),
+            modified(tmp.path().join("z.txt")),
+            key('h'),
+            key('h'),
+        ],
+    );
+
+    // Changes outside the zoomed-in root are not shown.
+    assert_eq!(frames.len(), 4);
+    let zoomed = &frames[1];
+    assert_eq!(
+        (0..4).map(|y| row_text(zoomed, y)).collect::<Vec<_>>(),
+        ["├── lib", "│   └── a.rs", "└── main.rs", ""]
+    );
+    assert!(row_text(zoomed, 8).contains("/src  |  3 entries  |  No changes"));
+
+    // Zooming out selects the directory zoomed into; there is nothing
+    // further out to go back to.
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 4), "└── z.txt");
+    assert!(last[(4, 0)].modifier.contains(Modifier::REVERSED));
+    assert_eq!(last, &frames[2]);
+}
+
+#[test]
+fn test_headless_root_moves_up_above_the_starting_root() {
+    let tmp = common::create_fixture(&["sub/x.txt", "top.txt"]);
+    let frames = run(&tmp.path().join("sub"), vec![key('u'), key('h')]);
+    assert_eq!(row_text(&frames[0], 0), "└── x.txt");
+
+    // The directory moved up from stays selected; there is no zoom to go
+    // back out of.
+    let up = &frames[1];
+    assert_eq!(
+        (0..3).map(|y| row_text(up, y)).collect::<Vec<_>>(),
+        ["├── sub", "│   └── x.txt", "└── top.txt"]
+    );
+    assert!(up[(4, 0)].modifier.contains(Modifier::REVERSED));
+    let status = row_text(up, 8);
+    assert!(status.contains("  |  3 entries"), "{status}");
+    assert!(!status.contains("/sub"), "{status}");
+    assert_eq!(frames.last().unwrap(), up);
+}
+
+#[test]
+fn test_headless_flat_list_shows_relative_paths() {
+    let tmp = common::create_fixture(&["src/lib/a.rs", "z.txt"]);
+    let frames = run(tmp.path(), vec![key('j'), key('v'), key('v')]);
+    let sep = std::path::MAIN_SEPARATOR;
+    let flat = &frames[2];
+    assert_eq!(
+        (0..4).map(|y| row_text(flat, y)).collect::<Vec<_>>(),
+        [
+            format!("src{sep}"),
+            format!("src{sep}lib{sep}"),
+            format!("src{sep}lib{sep}a.rs"),
+            "z.txt".to_string()
+        ]
+    );
+    assert!(flat[(0, 1)].modifier.contains(Modifier::REVERSED));
+    assert_eq!(row_text(&frames[3], 1), "│   └── lib");
+}
+
+#[test]
+fn test_headless_columns_pack_a_short_tree_across_the_terminal() {
+    let names: Vec<String> = (1..=12).map(|i| format!("{i:02}.txt")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let tmp = common::create_fixture(&names);
+    let frames = run(tmp.path(), vec![key('|'), special(KeyCode::End), key('|')]);
+    assert_eq!(row_text(&frames[0], 7), "├── 08.txt");
+    let packed = &frames[1];
+    assert_eq!(row_text(packed, 0), "├── 01.txt  ├── 07.txt");
+    assert_eq!(row_text(packed, 5), "├── 06.txt  └── 12.txt");
+    assert_eq!(row_text(packed, 6), "");
+    // The last entry is selected in place, without scrolling.
+    assert!(frames[2][(12, 5)].modifier.contains(Modifier::REVERSED));
+    assert_eq!(row_text(&frames[3], 7), "└── 12.txt");
+}
+
+#[test]
+fn test_headless_split_view_moves_the_focused_pane() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt"]);
+    let other = common::create_fixture(&["x.txt", "y.txt", "z.txt"]);
+    let (_split_tx, split_rx) = crossbeam_channel::unbounded();
+    let frames = run_with(
+        tmp.path(),
+        vec![special(KeyCode::Tab), key('j'), key('j')],
+        &LoopConfig {
+            split: Some((other.path().to_path_buf(), split_rx)),
+            ..loop_config()
+        },
+    );
+
+    // The main tree keeps the left half; the other directory is boxed on
+    // the right, and only the focused pane shows its selection.
+    let first = &frames[0];
+    assert!(row_text(first, 0).starts_with("├── a.txt"));
+    assert!(first[(4, 0)].modifier.contains(Modifier::REVERSED));
+    assert!(row_text(first, 0).contains(&format!("┌ {} ─", other.path().display())));
+    assert!(row_text(first, 1).ends_with("│├── x.txt                   │"));
+    let last = frames.last().unwrap();
+    assert!(!last[(4, 0)].modifier.contains(Modifier::REVERSED));
+    assert!(row_text(last, 3).contains("└── z.txt"));
+    assert!(last[(35, 3)].modifier.contains(Modifier::REVERSED));
+    assert!(!last[(35, 2)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn test_headless_compare_mode_marks_entries_on_one_side() {
+    let tmp = common::create_fixture(&["a.txt", "main_only.txt"]);
+    let other = common::create_fixture(&["a.txt", "other_only.txt"]);
+    let (_split_tx, split_rx) = crossbeam_channel::unbounded();
+    let config = LoopConfig {
+        split: Some((other.path().to_path_buf(), split_rx)),
+        compare: true,
+        ..loop_config()
+    };
+    let frames = |events: Vec<HeadlessEvent>| {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        for event in events {
+            tx.send(event).unwrap();
+        }
+        drop(tx);
+        run_headless(
+            TestBackend::new(80, 10),
+            tmp.path(),
+            &default_tree_config(),
+            &common::no_color_render_config(80),
+            rx,
+            &config,
+        )
+    };
+
+    let first = &frames(Vec::new())[0];
+    assert!(row_text(first, 1).starts_with("+└── main_only.txt"));
+    assert!(row_text(first, 2).contains("│+└── other_only.txt"));
+    assert!(row_text(first, 7).contains("+ only here"));
+
+    // `=` turns the marks and the legend off.
+    let last = frames(vec![key('=')]).pop().unwrap();
+    assert!(row_text(&last, 1).starts_with("└── main_only.txt"));
+    assert!(!row_text(&last, 7).contains("only here"));
+}
+
+#[test]
+fn test_headless_heatmap_tints_directories_with_frequent_changes() {
+    let tmp = common::create_fixture(&["a/x.txt", "b/y.txt"]);
+    let changes = || {
+        let x = tmp.path().join("a/x.txt");
+        vec![
+            modified(x.clone()),
+            modified(x.clone()),
+            modified(x),
+            modified(tmp.path().join("b/y.txt")),
+        ]
+    };
+    let frames = run(tmp.path(), changes());
+    assert_eq!(row_text(frames.last().unwrap(), 0), "├── a");
+
+    let mut events = changes();
+    events.push(key('T'));
+    let frames = run(tmp.path(), events);
+    let last = frames.last().unwrap();
+    assert_eq!(row_text(last, 0), "├── a (heat 2/4)");
+    assert_eq!(row_text(last, 2), "└── b (heat 1/4)");
+}
+
+#[test]
+fn test_headless_top_changers_panel_counts_changes_per_directory() {
+    let tmp = common::create_fixture(&["a/x.txt", "a/y.txt", "b/z.txt", "top.txt"]);
+    let frames = run_wide(
+        tmp.path(),
+        vec![
+            key('C'),
+            modified(tmp.path().join("a/x.txt")),
+            modified(tmp.path().join("b/z.txt")),
+            modified(tmp.path().join("a/y.txt")),
+            modified(tmp.path().join("top.txt")),
+        ],
+    );
+    // The panel's text sits between the last two vertical borders.
+    let panel = |frame: &Buffer| -> Vec<String> {
+        (1..7)
+            .filter_map(|y| {
+                row_text(frame, y)
+                    .rsplit('│')
+                    .nth(1)
+                    .map(str::trim)
+                    .map(String::from)
+            })
+            .filter(|line| !line.is_empty())
+            .collect()
+    };
+    assert_eq!(panel(&frames[1]), ["no changes in the last 5m"]);
+    assert_eq!(
+        panel(frames.last().unwrap()),
+        ["changes in the last 5m", "2  a/", "1  ./", "1  b/"]
+    );
+}
+
+#[test]
+fn test_headless_focused_file_is_selected_and_followed() {
+    let tmp = common::create_fixture(&["a.txt", "b.txt", "c.txt"]);
+    let focus = tmp.path().join("c.txt");
+    let frames = run_with(
+        tmp.path(),
+        vec![
+            key('k'),
+            modified(tmp.path().join("a.txt")),
+            modified(focus.clone()),
+        ],
+        &LoopConfig {
+            focus: Some(focus),
+            ..loop_config()
+        },
+    );
+    let selected =
+        |frame: &Buffer| (0..8).find(|&y| frame[(6, y)].modifier.contains(Modifier::REVERSED));
+    assert_eq!(selected(&frames[0]), Some(2));
+    assert!(row_text(&frames[0], 2).contains("c.txt (marked)"));
+    // Moving away sticks until the focused file changes again.
+    assert_eq!(selected(&frames[1]), Some(1));
+    assert_eq!(selected(&frames[2]), Some(1));
+    assert_eq!(selected(&frames[3]), Some(2));
+}
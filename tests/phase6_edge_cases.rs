@@ -80,17 +80,12 @@ fn test_symlink_loop_handled() {
 
 #[test]
 fn test_very_narrow_terminal() {
-    let entry = TreeEntry {
-        name: "very_long_filename_that_exceeds_width.rs".to_string(),
-        path: PathBuf::from("very_long_filename_that_exceeds_width.rs"),
-        depth: 1,
-        is_dir: false,
-        is_symlink: false,
-        symlink_target: None,
-        is_last: true,
-        prefix: "\u{2514}\u{2500}\u{2500} ".to_string(),
-        error: None,
-    };
+    let entry = TreeEntry::new(
+        "very_long_filename_that_exceeds_width.rs",
+        PathBuf::from("very_long_filename_that_exceeds_width.rs"),
+        1,
+        false,
+    );
 
     let cfg = no_color(20);
     let lines = tree_to_lines(&[entry], &cfg, &HashSet::new());
@@ -107,17 +102,7 @@ fn test_very_narrow_terminal() {
 
 #[test]
 fn test_terminal_width_1() {
-    let entry = TreeEntry {
-        name: "file.txt".to_string(),
-        path: PathBuf::from("file.txt"),
-        depth: 1,
-        is_dir: false,
-        is_symlink: false,
-        symlink_target: None,
-        is_last: true,
-        prefix: "\u{2514}\u{2500}\u{2500} ".to_string(),
-        error: None,
-    };
+    let entry = TreeEntry::new("file.txt", PathBuf::from("file.txt"), 1, false);
 
     let cfg = no_color(1);
     // Should not panic
@@ -164,7 +149,7 @@ fn test_symlink_to_file_shows_arrow() {
     assert!(link.is_symlink);
 
     let cfg = no_color(120);
-    let lines = tree_to_lines(&[link.clone()], &cfg, &HashSet::new());
+    let lines = tree_to_lines(std::slice::from_ref(link), &cfg, &HashSet::new());
     let text = line_to_plain_text(&lines[0]);
     assert!(
         text.contains("->"),
@@ -177,22 +162,12 @@ fn test_symlink_to_file_shows_arrow() {
 
 #[test]
 fn test_render_at_various_widths() {
-    let entry = TreeEntry {
-        name: "filename.txt".to_string(),
-        path: PathBuf::from("filename.txt"),
-        depth: 1,
-        is_dir: false,
-        is_symlink: false,
-        symlink_target: None,
-        is_last: true,
-        prefix: "\u{2514}\u{2500}\u{2500} ".to_string(),
-        error: None,
-    };
+    let entry = TreeEntry::new("filename.txt", PathBuf::from("filename.txt"), 1, false);
 
     // Render at multiple widths — none should panic
     for width in [1, 5, 10, 20, 40, 80, 120, 200] {
         let cfg = no_color(width);
-        let lines = tree_to_lines(&[entry.clone()], &cfg, &HashSet::new());
+        let lines = tree_to_lines(std::slice::from_ref(&entry), &cfg, &HashSet::new());
         assert_eq!(lines.len(), 1);
     }
 }
@@ -1,9 +1,6 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 
 #[test]
 fn test_generate_assets_binary_runs() {
-    Command::cargo_bin("generate-assets")
-        .unwrap()
-        .assert()
-        .success();
+    cargo_bin_cmd!("generate-assets").assert().success();
 }
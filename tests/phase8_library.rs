@@ -0,0 +1,226 @@
+mod common;
+
+use common::{create_fixture, default_tree_config};
+use livetree::live::LiveTree;
+use livetree::render::{line_to_plain_text, RenderConfig};
+use livetree::tree::{build_tree, TreeDiff};
+use std::fs;
+use std::time::Duration;
+
+#[test]
+fn test_live_tree_initial_snapshot() {
+    let tmp = create_fixture(&["src/", "src/main.rs", "README.md"]);
+    let live = LiveTree::start(tmp.path(), default_tree_config(), 100).unwrap();
+
+    let snapshot = live.snapshot();
+    let names: Vec<&str> = snapshot.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["src", "main.rs", "README.md"]);
+}
+
+#[test]
+fn test_live_tree_subscribe_receives_rebuilt_snapshot() {
+    let tmp = create_fixture(&["a.txt"]);
+    let live = LiveTree::start(tmp.path(), default_tree_config(), 100).unwrap();
+    let rx = live.subscribe();
+
+    std::thread::sleep(Duration::from_millis(200));
+    fs::write(tmp.path().join("b.txt"), "").unwrap();
+
+    let snapshot = rx
+        .recv_timeout(Duration::from_secs(2))
+        .expect("subscriber should receive a snapshot after the change");
+    assert!(snapshot.iter().any(|e| e.name == "b.txt"));
+    assert_eq!(
+        live.snapshot(),
+        snapshot,
+        "handle should expose the latest snapshot"
+    );
+}
+
+#[test]
+fn test_live_tree_nonexistent_root_is_error() {
+    let result = LiveTree::start(
+        std::path::Path::new("/nonexistent_path_xyz"),
+        default_tree_config(),
+        100,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_live_tree_patches_bring_old_snapshot_up_to_date() {
+    let tmp = create_fixture(&["a.txt", "c.txt"]);
+    let live = LiveTree::start(tmp.path(), default_tree_config(), 100).unwrap();
+    let patches = live.subscribe_patches();
+    let mut mirror = (*live.snapshot()).clone();
+
+    std::thread::sleep(Duration::from_millis(200));
+    fs::write(tmp.path().join("b.txt"), "").unwrap();
+
+    let ops = patches
+        .recv_timeout(Duration::from_secs(2))
+        .expect("patch subscriber should receive a delta after the change");
+    livetree::patch::apply(&mut mirror, &ops);
+    assert_eq!(mirror, *live.snapshot());
+    assert!(livetree::patch::patch_to_json(&ops).contains("\"op\":\"add\""));
+}
+
+#[test]
+fn test_snapshot_diff_reports_added_removed_and_changed_paths() {
+    let tmp = create_fixture(&["src/", "src/main.rs", "docs", "old.txt", "z.txt"]);
+    let config = default_tree_config();
+    let before = build_tree(tmp.path(), &config);
+
+    fs::remove_file(tmp.path().join("old.txt")).unwrap();
+    fs::remove_file(tmp.path().join("docs")).unwrap();
+    fs::create_dir(tmp.path().join("docs")).unwrap();
+    fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+    let after = build_tree(tmp.path(), &config);
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.added, vec![tmp.path().join("src/lib.rs")]);
+    assert_eq!(diff.removed, vec![tmp.path().join("old.txt")]);
+    // `main.rs` is no longer the last entry in `src`, but that is layout.
+    assert_eq!(diff.changed, vec![tmp.path().join("docs")]);
+    assert!(after.diff(&after).is_empty());
+    assert_eq!(after.diff(&before).added, diff.removed);
+    assert_eq!(TreeDiff::default(), before.diff(&before));
+}
+
+#[test]
+fn test_live_tree_builder_applies_options() {
+    let tmp = create_fixture(&["a.log", "src/", "src/deep/", "src/deep/x.rs", "src/main.rs"]);
+    let live = LiveTree::builder()
+        .root(tmp.path())
+        .max_depth(2)
+        .ignore("*.log")
+        .build()
+        .unwrap();
+
+    let config = RenderConfig::builder()
+        .use_color(false)
+        .terminal_width(80)
+        .truncate_names(false)
+        .build();
+    let lines: Vec<String> = live
+        .render_lines(&config)
+        .iter()
+        .map(line_to_plain_text)
+        .collect();
+    assert_eq!(lines, ["└── src", "    ├── deep", "    └── main.rs"]);
+
+    assert!(LiveTree::builder().max_depth(2).build().is_err());
+    let err = LiveTree::builder()
+        .root(tmp.path())
+        .ignore("[")
+        .build()
+        .err()
+        .unwrap();
+    assert!(err.contains("invalid ignore pattern '['"), "{err}");
+}
+
+#[test]
+fn test_core_types_round_trip_through_serde() {
+    use livetree::patch::snapshot_to_json;
+    use livetree::tree::TreeSnapshot;
+    use livetree::watcher::{ChangeKind, PathChange, WatchEvent};
+
+    let tmp = create_fixture(&["src/main.rs", "notes.txt"]);
+    let snapshot = build_tree(tmp.path(), &default_tree_config());
+    let json = serde_json::to_string(&snapshot).unwrap();
+    // Same document as --export writes.
+    assert_eq!(json, snapshot_to_json(&snapshot));
+    assert_eq!(
+        serde_json::from_str::<TreeSnapshot>(&json).unwrap(),
+        snapshot
+    );
+
+    let event = WatchEvent::Changed(vec![PathChange {
+        path: "src/main.rs".into(),
+        kind: ChangeKind::Metadata,
+    }]);
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(
+        json,
+        r#"{"type":"changed","data":[{"path":"src/main.rs","kind":"metadata"}]}"#
+    );
+    match serde_json::from_str(&json).unwrap() {
+        WatchEvent::Changed(changes) => assert_eq!(changes[0].kind, ChangeKind::Metadata),
+        other => panic!("expected Changed, got {other:?}"),
+    }
+
+    let config: livetree::tree::TreeConfig =
+        serde_json::from_str(&serde_json::to_string(&default_tree_config()).unwrap()).unwrap();
+    assert_eq!(config.max_entries, default_tree_config().max_entries);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_served_tree_reaches_a_connected_client() {
+    use livetree::serve::{Connection, Endpoint, Server};
+    use livetree::tree::{TreeBuilder, WalkdirTreeBuilder};
+    use livetree::watcher::{ChangeKind, PathChange, WatchEvent};
+
+    let tmp = create_fixture(&["a.txt"]);
+    let socket = tempfile::TempDir::new().unwrap();
+    let endpoint = Endpoint::Unix(socket.path().join("live.sock"));
+    let server = Server::bind(&endpoint).unwrap();
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let root = tmp.path().to_path_buf();
+    let serving = std::thread::spawn(move || {
+        server.run(&root, &default_tree_config(), &WalkdirTreeBuilder, &rx)
+    });
+
+    let (connection, events) = Connection::open(&endpoint).unwrap();
+    assert_eq!(connection.root(), tmp.path());
+    let names = |c: &Connection| -> Vec<String> {
+        c.build_tree(c.root(), &default_tree_config())
+            .iter()
+            .map(|e| e.name.clone())
+            .collect()
+    };
+    assert_eq!(names(&connection), ["a.txt"]);
+
+    let created = tmp.path().join("b.txt");
+    fs::write(&created, "").unwrap();
+    tx.send(WatchEvent::Changed(vec![PathChange {
+        path: created.clone(),
+        kind: ChangeKind::Created,
+    }]))
+    .unwrap();
+    match events.recv_timeout(Duration::from_secs(2)).unwrap() {
+        WatchEvent::Changed(changes) => assert_eq!(changes[0].path, created),
+        other => panic!("expected Changed, got {other:?}"),
+    }
+    assert_eq!(names(&connection), ["a.txt", "b.txt"]);
+
+    tx.send(WatchEvent::RootDeleted).unwrap();
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(2)).unwrap(),
+        WatchEvent::RootDeleted
+    ));
+    assert_eq!(
+        serving.join().unwrap(),
+        Ok(livetree::script::StreamEnd::RootDeleted)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serving_refuses_to_replace_a_file_that_is_not_a_socket() {
+    use livetree::serve::{Endpoint, Server};
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let notes = dir.path().join("notes.txt");
+    fs::write(&notes, "important").unwrap();
+    let error = Server::bind(&Endpoint::Unix(notes.clone()))
+        .err()
+        .expect("bound over a regular file");
+    assert!(error.contains("exists and is not a socket"), "{error}");
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "important");
+
+    // A stale socket left behind by a server that is gone is replaced.
+    let socket = dir.path().join("live.sock");
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+    assert!(Server::bind(&Endpoint::Unix(socket)).is_ok());
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::{create_fixture, default_tree_config};
+use livetree::live::run_async;
+use livetree::watcher::stream::watch;
+use livetree::watcher::WatchEvent;
+use std::fs;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_watch_stream_yields_changes() {
+    let tmp = create_fixture(&["a.txt"]);
+    let mut events = watch(tmp.path(), 100).unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    fs::write(tmp.path().join("b.txt"), "").unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(2), events.next())
+        .await
+        .expect("a change should arrive");
+    match event {
+        Some(WatchEvent::Changed(changes)) => {
+            assert!(changes.iter().any(|c| c.path.ends_with("b.txt")))
+        }
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_run_async_delivers_snapshots_until_break() {
+    let tmp = create_fixture(&["a.txt"]);
+    let path = tmp.path().to_path_buf();
+    let writer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        fs::write(path.join("b.txt"), "").unwrap();
+    });
+
+    let mut seen = Vec::new();
+    let run = run_async(tmp.path(), default_tree_config(), 100, |snapshot| {
+        seen.push(snapshot.total_entries);
+        if snapshot.iter().any(|e| e.name == "b.txt") {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("the loop should stop once b.txt appears")
+        .unwrap();
+    writer.await.unwrap();
+    assert_eq!(seen.first(), Some(&1));
+    assert_eq!(seen.last(), Some(&2));
+}
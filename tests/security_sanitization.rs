@@ -5,24 +5,23 @@ use std::path::PathBuf;
 
 #[test]
 fn test_terminal_control_chars_are_sanitized_in_rendered_output() {
-    let entry = TreeEntry {
-        name: "evil\u{001B}[31mname\twith\ncontrols".to_string(),
-        path: PathBuf::from("/tmp/evil"),
-        depth: 1,
-        is_dir: false,
-        is_symlink: true,
-        symlink_target: Some("target\r\u{001B}[2J".to_string()),
-        is_last: true,
-        prefix: "└── ".to_string(),
-        error: Some("bad\tinput\nvalue\r".to_string()),
-    };
+    let mut entry = TreeEntry::new(
+        "evil\u{001B}[31mname\twith\ncontrols",
+        PathBuf::from("/tmp/evil"),
+        1,
+        false,
+    );
+    entry.is_symlink = true;
+    entry.symlink_target = Some("target\r\u{001B}[2J".to_string());
+    entry.error = Some("bad\tinput\nvalue\r".to_string());
 
     let lines = tree_to_lines(
         &[entry],
-        &RenderConfig {
-            use_color: false,
-            terminal_width: 120,
-        },
+        &RenderConfig::builder()
+            .use_color(false)
+            .terminal_width(120)
+            .truncate_names(false)
+            .build(),
         &HashSet::new(),
     );
     assert_eq!(lines.len(), 1);